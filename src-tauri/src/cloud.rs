@@ -51,13 +51,20 @@ fn build_function_declarations(tools: &[ToolDefinition]) -> Value {
                         .get("description")
                         .and_then(|d| d.as_str())
                         .unwrap_or("");
-                    converted.insert(
-                        k.clone(),
-                        json!({
-                            "type": gemini_type(prop_type),
-                            "description": description,
-                        }),
-                    );
+                    let mut prop = json!({
+                        "type": gemini_type(prop_type),
+                        "description": description,
+                    });
+                    // Gemini rejects ARRAY parameters without an item type
+                    if prop_type == "array" {
+                        let item_type = v
+                            .get("items")
+                            .and_then(|i| i.get("type"))
+                            .and_then(|t| t.as_str())
+                            .unwrap_or("string");
+                        prop["items"] = json!({"type": gemini_type(item_type)});
+                    }
+                    converted.insert(k.clone(), prop);
                 }
                 Value::Object(converted)
             } else {
@@ -225,6 +232,7 @@ pub async fn call_gemini_with_retry(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tools::RiskLevel;
 
     #[test]
     fn test_gemini_type_mapping() {
@@ -262,6 +270,7 @@ mod tests {
                 },
                 "required": ["query"]
             }),
+            risk: RiskLevel::ReadOnly,
         }];
         let decls = build_function_declarations(&tools);
         let arr = decls.as_array().unwrap();
@@ -273,6 +282,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_build_function_declarations_array_items() {
+        let tools = vec![ToolDefinition {
+            name: "test_tool".into(),
+            description: "A test tool".into(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "apps": {"type": "array", "items": {"type": "string"}}
+                },
+                "required": []
+            }),
+            risk: RiskLevel::ReadOnly,
+        }];
+        let decls = build_function_declarations(&tools);
+        let apps = &decls[0]["parameters"]["properties"]["apps"];
+        assert_eq!(apps["type"], "ARRAY");
+        assert_eq!(apps["items"]["type"], "STRING");
+    }
+
     #[tokio::test]
     async fn test_call_gemini_no_api_key() {
         // Without GEMINI_API_KEY, should return None gracefully
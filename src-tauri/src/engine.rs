@@ -26,6 +26,36 @@ fn extract_words(s: &str) -> Vec<String> {
         .collect()
 }
 
+/// Whether a word is shaped like an OBD-II trouble code ("p0300", "U0100").
+fn looks_like_dtc(word: &str) -> bool {
+    let b = word.as_bytes();
+    b.len() == 5
+        && matches!(b[0].to_ascii_lowercase(), b'p' | b'c' | b'b' | b'u')
+        && (b'0'..=b'3').contains(&b[1])
+        && b[2..].iter().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Whether a word is shaped like a 17-character VIN: letters and digits,
+/// never I, O or Q.
+fn looks_like_vin(word: &str) -> bool {
+    word.len() == 17
+        && word.chars().all(|c| c.is_ascii_alphanumeric())
+        && word.chars().any(|c| c.is_ascii_digit())
+        && word.chars().any(|c| c.is_ascii_alphabetic())
+        && !word.chars().any(|c| matches!(c, 'i' | 'o' | 'q' | 'I' | 'O' | 'Q'))
+}
+
+/// A mileage like "42,000", "42000" or "42k".
+fn parse_mileage(word: &str) -> Option<u32> {
+    let w = word.trim_matches(|c: char| !c.is_ascii_alphanumeric());
+    let (digits, scale) = match w.strip_suffix('k') {
+        Some(rest) => (rest, 1000),
+        None => (w, 1),
+    };
+    let n: u32 = digits.replace(',', "").parse().ok()?;
+    (n * scale >= 100).then_some(n * scale)
+}
+
 /// Validate FunctionGemma output. Returns `true` if the result looks correct.
 ///
 /// Checks performed:
@@ -285,6 +315,10 @@ impl HybridEngine {
     /// 3. Gemini cloud (last resort)
     ///    → If cloud returns a valid tool name → execute via registry ("cloud (fallback)")
     /// 4. Final fallback → return tool_result: None
+    ///
+    /// Destructive tools are never run here: the registry returns a
+    /// `requires_confirmation` result carrying a token, which the UI confirms
+    /// through [`ModuleRegistry::confirm`].
     pub async fn route(&self, user_input: &str, module_filter: Option<&str>) -> RouteResult {
         let start = Instant::now();
         let tools = match module_filter {
@@ -400,6 +434,138 @@ impl HybridEngine {
 
         // --- Ordered from most specific to least specific ---
 
+        // a bare tool name, as sent by quick actions without a phrase
+        if self.registry.has_tool(lower.trim()) {
+            return (lower.trim().to_string(), json!({}), 0.9);
+        }
+
+        // phrases a module declared for its own tools (declarative modules)
+        for (tool, keywords) in self.registry.routing_keywords() {
+            if keywords.iter().any(|kw| lower.contains(&kw.to_lowercase())) {
+                return (tool, json!({}), 0.85);
+            }
+        }
+
+        // a VIN or a trouble code ("what does P0300 mean") names the tool
+        // on its own
+        if let Some(vin) = words
+            .iter()
+            .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()))
+            .find(|w| looks_like_vin(w))
+        {
+            return ("decode_vin".into(), json!({"vin": vin.to_uppercase()}), 0.9);
+        }
+        let codes: Vec<String> = words
+            .iter()
+            .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()))
+            .filter(|w| looks_like_dtc(w))
+            .map(|w| w.to_uppercase())
+            .collect();
+        if !codes.is_empty() {
+            return ("explain_dtc".into(), json!({"code": codes.join(", ")}), 0.9);
+        }
+
+        // trouble code management, before "freeze" means a hang and "clear"
+        // means caches
+        let code_words = words.iter().any(|w| {
+            matches!(
+                w.trim_matches(|c: char| !c.is_alphanumeric()),
+                "code" | "codes" | "dtc" | "dtcs"
+            )
+        });
+        if has(&["freeze frame", "freeze-frame"]) {
+            return ("read_freeze_frame".into(), json!({}), 0.9);
+        }
+        if code_words || has(&["engine light"]) {
+            if has(&["clear", "reset", "erase", "turn off"]) {
+                return ("clear_dtcs".into(), json!({}), 0.85);
+            }
+            if has(&["pending", "permanent", "stored"]) {
+                return ("read_dtcs".into(), json!({}), 0.85);
+            }
+        }
+
+        // vehicle maintenance, before "log" means the system log
+        if has(&[
+            "oil change", "changed the oil", "changed my oil", "tire rotation", "rotated",
+            "rotate the tires", "maintenance", "service due", "next service", "air filter",
+            "cabin filter",
+        ]) {
+            let service = if has(&["oil"]) {
+                Some("oil_change")
+            } else if has(&["rotat"]) {
+                Some("tire_rotation")
+            } else if has(&["cabin"]) {
+                Some("cabin_air_filter")
+            } else if has(&["air filter"]) {
+                Some("engine_air_filter")
+            } else {
+                None
+            };
+            let mileage = words.iter().find_map(|w| parse_mileage(w));
+            let done = words.first().is_some_and(|w| ["log", "record", "add"].contains(w))
+                || has(&[
+                    "just", "changed", "rotated", "got an", "got the", "got my", "had an",
+                    "had the", "had my", "did an", "did the",
+                ]);
+            if done && service.is_some() && !has(&["when", "due", "next"]) {
+                let mut args = json!({"service": service});
+                if let Some(miles) = mileage {
+                    args["mileage"] = json!(miles);
+                }
+                return ("log_maintenance".into(), args, 0.85);
+            }
+            let mut args = json!({});
+            if let Some(service) = service {
+                args["service"] = json!(service);
+            }
+            if let Some(miles) = mileage {
+                args["mileage"] = json!(miles);
+            }
+            return ("check_maintenance".into(), args, 0.85);
+        }
+
+        // crash / hang reports ("quit unexpectedly" before the kill rule)
+        let hang = has(&["freez", "froze", "hung", "hang"]) && !has(&["kill", "force"]);
+        if has(&["crash", "quit unexpectedly"]) || hang {
+            // "why does Xcode keep crashing" -> the first real word before the trigger
+            let skip = [
+                "my", "the", "app", "apps", "it", "did", "does", "do", "keep", "keeps", "why",
+                "is", "was", "has", "have", "this", "that", "a", "an", "always", "just", "show",
+                "list", "check", "any", "all", "recent", "latest", "last", "what", "me",
+            ];
+            let app = words
+                .iter()
+                .position(|w| {
+                    ["crash", "quit", "freez", "froze", "hung", "hang"]
+                        .iter()
+                        .any(|t| w.starts_with(t))
+                })
+                .and_then(|i| words[..i].iter().rev().take(3).find(|w| !skip.contains(w)))
+                .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()))
+                .filter(|w| !w.is_empty());
+            let args = match app {
+                Some(app) => json!({"app": app}),
+                None => json!({}),
+            };
+            return ("analyze_crashes".into(), args, 0.85);
+        }
+
+        // unified log search
+        if words.iter().any(|w| {
+            let w = w.trim_matches(|c: char| !c.is_alphanumeric());
+            w == "log" || w == "logs" || w == "console"
+        }) {
+            let args = if has(&["fault"]) {
+                json!({"level": "fault"})
+            } else if has(&["error"]) {
+                json!({"level": "error"})
+            } else {
+                json!({})
+            };
+            return ("search_logs".into(), args, 0.85);
+        }
+
         // kill / quit / force quit
         if has(&["kill", "quit", "force"]) {
             let process_name = words
@@ -414,6 +580,10 @@ impl HybridEngine {
             } else {
                 process_name
             };
+            // "kill 4312" / "kill pid 4312" target an exact process
+            if let Ok(pid) = pname.parse::<u32>() {
+                return ("kill_process".into(), json!({"pid": pid}), 0.85);
+            }
             return (
                 "kill_process".into(),
                 json!({"process_name": pname}),
@@ -442,11 +612,70 @@ impl HybridEngine {
             return ("run_full_checkup".into(), json!({}), 0.9);
         }
 
+        // peripherals: monitors, USB/Thunderbolt, Bluetooth devices, drive health
+        if has(&[
+            "external monitor", "second monitor", "my monitor", "display", "usb", "thunderbolt",
+            "bluetooth", "airpods", "hardware", "peripheral", "smart status", "external drive",
+        ]) {
+            let mut categories = Vec::new();
+            if has(&["monitor", "display"]) {
+                categories.push("displays");
+            }
+            if has(&["usb", "external drive"]) {
+                categories.extend(["usb", "storage"]);
+            }
+            if has(&["thunderbolt"]) {
+                categories.push("thunderbolt");
+            }
+            if has(&["bluetooth", "airpods"]) {
+                categories.push("bluetooth");
+            }
+            if has(&["smart status"]) && !categories.contains(&"storage") {
+                categories.push("storage");
+            }
+            let args = if categories.is_empty() {
+                json!({})
+            } else {
+                json!({"categories": categories})
+            };
+            return ("check_hardware".into(), args, 0.85);
+        }
+
         // battery / power / charging
         if has(&["battery", "power", "charging"]) {
             return ("diagnose_battery".into(), json!({}), 0.9);
         }
 
+        // hot / fans / thermal throttling (not the car's engine temperature)
+        let hot = words.iter().any(|w| {
+            matches!(
+                w.trim_matches(|c: char| !c.is_alphanumeric()),
+                "hot" | "fan" | "fans"
+            )
+        });
+        if (hot || has(&["overheat", "thermal", "throttl", "temperature"]))
+            && !has(&["engine", "coolant", "car", "vehicle"])
+        {
+            return ("check_thermals".into(), json!({}), 0.85);
+        }
+
+        // sockets / listening ports / which app is talking to the internet
+        if has(&["listening", "talking to", "sockets", "open connections"])
+            || words.iter().any(|w| *w == "port" || *w == "ports")
+        {
+            let port = words
+                .iter()
+                .position(|w| *w == "port")
+                .and_then(|i| words.get(i + 1))
+                .and_then(|w| w.trim_matches(|c: char| !c.is_ascii_digit()).parse::<u16>().ok());
+            let args = match port {
+                Some(port) => json!({"port": port}),
+                None if has(&["listening"]) => json!({"state": "listen"}),
+                None => json!({}),
+            };
+            return ("monitor_network".into(), args, 0.85);
+        }
+
         // network diagnosis (more specific keywords first)
         if has(&["network", "connection", "wifi", "internet"]) {
             if has(&["broken", "fix", "diagnose", "slow", "issue", "problem"]) {
@@ -455,11 +684,42 @@ impl HybridEngine {
             return ("monitor_network".into(), json!({}), 0.85);
         }
 
+        // disable a startup item (label keeps its original case)
+        if has(&["disable"]) && has(&["startup", "launch", "agent", "daemon", "login item"]) {
+            let label = input
+                .split_whitespace()
+                .last()
+                .map(|w| w.trim_end_matches(|c| ".,!?".contains(c)))
+                .unwrap_or("unknown");
+            return (
+                "disable_startup_item".into(),
+                json!({"label": label}),
+                0.85,
+            );
+        }
+
         // startup / boot / login items
         if has(&["startup", "boot", "login"]) {
             return ("check_startup_items".into(), json!({}), 0.85);
         }
 
+        // installed apps / versions / Homebrew / Rosetta
+        if has(&[
+            "version of", "outdated", "installed apps", "apps installed", "applications",
+            "homebrew", "brew", "rosetta", "intel app", "list apps", "my apps",
+        ]) {
+            // "what version of Xcode do I have" -> filter to that app
+            let args = match words.iter().position(|w| *w == "of") {
+                Some(i) if has(&["version of"]) => words
+                    .get(i + 1)
+                    .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()))
+                    .filter(|w| !w.is_empty() && !["my", "the"].contains(w))
+                    .map_or(json!({}), |name| json!({"name": name})),
+                _ => json!({}),
+            };
+            return ("list_apps".into(), args, 0.85);
+        }
+
         // security / firewall / update
         if has(&["security", "secure", "firewall", "update"]) {
             return ("check_security".into(), json!({}), 0.85);
@@ -480,6 +740,11 @@ impl HybridEngine {
             return ("monitor_memory".into(), json!({}), 0.9);
         }
 
+        // largest files / what is using space -> deep analysis
+        if has(&["largest", "biggest", "taking up", "using up", "reclaim", "analyze disk"]) {
+            return ("analyze_disk".into(), json!({}), 0.85);
+        }
+
         // disk / storage / space
         if has(&["disk", "storage", "space"]) {
             return ("monitor_disk".into(), json!({}), 0.9);
@@ -487,6 +752,20 @@ impl HybridEngine {
 
         // --- Auto mechanic tools ---
 
+        // which car this is: the decoded VIN, then the saved profile
+        let vin = words
+            .iter()
+            .any(|w| w.trim_matches(|c: char| !c.is_alphanumeric()) == "vin");
+        if (vin && !has(&["profile"])) || has(&["car made", "car built", "car was made"]) {
+            return ("decode_vin".into(), json!({}), 0.85);
+        }
+        if has(&[
+            "vehicle profile", "car profile", "what car", "which car", "recommended pressure",
+            "fluid spec", "what oil",
+        ]) {
+            return ("get_vehicle_profile".into(), json!({}), 0.85);
+        }
+
         // vehicle checkup (most specific first)
         if has(&["vehicle checkup", "car diagnostic", "car checkup"]) {
             return ("run_vehicle_checkup".into(), json!({}), 0.9);
@@ -563,6 +842,14 @@ mod tests {
         assert!(conf > 0.8);
     }
 
+    #[test]
+    fn test_route_analyze_disk() {
+        let e = engine();
+        let (name, _, conf) = e.local_route("what is taking up space on my drive", &[]);
+        assert_eq!(name, "analyze_disk");
+        assert!(conf > 0.8);
+    }
+
     #[test]
     fn test_route_network_monitor() {
         let e = engine();
@@ -570,6 +857,26 @@ mod tests {
         assert_eq!(name, "monitor_network");
     }
 
+    #[test]
+    fn test_route_disable_startup_item() {
+        let e = engine();
+        let (name, args, _) = e.local_route("disable startup item com.Example.Updater", &[]);
+        assert_eq!(name, "disable_startup_item");
+        assert_eq!(args["label"], "com.Example.Updater");
+        let (name, _, _) = e.local_route("show my startup items", &[]);
+        assert_eq!(name, "check_startup_items");
+    }
+
+    #[test]
+    fn test_route_network_inventory() {
+        let e = engine();
+        let (name, args, _) = e.local_route("what's listening on port 5432?", &[]);
+        assert_eq!(name, "monitor_network");
+        assert_eq!(args["port"], 5432);
+        let (name, _, _) = e.local_route("which app is talking to the internet", &[]);
+        assert_eq!(name, "monitor_network");
+    }
+
     #[test]
     fn test_route_network_diagnose() {
         let e = engine();
@@ -592,6 +899,14 @@ mod tests {
         assert_eq!(args["process_name"], "safari");
     }
 
+    #[test]
+    fn test_route_kill_by_pid() {
+        let e = engine();
+        let (name, args, _) = e.local_route("kill pid 4312", &[]);
+        assert_eq!(name, "kill_process");
+        assert_eq!(args["pid"], 4312);
+    }
+
     #[test]
     fn test_route_clear_caches() {
         let e = engine();
@@ -600,6 +915,180 @@ mod tests {
         assert_eq!(args["target"], "disk");
     }
 
+    #[test]
+    fn test_route_crashes() {
+        let e = engine();
+        let (name, args, _) = e.local_route("why does Xcode keep crashing", &[]);
+        assert_eq!(name, "analyze_crashes");
+        assert_eq!(args["app"], "xcode");
+
+        let (name, args, _) = e.local_route("Safari quit unexpectedly", &[]);
+        assert_eq!(name, "analyze_crashes");
+        assert_eq!(args["app"], "safari");
+
+        let (name, args, _) = e.local_route("show recent crash reports", &[]);
+        assert_eq!(name, "analyze_crashes");
+        assert!(args.get("app").is_none());
+
+        // Killing a frozen app is still a kill.
+        let (name, _, _) = e.local_route("force quit the frozen app", &[]);
+        assert_eq!(name, "kill_process");
+    }
+
+    #[test]
+    fn test_route_logs() {
+        let e = engine();
+        let (name, args, _) = e.local_route("show errors in the system log", &[]);
+        assert_eq!(name, "search_logs");
+        assert_eq!(args["level"], "error");
+
+        // "login" is not "log".
+        let (name, _, _) = e.local_route("show my login items", &[]);
+        assert_eq!(name, "check_startup_items");
+    }
+
+    #[test]
+    fn test_route_dtc() {
+        let e = engine();
+        let (name, args, _) = e.local_route("What does P0300 mean?", &[]);
+        assert_eq!(name, "explain_dtc");
+        assert_eq!(args["code"], "P0300");
+        let (_, args, _) = e.local_route("my engine shows p0171 and P0420", &[]);
+        assert_eq!(args["code"], "P0171, P0420");
+        // Words that merely look close are not codes.
+        let (name, _, _) = e.local_route("check engine codes", &[]);
+        assert_eq!(name, "check_engine");
+    }
+
+    #[test]
+    fn test_route_code_management() {
+        let e = engine();
+        let cases = [
+            ("show the freeze frame data", "read_freeze_frame"),
+            ("clear the check engine light", "clear_dtcs"),
+            ("reset trouble codes", "clear_dtcs"),
+            ("any pending codes?", "read_dtcs"),
+            ("clear xcode cache", "clear_caches"),
+            ("safari froze", "analyze_crashes"),
+        ];
+        for (phrase, tool) in cases {
+            let (name, _, _) = e.local_route(phrase, &[]);
+            assert_eq!(name, tool, "{}", phrase);
+        }
+    }
+
+    #[test]
+    fn test_route_maintenance() {
+        let e = engine();
+        let (name, args, _) = e.local_route("when is my next oil change due", &[]);
+        assert_eq!(name, "check_maintenance");
+        assert_eq!(args["service"], "oil_change");
+
+        let (name, args, _) = e.local_route("log an oil change at 42,000 miles", &[]);
+        assert_eq!(name, "log_maintenance");
+        assert_eq!(args, json!({"service": "oil_change", "mileage": 42000}));
+
+        let (name, args, _) = e.local_route("I just rotated the tires at 45k", &[]);
+        assert_eq!(name, "log_maintenance");
+        assert_eq!(args, json!({"service": "tire_rotation", "mileage": 45000}));
+
+        let (name, args, _) = e.local_route("is any maintenance due?", &[]);
+        assert_eq!(name, "check_maintenance");
+        assert_eq!(args, json!({}));
+
+        for phrase in ["show my vehicle profile", "what oil does my car take"] {
+            let (name, _, _) = e.local_route(phrase, &[]);
+            assert_eq!(name, "get_vehicle_profile", "{}", phrase);
+        }
+        // the system log is still the system log
+        let (name, _, _) = e.local_route("show errors in the system log", &[]);
+        assert_eq!(name, "search_logs");
+    }
+
+    #[test]
+    fn test_route_declared_keywords() {
+        use crate::tools::declarative::DeclarativeModule;
+
+        let module = DeclarativeModule::parse(
+            "name = \"extra\"\n[[tools]]\nname = \"get_uptime\"\ndescription = \"Uptime\"\n\
+             keywords = [\"Uptime\"]\ncommand = [\"uptime\"]",
+        )
+        .unwrap();
+        let mut registry = ModuleRegistry::new();
+        registry.register(Arc::new(module)).unwrap();
+        registry.register(Arc::new(MacTroubleshootModule::new())).unwrap();
+        let e = HybridEngine::new(Arc::new(registry), None);
+        let (name, args, _) = e.local_route("what's the uptime of this mac", &[]);
+        assert_eq!(name, "get_uptime");
+        assert_eq!(args, json!({}));
+        let (name, _, _) = e.local_route("check disk space", &[]);
+        assert_eq!(name, "monitor_disk");
+        let (name, _, _) = e.local_route("list_apps", &[]);
+        assert_eq!(name, "list_apps");
+    }
+
+    #[test]
+    fn test_route_vin() {
+        let e = engine();
+        let (name, args, _) = e.local_route("decode 1hgcv1f37la012345", &[]);
+        assert_eq!(name, "decode_vin");
+        assert_eq!(args["vin"], "1HGCV1F37LA012345");
+
+        for phrase in ["what is my VIN", "decode my vin", "where was my car made?"] {
+            let (name, args, _) = e.local_route(phrase, &[]);
+            assert_eq!(name, "decode_vin", "{}", phrase);
+            assert_eq!(args, json!({}));
+        }
+        // 17 letters is a word, not a VIN
+        assert!(!looks_like_vin("internationalized"));
+    }
+
+    #[test]
+    fn test_route_thermals() {
+        let e = engine();
+        for phrase in ["my mac is hot", "why are the fans so loud", "is my cpu throttling"] {
+            let (name, _, _) = e.local_route(phrase, &[]);
+            assert_eq!(name, "check_thermals", "{}", phrase);
+        }
+        // "screenshot" contains "hot"; engine temperature is the car.
+        let (name, _, _) = e.local_route("where is my screenshot folder on disk", &[]);
+        assert_eq!(name, "monitor_disk");
+        let (name, _, _) = e.local_route("what's the engine temperature", &[]);
+        assert_eq!(name, "check_engine");
+    }
+
+    #[test]
+    fn test_route_apps() {
+        let e = engine();
+        let (name, args, _) = e.local_route("what version of Xcode do I have?", &[]);
+        assert_eq!(name, "list_apps");
+        assert_eq!(args["name"], "xcode");
+
+        let (name, args, _) = e.local_route("which apps are outdated", &[]);
+        assert_eq!(name, "list_apps");
+        assert!(args.get("name").is_none());
+
+        let (name, _, _) = e.local_route("are any apps running under rosetta", &[]);
+        assert_eq!(name, "list_apps");
+    }
+
+    #[test]
+    fn test_route_hardware() {
+        let e = engine();
+        let (name, args, _) = e.local_route("my monitor isn't detected", &[]);
+        assert_eq!(name, "check_hardware");
+        assert_eq!(args["categories"], json!(["displays"]));
+
+        let (name, args, _) = e.local_route("USB drive not showing up", &[]);
+        assert_eq!(name, "check_hardware");
+        assert_eq!(args["categories"], json!(["usb", "storage"]));
+
+        // Peripheral batteries are Bluetooth, not the Mac's battery.
+        let (name, args, _) = e.local_route("what's my airpods battery level", &[]);
+        assert_eq!(name, "check_hardware");
+        assert_eq!(args["categories"], json!(["bluetooth"]));
+    }
+
     #[test]
     fn test_route_startup() {
         let e = engine();
@@ -649,6 +1138,16 @@ mod tests {
         assert!(result.tool_result.is_none());
     }
 
+    #[tokio::test]
+    async fn test_route_destructive_requires_confirmation() {
+        let e = engine();
+        let result = e.route("please quit the app", None).await;
+        assert_eq!(result.tool_name, "kill_process");
+        let tool_result = result.tool_result.unwrap();
+        assert_eq!(tool_result.data["requires_confirmation"], true);
+        assert!(tool_result.data["confirmation"]["token"].is_string());
+    }
+
     /// Simulate Whisper-style transcriptions and verify they route correctly.
     #[test]
     fn test_whisper_voice_phrases() {
@@ -666,6 +1165,7 @@ mod tests {
             ("How much RAM do I have?", "monitor_memory"),
             ("Check disk space.", "monitor_disk"),
             ("How much storage is left?", "monitor_disk"),
+            ("What are my largest files?", "analyze_disk"),
             ("Show network connections.", "monitor_network"),
             ("Check my internet connection.", "monitor_network"),
             ("My WiFi is slow.", "diagnose_network"),
@@ -677,11 +1177,19 @@ mod tests {
             ("Run full health check.", "run_full_checkup"),
             ("What are my startup items?", "check_startup_items"),
             ("Kill Safari.", "kill_process"),
+            ("Why did Safari crash?", "analyze_crashes"),
+            ("My Mac is running hot.", "check_thermals"),
+            ("Which apps are outdated?", "list_apps"),
+            ("My external monitor is not detected.", "check_hardware"),
             ("Clear the cache.", "clear_caches"),
             // Natural voice commands
             ("My computer is slow.", "monitor_cpu"),
             ("Why is my Mac slow?", "monitor_cpu"),
             ("Check the engine.", "check_engine"),
+            ("What does code P0420 mean?", "explain_dtc"),
+            ("Clear the engine codes.", "clear_dtcs"),
+            ("When is my next oil change due?", "check_maintenance"),
+            ("Where was my car made?", "decode_vin"),
             ("What's my tire pressure?", "check_tires"),
             ("Check fluid levels.", "check_fluids"),
         ];
@@ -1,10 +1,12 @@
 pub mod cactus_ffi;
 pub mod cloud;
 pub mod engine;
+pub mod mcp;
 pub mod tools;
 
 use std::path::PathBuf;
 use std::sync::Arc;
+use tauri::Emitter;
 use tokio::sync::Mutex;
 
 /// Whisper is lazily loaded on first transcription request.
@@ -54,6 +56,10 @@ pub struct AppState {
     pub engine: engine::HybridEngine,
     /// Module registry for direct tool access from the UI.
     pub registry: Arc<tools::ModuleRegistry>,
+    /// Vehicle module, kept for live telemetry outside the tool interface.
+    pub auto_mechanic: Arc<tools::auto_mechanic::AutoMechanicModule>,
+    /// The running telemetry session, if any.
+    pub telemetry: Option<tools::auto_mechanic::TelemetrySession>,
 }
 
 /// Route a natural-language command through the hybrid engine and return the result.
@@ -98,6 +104,77 @@ async fn execute_tool(
     Ok(state.registry.execute(&tool_name, args))
 }
 
+/// Execute an action previously proposed with `requires_confirmation`.
+#[tauri::command]
+async fn confirm_action(
+    token: String,
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+) -> Result<tools::ToolResult, String> {
+    let state = state.lock().await;
+    Ok(state.registry.confirm(&token))
+}
+
+/// Discard a proposed action without running it.
+#[tauri::command]
+async fn cancel_action(
+    token: String,
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+) -> Result<bool, String> {
+    let state = state.lock().await;
+    Ok(state.registry.cancel(&token))
+}
+
+/// Directory for telemetry recordings: SENTINEL_RECORDINGS_DIR or recordings/ at the repo root.
+fn recordings_dir() -> PathBuf {
+    std::env::var("SENTINEL_RECORDINGS_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../recordings"))
+}
+
+/// Start streaming vehicle PIDs as `telemetry-sample` events. Unset options
+/// fall back to the `telemetry` section of vehicle.json; `record` writes the
+/// session to a timestamped CSV + JSONL pair in the recordings directory.
+#[tauri::command]
+async fn start_telemetry(
+    pids: Option<Vec<String>>,
+    interval_ms: Option<u64>,
+    record: Option<bool>,
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+) -> Result<(), String> {
+    let mut state = state.lock().await;
+    if state.telemetry.is_some() {
+        return Err("Telemetry is already running".into());
+    }
+    let record = record.unwrap_or(false).then(|| {
+        let stamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        recordings_dir().join(format!("telemetry-{}", stamp))
+    });
+    let options = tools::auto_mechanic::TelemetryOptions {
+        pids: pids.unwrap_or_default(),
+        interval_ms,
+        record,
+    };
+    let session = state.auto_mechanic.start_telemetry(options, move |sample| {
+        let _ = app.emit("telemetry-sample", sample);
+    })?;
+    state.telemetry = Some(session);
+    Ok(())
+}
+
+/// Stop the telemetry session and report how many samples it took and where it was recorded.
+#[tauri::command]
+async fn stop_telemetry(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+) -> Result<tools::auto_mechanic::TelemetrySummary, String> {
+    let mut state = state.lock().await;
+    let session = state.telemetry.take().ok_or("Telemetry is not running")?;
+    session.stop()
+}
+
 /// Transcribe raw PCM audio (16-bit, 16 kHz, mono) using the on-device Whisper model.
 /// Audio is received as a base64-encoded string to avoid huge JSON arrays.
 #[tauri::command]
@@ -142,19 +219,102 @@ async fn whisper_ready() -> bool {
     candidates.iter().any(|p| p.exists())
 }
 
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
-    // Load .env from the repo root (sentinel/)
+/// Load .env from the repo root (sentinel/).
+fn load_env() {
     let env_path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../.env");
     let _ = dotenvy::from_path(&env_path);
+}
 
+/// Build the registry with every module, the policy and the garage. Logs go
+/// to stderr: in MCP mode stdout carries the protocol.
+fn build_registry() -> (tools::ModuleRegistry, Arc<tools::auto_mechanic::AutoMechanicModule>) {
     let mut registry = tools::ModuleRegistry::new();
+
+    // Tool policy: SENTINEL_POLICY_PATH or policy.json at the repo root.
+    let policy_path = std::env::var("SENTINEL_POLICY_PATH")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../policy.json"));
+    match tools::policy::ToolPolicy::load(&policy_path) {
+        Ok(policy) => registry.set_policy(policy),
+        Err(e) => eprintln!("[sentinel] {}. Using default policy.", e),
+    }
+
     registry
         .register(Arc::new(tools::mac_troubleshoot::MacTroubleshootModule::new()))
         .expect("Failed to register mac_troubleshoot module");
+    // Vehicle data source: SENTINEL_VEHICLE_CONFIG or vehicle.json at the repo root.
+    let vehicle_path = std::env::var("SENTINEL_VEHICLE_CONFIG")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../vehicle.json"));
+    let mut auto_mechanic = tools::auto_mechanic::VehicleConfig::load(&vehicle_path)
+        .and_then(|config| tools::auto_mechanic::AutoMechanicModule::with_config(&config))
+        .unwrap_or_else(|e| {
+            eprintln!("[sentinel] {}. Using demo vehicle data.", e);
+            tools::auto_mechanic::AutoMechanicModule::new()
+        });
+    // Vehicle profile + maintenance log: SENTINEL_GARAGE_PATH or garage.json at the repo root.
+    let garage_path = std::env::var("SENTINEL_GARAGE_PATH")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../garage.json"));
+    match tools::auto_mechanic::Garage::load(&garage_path) {
+        Ok(garage) => auto_mechanic.set_garage(garage),
+        Err(e) => eprintln!("[sentinel] {}. Maintenance log will not be saved.", e),
+    }
+    let auto_mechanic = Arc::new(auto_mechanic);
     registry
-        .register(Arc::new(tools::auto_mechanic::AutoMechanicModule::new()))
+        .register(auto_mechanic.clone())
         .expect("Failed to register auto_mechanic module");
+    // Declarative modules: *.toml in SENTINEL_MODULES_DIR or modules/ at the repo root.
+    let modules_dir = std::env::var("SENTINEL_MODULES_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../modules"));
+    for module in tools::declarative::load_dir(&modules_dir) {
+        let registered = module.and_then(|m| {
+            let name = tools::ToolModule::name(&m).to_string();
+            registry.register(Arc::new(m)).map(|_| name)
+        });
+        match registered {
+            Ok(name) => {
+                eprintln!("[sentinel] Loaded module {} from {}", name, modules_dir.display())
+            }
+            Err(e) => eprintln!("[sentinel] {}. Skipping module.", e),
+        }
+    }
+    // Plugins: executables (or *.toml manifests) in SENTINEL_PLUGINS_DIR or plugins/.
+    let plugins_dir = std::env::var("SENTINEL_PLUGINS_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../plugins"));
+    for plugin in tools::plugin::load_dir(&plugins_dir) {
+        let registered = plugin.and_then(|p| {
+            let name = tools::ToolModule::name(&p).to_string();
+            registry.register(Arc::new(p)).map(|_| name)
+        });
+        match registered {
+            Ok(name) => {
+                eprintln!("[sentinel] Started plugin {} from {}", name, plugins_dir.display())
+            }
+            Err(e) => eprintln!("[sentinel] {}. Skipping plugin.", e),
+        }
+    }
+    (registry, auto_mechanic)
+}
+
+/// Serve the registry's tools over MCP on stdin/stdout instead of opening
+/// the app (`sentinel --mcp`).
+pub fn run_mcp() {
+    load_env();
+    let (registry, _auto_mechanic) = build_registry();
+    let stdin = std::io::stdin();
+    if let Err(e) = mcp::serve(&registry, stdin.lock(), std::io::stdout().lock()) {
+        eprintln!("[sentinel] MCP server stopped: {}", e);
+        std::process::exit(1);
+    }
+}
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    load_env();
+    let (registry, auto_mechanic) = build_registry();
     let registry = Arc::new(registry);
 
     // Try to load FunctionGemma model for intelligent routing.
@@ -194,6 +354,8 @@ pub fn run() {
     let state = Arc::new(Mutex::new(AppState {
         engine,
         registry,
+        auto_mechanic,
+        telemetry: None,
     }));
 
     tauri::Builder::default()
@@ -204,6 +366,10 @@ pub fn run() {
             get_tools,
             get_modules,
             execute_tool,
+            confirm_action,
+            cancel_action,
+            start_telemetry,
+            stop_telemetry,
             transcribe_audio,
             whisper_ready,
         ])
@@ -12,6 +12,12 @@ fn main() {
         return;
     }
 
+    // `sentinel --mcp` serves the diagnostic tools to MCP clients over stdio.
+    if std::env::args().skip(1).any(|arg| arg == "--mcp") {
+        sentinel_lib::run_mcp();
+        return;
+    }
+
     sentinel_lib::run()
 }
 
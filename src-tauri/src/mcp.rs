@@ -0,0 +1,470 @@
+//! Model Context Protocol server: the module registry's tools, served over
+//! stdio to other agents and editors (`sentinel --mcp`).
+//!
+//! Messages are JSON-RPC 2.0, one per line. `tools/list` publishes every
+//! tool the policy allows, with its JSON Schema and read-only/destructive
+//! hints. `tools/call` runs it through [`ModuleRegistry::execute`] and
+//! returns the tool's data as `structuredContent` (and as JSON text for
+//! clients that only read `content`).
+//!
+//! Calls that need confirmation are never run on the agent's say-so. The
+//! server asks the user through the client with `elicitation/create`, then
+//! confirms or cancels the proposal. A client without elicitation gets an
+//! error explaining that nothing ran. `dry_run` previews without asking only
+//! on tools that declare it; any other destructive call is refused.
+
+use crate::tools::{ModuleRegistry, RiskLevel, ToolResult};
+use serde_json::{json, Value};
+use std::io::{self, BufRead, Write};
+
+/// Protocol revisions this server speaks, newest first.
+const PROTOCOL_VERSIONS: [&str; 3] = ["2025-06-18", "2025-03-26", "2024-11-05"];
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Serve requests from `input` until it closes.
+pub fn serve(registry: &ModuleRegistry, input: impl BufRead, output: impl Write) -> io::Result<()> {
+    Session {
+        registry,
+        lines: input.lines(),
+        output,
+        can_elicit: false,
+        next_id: 1,
+    }
+    .run()
+}
+
+struct Session<'a, R, W> {
+    registry: &'a ModuleRegistry,
+    lines: io::Lines<R>,
+    output: W,
+    /// The client said in `initialize` that it can ask its user questions.
+    can_elicit: bool,
+    next_id: u64,
+}
+
+fn response(id: &Value, result: Value) -> Value {
+    json!({"jsonrpc": "2.0", "id": id, "result": result})
+}
+
+fn error(id: &Value, code: i64, message: impl Into<String>) -> Value {
+    json!({"jsonrpc": "2.0", "id": id, "error": {"code": code, "message": message.into()}})
+}
+
+/// A `tools/call` result: the data as structured content (which must be an
+/// object) and as text.
+fn call_result(data: Value, text: Option<String>, is_error: bool) -> Value {
+    let structured = match data {
+        Value::Object(_) => data,
+        Value::Null => json!({}),
+        other => json!({"result": other}),
+    };
+    let text = text.unwrap_or_else(|| structured.to_string());
+    json!({
+        "content": [{"type": "text", "text": text}],
+        "structuredContent": structured,
+        "isError": is_error,
+    })
+}
+
+fn tool_result(result: ToolResult) -> Value {
+    if result.success {
+        call_result(result.data, None, false)
+    } else {
+        let message = result.error.unwrap_or_else(|| "Tool failed".into());
+        call_result(json!({"error": message}), Some(message), true)
+    }
+}
+
+impl<R: BufRead, W: Write> Session<'_, R, W> {
+    fn send(&mut self, message: &Value) -> io::Result<()> {
+        writeln!(self.output, "{}", message)?;
+        self.output.flush()
+    }
+
+    /// Next message from the client, or `None` when it hangs up. Lines that
+    /// are not JSON are answered with a parse error.
+    fn receive(&mut self) -> io::Result<Option<Value>> {
+        while let Some(line) = self.lines.next() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line) {
+                Ok(message) => return Ok(Some(message)),
+                Err(e) => self.send(&error(&Value::Null, PARSE_ERROR, e.to_string()))?,
+            }
+        }
+        Ok(None)
+    }
+
+    fn run(mut self) -> io::Result<()> {
+        while let Some(message) = self.receive()? {
+            if let Some(reply) = self.handle(message)? {
+                self.send(&reply)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Answer one message. Notifications and stray responses get no reply.
+    fn handle(&mut self, message: Value) -> io::Result<Option<Value>> {
+        let Some(method) = message.get("method").and_then(|m| m.as_str()) else {
+            return Ok(None);
+        };
+        let Some(id) = message.get("id").cloned() else {
+            // Notifications: initialized, cancelled, ... need no answer.
+            return Ok(None);
+        };
+        let params = message.get("params").cloned().unwrap_or(json!({}));
+        let reply = match method {
+            "initialize" => response(&id, self.initialize(&params)),
+            "ping" => response(&id, json!({})),
+            "tools/list" => response(&id, json!({"tools": self.list_tools()})),
+            "tools/call" => match self.call_tool(&params)? {
+                Ok(result) => response(&id, result),
+                Err((code, message)) => error(&id, code, message),
+            },
+            _ => error(
+                &id,
+                METHOD_NOT_FOUND,
+                format!("Method not found: {}", method),
+            ),
+        };
+        Ok(Some(reply))
+    }
+
+    fn initialize(&mut self, params: &Value) -> Value {
+        self.can_elicit = params
+            .pointer("/capabilities/elicitation")
+            .is_some_and(|e| e.is_object());
+        let requested = params["protocolVersion"].as_str().unwrap_or_default();
+        let version = PROTOCOL_VERSIONS
+            .into_iter()
+            .find(|v| *v == requested)
+            .unwrap_or(PROTOCOL_VERSIONS[0]);
+        json!({
+            "protocolVersion": version,
+            "capabilities": {"tools": {"listChanged": false}},
+            "serverInfo": {"name": "sentinel", "version": env!("CARGO_PKG_VERSION")},
+            "instructions": "Mac and vehicle diagnostics. Destructive tools ask the user \
+                to confirm before they run. Tools with a dry_run parameter can preview \
+                what they would do without asking.",
+        })
+    }
+
+    fn list_tools(&self) -> Vec<Value> {
+        self.registry
+            .all_tools()
+            .into_iter()
+            .map(|tool| {
+                json!({
+                    "name": tool.name,
+                    "description": tool.description,
+                    "inputSchema": tool.parameters,
+                    "annotations": {
+                        "readOnlyHint": tool.risk == RiskLevel::ReadOnly,
+                        "destructiveHint": tool.risk == RiskLevel::Destructive,
+                    },
+                })
+            })
+            .collect()
+    }
+
+    fn call_tool(&mut self, params: &Value) -> io::Result<Result<Value, (i64, String)>> {
+        let Some(name) = params.get("name").and_then(|n| n.as_str()) else {
+            return Ok(Err((INVALID_PARAMS, "Missing tool name".into())));
+        };
+        if !self.registry.has_tool(name) {
+            return Ok(Err((INVALID_PARAMS, format!("Unknown tool: {}", name))));
+        }
+        let args = params.get("arguments").cloned().unwrap_or(json!({}));
+        let result = self.registry.execute(name, args);
+        if result.data["requires_confirmation"] != json!(true) {
+            return Ok(Ok(tool_result(result)));
+        }
+
+        let proposal = result.data["confirmation"].clone();
+        let token = proposal["token"].as_str().unwrap_or_default().to_string();
+        // The token stays with the server: the agent must not be able to
+        // confirm its own proposal.
+        let mut shown = proposal;
+        if let Some(fields) = shown.as_object_mut() {
+            fields.remove("token");
+        }
+        if !self.can_elicit {
+            self.registry.cancel(&token);
+            let previewable =
+                self.registry.all_tools().iter().any(|t| {
+                    t.name == name && t.parameters.pointer("/properties/dry_run").is_some()
+                });
+            let message = format!(
+                "{} needs the user's confirmation, which this client cannot ask for. \
+                 Nothing was run.{}",
+                name,
+                if previewable {
+                    " It supports \"dry_run\": true to preview instead."
+                } else {
+                    ""
+                }
+            );
+            let data = json!({"requires_confirmation": true, "confirmation": shown});
+            return Ok(Ok(call_result(data, Some(message), true)));
+        }
+        if self.ask_user(name, &shown)? {
+            Ok(Ok(tool_result(self.registry.confirm(&token))))
+        } else {
+            self.registry.cancel(&token);
+            let message = format!("The user declined to run {}. Nothing was run.", name);
+            let data = json!({"cancelled": true, "confirmation": shown});
+            Ok(Ok(call_result(data, Some(message), true)))
+        }
+    }
+
+    /// Ask the client to confirm a proposal with its user. Anything but an
+    /// explicit yes, including the client hanging up, is a no.
+    fn ask_user(&mut self, name: &str, proposal: &Value) -> io::Result<bool> {
+        let id = json!(format!("confirm-{}", self.next_id));
+        self.next_id += 1;
+        let mut message = format!(
+            "Run {}? {}",
+            name,
+            proposal["summary"].as_str().unwrap_or("")
+        );
+        if !proposal["preview"].is_null() {
+            message.push_str(&format!("\nPreview: {}", proposal["preview"]));
+        }
+        self.send(&json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "elicitation/create",
+            "params": {
+                "message": message,
+                "requestedSchema": {
+                    "type": "object",
+                    "properties": {
+                        "confirm": {"type": "boolean", "title": format!("Run {}", name)}
+                    },
+                    "required": ["confirm"],
+                },
+            },
+        }))?;
+        while let Some(message) = self.receive()? {
+            if message.get("id") == Some(&id) && message.get("method").is_none() {
+                return Ok(message.pointer("/result/action") == Some(&json!("accept"))
+                    && message.pointer("/result/content/confirm") == Some(&json!(true)));
+            }
+            // One call at a time: anything else that arrives while the user
+            // decides is answered as usual, except another tool call.
+            let reply = match message.get("method").and_then(|m| m.as_str()) {
+                Some("tools/call") => message.get("id").map(|id| {
+                    error(
+                        id,
+                        INVALID_REQUEST,
+                        "Waiting for the user to confirm another tool",
+                    )
+                }),
+                _ => self.handle(message)?,
+            };
+            if let Some(reply) = reply {
+                self.send(&reply)?;
+            }
+        }
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::{ToolDefinition, ToolModule};
+    use std::sync::Arc;
+
+    struct Shed;
+
+    impl ToolModule for Shed {
+        fn name(&self) -> &str {
+            "shed"
+        }
+
+        fn description(&self) -> &str {
+            "Test module"
+        }
+
+        fn tools(&self) -> Vec<ToolDefinition> {
+            vec![
+                ToolDefinition {
+                    name: "count_tools".into(),
+                    description: "Count the tools".into(),
+                    parameters: json!({"type": "object", "properties": {}}),
+                    risk: RiskLevel::ReadOnly,
+                },
+                ToolDefinition {
+                    name: "burn_shed".into(),
+                    description: "Burn the shed down".into(),
+                    parameters: json!({"type": "object", "properties": {}}),
+                    risk: RiskLevel::Destructive,
+                },
+                ToolDefinition {
+                    name: "broken".into(),
+                    description: "Always fails".into(),
+                    parameters: json!({"type": "object", "properties": {}}),
+                    risk: RiskLevel::ReadOnly,
+                },
+            ]
+        }
+
+        fn execute(&self, tool_name: &str, args: Value) -> ToolResult {
+            let data = match tool_name {
+                "count_tools" => json!({"count": 3}),
+                "burn_shed" => json!({"burned": true, "dry_run": args["dry_run"]}),
+                _ => {
+                    return ToolResult {
+                        success: false,
+                        data: Value::Null,
+                        error: Some("it broke".into()),
+                    }
+                }
+            };
+            ToolResult {
+                success: true,
+                data,
+                error: None,
+            }
+        }
+    }
+
+    fn registry() -> ModuleRegistry {
+        let mut registry = ModuleRegistry::new();
+        registry.register(Arc::new(Shed)).unwrap();
+        registry
+    }
+
+    /// Run a session over `messages` and return every line the server sent.
+    fn exchange(registry: &ModuleRegistry, messages: &[Value]) -> Vec<Value> {
+        let input: String = messages.iter().map(|m| format!("{}\n", m)).collect();
+        let mut output = Vec::new();
+        serve(registry, input.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect()
+    }
+
+    fn initialize(elicitation: bool) -> Value {
+        let capabilities = if elicitation {
+            json!({"elicitation": {}})
+        } else {
+            json!({})
+        };
+        json!({"jsonrpc": "2.0", "id": 0, "method": "initialize", "params": {
+            "protocolVersion": "2025-06-18",
+            "capabilities": capabilities,
+            "clientInfo": {"name": "test", "version": "1"}
+        }})
+    }
+
+    fn call(id: u64, name: &str, arguments: Value) -> Value {
+        json!({"jsonrpc": "2.0", "id": id, "method": "tools/call",
+               "params": {"name": name, "arguments": arguments}})
+    }
+
+    #[test]
+    fn test_list_and_call_tools() {
+        let replies = exchange(
+            &registry(),
+            &[
+                initialize(false),
+                json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+                json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list"}),
+                call(2, "count_tools", json!({})),
+                call(3, "broken", json!({})),
+                call(4, "missing", json!({})),
+                json!({"jsonrpc": "2.0", "id": 5, "method": "resources/list"}),
+            ],
+        );
+        assert_eq!(replies.len(), 6);
+        assert_eq!(replies[0]["result"]["protocolVersion"], "2025-06-18");
+        assert_eq!(replies[0]["result"]["serverInfo"]["name"], "sentinel");
+
+        let tools = replies[1]["result"]["tools"].as_array().unwrap();
+        assert_eq!(tools.len(), 3);
+        assert_eq!(tools[0]["inputSchema"]["type"], "object");
+        assert_eq!(tools[0]["annotations"]["readOnlyHint"], true);
+        assert_eq!(tools[1]["annotations"]["destructiveHint"], true);
+
+        assert_eq!(
+            replies[2]["result"]["structuredContent"],
+            json!({"count": 3})
+        );
+        assert_eq!(replies[2]["result"]["isError"], false);
+        assert_eq!(replies[3]["result"]["isError"], true);
+        assert_eq!(replies[3]["result"]["content"][0]["text"], "it broke");
+        assert_eq!(replies[4]["error"]["code"], INVALID_PARAMS);
+        assert_eq!(replies[5]["error"]["code"], METHOD_NOT_FOUND);
+    }
+
+    #[test]
+    fn test_destructive_tool_needs_user_confirmation() {
+        // Without elicitation nothing runs, and no token leaks to the agent.
+        let registry = registry();
+        let replies = exchange(
+            &registry,
+            &[
+                initialize(false),
+                call(1, "burn_shed", json!({})),
+                call(2, "burn_shed", json!({"dry_run": true})),
+            ],
+        );
+        let refused = &replies[1]["result"];
+        assert_eq!(refused["isError"], true);
+        assert_eq!(refused["structuredContent"]["requires_confirmation"], true);
+        assert!(refused["structuredContent"]["confirmation"]
+            .get("token")
+            .is_none());
+        // burn_shed has no dry_run parameter, so asking for one is no way
+        // around the user.
+        let refused = &replies[2]["result"];
+        assert_eq!(refused["isError"], true);
+        assert_eq!(refused["structuredContent"]["requires_confirmation"], true);
+        assert!(!refused["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("dry_run"));
+
+        // With elicitation the user decides; a ping while they think is
+        // still answered.
+        let accept = |id: &str, confirm: bool| {
+            json!({"jsonrpc": "2.0", "id": id,
+                   "result": {"action": "accept", "content": {"confirm": confirm}}})
+        };
+        let replies = exchange(
+            &registry,
+            &[
+                initialize(true),
+                call(1, "burn_shed", json!({})),
+                json!({"jsonrpc": "2.0", "id": 9, "method": "ping"}),
+                accept("confirm-1", true),
+                call(2, "burn_shed", json!({})),
+                accept("confirm-2", false),
+            ],
+        );
+        assert_eq!(replies[1]["method"], "elicitation/create");
+        assert!(replies[1]["params"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("Burn the shed down"));
+        assert_eq!(replies[2], response(&json!(9), json!({})));
+        assert_eq!(
+            replies[3]["result"]["structuredContent"],
+            json!({"burned": true, "dry_run": null})
+        );
+        assert_eq!(replies[4]["method"], "elicitation/create");
+        assert_eq!(replies[5]["result"]["isError"], true);
+        assert_eq!(replies[5]["result"]["structuredContent"]["cancelled"], true);
+    }
+}
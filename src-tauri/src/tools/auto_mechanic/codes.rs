@@ -0,0 +1,256 @@
+//! Trouble code management: pending and permanent codes (modes 07 and 0A),
+//! freeze frames (mode 02), and clearing codes (mode 04).
+
+use super::obd::{self, DtcKind};
+use super::source::VehicleSource;
+use super::{dtc, failure, ToolResult};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// Freeze frames probed per stored code; most ECUs only keep frame 0.
+const MAX_FREEZE_FRAMES: u8 = 8;
+
+/// Every code list plus the MIL, read in one go.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(super) struct CodeSnapshot {
+    pub mil_on: bool,
+    pub stored: Vec<String>,
+    pub pending: Vec<String>,
+    pub permanent: Vec<String>,
+}
+
+impl CodeSnapshot {
+    pub(super) fn read(source: &mut dyn VehicleSource) -> Result<Self, String> {
+        Ok(CodeSnapshot {
+            mil_on: source
+                .read_pid(obd::MONITOR_STATUS)?
+                .is_some_and(|data| obd::mil_on(&data)),
+            stored: source.read_dtcs(DtcKind::Stored)?,
+            pending: source.read_dtcs(DtcKind::Pending)?,
+            permanent: source.read_dtcs(DtcKind::Permanent)?,
+        })
+    }
+
+    /// Stored and pending codes, which a clear removes.
+    fn clearable(&self) -> Vec<String> {
+        let mut codes = self.stored.clone();
+        codes.extend(
+            self.pending
+                .iter()
+                .filter(|c| !self.stored.contains(c))
+                .cloned(),
+        );
+        codes
+    }
+}
+
+/// Codes with descriptions, using `make`'s enhanced table where it has one.
+fn explain(codes: &[String], make: Option<&str>) -> Vec<dtc::DtcInfo> {
+    codes.iter().filter_map(|c| dtc::lookup(c, make)).collect()
+}
+
+pub(super) fn read_dtcs(source: &mut dyn VehicleSource, make: Option<&str>) -> ToolResult {
+    match CodeSnapshot::read(source) {
+        Ok(snapshot) => {
+            let mut data = source.describe();
+            data["mil_on"] = json!(snapshot.mil_on);
+            data["stored"] = json!(explain(&snapshot.stored, make));
+            data["pending"] = json!(explain(&snapshot.pending, make));
+            data["permanent"] = json!(explain(&snapshot.permanent, make));
+            ToolResult {
+                success: true,
+                data,
+                error: None,
+            }
+        }
+        Err(e) => failure(e),
+    }
+}
+
+/// One freeze frame: the code that stored it and the conditions at the time.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(super) struct FreezeFrame {
+    pub frame: u8,
+    pub code: String,
+    pub description: Option<String>,
+    pub readings: Vec<obd::Reading>,
+}
+
+fn read_frame(
+    source: &mut dyn VehicleSource,
+    frame: u8,
+    make: Option<&str>,
+) -> Result<Option<FreezeFrame>, String> {
+    let code = match source.read_freeze_pid(frame, obd::FREEZE_DTC)? {
+        Some(d) if d.len() >= 2 && (d[0], d[1]) != (0, 0) => obd::decode_dtc(d[0], d[1]),
+        _ => return Ok(None),
+    };
+    let mut readings = Vec::new();
+    for &pid in obd::FREEZE_FRAME_PIDS {
+        if let Some(reading) = source
+            .read_freeze_pid(frame, pid)?
+            .and_then(|data| obd::decode_pid(pid, &data))
+        {
+            readings.push(reading);
+        }
+    }
+    Ok(Some(FreezeFrame {
+        frame,
+        description: dtc::lookup(&code, make).map(|info| info.description),
+        code,
+        readings,
+    }))
+}
+
+/// Freeze frames for the stored codes. Frames are numbered independently of
+/// codes, so one frame per stored code is probed and matched by the code it
+/// records.
+pub(super) fn read_freeze_frame(source: &mut dyn VehicleSource, make: Option<&str>) -> ToolResult {
+    let mut read = || -> Result<Value, String> {
+        let stored = source.read_dtcs(DtcKind::Stored)?;
+        let probes = (stored.len() as u8).clamp(1, MAX_FREEZE_FRAMES);
+        let mut frames = Vec::new();
+        for frame in 0..probes {
+            if let Some(ff) = read_frame(source, frame, make)? {
+                frames.push(ff);
+            }
+        }
+        let without: Vec<&String> = stored
+            .iter()
+            .filter(|code| !frames.iter().any(|f| f.code == **code))
+            .collect();
+        let mut data = source.describe();
+        data["stored"] = json!(stored);
+        data["freeze_frames"] = json!(frames);
+        data["codes_without_frame"] = json!(without);
+        Ok(data)
+    };
+    match read() {
+        Ok(data) => ToolResult {
+            success: true,
+            data,
+            error: None,
+        },
+        Err(e) => failure(e),
+    }
+}
+
+const CLEAR_NOTE: &str = "Clearing also erases freeze frames and resets readiness monitors, so \
+     the car will not pass an emissions inspection until it has been driven through a few \
+     drive cycles. Permanent codes clear themselves once the fault passes its self-test.";
+
+/// What a clear would remove, without clearing. Used as the confirmation
+/// preview and for `dry_run`.
+pub(super) fn preview_clear(
+    source: &mut dyn VehicleSource,
+    make: Option<&str>,
+) -> Result<Value, String> {
+    let before = CodeSnapshot::read(source)?;
+    Ok(json!({
+        "dry_run": true,
+        "would_clear": explain(&before.clearable(), make),
+        "permanent_stays": before.permanent,
+        "mil_on": before.mil_on,
+        "note": CLEAR_NOTE,
+    }))
+}
+
+/// Clear codes (mode 04) and report the codes before and after. Destructive:
+/// the registry only runs this after the user confirms.
+pub(super) fn clear_dtcs(
+    source: &mut dyn VehicleSource,
+    args: &Value,
+    make: Option<&str>,
+) -> ToolResult {
+    if args.get("dry_run").and_then(|v| v.as_bool()) == Some(true) {
+        return match preview_clear(source, make) {
+            Ok(data) => ToolResult {
+                success: true,
+                data,
+                error: None,
+            },
+            Err(e) => failure(e),
+        };
+    }
+    let mut run = || -> Result<Value, String> {
+        let before = CodeSnapshot::read(source)?;
+        source.clear_dtcs()?;
+        let after = CodeSnapshot::read(source)?;
+        let remaining = after.clearable();
+        let cleared: Vec<String> = before
+            .clearable()
+            .into_iter()
+            .filter(|c| !remaining.contains(c))
+            .collect();
+        let mut data = source.describe();
+        data["cleared"] = json!(cleared);
+        data["remaining"] = json!(remaining);
+        data["before"] = json!(before);
+        data["after"] = json!(after);
+        data["note"] = json!(CLEAR_NOTE);
+        Ok(data)
+    };
+    match run() {
+        Ok(data) => ToolResult {
+            success: true,
+            data,
+            error: None,
+        },
+        Err(e) => failure(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::source::DemoSource;
+    use super::*;
+
+    #[test]
+    fn test_read_dtcs_all_kinds() {
+        let result = read_dtcs(&mut DemoSource::default(), None);
+        assert!(result.success);
+        assert_eq!(result.data["mil_on"], true);
+        assert_eq!(result.data["stored"][0]["code"], "P0171");
+        assert_eq!(result.data["pending"][0]["code"], "P0300");
+        assert_eq!(result.data["permanent"][0]["code"], "P0420");
+    }
+
+    #[test]
+    fn test_freeze_frame_matches_stored_code() {
+        let result = read_freeze_frame(&mut DemoSource::default(), None);
+        assert!(result.success);
+        let frame = &result.data["freeze_frames"][0];
+        assert_eq!(frame["code"], "P0171");
+        assert_eq!(frame["description"], "System Too Lean (Bank 1)");
+        let rpm = frame["readings"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|r| r["key"] == "rpm")
+            .unwrap();
+        assert_eq!(rpm["value"], 2200.0);
+        assert_eq!(result.data["codes_without_frame"], json!(["P0420"]));
+    }
+
+    #[test]
+    fn test_clear_reports_before_and_after() {
+        let mut source = DemoSource::default();
+        let preview = clear_dtcs(&mut source, &json!({"dry_run": true}), None);
+        assert_eq!(preview.data["would_clear"].as_array().unwrap().len(), 3);
+        assert_eq!(preview.data["permanent_stays"], json!(["P0420"]));
+        // A dry run changes nothing.
+        assert_eq!(CodeSnapshot::read(&mut source).unwrap().stored.len(), 2);
+
+        let result = clear_dtcs(&mut source, &json!({}), None);
+        assert!(result.success);
+        assert_eq!(result.data["cleared"], json!(["P0171", "P0420", "P0300"]));
+        assert_eq!(result.data["remaining"], json!([]));
+        assert_eq!(result.data["before"]["mil_on"], true);
+        assert_eq!(result.data["after"]["mil_on"], false);
+        assert_eq!(result.data["after"]["permanent"], json!(["P0420"]));
+        assert!(read_freeze_frame(&mut source, None).data["freeze_frames"]
+            .as_array()
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,195 @@
+//! Vehicle data source configuration (`vehicle.json`).
+
+use super::elm327::Elm327;
+use super::source::{DemoSource, Elm327Source, VehicleSource};
+use super::telemetry::{ReplaySource, TelemetryOptions};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceKind {
+    /// Canned data, no adapter needed.
+    #[default]
+    Demo,
+    /// USB or Bluetooth ELM327 on a serial device.
+    Serial,
+    /// Wi-Fi ELM327 dongle.
+    Tcp,
+    /// A recorded telemetry session (`.jsonl`), played back in real time.
+    Replay,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SerialConfig {
+    #[serde(default)]
+    pub path: String,
+    #[serde(default = "default_baud")]
+    pub baud: u32,
+}
+
+impl Default for SerialConfig {
+    fn default() -> Self {
+        SerialConfig {
+            path: String::new(),
+            baud: default_baud(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TcpConfig {
+    #[serde(default = "default_host")]
+    pub host: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+}
+
+impl Default for TcpConfig {
+    fn default() -> Self {
+        TcpConfig {
+            host: default_host(),
+            port: default_port(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReplayConfig {
+    #[serde(default)]
+    pub path: PathBuf,
+}
+
+fn default_baud() -> u32 {
+    38400
+}
+
+/// Address most Wi-Fi ELM327 clones use.
+fn default_host() -> String {
+    "192.168.0.10".into()
+}
+
+fn default_port() -> u16 {
+    35000
+}
+
+fn default_timeout_ms() -> u64 {
+    5000
+}
+
+/// Where vehicle data comes from, loaded from `vehicle.json`:
+///
+/// ```json
+/// {
+///   "source": "serial",
+///   "serial": { "path": "/dev/tty.usbserial-0001", "baud": 38400 }
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VehicleConfig {
+    #[serde(default)]
+    pub source: SourceKind,
+    #[serde(default)]
+    pub serial: SerialConfig,
+    #[serde(default)]
+    pub tcp: TcpConfig,
+    #[serde(default)]
+    pub replay: ReplayConfig,
+    /// Default PIDs and rate for live telemetry.
+    #[serde(default)]
+    pub telemetry: TelemetryOptions,
+    /// Per-command adapter timeout.
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+impl Default for VehicleConfig {
+    fn default() -> Self {
+        VehicleConfig {
+            source: SourceKind::default(),
+            serial: SerialConfig::default(),
+            tcp: TcpConfig::default(),
+            replay: ReplayConfig::default(),
+            telemetry: TelemetryOptions::default(),
+            timeout_ms: default_timeout_ms(),
+        }
+    }
+}
+
+impl VehicleConfig {
+    /// Load a config file. A missing file yields the demo source.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let raw = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read vehicle config {}: {}", path.display(), e))?;
+        serde_json::from_str(&raw)
+            .map_err(|e| format!("Invalid vehicle config {}: {}", path.display(), e))
+    }
+
+    /// Build the configured source. Adapters connect lazily on first use,
+    /// so this only fails on an incomplete config or an unreadable recording.
+    pub(super) fn open_source(&self) -> Result<Box<dyn VehicleSource>, String> {
+        let timeout = Duration::from_millis(self.timeout_ms.max(100));
+        match self.source {
+            SourceKind::Demo => Ok(Box::new(DemoSource::default())),
+            SourceKind::Serial => {
+                if self.serial.path.is_empty() {
+                    return Err("Vehicle config: serial source needs serial.path".into());
+                }
+                let serial = self.serial.clone();
+                Ok(Box::new(Elm327Source::new("serial", move || {
+                    Elm327::connect_serial(&serial.path, serial.baud, timeout)
+                })))
+            }
+            SourceKind::Tcp => {
+                let tcp = self.tcp.clone();
+                Ok(Box::new(Elm327Source::new("tcp", move || {
+                    Elm327::connect_tcp(&tcp.host, tcp.port, timeout)
+                })))
+            }
+            SourceKind::Replay => {
+                if self.replay.path.as_os_str().is_empty() {
+                    return Err("Vehicle config: replay source needs replay.path".into());
+                }
+                Ok(Box::new(ReplaySource::open(&self.replay.path)?))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_load_config() {
+        let missing = VehicleConfig::load(Path::new("/nonexistent/vehicle.json")).unwrap();
+        assert_eq!(missing.source, SourceKind::Demo);
+        assert_eq!(missing.open_source().unwrap().kind(), "demo");
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        write!(file, r#"{{"source": "tcp", "tcp": {{"port": 35001}}}}"#).unwrap();
+        let config = VehicleConfig::load(file.path()).unwrap();
+        assert_eq!(config.source, SourceKind::Tcp);
+        assert_eq!(config.tcp.host, "192.168.0.10");
+        assert_eq!(config.tcp.port, 35001);
+        assert_eq!(config.timeout_ms, 5000);
+        assert_eq!(config.open_source().unwrap().kind(), "tcp");
+
+        let serial: VehicleConfig = serde_json::from_str(r#"{"source": "serial"}"#).unwrap();
+        assert!(serial.open_source().is_err());
+        let replay: VehicleConfig = serde_json::from_str(
+            r#"{"source": "replay", "replay": {"path": "/nonexistent/drive.jsonl"}}"#,
+        )
+        .unwrap();
+        assert!(replay
+            .open_source()
+            .is_err_and(|e| e.contains("Cannot open recording")));
+        assert_eq!(config.telemetry.interval_ms, None);
+        assert!(serde_json::from_str::<VehicleConfig>(r#"{"source": "can"}"#).is_err());
+    }
+}
@@ -0,0 +1,435 @@
+//! Diagnostic trouble code knowledge base.
+//!
+//! Descriptions, likely causes, severity and driving guidance for common
+//! generic (SAE J2012) codes ship with the crate in `data/dtc_generic.json`;
+//! `data/dtc_manufacturer.json` adds manufacturer-specific codes per make.
+//! Coverage is widest for powertrain (P) codes. Body (B), chassis (C) and
+//! network (U) entries cover the common airbag, ABS/stability and
+//! lost-communication codes rather than the full J2012 list, and only a
+//! handful of makes have manufacturer codes. Codes missing from both are
+//! still decoded structurally: system, generic vs. manufacturer range, and
+//! the powertrain subsystem.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+const GENERIC_TABLE: &str = include_str!("data/dtc_generic.json");
+const MANUFACTURER_TABLE: &str = include_str!("data/dtc_manufacturer.json");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(super) enum Severity {
+    Unknown,
+    Low,
+    Moderate,
+    High,
+    Critical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(super) enum SafeToDrive {
+    Yes,
+    Caution,
+    No,
+}
+
+impl Severity {
+    fn default_guidance(self) -> (SafeToDrive, &'static str) {
+        match self {
+            Severity::Low => (
+                SafeToDrive::Yes,
+                "Safe to drive. Have it checked at your next service.",
+            ),
+            Severity::Moderate | Severity::Unknown => (
+                SafeToDrive::Caution,
+                "Drivable, but have it diagnosed soon; left alone it can cause further damage \
+                 or poor fuel economy.",
+            ),
+            Severity::High => (
+                SafeToDrive::Caution,
+                "Drive only as far as a repair shop and avoid hard acceleration.",
+            ),
+            Severity::Critical => (
+                SafeToDrive::No,
+                "Stop driving as soon as it is safe and have the vehicle towed.",
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Entry {
+    description: String,
+    #[serde(default)]
+    causes: Vec<String>,
+    severity: Severity,
+    #[serde(default)]
+    safe_to_drive: Option<SafeToDrive>,
+    #[serde(default)]
+    guidance: Option<String>,
+}
+
+/// A run of numbered codes sharing one entry, such as the per-cylinder
+/// misfire codes; `{n}` in the text is the position within the run.
+#[derive(Debug, Clone, Deserialize)]
+struct Range {
+    from: String,
+    to: String,
+    #[serde(flatten)]
+    entry: Entry,
+}
+
+#[derive(Debug, Deserialize)]
+struct GenericTable {
+    codes: HashMap<String, Entry>,
+    #[serde(default)]
+    ranges: Vec<Range>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MakeTable {
+    makes: Vec<String>,
+    codes: HashMap<String, Entry>,
+}
+
+struct KnowledgeBase {
+    generic: GenericTable,
+    manufacturers: Vec<(String, MakeTable)>,
+}
+
+fn knowledge_base() -> &'static KnowledgeBase {
+    static KB: OnceLock<KnowledgeBase> = OnceLock::new();
+    KB.get_or_init(|| {
+        let mut manufacturers: Vec<(String, MakeTable)> =
+            serde_json::from_str::<HashMap<String, MakeTable>>(MANUFACTURER_TABLE)
+                .expect("bundled manufacturer DTC table is valid")
+                .into_iter()
+                .collect();
+        manufacturers.sort_by(|a, b| a.0.cmp(&b.0));
+        KnowledgeBase {
+            generic: serde_json::from_str(GENERIC_TABLE).expect("bundled DTC table is valid"),
+            manufacturers,
+        }
+    })
+}
+
+/// A manufacturer's meaning for a code when the make is not known.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(super) struct Candidate {
+    pub manufacturer: String,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(super) struct DtcInfo {
+    pub code: String,
+    /// Powertrain, Body, Chassis or Network.
+    pub system: &'static str,
+    /// "generic" (SAE-defined) or "manufacturer".
+    pub scope: &'static str,
+    /// Powertrain subsystem from the third character, when defined.
+    pub area: Option<&'static str>,
+    /// Whether the description came from a table rather than the code's
+    /// structure.
+    pub known: bool,
+    pub description: String,
+    pub causes: Vec<String>,
+    pub severity: Severity,
+    pub safe_to_drive: SafeToDrive,
+    pub guidance: String,
+    /// "generic", a manufacturer table name, or "structure".
+    pub source: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub candidates: Vec<Candidate>,
+}
+
+/// Upper-case a code and check its shape: a system letter, a digit 0-3 and
+/// three hex digits ("P0300", "u0100").
+pub(super) fn normalize(code: &str) -> Option<String> {
+    let code = code.trim().to_uppercase();
+    let bytes = code.as_bytes();
+    let valid = bytes.len() == 5
+        && matches!(bytes[0], b'P' | b'C' | b'B' | b'U')
+        && (b'0'..=b'3').contains(&bytes[1])
+        && bytes[2..].iter().all(|b| b.is_ascii_hexdigit());
+    valid.then_some(code)
+}
+
+/// Every distinct code mentioned in free text, in order.
+pub(super) fn find_codes(text: &str) -> Vec<String> {
+    let mut codes: Vec<String> = Vec::new();
+    for word in text.split(|c: char| !c.is_ascii_alphanumeric()) {
+        if let Some(code) = normalize(word) {
+            if !codes.contains(&code) {
+                codes.push(code);
+            }
+        }
+    }
+    codes
+}
+
+fn system(code: &str) -> &'static str {
+    match code.as_bytes()[0] {
+        b'P' => "Powertrain",
+        b'B' => "Body",
+        b'C' => "Chassis",
+        _ => "Network",
+    }
+}
+
+/// SAE J2012 assigns P0, P2, P34-P39 and B0/C0/U0/B3/C3/U3 to generic codes;
+/// the rest belong to manufacturers.
+fn is_manufacturer_specific(code: &str) -> bool {
+    let b = code.as_bytes();
+    match (b[0], b[1]) {
+        (b'P', b'1') => true,
+        (b'P', b'3') => b[2] <= b'3',
+        (b'P', _) => false,
+        (_, b'1') | (_, b'2') => true,
+        _ => false,
+    }
+}
+
+fn powertrain_area(code: &str) -> Option<&'static str> {
+    if !code.starts_with('P') {
+        return None;
+    }
+    Some(match code.as_bytes()[2] {
+        b'0' => "Fuel and air metering and auxiliary emission controls",
+        b'1' | b'2' => "Fuel and air metering",
+        b'3' => "Ignition system or misfire",
+        b'4' => "Auxiliary emission controls",
+        b'5' => "Vehicle speed, idle control and auxiliary inputs",
+        b'6' => "Computer and output circuits",
+        b'7' | b'8' | b'9' => "Transmission",
+        b'A' | b'B' | b'C' => "Hybrid propulsion",
+        _ => return None,
+    })
+}
+
+/// Position of `code` within a numbered range, for `{n}` substitution.
+fn range_position(range: &Range, code: &str) -> Option<u32> {
+    let number = |c: &str| -> Option<u32> {
+        (c.len() == 5 && c.as_bytes()[0] == code.as_bytes()[0])
+            .then(|| c[1..].parse().ok())
+            .flatten()
+    };
+    let (from, to, n) = (number(&range.from)?, number(&range.to)?, number(code)?);
+    (from..=to).contains(&n).then(|| n - from + 1)
+}
+
+fn make_table<'a>(kb: &'a KnowledgeBase, make: &str) -> Option<&'a (String, MakeTable)> {
+    let make = make.trim().to_lowercase();
+    kb.manufacturers
+        .iter()
+        .find(|(name, table)| *name == make || table.makes.contains(&make))
+}
+
+fn info(code: &str, entry: &Entry, source: &str, n: Option<u32>) -> DtcInfo {
+    let fill = |s: &str| match n {
+        Some(n) => s.replace("{n}", &n.to_string()),
+        None => s.to_string(),
+    };
+    let (safe, guidance) = entry.severity.default_guidance();
+    DtcInfo {
+        code: code.to_string(),
+        system: system(code),
+        scope: if is_manufacturer_specific(code) {
+            "manufacturer"
+        } else {
+            "generic"
+        },
+        area: powertrain_area(code),
+        known: true,
+        description: fill(&entry.description),
+        causes: entry.causes.iter().map(|c| fill(c)).collect(),
+        severity: entry.severity,
+        safe_to_drive: entry.safe_to_drive.unwrap_or(safe),
+        guidance: entry
+            .guidance
+            .clone()
+            .unwrap_or_else(|| guidance.to_string()),
+        source: source.to_string(),
+        candidates: Vec::new(),
+    }
+}
+
+/// Explain a code. Manufacturer-specific codes need the vehicle's make to
+/// pick a table; without one, every bundled meaning is listed as a
+/// candidate. Returns `None` for strings that are not codes.
+pub(super) fn lookup(code: &str, make: Option<&str>) -> Option<DtcInfo> {
+    let code = normalize(code)?;
+    let kb = knowledge_base();
+
+    if is_manufacturer_specific(&code) {
+        if let Some((name, table)) = make.and_then(|m| make_table(kb, m)) {
+            if let Some(entry) = table.codes.get(&code) {
+                return Some(info(&code, entry, name, None));
+            }
+        }
+    } else {
+        if let Some(entry) = kb.generic.codes.get(&code) {
+            return Some(info(&code, entry, "generic", None));
+        }
+        for range in &kb.generic.ranges {
+            if let Some(n) = range_position(range, &code) {
+                return Some(info(&code, &range.entry, "generic", Some(n)));
+            }
+        }
+    }
+
+    let manufacturer = is_manufacturer_specific(&code);
+    let area = powertrain_area(&code);
+    let description = format!(
+        "{} {} code{}",
+        if manufacturer {
+            "Manufacturer-specific"
+        } else {
+            "Generic"
+        },
+        system(&code).to_lowercase(),
+        area.map(|a| format!(" ({})", a.to_lowercase()))
+            .unwrap_or_default()
+    );
+    let candidates = if manufacturer && make.is_none() {
+        kb.manufacturers
+            .iter()
+            .filter_map(|(name, table)| {
+                table.codes.get(&code).map(|entry| Candidate {
+                    manufacturer: name.clone(),
+                    description: entry.description.clone(),
+                })
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let guidance = match (manufacturer, make) {
+        (true, Some(make)) => format!(
+            "No bundled description for this code on a {}; a make-specific scan tool or the \
+             service manual has its exact meaning.",
+            make.trim()
+        ),
+        (true, None) => "Its meaning depends on the manufacturer; give the vehicle's make to \
+                         narrow it down."
+            .to_string(),
+        (false, _) => "Not in the bundled table; have it looked up with a full scan tool before \
+                       driving far."
+            .to_string(),
+    };
+    Some(DtcInfo {
+        code: code.clone(),
+        system: system(&code),
+        scope: if manufacturer {
+            "manufacturer"
+        } else {
+            "generic"
+        },
+        area,
+        known: false,
+        description,
+        causes: Vec::new(),
+        severity: Severity::Unknown,
+        safe_to_drive: SafeToDrive::Caution,
+        guidance,
+        source: "structure".into(),
+        candidates,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_tables_parse_and_are_valid() {
+        let kb = knowledge_base();
+        assert!(kb.generic.codes.len() > 50);
+        for code in kb.generic.codes.keys() {
+            assert_eq!(normalize(code).as_deref(), Some(code.as_str()), "{}", code);
+            assert!(!is_manufacturer_specific(code), "{} is not generic", code);
+        }
+        for (name, table) in &kb.manufacturers {
+            for code in table.codes.keys() {
+                assert!(
+                    is_manufacturer_specific(code),
+                    "{} in {} is generic",
+                    code,
+                    name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_normalize_and_find_codes() {
+        assert_eq!(normalize(" p0300 ").as_deref(), Some("P0300"));
+        assert_eq!(normalize("U0100").as_deref(), Some("U0100"));
+        assert!(normalize("P4300").is_none());
+        assert!(normalize("X0300").is_none());
+        assert!(normalize("P030").is_none());
+        assert_eq!(
+            find_codes("what do P0171, p0420 and P0171 mean?"),
+            vec!["P0171", "P0420"]
+        );
+        assert!(find_codes("check the engine").is_empty());
+    }
+
+    #[test]
+    fn test_lookup_generic_and_ranges() {
+        let misfire = lookup("P0300", None).unwrap();
+        assert!(misfire.known);
+        assert_eq!(misfire.severity, Severity::High);
+        assert_eq!(misfire.area, Some("Ignition system or misfire"));
+        assert!(misfire.guidance.contains("flashing"));
+
+        let cyl = lookup("p0304", None).unwrap();
+        assert_eq!(cyl.description, "Cylinder 4 Misfire Detected");
+        assert!(cyl.causes[0].contains("cylinder 4"));
+
+        let cap = lookup("P0457", None).unwrap();
+        assert_eq!(cap.safe_to_drive, SafeToDrive::Yes);
+        assert_eq!(
+            lookup("P0524", None).unwrap().safe_to_drive,
+            SafeToDrive::No
+        );
+
+        let airbag = lookup("B0020", None).unwrap();
+        assert!(airbag.known);
+        assert_eq!(airbag.severity, Severity::High);
+        assert!(lookup("C0196", None).unwrap().known);
+        assert!(lookup("U0151", None).unwrap().known);
+
+        let unlisted = lookup("P0999", None).unwrap();
+        assert!(!unlisted.known);
+        assert_eq!(unlisted.scope, "generic");
+        assert_eq!(
+            unlisted.description,
+            "Generic powertrain code (transmission)"
+        );
+        assert!(lookup("hello", None).is_none());
+    }
+
+    #[test]
+    fn test_lookup_manufacturer_codes() {
+        let ford = lookup("P1000", Some("Lincoln")).unwrap();
+        assert!(ford.known);
+        assert_eq!(ford.source, "ford");
+        assert_eq!(ford.scope, "manufacturer");
+
+        let no_make = lookup("P1349", None).unwrap();
+        assert!(!no_make.known);
+        assert_eq!(no_make.candidates.len(), 1);
+        assert_eq!(no_make.candidates[0].manufacturer, "toyota");
+
+        let wrong_make = lookup("P1349", Some("Ford")).unwrap();
+        assert!(!wrong_make.known);
+        assert!(wrong_make.candidates.is_empty());
+        assert!(wrong_make.guidance.contains("Ford"));
+
+        assert_eq!(lookup("B1234", None).unwrap().scope, "manufacturer");
+        assert_eq!(lookup("P3400", None).unwrap().scope, "generic");
+    }
+}
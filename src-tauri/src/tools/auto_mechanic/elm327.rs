@@ -0,0 +1,356 @@
+//! ELM327 AT-command client.
+//!
+//! The adapter speaks a line protocol: every command is terminated by `\r`
+//! and every reply ends with a `>` prompt. The client works over any
+//! byte stream, so the same code drives a USB/Bluetooth serial port, a
+//! Wi-Fi dongle's TCP socket, or a simulated adapter in tests.
+
+use super::obd;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+/// Sent once after connecting: reset, echo off, linefeeds off, spaces off,
+/// headers off, automatic protocol.
+const INIT_COMMANDS: &[&str] = &["ATZ", "ATE0", "ATL0", "ATS0", "ATH0", "ATSP0"];
+/// The reset banner can take a while on clones.
+const RESET_TIMEOUT: Duration = Duration::from_secs(5);
+/// Replies that mean the vehicle did not answer at all.
+const BUS_ERRORS: &[&str] = &[
+    "UNABLE TO CONNECT",
+    "BUS INIT: ...ERROR",
+    "BUS ERROR",
+    "CAN ERROR",
+    "FB ERROR",
+    "DATA ERROR",
+    "BUFFER FULL",
+    "STOPPED",
+    "ERROR",
+];
+
+/// Anything the client can talk through.
+pub(super) trait Transport: Read + Write + Send {}
+impl<T: Read + Write + Send> Transport for T {}
+
+pub(super) struct Elm327 {
+    io: Box<dyn Transport>,
+    timeout: Duration,
+    /// Banner returned by `ATZ`, e.g. "ELM327 v1.5".
+    pub version: Option<String>,
+    /// Protocol the adapter settled on (`ATDP`), known after [`initialize`](Self::initialize).
+    pub protocol: Option<String>,
+}
+
+impl Elm327 {
+    pub(super) fn new(io: Box<dyn Transport>, timeout: Duration) -> Self {
+        Elm327 {
+            io,
+            timeout,
+            version: None,
+            protocol: None,
+        }
+    }
+
+    pub(super) fn connect_tcp(host: &str, port: u16, timeout: Duration) -> Result<Self, String> {
+        let addr = (host, port)
+            .to_socket_addrs()
+            .map_err(|e| format!("Cannot resolve {}:{}: {}", host, port, e))?
+            .next()
+            .ok_or_else(|| format!("Cannot resolve {}:{}", host, port))?;
+        let stream = TcpStream::connect_timeout(&addr, timeout)
+            .map_err(|e| format!("Cannot connect to ELM327 at {}: {}", addr, e))?;
+        stream
+            .set_read_timeout(Some(timeout))
+            .map_err(|e| e.to_string())?;
+        let _ = stream.set_nodelay(true);
+        Ok(Self::new(Box::new(stream), timeout))
+    }
+
+    pub(super) fn connect_serial(path: &str, baud: u32, timeout: Duration) -> Result<Self, String> {
+        let port = serialport::new(path, baud)
+            .timeout(timeout)
+            .open()
+            .map_err(|e| format!("Cannot open serial port {}: {}", path, e))?;
+        Ok(Self::new(Box::new(port), timeout))
+    }
+
+    /// Reset and configure the adapter, then make the first request so the
+    /// adapter searches for the vehicle's protocol.
+    pub(super) fn initialize(&mut self) -> Result<(), String> {
+        for cmd in INIT_COMMANDS {
+            let timeout = if *cmd == "ATZ" {
+                RESET_TIMEOUT
+            } else {
+                self.timeout
+            };
+            let reply = self.exchange(cmd, timeout)?;
+            if *cmd == "ATZ" {
+                self.version = reply.iter().find(|l| l.starts_with("ELM")).cloned();
+                if self.version.is_none() {
+                    return Err(format!(
+                        "No ELM327 banner after reset (got {:?})",
+                        reply.join(" ")
+                    ));
+                }
+            } else if !reply.iter().any(|l| l == "OK") {
+                return Err(format!("Adapter rejected {}: {}", cmd, reply.join(" ")));
+            }
+        }
+        // Supported-PIDs query: the first one after ATSP0 triggers the search.
+        self.query(&[0x01, 0x00])?;
+        self.protocol = self.exchange("ATDP", self.timeout)?.into_iter().next();
+        Ok(())
+    }
+
+    /// Send an OBD request (mode byte, then any PID and frame bytes) and
+    /// return one byte frame per responding ECU. "NO DATA" (the vehicle does
+    /// not support it) is an empty result.
+    pub(super) fn query(&mut self, request: &[u8]) -> Result<Vec<Vec<u8>>, String> {
+        let cmd: String = request.iter().map(|b| format!("{:02X}", b)).collect();
+        let reply = self.exchange(&cmd, self.timeout)?;
+        if reply.iter().any(|l| l == "NO DATA") {
+            return Ok(Vec::new());
+        }
+        if let Some(err) = reply
+            .iter()
+            .find(|l| BUS_ERRORS.iter().any(|e| l.contains(e)) || l.as_str() == "?")
+        {
+            return Err(format!("ELM327 {} failed: {}", cmd, err));
+        }
+        Ok(obd::parse_frames(&reply))
+    }
+
+    /// Send one command and collect the reply lines up to the prompt, without
+    /// the echo and progress messages.
+    fn exchange(&mut self, cmd: &str, timeout: Duration) -> Result<Vec<String>, String> {
+        self.io
+            .write_all(format!("{}\r", cmd).as_bytes())
+            .and_then(|_| self.io.flush())
+            .map_err(|e| format!("ELM327 write failed: {}", e))?;
+        let raw = self.read_until_prompt(timeout)?;
+        Ok(raw
+            .split(['\r', '\n'])
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty() && *l != cmd && *l != "SEARCHING...")
+            .map(String::from)
+            .collect())
+    }
+
+    fn read_until_prompt(&mut self, timeout: Duration) -> Result<String, String> {
+        let deadline = Instant::now() + timeout;
+        let mut out = Vec::new();
+        let mut buf = [0u8; 256];
+        loop {
+            match self.io.read(&mut buf) {
+                Ok(0) => return Err("ELM327 connection closed".into()),
+                Ok(n) => {
+                    out.extend_from_slice(&buf[..n]);
+                    if let Some(end) = out.iter().position(|b| *b == b'>') {
+                        out.truncate(end);
+                        return Ok(String::from_utf8_lossy(&out).into_owned());
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+                Err(e) => return Err(format!("ELM327 read failed: {}", e)),
+            }
+            if Instant::now() >= deadline {
+                return Err(format!(
+                    "Timed out waiting for ELM327 prompt after {:?}",
+                    timeout
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub(super) mod sim {
+    //! A simulated ELM327 for tests: answers AT commands like the real chip
+    //! and OBD requests from a table.
+
+    use std::collections::HashMap;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    pub struct SimElm {
+        pub echo: bool,
+        pub responses: HashMap<String, String>,
+    }
+
+    impl SimElm {
+        /// An engine idling at 850 rpm with P0171 and P0420 stored (a freeze
+        /// frame for P0171), P0300 pending and P0420 permanent, and a VIN.
+        pub fn car() -> Self {
+            let responses = [
+                ("0100", "41 00 BE 3F B8 11"),
+                ("0101", "41 01 82 07 65 00"),
+                ("0104", "41 04 40"),
+                ("0105", "41 05 7B"),
+                ("0106", "41 06 85"),
+                ("0107", "41 07 8A"),
+                ("0108", "NO DATA"),
+                ("0109", "NO DATA"),
+                ("010C", "41 0C 0D 48"),
+                ("010D", "41 0D 00"),
+                ("0142", "41 42 38 A4"),
+                ("03", "43 02 01 71 04 20"),
+                ("07", "47 01 03 00"),
+                ("0A", "4A 01 04 20"),
+                ("020200", "42 02 00 01 71"),
+                ("020400", "42 04 00 59"),
+                ("020500", "42 05 00 80"),
+                ("020C00", "42 0C 00 22 60"),
+                ("020D00", "42 0D 00 48"),
+                (
+                    "0902",
+                    "014\r0: 49 02 01 31 48 47\r1: 43 56 31 46 33 37 4C\r2: 41 30 31 32 33 34 35",
+                ),
+            ];
+            SimElm {
+                echo: true,
+                responses: responses
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+            }
+        }
+
+        fn reply(&mut self, cmd: &str) -> String {
+            let cmd = cmd.trim().to_uppercase().replace(' ', "");
+            match cmd.as_str() {
+                "ATZ" => {
+                    self.echo = true;
+                    "\r\rELM327 v1.5".into()
+                }
+                "ATE0" => {
+                    self.echo = false;
+                    "OK".into()
+                }
+                "ATDP" => "AUTO, ISO 15765-4 (CAN 11/500)".into(),
+                // Clearing drops stored and pending codes and freeze frames
+                // and turns the MIL off; permanent codes stay.
+                "04" => {
+                    self.responses.retain(|k, _| !k.starts_with("02"));
+                    self.responses.insert("03".into(), "43 00".into());
+                    self.responses.insert("07".into(), "47 00".into());
+                    self.responses
+                        .insert("0101".into(), "41 01 00 07 65 00".into());
+                    "44".into()
+                }
+                c if c.starts_with("AT") => "OK".into(),
+                c => match self.responses.get(c) {
+                    Some(r) if c == "0100" => format!("SEARCHING...\r{}", r),
+                    Some(r) => r.clone(),
+                    None => "NO DATA".into(),
+                },
+            }
+        }
+
+        /// Serve commands on a stream until it closes.
+        pub fn serve(mut self, io: impl Read + Write) {
+            let mut reader = BufReader::new(io);
+            let mut cmd = Vec::new();
+            loop {
+                cmd.clear();
+                match reader.read_until(b'\r', &mut cmd) {
+                    Ok(0) | Err(_) => return,
+                    Ok(_) => {}
+                }
+                let text = String::from_utf8_lossy(&cmd).trim().to_string();
+                let mut out = String::new();
+                if self.echo {
+                    out.push_str(&text);
+                    out.push('\r');
+                }
+                out.push_str(&self.reply(&text));
+                out.push_str("\r\r>");
+                if reader.get_mut().write_all(out.as_bytes()).is_err() {
+                    return;
+                }
+                let _ = reader.get_mut().flush();
+            }
+        }
+
+        /// Listen on a local TCP port and serve each connection in turn.
+        /// Returns the port.
+        pub fn spawn_tcp(make: fn() -> SimElm) -> u16 {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let port = listener.local_addr().unwrap().port();
+            std::thread::spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    make().serve(stream);
+                }
+            });
+            port
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::sim::SimElm;
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(2);
+
+    #[test]
+    fn test_initialize_and_query_over_tcp() {
+        let port = SimElm::spawn_tcp(SimElm::car);
+        let mut elm = Elm327::connect_tcp("127.0.0.1", port, TIMEOUT).unwrap();
+        elm.initialize().unwrap();
+        assert_eq!(elm.version.as_deref(), Some("ELM327 v1.5"));
+        assert_eq!(
+            elm.protocol.as_deref(),
+            Some("AUTO, ISO 15765-4 (CAN 11/500)")
+        );
+
+        let frames = elm.query(&[0x01, obd::RPM]).unwrap();
+        assert_eq!(obd::pid_payload(&frames, obd::RPM), Some(&[0x0D, 0x48][..]));
+        assert!(elm.query(&[0x01, obd::STFT_BANK2]).unwrap().is_empty());
+        let dtcs = elm.query(&[0x03]).unwrap();
+        assert_eq!(obd::parse_dtcs(&dtcs, 0x43), vec!["P0171", "P0420"]);
+    }
+
+    #[test]
+    fn test_bus_error_and_timeout() {
+        fn dead_bus() -> SimElm {
+            let mut sim = SimElm::car();
+            sim.responses
+                .insert("0100".into(), "UNABLE TO CONNECT".into());
+            sim
+        }
+        let port = SimElm::spawn_tcp(dead_bus);
+        let mut elm = Elm327::connect_tcp("127.0.0.1", port, TIMEOUT).unwrap();
+        let err = elm.initialize().unwrap_err();
+        assert!(err.contains("UNABLE TO CONNECT"), "{}", err);
+
+        // A listener that accepts but never answers.
+        let silent = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = silent.local_addr().unwrap().port();
+        let mut elm = Elm327::connect_tcp("127.0.0.1", port, Duration::from_millis(200)).unwrap();
+        let err = elm.query(&[0x01, obd::RPM]).unwrap_err();
+        assert!(err.contains("Timed out"), "{}", err);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_initialize_over_pty() {
+        use serialport::{SerialPort, TTYPort};
+
+        // Keep the slave end open so the master never sees a hangup.
+        let (master, slave) = TTYPort::pair().expect("pty pair");
+        let name = slave.name().expect("pty name");
+        let mut elm = Elm327::connect_serial(&name, 38400, TIMEOUT).unwrap();
+        std::thread::spawn(move || SimElm::car().serve(master));
+
+        elm.initialize().unwrap();
+        let frames = elm.query(&[0x01, obd::COOLANT_TEMP]).unwrap();
+        let payload = obd::pid_payload(&frames, obd::COOLANT_TEMP).unwrap();
+        assert_eq!(
+            obd::decode_pid(obd::COOLANT_TEMP, payload).unwrap().value,
+            83.0
+        );
+        drop(slave);
+    }
+}
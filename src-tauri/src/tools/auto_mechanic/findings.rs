@@ -0,0 +1,497 @@
+//! Checkup findings: thresholds applied to the engine, tire, battery and
+//! fluid results, turning raw readings into "front left is 7 psi low" with
+//! what to do about it. The thresholds are [`CheckupRules`], kept in the
+//! vehicle profile so they can be tuned per car.
+
+use super::dtc::Severity;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// How urgent a finding is, least to most.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(super) enum Level {
+    /// Worth knowing; no action needed yet.
+    Advisory,
+    /// Drivable, but fix it soon.
+    Warning,
+    /// Do not drive until it is fixed.
+    Critical,
+}
+
+/// Fluid states to levels, per fluid; `default` covers fluids without
+/// their own entry.
+type FluidRules = BTreeMap<String, BTreeMap<String, Level>>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CheckupRules {
+    /// psi under the recommended pressure that counts as low.
+    pub pressure_low_psi: f64,
+    /// psi under the recommended pressure that is unsafe to drive on.
+    pub pressure_critical_psi: f64,
+    /// psi over the recommended pressure that counts as overinflated.
+    pub pressure_high_psi: f64,
+    /// Tread depth to start planning new tires.
+    pub tread_warn_mm: f64,
+    /// Legal minimum tread depth in most places (2/32 in).
+    pub tread_min_mm: f64,
+    /// Resting voltage below which the battery is low.
+    pub battery_low_volts: f64,
+    /// Resting voltage below which the car may not start.
+    pub battery_critical_volts: f64,
+    /// Charging voltage above which the regulator is suspect.
+    pub charging_max_volts: f64,
+    /// The battery's rated cold cranking amps, from its label.
+    pub cca_rated: Option<f64>,
+    /// Measured CCA as a percentage of rated that warrants a warning.
+    pub cca_warn_pct: f64,
+    pub cca_critical_pct: f64,
+    pub health_warn_pct: f64,
+    pub health_critical_pct: f64,
+    /// Coolant temperature that means the engine is overheating.
+    pub coolant_max_c: f64,
+    /// Trouble code severity to finding level.
+    pub dtc: BTreeMap<Severity, Level>,
+    pub fluids: FluidRules,
+}
+
+impl Default for CheckupRules {
+    fn default() -> Self {
+        let states = |pairs: &[(&str, Level)]| -> BTreeMap<String, Level> {
+            pairs.iter().map(|(s, l)| (s.to_string(), *l)).collect()
+        };
+        let fluids = [
+            (
+                "default",
+                states(&[("low", Level::Warning), ("empty", Level::Critical)]),
+            ),
+            // Low oil or brake fluid is a leak or worn pads, not a top-up.
+            (
+                "oil",
+                states(&[("low", Level::Critical), ("empty", Level::Critical)]),
+            ),
+            (
+                "brake_fluid",
+                states(&[("low", Level::Critical), ("empty", Level::Critical)]),
+            ),
+            (
+                "washer",
+                states(&[("low", Level::Advisory), ("empty", Level::Advisory)]),
+            ),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect();
+        CheckupRules {
+            pressure_low_psi: 3.0,
+            pressure_critical_psi: 8.0,
+            pressure_high_psi: 5.0,
+            tread_warn_mm: 3.0,
+            tread_min_mm: 1.6,
+            battery_low_volts: 12.4,
+            battery_critical_volts: 12.0,
+            charging_max_volts: 14.8,
+            cca_rated: None,
+            cca_warn_pct: 80.0,
+            cca_critical_pct: 60.0,
+            health_warn_pct: 70.0,
+            health_critical_pct: 50.0,
+            coolant_max_c: 110.0,
+            dtc: [
+                (Severity::Unknown, Level::Warning),
+                (Severity::Low, Level::Advisory),
+                (Severity::Moderate, Level::Warning),
+                (Severity::High, Level::Warning),
+                (Severity::Critical, Level::Critical),
+            ]
+            .into_iter()
+            .collect(),
+            fluids,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(super) struct Finding {
+    pub severity: Level,
+    /// engine, tires, battery or fluids.
+    pub area: &'static str,
+    /// The tire, code or fluid the finding is about.
+    pub item: String,
+    pub message: String,
+    pub action: String,
+}
+
+impl Finding {
+    fn new(
+        severity: Level,
+        area: &'static str,
+        item: &str,
+        message: String,
+        action: String,
+    ) -> Self {
+        Finding {
+            severity,
+            area,
+            item: item.to_string(),
+            message,
+            action,
+        }
+    }
+}
+
+fn engine(data: &Value, rules: &CheckupRules, out: &mut Vec<Finding>) {
+    if let Some(temp) = data["coolant_temp_c"].as_f64() {
+        if temp >= rules.coolant_max_c {
+            out.push(Finding::new(
+                Level::Critical,
+                "engine",
+                "coolant_temp",
+                format!("Engine is overheating at {} °C", temp),
+                "Pull over and switch the engine off; check the coolant once it has cooled".into(),
+            ));
+        }
+    }
+    for code in data["codes"].as_array().into_iter().flatten() {
+        let severity =
+            serde_json::from_value(code["severity"].clone()).unwrap_or(Severity::Unknown);
+        let level = rules.dtc.get(&severity).copied().unwrap_or(Level::Warning);
+        let name = code["code"].as_str().unwrap_or("");
+        out.push(Finding::new(
+            level,
+            "engine",
+            name,
+            format!("{}: {}", name, code["description"].as_str().unwrap_or("")),
+            code["guidance"].as_str().unwrap_or("").to_string(),
+        ));
+    }
+}
+
+fn tires(data: &Value, rules: &CheckupRules, out: &mut Vec<Finding>) {
+    for tire in data["tires"].as_array().into_iter().flatten() {
+        let position = tire["position"].as_str().unwrap_or("Tire");
+        if let (Some(psi), Some(recommended)) = (
+            tire["pressure_psi"].as_f64(),
+            tire["recommended_psi"].as_f64(),
+        ) {
+            // Rounded like the sensor readings, so 35 - 26.8 reads 8.2.
+            let under = ((recommended - psi) * 10.0).round() / 10.0;
+            let level = if under >= rules.pressure_critical_psi {
+                Some(Level::Critical)
+            } else if under >= rules.pressure_low_psi {
+                Some(Level::Warning)
+            } else {
+                None
+            };
+            if let Some(level) = level {
+                out.push(Finding::new(
+                    level,
+                    "tires",
+                    position,
+                    format!(
+                        "{} is {} psi below the recommended {} psi",
+                        position, under, recommended
+                    ),
+                    format!(
+                        "Inflate to {} psi when cold and check for a nail or slow leak",
+                        recommended
+                    ),
+                ));
+            } else if -under >= rules.pressure_high_psi {
+                out.push(Finding::new(
+                    Level::Warning,
+                    "tires",
+                    position,
+                    format!(
+                        "{} is {} psi above the recommended {} psi",
+                        position, -under, recommended
+                    ),
+                    format!("Let air out to {} psi when cold", recommended),
+                ));
+            }
+        }
+        if let Some(tread) = tire["tread_mm"].as_f64() {
+            if tread < rules.tread_min_mm {
+                out.push(Finding::new(
+                    Level::Critical,
+                    "tires",
+                    position,
+                    format!(
+                        "{} tread is {} mm, below the {} mm minimum",
+                        position, tread, rules.tread_min_mm
+                    ),
+                    "Replace the tire before driving in the wet".into(),
+                ));
+            } else if tread < rules.tread_warn_mm {
+                out.push(Finding::new(
+                    Level::Warning,
+                    "tires",
+                    position,
+                    format!("{} tread is down to {} mm", position, tread),
+                    "Plan to replace the tire; wet grip falls off quickly from here".into(),
+                ));
+            }
+        }
+    }
+}
+
+fn battery(data: &Value, rules: &CheckupRules, out: &mut Vec<Finding>) {
+    if let Some(volts) = data["voltage"].as_f64() {
+        let finding = if volts > rules.charging_max_volts {
+            Some((
+                Level::Warning,
+                format!("Charging voltage is high at {} V", volts),
+                "Have the alternator and voltage regulator checked; overcharging boils the battery",
+            ))
+        } else if data["status"] == "charging" {
+            None
+        } else if volts < rules.battery_critical_volts {
+            Some((
+                Level::Critical,
+                format!("Battery is at {} V", volts),
+                "Charge and load-test the battery; it may not start the car",
+            ))
+        } else if volts < rules.battery_low_volts {
+            Some((
+                Level::Warning,
+                format!("Battery is low at {} V", volts),
+                "Charge it or take a longer drive; test it if it stays low",
+            ))
+        } else {
+            None
+        };
+        if let Some((level, message, action)) = finding {
+            out.push(Finding::new(
+                level,
+                "battery",
+                "voltage",
+                message,
+                action.into(),
+            ));
+        }
+    }
+    let mut band = |item: &str, pct: f64, warn: f64, critical: f64, message: String| {
+        let level = if pct < critical {
+            Level::Critical
+        } else if pct < warn {
+            Level::Warning
+        } else {
+            return;
+        };
+        out.push(Finding::new(
+            level,
+            "battery",
+            item,
+            message,
+            "Replace the battery before it leaves you stranded".into(),
+        ));
+    };
+    if let (Some(cca), Some(rated)) = (data["cca"].as_f64(), rules.cca_rated) {
+        let pct = (cca / rated * 100.0).round();
+        band(
+            "cca",
+            pct,
+            rules.cca_warn_pct,
+            rules.cca_critical_pct,
+            format!(
+                "Battery delivers {} CCA, {}% of its {} rating",
+                cca, pct, rated
+            ),
+        );
+    }
+    if let Some(health) = data["health_pct"].as_f64() {
+        band(
+            "health",
+            health,
+            rules.health_warn_pct,
+            rules.health_critical_pct,
+            format!("Battery health is {}%", health),
+        );
+    }
+}
+
+fn fluids(data: &Value, rules: &CheckupRules, out: &mut Vec<Finding>) {
+    let Some(levels) = data.as_object() else {
+        return;
+    };
+    for (fluid, state) in levels {
+        let Some(state) = state.as_str() else {
+            continue;
+        };
+        let level = [fluid.as_str(), "default"]
+            .iter()
+            .find_map(|key| rules.fluids.get(*key).and_then(|states| states.get(state)));
+        let Some(level) = level else {
+            continue;
+        };
+        let name = fluid.replace('_', " ");
+        let action = match data["specs"][fluid].as_str() {
+            Some(spec) => format!("Top up with {}", spec),
+            None => format!("Top up the {}", name),
+        };
+        let action = match fluid.as_str() {
+            "coolant" => format!("{} once the engine is cold, and look for leaks", action),
+            "brake_fluid" => format!("{}, and have the pads and lines inspected", action),
+            "oil" => format!("{}, and find out where it is going", action),
+            _ => action,
+        };
+        out.push(Finding::new(
+            *level,
+            "fluids",
+            fluid,
+            format!("{} is {}", capitalize(&name), state),
+            action,
+        ));
+    }
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Findings for the checkup sections present in `checkup` (keyed engine,
+/// tires, battery, fluids), most severe first.
+pub(super) fn evaluate(
+    checkup: &serde_json::Map<String, Value>,
+    rules: &CheckupRules,
+) -> Vec<Finding> {
+    type Rule = fn(&Value, &CheckupRules, &mut Vec<Finding>);
+    let sections: [(&str, Rule); 4] = [
+        ("engine", engine),
+        ("tires", tires),
+        ("battery", battery),
+        ("fluids", fluids),
+    ];
+    let mut findings = Vec::new();
+    for (key, rule) in sections {
+        if let Some(data) = checkup.get(key) {
+            rule(data, rules, &mut findings);
+        }
+    }
+    // Stable, so findings of one level keep the section order.
+    findings.sort_by_key(|f| std::cmp::Reverse(f.severity));
+    findings
+}
+
+/// One line for the whole checkup, led by the worst finding.
+pub(super) fn verdict(findings: &[Finding]) -> String {
+    let count = |level| findings.iter().filter(|f| f.severity == level).count();
+    let plural = |n: usize, word: &str| format!("{} {}{}", n, word, if n == 1 { "" } else { "s" });
+    let Some(worst) = findings.first() else {
+        return "All checks passed.".into();
+    };
+    let others = findings.len() - 1;
+    let more = if others > 0 {
+        format!(" (+{} more)", others)
+    } else {
+        String::new()
+    };
+    match worst.severity {
+        Level::Critical => format!("Not safe to drive until fixed: {}{}.", worst.message, more),
+        Level::Warning => format!(
+            "Drivable, but {} need attention soon: {}{}.",
+            plural(count(Level::Warning), "issue"),
+            worst.message,
+            more
+        ),
+        Level::Advisory => format!(
+            "In good shape; {} to keep an eye on.",
+            plural(count(Level::Advisory), "minor item")
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn checkup(value: Value) -> serde_json::Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_findings_ordered_by_severity() {
+        let data = checkup(json!({
+            "engine": {"coolant_temp_c": 90.0, "codes": [
+                {"code": "P0420", "description": "Catalyst", "severity": "low", "guidance": "Later"}
+            ]},
+            "tires": {"tires": [
+                {"position": "Front Left", "pressure_psi": 28, "recommended_psi": 35, "tread_mm": 5.2},
+                {"position": "Rear Right", "pressure_psi": 34, "recommended_psi": 35, "tread_mm": 1.2}
+            ]},
+            "battery": {"voltage": 12.2, "health_pct": 87, "status": "low"},
+            "fluids": {"oil": "ok", "coolant": "low", "specs": {"coolant": "Type 2 blue"}}
+        }));
+        let findings = evaluate(&data, &CheckupRules::default());
+        let summary: Vec<(Level, &str)> = findings
+            .iter()
+            .map(|f| (f.severity, f.item.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (Level::Critical, "Rear Right"),
+                (Level::Warning, "Front Left"),
+                (Level::Warning, "voltage"),
+                (Level::Warning, "coolant"),
+                (Level::Advisory, "P0420"),
+            ]
+        );
+        assert_eq!(
+            findings[1].message,
+            "Front Left is 7 psi below the recommended 35 psi"
+        );
+        assert!(findings[3].action.starts_with("Top up with Type 2 blue"));
+        assert!(verdict(&findings).starts_with("Not safe to drive until fixed: Rear Right tread"));
+
+        // Tuned per vehicle: a stricter tire and a laxer pressure rule.
+        let rules = CheckupRules {
+            pressure_low_psi: 8.0,
+            tread_min_mm: 1.0,
+            ..CheckupRules::default()
+        };
+        let findings = evaluate(&data, &rules);
+        assert!(findings.iter().all(|f| f.severity != Level::Critical));
+        assert!(!findings.iter().any(|f| f.item == "Front Left"));
+    }
+
+    #[test]
+    fn test_tire_pressure_delta_is_rounded() {
+        let data = checkup(json!({"tires": {"tires": [
+            {"position": "Front Left", "pressure_psi": 26.8, "recommended_psi": 35},
+            {"position": "Rear Left", "pressure_psi": 40.3, "recommended_psi": 35}
+        ]}}));
+        let messages: Vec<String> = evaluate(&data, &CheckupRules::default())
+            .into_iter()
+            .map(|f| f.message)
+            .collect();
+        assert!(messages.contains(&"Front Left is 8.2 psi below the recommended 35 psi".into()));
+        assert!(messages.contains(&"Rear Left is 5.3 psi above the recommended 35 psi".into()));
+    }
+
+    #[test]
+    fn test_verdict() {
+        assert_eq!(verdict(&[]), "All checks passed.");
+        let advisory = Finding::new(
+            Level::Advisory,
+            "fluids",
+            "washer",
+            "Washer is low".into(),
+            String::new(),
+        );
+        assert_eq!(
+            verdict(&[advisory]),
+            "In good shape; 1 minor item to keep an eye on."
+        );
+        let rules: CheckupRules = serde_json::from_value(json!({"tread_min_mm": 2.0})).unwrap();
+        assert_eq!(rules.tread_min_mm, 2.0);
+        assert_eq!(rules.pressure_low_psi, 3.0);
+    }
+}
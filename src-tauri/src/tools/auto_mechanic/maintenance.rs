@@ -0,0 +1,461 @@
+//! The maintenance log and what is due next.
+//!
+//! Each [`ScheduleRule`] gives a service interval in miles, months, or both;
+//! whichever comes first makes the service due, counted from the latest
+//! log entry for that service. Dates are plain `YYYY-MM-DD` strings.
+
+use super::profile::Garage;
+use super::source::VehicleSource;
+use super::{failure, obd, read, ToolResult};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Warn this far ahead of a mileage interval...
+const SOON_MILES: i64 = 500;
+/// ...or a time interval.
+const SOON_DAYS: i64 = 30;
+
+pub(super) const KM_PER_MILE: f64 = 1.609344;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleRule {
+    /// `oil_change`, `tire_rotation`, or any name used in the log.
+    pub service: String,
+    #[serde(default)]
+    pub miles: Option<u32>,
+    #[serde(default)]
+    pub months: Option<u32>,
+}
+
+impl ScheduleRule {
+    /// Typical intervals for a modern car on synthetic oil.
+    pub(super) fn defaults() -> Vec<ScheduleRule> {
+        let rule = |service: &str, miles, months| ScheduleRule {
+            service: service.into(),
+            miles,
+            months,
+        };
+        vec![
+            rule("oil_change", Some(5000), Some(6)),
+            rule("tire_rotation", Some(7500), None),
+            rule("engine_air_filter", Some(15000), Some(24)),
+            rule("cabin_air_filter", Some(15000), Some(12)),
+            rule("brake_fluid", None, Some(24)),
+            rule("coolant", Some(100000), Some(120)),
+        ]
+    }
+}
+
+/// One line of the maintenance log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServiceEntry {
+    pub service: String,
+    /// `YYYY-MM-DD`.
+    pub date: String,
+    #[serde(default)]
+    pub mileage: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
+
+/// Accept "oil", "Oil Change", "rotation" for the built-in services.
+pub(super) fn normalize_service(name: &str) -> String {
+    let key = name.trim().to_lowercase().replace([' ', '-'], "_");
+    match key.as_str() {
+        "oil" | "oil_and_filter" | "oil_change" => "oil_change".into(),
+        "rotation" | "tire_rotation" | "tyre_rotation" | "rotate_tires" => "tire_rotation".into(),
+        "air_filter" | "engine_air_filter" => "engine_air_filter".into(),
+        "cabin_filter" | "cabin_air_filter" => "cabin_air_filter".into(),
+        _ => key,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Dates
+// ---------------------------------------------------------------------------
+
+/// Days since 1970-01-01 (Howard Hinnant's `days_from_civil`).
+fn days_from_civil(y: i64, m: u32, d: u32) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (m as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + d as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn civil_from_days(z: i64) -> (i64, u32, u32) {
+    let z = z + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    (
+        if m <= 2 {
+            yoe + era * 400 + 1
+        } else {
+            yoe + era * 400
+        },
+        m,
+        d,
+    )
+}
+
+pub(super) fn parse_date(s: &str) -> Option<i64> {
+    let mut parts = s.trim().splitn(3, '-');
+    let y: i64 = parts.next()?.parse().ok()?;
+    let m: u32 = parts.next()?.parse().ok()?;
+    let d: u32 = parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&m) || !(1..=31).contains(&d) {
+        return None;
+    }
+    let days = days_from_civil(y, m, d);
+    // Reject dates that roll over, like 2025-02-30.
+    (civil_from_days(days) == (y, m, d)).then_some(days)
+}
+
+pub(super) fn format_date(days: i64) -> String {
+    let (y, m, d) = civil_from_days(days);
+    format!("{:04}-{:02}-{:02}", y, m, d)
+}
+
+/// Same day `months` later, clamped to the end of shorter months.
+fn add_months(days: i64, months: u32) -> i64 {
+    let (y, m, d) = civil_from_days(days);
+    let total = y * 12 + (m as i64 - 1) + months as i64;
+    let (y, m) = (total.div_euclid(12), total.rem_euclid(12) as u32 + 1);
+    let last = days_from_civil(y + (m / 12) as i64, m % 12 + 1, 1) - days_from_civil(y, m, 1);
+    days_from_civil(y, m, d.min(last as u32))
+}
+
+pub(super) fn today() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64 / 86_400)
+        .unwrap_or(0)
+}
+
+// ---------------------------------------------------------------------------
+// Schedule
+// ---------------------------------------------------------------------------
+
+/// The latest entry for a service: by date, then by mileage.
+fn last_service<'a>(log: &'a [ServiceEntry], service: &str) -> Option<&'a ServiceEntry> {
+    log.iter()
+        .filter(|e| e.service == service)
+        .max_by_key(|e| (parse_date(&e.date), e.mileage))
+}
+
+/// The highest mileage in the log, as a fallback odometer reading.
+pub(super) fn last_logged_mileage(log: &[ServiceEntry]) -> Option<u32> {
+    log.iter().filter_map(|e| e.mileage).max()
+}
+
+/// When `rule` is next due and how close it is, given the current mileage
+/// (if known) and today's date.
+pub(super) fn due(
+    rule: &ScheduleRule,
+    log: &[ServiceEntry],
+    mileage: Option<u32>,
+    today: i64,
+) -> Value {
+    let Some(last) = last_service(log, &rule.service) else {
+        return json!({
+            "service": rule.service,
+            "status": "no_record",
+            "interval_miles": rule.miles,
+            "interval_months": rule.months,
+        });
+    };
+    let due_mileage = rule.miles.zip(last.mileage).map(|(every, at)| at + every);
+    let miles_left = due_mileage
+        .zip(mileage)
+        .map(|(due, now)| due as i64 - now as i64);
+    let due_date = rule
+        .months
+        .zip(parse_date(&last.date))
+        .map(|(every, at)| add_months(at, every));
+    let days_left = due_date.map(|due| due - today);
+
+    let status = if miles_left.is_some_and(|m| m <= 0) || days_left.is_some_and(|d| d <= 0) {
+        "overdue"
+    } else if miles_left.is_some_and(|m| m <= SOON_MILES)
+        || days_left.is_some_and(|d| d <= SOON_DAYS)
+    {
+        "due_soon"
+    } else {
+        "ok"
+    };
+    json!({
+        "service": rule.service,
+        "status": status,
+        "last_date": last.date,
+        "last_mileage": last.mileage,
+        "due_mileage": due_mileage,
+        "miles_left": miles_left,
+        "due_date": due_date.map(format_date),
+        "days_left": days_left,
+        "interval_miles": rule.miles,
+        "interval_months": rule.months,
+    })
+}
+
+/// Most urgent first: overdue, due soon, ok, then services never logged.
+pub(super) fn urgency(item: &Value) -> u8 {
+    match item["status"].as_str() {
+        Some("overdue") => 0,
+        Some("due_soon") => 1,
+        Some("ok") => 2,
+        _ => 3,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tools
+// ---------------------------------------------------------------------------
+
+fn arg_mileage(args: &Value) -> Option<u32> {
+    args.get("mileage")
+        .and_then(|v| v.as_f64())
+        .filter(|m| *m >= 0.0)
+        .map(|m| m.round() as u32)
+}
+
+/// Current mileage: what the user said, else the car's odometer (PID A6),
+/// else the highest mileage in the log.
+fn current_mileage(
+    source: &mut dyn VehicleSource,
+    garage: &Garage,
+    args: &Value,
+) -> (Option<u32>, &'static str) {
+    if let Some(miles) = arg_mileage(args) {
+        return (Some(miles), "input");
+    }
+    // A dropped adapter should not stop the schedule from being shown.
+    if let Ok(Some(km)) = read(source, obd::ODOMETER) {
+        return (Some((km / KM_PER_MILE).round() as u32), "odometer");
+    }
+    match last_logged_mileage(&garage.log) {
+        Some(miles) => (Some(miles), "log"),
+        None => (None, "unknown"),
+    }
+}
+
+/// Record a service in the log and report when it is next due.
+pub(super) fn log_maintenance(
+    source: &mut dyn VehicleSource,
+    garage: &mut Garage,
+    args: &Value,
+) -> ToolResult {
+    let Some(service) = args
+        .get("service")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.trim().is_empty())
+        .map(normalize_service)
+    else {
+        return failure("Which service? e.g. oil_change or tire_rotation".into());
+    };
+    let date = match args.get("date").and_then(|v| v.as_str()) {
+        Some(date) => match parse_date(date) {
+            Some(days) => format_date(days),
+            None => return failure(format!("Invalid date {:?}; use YYYY-MM-DD", date)),
+        },
+        None => format_date(today()),
+    };
+    let mileage = match arg_mileage(args) {
+        Some(miles) => Some(miles),
+        None => match current_mileage(source, garage, args) {
+            (miles, "odometer") => miles,
+            _ => None,
+        },
+    };
+    let entry = ServiceEntry {
+        service,
+        date,
+        mileage,
+        notes: args
+            .get("notes")
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.trim().is_empty())
+            .map(String::from),
+    };
+    garage.log.push(entry.clone());
+    if let Err(e) = garage.save() {
+        garage.log.pop();
+        return failure(e);
+    }
+    let next = garage
+        .profile
+        .schedule
+        .iter()
+        .find(|r| r.service == entry.service)
+        .map(|rule| due(rule, &garage.log, mileage, today()));
+    ToolResult {
+        success: true,
+        data: json!({
+            "logged": entry,
+            "next": next,
+        }),
+        error: None,
+    }
+}
+
+/// What is due, most urgent first, optionally for one service ("when is my
+/// next oil change").
+pub(super) fn check_maintenance(
+    source: &mut dyn VehicleSource,
+    garage: &Garage,
+    args: &Value,
+) -> ToolResult {
+    let only = args
+        .get("service")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.trim().is_empty())
+        .map(normalize_service);
+    let rules: Vec<&ScheduleRule> = garage
+        .profile
+        .schedule
+        .iter()
+        .filter(|r| only.as_ref().is_none_or(|s| &r.service == s))
+        .collect();
+    if rules.is_empty() {
+        return failure(format!(
+            "No schedule for {}; the profile tracks {}",
+            only.unwrap_or_default(),
+            garage
+                .profile
+                .schedule
+                .iter()
+                .map(|r| r.service.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    let (mileage, mileage_source) = current_mileage(source, garage, args);
+    let today = today();
+    let mut items: Vec<Value> = rules
+        .iter()
+        .map(|rule| due(rule, &garage.log, mileage, today))
+        .collect();
+    items.sort_by_key(urgency);
+
+    let mut history: Vec<&ServiceEntry> = garage.log.iter().collect();
+    history.sort_by_key(|e| std::cmp::Reverse((parse_date(&e.date), e.mileage)));
+    history.truncate(10);
+    ToolResult {
+        success: true,
+        data: json!({
+            "mileage": mileage,
+            "mileage_source": mileage_source,
+            "today": format_date(today),
+            "items": items,
+            "history": history,
+        }),
+        error: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::source::DemoSource;
+    use super::*;
+
+    fn entry(service: &str, date: &str, mileage: u32) -> ServiceEntry {
+        ServiceEntry {
+            service: service.into(),
+            date: date.into(),
+            mileage: Some(mileage),
+            notes: None,
+        }
+    }
+
+    #[test]
+    fn test_dates() {
+        assert_eq!(parse_date("1970-01-01"), Some(0));
+        assert_eq!(format_date(parse_date("2024-02-29").unwrap()), "2024-02-29");
+        assert_eq!(parse_date("2025-02-29"), None);
+        assert_eq!(parse_date("2025-13-01"), None);
+        let jan31 = parse_date("2025-01-31").unwrap();
+        assert_eq!(format_date(add_months(jan31, 1)), "2025-02-28");
+        assert_eq!(format_date(add_months(jan31, 6)), "2025-07-31");
+        assert_eq!(
+            format_date(add_months(parse_date("2025-11-15").unwrap(), 3)),
+            "2026-02-15"
+        );
+    }
+
+    #[test]
+    fn test_due_by_miles_or_months() {
+        let rule = &ScheduleRule::defaults()[0];
+        let log = vec![
+            entry("oil_change", "2025-01-10", 40000),
+            entry("oil_change", "2025-06-01", 44800),
+            entry("tire_rotation", "2025-06-01", 44800),
+        ];
+        let june = parse_date("2025-07-01").unwrap();
+
+        let item = due(rule, &log, Some(45000), june);
+        assert_eq!(item["status"], "ok");
+        assert_eq!(item["last_mileage"], 44800);
+        assert_eq!(item["due_mileage"], 49800);
+        assert_eq!(item["miles_left"], 4800);
+        assert_eq!(item["due_date"], "2025-12-01");
+
+        assert_eq!(due(rule, &log, Some(49500), june)["status"], "due_soon");
+        assert_eq!(due(rule, &log, Some(50000), june)["status"], "overdue");
+        // Six months pass before the miles do.
+        let december = parse_date("2025-12-02").unwrap();
+        assert_eq!(due(rule, &log, Some(46000), december)["status"], "overdue");
+        // Unknown mileage: only the date counts.
+        assert_eq!(due(rule, &log, None, june)["miles_left"], Value::Null);
+        assert_eq!(due(rule, &log, None, june)["status"], "ok");
+
+        let coolant = &ScheduleRule::defaults()[5];
+        assert_eq!(due(coolant, &log, None, june)["status"], "no_record");
+        assert_eq!(last_logged_mileage(&log), Some(44800));
+        assert_eq!(normalize_service("Oil Change"), "oil_change");
+        assert_eq!(normalize_service("rotation"), "tire_rotation");
+    }
+
+    #[test]
+    fn test_log_then_check_oil_change() {
+        let mut source = DemoSource::default();
+        let mut garage = Garage::default();
+        let missing = log_maintenance(&mut source, &mut garage, &json!({}));
+        assert!(!missing.success);
+        let bad_date = json!({"service": "oil", "date": "yesterday"});
+        assert!(!log_maintenance(&mut source, &mut garage, &bad_date).success);
+
+        let logged = log_maintenance(
+            &mut source,
+            &mut garage,
+            &json!({"service": "Oil change", "mileage": 42000, "notes": "0W-20"}),
+        );
+        assert!(logged.success);
+        assert_eq!(logged.data["logged"]["service"], "oil_change");
+        assert_eq!(logged.data["logged"]["date"], format_date(today()));
+        assert_eq!(logged.data["next"]["due_mileage"], 47000);
+
+        // The demo car reports 45,000 miles on its odometer.
+        let oil = check_maintenance(&mut source, &garage, &json!({"service": "oil"}));
+        assert!(oil.success);
+        assert_eq!(oil.data["mileage_source"], "odometer");
+        assert_eq!(oil.data["items"].as_array().unwrap().len(), 1);
+        assert_eq!(oil.data["items"][0]["miles_left"], 2000);
+        assert_eq!(oil.data["items"][0]["status"], "ok");
+
+        let all = check_maintenance(&mut source, &garage, &json!({"mileage": 46800}));
+        assert_eq!(all.data["mileage_source"], "input");
+        assert_eq!(all.data["items"][0]["service"], "oil_change");
+        assert_eq!(all.data["items"][0]["status"], "due_soon");
+        assert_eq!(all.data["history"][0]["notes"], "0W-20");
+        assert!(
+            !check_maintenance(&mut source, &garage, &json!({"service": "spark_plugs"})).success
+        );
+    }
+}
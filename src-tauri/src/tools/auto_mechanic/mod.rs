@@ -0,0 +1,685 @@
+//! Auto-mechanic module: vehicle diagnostics over OBD-II.
+//!
+//! Engine and battery readings come from the configured [`VehicleConfig`]
+//! source: a live ELM327 adapter on a serial port or TCP socket, or canned
+//! demo data. Tires and fluids have no OBD-II PIDs and are demo-only.
+//! Trouble codes are explained from the bundled [`dtc`] knowledge base;
+//! clearing them is destructive and goes through the registry's confirmation.
+//! Live [`telemetry`] polls PIDs in the background, records sessions and
+//! replays them as a source. The vehicle [`profile`] (VIN, recommended
+//! pressures, fluid specs) and the [`maintenance`] log live in `garage.json`.
+//! The [`vin`] decoder identifies the car offline; once known, that identity
+//! is attached to every result and selects manufacturer-specific codes.
+//! A checkup ends in [`findings`]: the profile's thresholds applied to every
+//! reading, worst first, with a one-line verdict.
+
+mod codes;
+mod config;
+mod dtc;
+mod elm327;
+mod findings;
+mod maintenance;
+mod obd;
+mod profile;
+mod source;
+mod telemetry;
+mod vin;
+
+pub use config::{SourceKind, VehicleConfig};
+pub use profile::Garage;
+pub use telemetry::{Sample, TelemetryOptions, TelemetrySession, TelemetrySummary};
+
+use super::{RiskLevel, ToolDefinition, ToolModule, ToolResult};
+use obd::DtcKind;
+use profile::VehicleProfile;
+use serde_json::{json, Map, Value};
+use source::{DemoSource, VehicleSource};
+use std::sync::{Arc, Mutex};
+
+pub struct AutoMechanicModule {
+    /// Shared with the telemetry poller.
+    source: Arc<Mutex<Box<dyn VehicleSource>>>,
+    telemetry: TelemetryOptions,
+    garage: Mutex<Garage>,
+}
+
+impl AutoMechanicModule {
+    /// A module backed by the demo source.
+    pub fn new() -> Self {
+        Self::with_source(Box::new(DemoSource::default()))
+    }
+
+    /// A module backed by the source described in `config`.
+    pub fn with_config(config: &VehicleConfig) -> Result<Self, String> {
+        let mut module = Self::with_source(config.open_source()?);
+        module.telemetry = config.telemetry.clone();
+        Ok(module)
+    }
+
+    fn with_source(source: Box<dyn VehicleSource>) -> Self {
+        AutoMechanicModule {
+            source: Arc::new(Mutex::new(source)),
+            telemetry: TelemetryOptions::default(),
+            garage: Mutex::new(Garage::default()),
+        }
+    }
+
+    /// Use a saved vehicle profile and maintenance log.
+    pub fn set_garage(&mut self, garage: Garage) {
+        self.garage = Mutex::new(garage);
+    }
+
+    /// Start streaming the PIDs in `options` (unset fields come from
+    /// `vehicle.json`), calling `on_sample` after every poll.
+    pub fn start_telemetry(
+        &self,
+        options: TelemetryOptions,
+        on_sample: impl FnMut(&Sample) + Send + 'static,
+    ) -> Result<TelemetrySession, String> {
+        TelemetrySession::start(self.source.clone(), &options.or(&self.telemetry), on_sample)
+    }
+}
+
+impl ToolModule for AutoMechanicModule {
+    fn name(&self) -> &str {
+        "auto_mechanic"
+    }
+
+    fn description(&self) -> &str {
+        "Vehicle diagnostics, engine health, and maintenance tools"
+    }
+
+    fn tools(&self) -> Vec<ToolDefinition> {
+        vec![
+            ToolDefinition {
+                name: "check_engine".into(),
+                description: "Check engine health, RPM, coolant temperature, load, fuel trims, and OBD-II diagnostic codes"
+                    .into(),
+                parameters: json!({"type": "object", "properties": {}, "required": []}),
+                risk: RiskLevel::ReadOnly,
+            },
+            ToolDefinition {
+                name: "explain_dtc".into(),
+                description: "Explain OBD-II diagnostic trouble codes such as P0300: meaning, likely causes, severity, and whether it is safe to drive. Covers common powertrain codes broadly; body, chassis and network codes only for common airbag, ABS/stability and communication faults; other codes are decoded by system and range only"
+                    .into(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "code": {"type": "string", "description": "One or more codes, e.g. \"P0300\" or \"P0171, P0420\""},
+                        "make": {"type": "string", "description": "Vehicle make, for manufacturer-specific codes (P1xxx, B1xxx, ...)"}
+                    },
+                    "required": ["code"]
+                }),
+                risk: RiskLevel::ReadOnly,
+            },
+            ToolDefinition {
+                name: "read_dtcs".into(),
+                description: "Read stored, pending, and permanent OBD-II trouble codes and the check engine light"
+                    .into(),
+                parameters: json!({"type": "object", "properties": {}, "required": []}),
+                risk: RiskLevel::ReadOnly,
+            },
+            ToolDefinition {
+                name: "read_freeze_frame".into(),
+                description: "Read the freeze-frame data (RPM, load, temperature, fuel trims, speed) recorded when each stored code was set"
+                    .into(),
+                parameters: json!({"type": "object", "properties": {}, "required": []}),
+                risk: RiskLevel::ReadOnly,
+            },
+            ToolDefinition {
+                name: "clear_dtcs".into(),
+                description: "Clear stored and pending trouble codes and turn off the check engine light (erases freeze frames and readiness monitors)"
+                    .into(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "dry_run": {"type": "boolean", "description": "Only report what would be cleared"}
+                    },
+                    "required": []
+                }),
+                risk: RiskLevel::Destructive,
+            },
+            ToolDefinition {
+                name: "get_vehicle_profile".into(),
+                description: "Show the saved vehicle profile (VIN, make, model, year, recommended tire pressures, fluid specs) and the VIN the car reports"
+                    .into(),
+                parameters: json!({"type": "object", "properties": {}, "required": []}),
+                risk: RiskLevel::ReadOnly,
+            },
+            ToolDefinition {
+                name: "decode_vin".into(),
+                description: "Decode a VIN: manufacturer, country, model year, assembly plant, serial, and whether the check digit is valid. Defaults to the profile's VIN or reads it from the car"
+                    .into(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "vin": {"type": "string", "description": "17-character VIN (optional)"}
+                    },
+                    "required": []
+                }),
+                risk: RiskLevel::ReadOnly,
+            },
+            ToolDefinition {
+                name: "set_vehicle_profile".into(),
+                description: "Save which vehicle this is: VIN (typed or read from the car), make, model, year, recommended tire pressures, and fluid specs"
+                    .into(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "vin": {"type": "string", "description": "17-character VIN"},
+                        "read_vin": {"type": "boolean", "description": "Read the VIN from the car over OBD-II"},
+                        "make": {"type": "string"},
+                        "model": {"type": "string"},
+                        "year": {"type": "integer"},
+                        "front_psi": {"type": "number", "description": "Recommended front tire pressure"},
+                        "rear_psi": {"type": "number", "description": "Recommended rear tire pressure"},
+                        "oil": {"type": "string", "description": "Oil spec, e.g. \"0W-20 synthetic, 4.4 qt\""},
+                        "coolant": {"type": "string"},
+                        "brake_fluid": {"type": "string"},
+                        "transmission_fluid": {"type": "string"},
+                        "checkup": {"type": "object", "description": "Checkup thresholds to change, e.g. {\"tread_min_mm\": 2.0, \"cca_rated\": 650}"}
+                    },
+                    "required": []
+                }),
+                risk: RiskLevel::Modifying,
+            },
+            ToolDefinition {
+                name: "log_maintenance".into(),
+                description: "Record a completed service such as an oil change or tire rotation, with mileage and date"
+                    .into(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "service": {"type": "string", "description": "oil_change, tire_rotation, engine_air_filter, cabin_air_filter, brake_fluid, coolant, or any other name"},
+                        "mileage": {"type": "number", "description": "Odometer reading in miles (defaults to the car's odometer)"},
+                        "date": {"type": "string", "description": "YYYY-MM-DD (defaults to today)"},
+                        "notes": {"type": "string"}
+                    },
+                    "required": ["service"]
+                }),
+                risk: RiskLevel::Modifying,
+            },
+            ToolDefinition {
+                name: "check_maintenance".into(),
+                description: "Show which services are due or overdue, e.g. when the next oil change is due, from the maintenance log, mileage, and schedule"
+                    .into(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "service": {"type": "string", "description": "Only this service, e.g. oil_change"},
+                        "mileage": {"type": "number", "description": "Current mileage, if the car does not report it"}
+                    },
+                    "required": []
+                }),
+                risk: RiskLevel::ReadOnly,
+            },
+            ToolDefinition {
+                name: "check_tires".into(),
+                description: "Check tire pressure and tread depth for all four tires".into(),
+                parameters: json!({"type": "object", "properties": {}, "required": []}),
+                risk: RiskLevel::ReadOnly,
+            },
+            ToolDefinition {
+                name: "check_battery_vehicle".into(),
+                description: "Check vehicle battery voltage, CCA, and overall health".into(),
+                parameters: json!({"type": "object", "properties": {}, "required": []}),
+                risk: RiskLevel::ReadOnly,
+            },
+            ToolDefinition {
+                name: "check_fluids".into(),
+                description: "Check all vehicle fluid levels (oil, coolant, brake, transmission, washer)".into(),
+                parameters: json!({"type": "object", "properties": {}, "required": []}),
+                risk: RiskLevel::ReadOnly,
+            },
+            ToolDefinition {
+                name: "run_vehicle_checkup".into(),
+                description: "Run a full vehicle diagnostic scan covering engine, tires, battery, and fluids, with prioritized findings and a verdict".into(),
+                parameters: json!({"type": "object", "properties": {}, "required": []}),
+                risk: RiskLevel::ReadOnly,
+            },
+        ]
+    }
+
+    fn execute(&self, tool_name: &str, args: Value) -> ToolResult {
+        let mut source = self.source.lock().unwrap_or_else(|e| e.into_inner());
+        let source = source.as_mut();
+        let mut garage = self.garage.lock().unwrap_or_else(|e| e.into_inner());
+        let identity = garage.identity();
+        let make = identity.as_ref().and_then(|i| i.make.as_deref());
+        let mut result = match tool_name {
+            "check_engine" => check_engine(source, make),
+            "explain_dtc" => explain_dtc(&args, make),
+            "read_dtcs" => codes::read_dtcs(source, make),
+            "read_freeze_frame" => codes::read_freeze_frame(source, make),
+            "clear_dtcs" => codes::clear_dtcs(source, &args, make),
+            "decode_vin" => profile::decode_vin(source, &mut garage, &args),
+            "get_vehicle_profile" => profile::get_vehicle_profile(source, &mut garage),
+            "set_vehicle_profile" => profile::set_vehicle_profile(source, &mut garage, &args),
+            "log_maintenance" => maintenance::log_maintenance(source, &mut garage, &args),
+            "check_maintenance" => maintenance::check_maintenance(source, &garage, &args),
+            "check_tires" => check_tires(source, &garage.profile),
+            "check_battery_vehicle" => check_battery_vehicle(source),
+            "check_fluids" => check_fluids(source, &garage.profile),
+            "run_vehicle_checkup" => run_vehicle_checkup(source, &garage.profile, make),
+            _ => ToolResult {
+                success: false,
+                data: Value::Null,
+                error: Some(format!("Unknown tool: {}", tool_name)),
+            },
+        };
+        // Profile tools report the identity themselves; everything else is
+        // labelled with the car it was run against.
+        let labelled = !matches!(
+            tool_name,
+            "decode_vin" | "get_vehicle_profile" | "set_vehicle_profile"
+        );
+        if labelled && result.success {
+            let identity = garage.identity();
+            if let (Some(identity), Some(data)) = (identity, result.data.as_object_mut()) {
+                data.insert("vehicle".into(), json!(identity));
+            }
+        }
+        result
+    }
+
+    fn preview(&self, tool_name: &str, _args: &Value) -> Option<Value> {
+        match tool_name {
+            "clear_dtcs" => {
+                let mut source = self.source.lock().unwrap_or_else(|e| e.into_inner());
+                let garage = self.garage.lock().unwrap_or_else(|e| e.into_inner());
+                let make = garage.identity().and_then(|i| i.make);
+                codes::preview_clear(source.as_mut(), make.as_deref()).ok()
+            }
+            _ => None,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tool implementations
+// ---------------------------------------------------------------------------
+
+fn failure(error: String) -> ToolResult {
+    ToolResult {
+        success: false,
+        data: Value::Null,
+        error: Some(error),
+    }
+}
+
+fn demo_only(source: &dyn VehicleSource, what: &str) -> Option<ToolResult> {
+    (source.kind() != "demo").then(|| {
+        failure(format!(
+            "{} cannot be read over OBD-II; inspect it by hand or switch to the demo source",
+            what
+        ))
+    })
+}
+
+/// Read a PID and decode it, treating unsupported PIDs as absent.
+fn read(source: &mut dyn VehicleSource, pid: u8) -> Result<Option<f64>, String> {
+    Ok(source
+        .read_pid(pid)?
+        .and_then(|data| obd::decode_pid(pid, &data))
+        .map(|r| r.value))
+}
+
+fn check_engine(source: &mut dyn VehicleSource, make: Option<&str>) -> ToolResult {
+    let mut readings = || -> Result<Value, String> {
+        let rpm = read(source, obd::RPM)?;
+        let coolant = read(source, obd::COOLANT_TEMP)?;
+        let load = read(source, obd::ENGINE_LOAD)?;
+        let mut trims = Map::new();
+        for pid in [
+            obd::STFT_BANK1,
+            obd::LTFT_BANK1,
+            obd::STFT_BANK2,
+            obd::LTFT_BANK2,
+        ] {
+            if let Some(value) = read(source, pid)? {
+                let key = obd::pid(pid).map_or("", |p| p.key);
+                trims.insert(key.into(), json!(value));
+            }
+        }
+        let mil_on = source
+            .read_pid(obd::MONITOR_STATUS)?
+            .is_some_and(|data| obd::mil_on(&data));
+        let codes: Vec<dtc::DtcInfo> = source
+            .read_dtcs(DtcKind::Stored)?
+            .iter()
+            .filter_map(|code| dtc::lookup(code, make))
+            .collect();
+
+        let mut data = source.describe();
+        data["rpm"] = json!(rpm);
+        data["temp_f"] = json!(coolant.map(|c| (c * 9.0 / 5.0 + 32.0).round()));
+        data["coolant_temp_c"] = json!(coolant);
+        data["engine_load_pct"] = json!(load);
+        data["fuel_trims"] = Value::Object(trims);
+        data["mil_on"] = json!(mil_on);
+        data["status"] = json!(match rpm {
+            Some(r) if r > 0.0 => "running",
+            Some(_) => "off",
+            None => "unknown",
+        });
+        data["codes"] = json!(codes);
+        Ok(data)
+    };
+    match readings() {
+        Ok(data) => ToolResult {
+            success: true,
+            data,
+            error: None,
+        },
+        Err(e) => failure(e),
+    }
+}
+
+/// `make` from the arguments wins over the one the car is identified as.
+fn explain_dtc(args: &Value, vehicle_make: Option<&str>) -> ToolResult {
+    let text = args.get("code").and_then(|v| v.as_str()).unwrap_or("");
+    let make = args
+        .get("make")
+        .and_then(|v| v.as_str())
+        .filter(|m| !m.trim().is_empty())
+        .or(vehicle_make);
+    let codes: Vec<dtc::DtcInfo> = dtc::find_codes(text)
+        .iter()
+        .filter_map(|code| dtc::lookup(code, make))
+        .collect();
+    if codes.is_empty() {
+        return failure(format!(
+            "No diagnostic trouble code in {:?}; codes look like P0300 or U0100",
+            text
+        ));
+    }
+    ToolResult {
+        success: true,
+        data: json!({ "codes": codes }),
+        error: None,
+    }
+}
+
+fn check_tires(source: &mut dyn VehicleSource, profile: &VehicleProfile) -> ToolResult {
+    if let Some(unavailable) = demo_only(source, "Tire pressure and tread") {
+        return unavailable;
+    }
+    let front = profile.tire_pressure.front_psi;
+    let rear = profile.tire_pressure.rear_psi;
+    ToolResult {
+        success: true,
+        data: json!({
+            "tires": [
+                { "position": "Front Left",  "pressure_psi": 28, "recommended_psi": front, "tread_mm": 5.2 },
+                { "position": "Front Right", "pressure_psi": 34, "recommended_psi": front, "tread_mm": 5.0 },
+                { "position": "Rear Left",   "pressure_psi": 33, "recommended_psi": rear,  "tread_mm": 4.8 },
+                { "position": "Rear Right",  "pressure_psi": 34, "recommended_psi": rear,  "tread_mm": 4.6 }
+            ],
+        }),
+        error: None,
+    }
+}
+
+/// Resting 12.6 V is full; above ~13.2 V the alternator is charging.
+fn battery_status(voltage: f64) -> &'static str {
+    if voltage >= 13.2 {
+        "charging"
+    } else if voltage >= 12.4 {
+        "good"
+    } else if voltage >= 12.0 {
+        "low"
+    } else {
+        "critical"
+    }
+}
+
+fn check_battery_vehicle(source: &mut dyn VehicleSource) -> ToolResult {
+    if source.kind() == "demo" {
+        return ToolResult {
+            success: true,
+            data: json!({
+                "voltage": 12.4,
+                "cca": 650,
+                "health_pct": 87,
+                "age_months": 18,
+                "status": "good"
+            }),
+            error: None,
+        };
+    }
+    // Control module voltage is the closest OBD-II gets to battery voltage;
+    // cranking amps and age need a battery tester.
+    match read(source, obd::CONTROL_MODULE_VOLTAGE) {
+        Ok(Some(voltage)) => {
+            let mut data = source.describe();
+            data["voltage"] = json!(voltage);
+            data["cca"] = Value::Null;
+            data["health_pct"] = Value::Null;
+            data["age_months"] = Value::Null;
+            data["status"] = json!(battery_status(voltage));
+            ToolResult {
+                success: true,
+                data,
+                error: None,
+            }
+        }
+        Ok(None) => failure("Vehicle does not report control module voltage (PID 42)".into()),
+        Err(e) => failure(e),
+    }
+}
+
+fn check_fluids(source: &mut dyn VehicleSource, profile: &VehicleProfile) -> ToolResult {
+    if let Some(unavailable) = demo_only(source, "Fluid levels") {
+        return unavailable;
+    }
+    ToolResult {
+        success: true,
+        data: json!({
+            "oil": "ok",
+            "coolant": "low",
+            "brake_fluid": "ok",
+            "transmission": "ok",
+            "washer": "low",
+            "specs": profile.fluids,
+        }),
+        error: None,
+    }
+}
+
+/// Every check the source supports; the ones it does not are listed under
+/// `unavailable` instead of failing the whole checkup.
+fn run_vehicle_checkup(
+    source: &mut dyn VehicleSource,
+    profile: &VehicleProfile,
+    make: Option<&str>,
+) -> ToolResult {
+    type Check<'a> = &'a dyn Fn(&mut dyn VehicleSource) -> ToolResult;
+    let checks: [(&str, Check); 4] = [
+        ("engine", &|s| check_engine(s, make)),
+        ("tires", &|s| check_tires(s, profile)),
+        ("battery", &check_battery_vehicle),
+        ("fluids", &|s| check_fluids(s, profile)),
+    ];
+    let mut data = Map::new();
+    let mut unavailable = Map::new();
+    for (key, check) in checks {
+        let result = check(source);
+        if result.success {
+            data.insert(key.into(), result.data);
+        } else {
+            unavailable.insert(key.into(), json!(result.error));
+        }
+    }
+    let found = findings::evaluate(&data, &profile.checkup);
+    data.insert("verdict".into(), json!(findings::verdict(&found)));
+    data.insert("findings".into(), json!(found));
+    if !unavailable.is_empty() {
+        data.insert("unavailable".into(), Value::Object(unavailable));
+    }
+    let success = data.contains_key("engine");
+    ToolResult {
+        success,
+        error: (!success).then(|| "Could not read the engine".to_string()),
+        data: Value::Object(data),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::elm327::sim::SimElm;
+    use super::*;
+
+    fn tcp_module(port: u16) -> AutoMechanicModule {
+        let config: VehicleConfig = serde_json::from_value(json!({
+            "source": "tcp",
+            "tcp": { "host": "127.0.0.1", "port": port },
+            "timeout_ms": 2000
+        }))
+        .unwrap();
+        AutoMechanicModule::with_config(&config).unwrap()
+    }
+
+    #[test]
+    fn test_demo_engine() {
+        let result = AutoMechanicModule::new().execute("check_engine", json!({}));
+        assert!(result.success);
+        let data = result.data;
+        assert_eq!(data["source"], "demo");
+        assert_eq!(data["rpm"], 850.0);
+        assert_eq!(data["temp_f"], 194.0);
+        assert_eq!(data["status"], "running");
+        assert_eq!(data["mil_on"], true);
+        assert_eq!(data["fuel_trims"]["ltft_bank1"], 11.7);
+        assert_eq!(data["codes"][0]["code"], "P0171");
+        assert_eq!(
+            data["codes"][1]["description"],
+            "Catalyst System Efficiency Below Threshold (Bank 1)"
+        );
+        assert_eq!(data["codes"][0]["severity"], "moderate");
+        assert_eq!(data["codes"][1]["safe_to_drive"], "yes");
+    }
+
+    #[test]
+    fn test_explain_dtc() {
+        let module = AutoMechanicModule::new();
+        let result = module.execute("explain_dtc", json!({"code": "what does p0300 mean"}));
+        assert!(result.success);
+        assert_eq!(result.data["codes"][0]["code"], "P0300");
+        assert_eq!(result.data["codes"][0]["severity"], "high");
+
+        let result = module.execute("explain_dtc", json!({"code": "P1259", "make": "Acura"}));
+        assert_eq!(
+            result.data["codes"][0]["description"],
+            "VTEC System Malfunction"
+        );
+
+        let result = module.execute("explain_dtc", json!({"code": "engine light"}));
+        assert!(!result.success);
+    }
+
+    #[test]
+    fn test_engine_and_battery_from_simulated_adapter() {
+        let module = tcp_module(SimElm::spawn_tcp(SimElm::car));
+        let engine = module.execute("check_engine", json!({}));
+        assert!(engine.success, "{:?}", engine.error);
+        assert_eq!(engine.data["source"], "tcp");
+        assert_eq!(engine.data["adapter"], "ELM327 v1.5");
+        assert_eq!(engine.data["rpm"], 850.0);
+        assert_eq!(engine.data["coolant_temp_c"], 83.0);
+        assert_eq!(engine.data["engine_load_pct"], 25.1);
+        assert_eq!(
+            engine.data["fuel_trims"],
+            json!({"stft_bank1": 3.9, "ltft_bank1": 7.8})
+        );
+        assert_eq!(engine.data["codes"].as_array().unwrap().len(), 2);
+
+        let battery = module.execute("check_battery_vehicle", json!({}));
+        assert_eq!(battery.data["voltage"], 14.5);
+        assert_eq!(battery.data["status"], "charging");
+
+        let checkup = module.execute("run_vehicle_checkup", json!({}));
+        assert!(checkup.success);
+        assert!(checkup.data.get("tires").is_none());
+        assert!(checkup.data["unavailable"]["fluids"].is_string());
+    }
+
+    #[test]
+    fn test_demo_checkup_findings() {
+        let module = AutoMechanicModule::new();
+        let checkup = module.execute("run_vehicle_checkup", json!({})).data;
+        let findings = checkup["findings"].as_array().unwrap();
+        assert_eq!(findings[0]["severity"], "warning");
+        assert_eq!(findings[0]["item"], "P0171");
+        assert_eq!(
+            findings[1]["message"],
+            "Front Left is 7 psi below the recommended 35 psi"
+        );
+        assert_eq!(findings.last().unwrap()["severity"], "advisory");
+        assert!(checkup["verdict"]
+            .as_str()
+            .unwrap()
+            .starts_with("Drivable, but 3 issues need attention soon: P0171"));
+
+        // Treat low coolant as a stop-driving fault for this car.
+        let rules = json!({"checkup": {"fluids": {"coolant": {"low": "critical"}}}});
+        assert!(module.execute("set_vehicle_profile", rules).success);
+        let checkup = module.execute("run_vehicle_checkup", json!({})).data;
+        assert_eq!(checkup["findings"][0]["item"], "coolant");
+        assert_eq!(
+            checkup["verdict"],
+            "Not safe to drive until fixed: Coolant is low (+4 more)."
+        );
+    }
+
+    #[test]
+    fn test_profile_sets_recommended_pressure() {
+        let module = AutoMechanicModule::new();
+        let tires = module.execute("check_tires", json!({})).data;
+        assert_eq!(tires["tires"][0]["recommended_psi"], 35.0);
+        assert_eq!(tires["vehicle"], Value::Null);
+
+        let set = json!({"make": "Honda", "model": "Accord", "year": 2020, "front_psi": 33, "rear_psi": 32, "oil": "0W-20"});
+        assert!(module.execute("set_vehicle_profile", set).success);
+        let tires = module.execute("check_tires", json!({})).data;
+        assert_eq!(tires["tires"][0]["recommended_psi"], 33.0);
+        assert_eq!(tires["tires"][3]["recommended_psi"], 32.0);
+        assert_eq!(tires["vehicle"]["label"], "2020 Honda Accord");
+        let fluids = module.execute("check_fluids", json!({})).data;
+        assert_eq!(fluids["specs"]["oil"], "0W-20");
+    }
+
+    #[test]
+    fn test_clear_dtcs_needs_confirmation() {
+        use crate::tools::ModuleRegistry;
+        use std::sync::Arc;
+
+        let mut registry = ModuleRegistry::new();
+        registry
+            .register(Arc::new(tcp_module(SimElm::spawn_tcp(SimElm::car))))
+            .unwrap();
+        let parked = registry.execute("clear_dtcs", json!({}));
+        assert_eq!(parked.data["requires_confirmation"], true);
+        let preview = &parked.data["confirmation"]["preview"];
+        assert_eq!(preview["would_clear"].as_array().unwrap().len(), 3);
+        // Nothing is cleared until the token is confirmed.
+        let codes = registry.execute("read_dtcs", json!({}));
+        assert_eq!(codes.data["stored"].as_array().unwrap().len(), 2);
+
+        let token = parked.data["confirmation"]["token"].as_str().unwrap();
+        let cleared = registry.confirm(token);
+        assert!(cleared.success, "{:?}", cleared.error);
+        assert_eq!(cleared.data["cleared"], json!(["P0171", "P0420", "P0300"]));
+        assert_eq!(cleared.data["after"]["mil_on"], false);
+        assert_eq!(cleared.data["after"]["permanent"], json!(["P0420"]));
+    }
+
+    #[test]
+    fn test_unreachable_adapter_fails_cleanly() {
+        let unused = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = unused.local_addr().unwrap().port();
+        drop(unused);
+        let result = tcp_module(port).execute("check_engine", json!({}));
+        assert!(!result.success);
+        assert!(result.error.unwrap().contains("Cannot connect"));
+    }
+}
@@ -0,0 +1,406 @@
+//! OBD-II response decoding (SAE J1979), independent of the transport.
+//!
+//! The adapter hands back hex lines; [`parse_frames`] turns them into byte
+//! frames (reassembling the numbered lines of multi-frame CAN replies), and
+//! the decoders here turn mode 01 PID payloads into engineering units and
+//! mode 03 payloads into DTC strings.
+
+use serde::Serialize;
+
+/// A mode 01 PID this module knows how to decode.
+pub(super) struct Pid {
+    pub id: u8,
+    pub key: &'static str,
+    pub unit: &'static str,
+    /// Data bytes the formula needs.
+    bytes: usize,
+    decode: fn(&[u8]) -> f64,
+}
+
+impl Pid {
+    const fn new(
+        id: u8,
+        key: &'static str,
+        unit: &'static str,
+        bytes: usize,
+        decode: fn(&[u8]) -> f64,
+    ) -> Self {
+        Pid {
+            id,
+            key,
+            unit,
+            bytes,
+            decode,
+        }
+    }
+}
+
+fn word(d: &[u8]) -> f64 {
+    d[0] as f64 * 256.0 + d[1] as f64
+}
+
+fn pct(d: &[u8]) -> f64 {
+    d[0] as f64 * 100.0 / 255.0
+}
+
+fn trim(d: &[u8]) -> f64 {
+    (d[0] as f64 - 128.0) * 100.0 / 128.0
+}
+
+fn celsius(d: &[u8]) -> f64 {
+    d[0] as f64 - 40.0
+}
+
+pub(super) const MONITOR_STATUS: u8 = 0x01;
+/// Mode 02 only: the DTC that stored a freeze frame.
+pub(super) const FREEZE_DTC: u8 = 0x02;
+pub(super) const ENGINE_LOAD: u8 = 0x04;
+pub(super) const COOLANT_TEMP: u8 = 0x05;
+pub(super) const STFT_BANK1: u8 = 0x06;
+pub(super) const LTFT_BANK1: u8 = 0x07;
+pub(super) const STFT_BANK2: u8 = 0x08;
+pub(super) const LTFT_BANK2: u8 = 0x09;
+pub(super) const RPM: u8 = 0x0C;
+pub(super) const SPEED: u8 = 0x0D;
+pub(super) const CONTROL_MODULE_VOLTAGE: u8 = 0x42;
+/// Not every ECU reports it; most cars before 2019 do not.
+pub(super) const ODOMETER: u8 = 0xA6;
+
+pub(super) const PIDS: &[Pid] = &[
+    Pid::new(MONITOR_STATUS, "dtc_count", "", 4, |d| (d[0] & 0x7F) as f64),
+    Pid::new(ENGINE_LOAD, "engine_load", "%", 1, pct),
+    Pid::new(COOLANT_TEMP, "coolant_temp", "°C", 1, celsius),
+    Pid::new(STFT_BANK1, "stft_bank1", "%", 1, trim),
+    Pid::new(LTFT_BANK1, "ltft_bank1", "%", 1, trim),
+    Pid::new(STFT_BANK2, "stft_bank2", "%", 1, trim),
+    Pid::new(LTFT_BANK2, "ltft_bank2", "%", 1, trim),
+    Pid::new(RPM, "rpm", "rpm", 2, |d| word(d) / 4.0),
+    Pid::new(SPEED, "speed", "km/h", 1, |d| d[0] as f64),
+    Pid::new(0x0F, "intake_temp", "°C", 1, celsius),
+    Pid::new(0x10, "maf", "g/s", 2, |d| word(d) / 100.0),
+    Pid::new(0x11, "throttle", "%", 1, pct),
+    Pid::new(0x2F, "fuel_level", "%", 1, pct),
+    Pid::new(
+        CONTROL_MODULE_VOLTAGE,
+        "control_module_voltage",
+        "V",
+        2,
+        |d| word(d) / 1000.0,
+    ),
+    Pid::new(0x5C, "oil_temp", "°C", 1, celsius),
+    Pid::new(ODOMETER, "odometer", "km", 4, |d| {
+        u32::from_be_bytes([d[0], d[1], d[2], d[3]]) as f64 / 10.0
+    }),
+];
+
+pub(super) fn pid(id: u8) -> Option<&'static Pid> {
+    PIDS.iter().find(|p| p.id == id)
+}
+
+/// One decoded mode 01 value.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Reading {
+    pub pid: u8,
+    pub key: &'static str,
+    pub value: f64,
+    pub unit: &'static str,
+}
+
+/// Decode the data bytes of a mode 01 reply (after `41 <pid>`).
+pub(super) fn decode_pid(id: u8, data: &[u8]) -> Option<Reading> {
+    let p = pid(id)?;
+    if data.len() < p.bytes {
+        return None;
+    }
+    Some(Reading {
+        pid: id,
+        key: p.key,
+        value: ((p.decode)(data) * 10.0).round() / 10.0,
+        unit: p.unit,
+    })
+}
+
+/// Whether the MIL (check-engine lamp) bit is set in a PID 01 payload.
+pub(super) fn mil_on(data: &[u8]) -> bool {
+    data.first().is_some_and(|a| a & 0x80 != 0)
+}
+
+pub(super) fn hex_bytes(s: &str) -> Option<Vec<u8>> {
+    let digits: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+    if digits.is_empty() || !digits.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Turn adapter output lines into byte frames, one per responding ECU.
+///
+/// With headers off, a multi-frame CAN reply is printed as a byte-count line
+/// (`00A`) followed by numbered lines (`0: 43 05 ...`, `1: ...`); those are
+/// joined back into one frame. Lines that are not hex are skipped.
+pub(super) fn parse_frames(lines: &[String]) -> Vec<Vec<u8>> {
+    let mut frames = Vec::new();
+    let mut multi: Option<Vec<u8>> = None;
+    for line in lines {
+        let line = line.trim();
+        if line.len() == 3 && line.chars().all(|c| c.is_ascii_hexdigit()) {
+            if let Some(frame) = multi.replace(Vec::new()) {
+                frames.push(frame);
+            }
+            continue;
+        }
+        if let Some((index, rest)) = line.split_once(':') {
+            if index.trim().len() == 1 {
+                if let Some(bytes) = hex_bytes(rest) {
+                    multi.get_or_insert_with(Vec::new).extend(bytes);
+                    continue;
+                }
+            }
+        }
+        if let Some(frame) = multi.take() {
+            frames.push(frame);
+        }
+        if let Some(bytes) = hex_bytes(line) {
+            frames.push(bytes);
+        }
+    }
+    frames.extend(multi);
+    frames
+}
+
+/// The payload of the first frame answering `mode 01 <pid>`.
+pub(super) fn pid_payload(frames: &[Vec<u8>], id: u8) -> Option<&[u8]> {
+    frames
+        .iter()
+        .find(|f| f.len() > 2 && f[0] == 0x41 && f[1] == id)
+        .map(|f| &f[2..])
+}
+
+/// PIDs read from each freeze frame.
+pub(super) const FREEZE_FRAME_PIDS: &[u8] = &[
+    ENGINE_LOAD,
+    COOLANT_TEMP,
+    STFT_BANK1,
+    LTFT_BANK1,
+    STFT_BANK2,
+    LTFT_BANK2,
+    RPM,
+    SPEED,
+];
+
+/// The payload of the first frame answering `mode 02 <pid> <frame>`.
+pub(super) fn freeze_payload(frames: &[Vec<u8>], id: u8, frame: u8) -> Option<&[u8]> {
+    frames
+        .iter()
+        .find(|f| f.len() > 3 && f[0] == 0x42 && f[1] == id && f[2] == frame)
+        .map(|f| &f[3..])
+}
+
+/// Mode 09 info type for the VIN.
+pub(super) const VIN: u8 = 0x02;
+
+/// The VIN from a `09 02` reply. CAN sends one frame (`49 02 01` and 17
+/// ASCII bytes); older protocols send five numbered frames of four bytes,
+/// the first padded with zeros.
+pub(super) fn parse_vin(frames: &[Vec<u8>]) -> Option<String> {
+    let mut bytes = Vec::new();
+    for frame in frames
+        .iter()
+        .filter(|f| f.len() > 3 && f[0] == 0x49 && f[1] == VIN)
+    {
+        bytes.extend_from_slice(&frame[3..]);
+    }
+    let vin: String = bytes
+        .iter()
+        .filter(|b| b.is_ascii_alphanumeric())
+        .map(|&b| b as char)
+        .collect();
+    (vin.len() >= 17).then(|| vin[vin.len() - 17..].to_string())
+}
+
+/// Which DTC list to read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(super) enum DtcKind {
+    /// Mode 03: confirmed codes that set the MIL.
+    Stored,
+    /// Mode 07: detected on the current or last drive cycle, not yet confirmed.
+    Pending,
+    /// Mode 0A: only the ECU can clear these, once the fault passes its test.
+    Permanent,
+}
+
+impl DtcKind {
+    pub(super) fn mode(self) -> u8 {
+        match self {
+            DtcKind::Stored => 0x03,
+            DtcKind::Pending => 0x07,
+            DtcKind::Permanent => 0x0A,
+        }
+    }
+}
+
+/// Check the reply to mode 04 (clear DTCs): `44` on success, or a negative
+/// response `7F 04 <reason>` when the ECU refuses.
+pub(super) fn check_clear_reply(frames: &[Vec<u8>]) -> Result<(), String> {
+    if frames.iter().any(|f| f.first() == Some(&0x44)) {
+        return Ok(());
+    }
+    match frames
+        .iter()
+        .find(|f| f.len() >= 3 && f[0] == 0x7F && f[1] == 0x04)
+    {
+        Some(f) if f[2] == 0x22 => Err("ECU refused to clear codes: conditions not correct \
+             (switch the engine off, leave the ignition on, and try again)"
+            .into()),
+        Some(f) => Err(format!("ECU refused to clear codes (reason {:02X})", f[2])),
+        None => Err("Vehicle did not acknowledge the clear request".into()),
+    }
+}
+
+/// "P0171" from the two bytes of a DTC.
+pub(super) fn decode_dtc(a: u8, b: u8) -> String {
+    let system = ['P', 'C', 'B', 'U'][(a >> 6) as usize];
+    format!("{}{}{:X}{:02X}", system, (a >> 4) & 0x03, a & 0x0F, b)
+}
+
+/// Decode the DTCs in every frame that answers `response_mode` (0x43 for
+/// mode 03), skipping `0000` padding and duplicates across ECUs.
+pub(super) fn parse_dtcs(frames: &[Vec<u8>], response_mode: u8) -> Vec<String> {
+    let mut codes: Vec<String> = Vec::new();
+    for frame in frames.iter().filter(|f| f.first() == Some(&response_mode)) {
+        let mut body = &frame[1..];
+        // CAN replies carry a DTC count before the pairs, leaving an odd
+        // number of bytes; legacy protocols pad to three pairs.
+        if !body.len().is_multiple_of(2) {
+            body = &body[1..];
+        }
+        for pair in body.chunks_exact(2) {
+            if pair == [0, 0] {
+                continue;
+            }
+            let code = decode_dtc(pair[0], pair[1]);
+            if !codes.contains(&code) {
+                codes.push(code);
+            }
+        }
+    }
+    codes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(raw: &[&str]) -> Vec<String> {
+        raw.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_decode_pids() {
+        assert_eq!(decode_pid(RPM, &[0x0D, 0x48]).unwrap().value, 850.0);
+        assert_eq!(decode_pid(COOLANT_TEMP, &[0x7B]).unwrap().value, 83.0);
+        assert_eq!(decode_pid(ENGINE_LOAD, &[0xFF]).unwrap().value, 100.0);
+        assert_eq!(decode_pid(STFT_BANK1, &[0x80]).unwrap().value, 0.0);
+        assert_eq!(decode_pid(LTFT_BANK1, &[0x8A]).unwrap().value, 7.8);
+        assert_eq!(
+            decode_pid(CONTROL_MODULE_VOLTAGE, &[0x30, 0x70])
+                .unwrap()
+                .value,
+            12.4
+        );
+        assert_eq!(
+            decode_pid(MONITOR_STATUS, &[0x82, 0x07, 0x65, 0x00])
+                .unwrap()
+                .value,
+            2.0
+        );
+        assert!(mil_on(&[0x82]));
+        assert!(!mil_on(&[0x02]));
+        assert!(decode_pid(RPM, &[0x0D]).is_none());
+        assert!(decode_pid(0xEE, &[0x00]).is_none());
+    }
+
+    #[test]
+    fn test_parse_frames_single_and_multi() {
+        let frames = parse_frames(&lines(&["41 0C 0D 48", "41 0C 0D 50"]));
+        assert_eq!(frames.len(), 2);
+        assert_eq!(pid_payload(&frames, RPM), Some(&[0x0D, 0x48][..]));
+
+        // Multi-frame CAN with headers off.
+        let frames = parse_frames(&lines(&[
+            "00A",
+            "0: 43 04 01 71 04",
+            "1: 20 03 00 01 33 00 00",
+        ]));
+        assert_eq!(
+            frames,
+            vec![vec![
+                0x43, 0x04, 0x01, 0x71, 0x04, 0x20, 0x03, 0x00, 0x01, 0x33, 0x00, 0x00
+            ]]
+        );
+        assert!(parse_frames(&lines(&["BUS INIT: ...", ""])).is_empty());
+    }
+
+    #[test]
+    fn test_freeze_frame_and_clear_replies() {
+        let frames = parse_frames(&lines(&["42 02 00 01 71", "42 0C 00 22 60"]));
+        assert_eq!(
+            freeze_payload(&frames, FREEZE_DTC, 0),
+            Some(&[0x01, 0x71][..])
+        );
+        assert_eq!(freeze_payload(&frames, RPM, 0), Some(&[0x22, 0x60][..]));
+        assert!(freeze_payload(&frames, RPM, 1).is_none());
+
+        assert!(check_clear_reply(&parse_frames(&lines(&["44"]))).is_ok());
+        let refused = check_clear_reply(&parse_frames(&lines(&["7F 04 22"]))).unwrap_err();
+        assert!(refused.contains("conditions not correct"));
+        assert!(check_clear_reply(&[]).is_err());
+    }
+
+    #[test]
+    fn test_parse_vin() {
+        let can = parse_frames(&lines(&[
+            "014",
+            "0: 49 02 01 31 48 47",
+            "1: 43 56 31 46 33 37 4C",
+            "2: 41 30 31 32 33 34 35",
+        ]));
+        assert_eq!(parse_vin(&can).as_deref(), Some("1HGCV1F37LA012345"));
+
+        let legacy = parse_frames(&lines(&[
+            "49 02 01 00 00 00 31",
+            "49 02 02 48 47 43 56",
+            "49 02 03 31 46 33 37",
+            "49 02 04 4C 41 30 31",
+            "49 02 05 32 33 34 35",
+        ]));
+        assert_eq!(parse_vin(&legacy).as_deref(), Some("1HGCV1F37LA012345"));
+        assert_eq!(parse_vin(&parse_frames(&lines(&["49 02 01 31 48"]))), None);
+        assert_eq!(
+            decode_pid(ODOMETER, &[0x00, 0x01, 0xE2, 0x40])
+                .unwrap()
+                .value,
+            12345.6
+        );
+    }
+
+    #[test]
+    fn test_parse_dtcs() {
+        assert_eq!(decode_dtc(0x01, 0x71), "P0171");
+        assert_eq!(decode_dtc(0x44, 0x20), "C0420");
+        assert_eq!(decode_dtc(0xC1, 0x00), "U0100");
+
+        // Legacy: three pairs, zero padded, two ECUs repeating a code.
+        let legacy = parse_frames(&lines(&["43 01 71 04 20 00 00", "43 01 71 00 00 00 00"]));
+        assert_eq!(parse_dtcs(&legacy, 0x43), vec!["P0171", "P0420"]);
+
+        // CAN: count byte first.
+        let can = parse_frames(&lines(&["43 02 01 71 04 20"]));
+        assert_eq!(parse_dtcs(&can, 0x43), vec!["P0171", "P0420"]);
+        assert!(parse_dtcs(&parse_frames(&lines(&["43 00"])), 0x43).is_empty());
+    }
+}
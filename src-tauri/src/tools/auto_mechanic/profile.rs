@@ -0,0 +1,510 @@
+//! Which car this is: the vehicle profile and its maintenance log, kept
+//! together in `garage.json`, and the identity decoded from its VIN.
+
+use super::findings::CheckupRules;
+use super::maintenance::{ScheduleRule, ServiceEntry};
+use super::source::VehicleSource;
+use super::vin;
+use super::{failure, ToolResult};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+/// Door-jamb pressure used until the profile sets one.
+const GENERIC_PSI: f64 = 35.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TirePressure {
+    pub front_psi: f64,
+    pub rear_psi: f64,
+}
+
+impl Default for TirePressure {
+    fn default() -> Self {
+        TirePressure {
+            front_psi: GENERIC_PSI,
+            rear_psi: GENERIC_PSI,
+        }
+    }
+}
+
+/// What to top up with, from the owner's manual.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FluidSpecs {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oil: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coolant: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub brake_fluid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transmission: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VehicleProfile {
+    #[serde(default)]
+    pub vin: Option<String>,
+    #[serde(default)]
+    pub make: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub year: Option<u16>,
+    #[serde(default)]
+    pub tire_pressure: TirePressure,
+    #[serde(default)]
+    pub fluids: FluidSpecs,
+    /// Service intervals; see [`ScheduleRule::defaults`].
+    #[serde(default = "ScheduleRule::defaults")]
+    pub schedule: Vec<ScheduleRule>,
+    /// Checkup thresholds; see [`CheckupRules`].
+    #[serde(default)]
+    pub checkup: CheckupRules,
+}
+
+impl Default for VehicleProfile {
+    fn default() -> Self {
+        VehicleProfile {
+            vin: None,
+            make: None,
+            model: None,
+            year: None,
+            tire_pressure: TirePressure::default(),
+            fluids: FluidSpecs::default(),
+            schedule: ScheduleRule::defaults(),
+            checkup: CheckupRules::default(),
+        }
+    }
+}
+
+impl VehicleProfile {
+    /// Whether anything identifies the car.
+    pub(super) fn configured(&self) -> bool {
+        self.vin.is_some() || self.make.is_some() || self.model.is_some()
+    }
+
+    /// Apply the fields present in `set_vehicle_profile` arguments.
+    pub(super) fn update(&mut self, args: &Value) -> Result<(), String> {
+        let text = |key: &str| {
+            args.get(key)
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(String::from)
+        };
+        if let Some(raw) = text("vin") {
+            let info = vin::decode(&raw)?;
+            if !info.valid() {
+                return Err(format!(
+                    "VIN {} fails its check digit (expected {} at position 9); check for a typo",
+                    info.vin, info.check_digit.expected
+                ));
+            }
+            self.vin = Some(info.vin);
+        }
+        if let Some(make) = text("make") {
+            self.make = Some(make);
+        }
+        if let Some(model) = text("model") {
+            self.model = Some(model);
+        }
+        if let Some(year) = args.get("year").and_then(|v| v.as_u64()) {
+            if !(1981..=2100).contains(&year) {
+                return Err(format!("Model year {} is out of range", year));
+            }
+            self.year = Some(year as u16);
+        }
+        for (key, psi) in [
+            ("front_psi", &mut self.tire_pressure.front_psi),
+            ("rear_psi", &mut self.tire_pressure.rear_psi),
+        ] {
+            if let Some(value) = args.get(key).and_then(|v| v.as_f64()) {
+                if !(15.0..=80.0).contains(&value) {
+                    return Err(format!("{} of {} psi is out of range", key, value));
+                }
+                *psi = value;
+            }
+        }
+        for (key, spec) in [
+            ("oil", &mut self.fluids.oil),
+            ("coolant", &mut self.fluids.coolant),
+            ("brake_fluid", &mut self.fluids.brake_fluid),
+            ("transmission_fluid", &mut self.fluids.transmission),
+        ] {
+            if let Some(value) = text(key) {
+                *spec = Some(value);
+            }
+        }
+        if let Some(patch) = args.get("checkup") {
+            self.checkup = merge_rules(&self.checkup, patch)?;
+        }
+        Ok(())
+    }
+}
+
+/// Overlay the thresholds in `patch` on `rules`. Fluid tables merge per
+/// fluid, so `{"fluids": {"coolant": {"low": "critical"}}}` leaves the
+/// others alone.
+fn merge_rules(rules: &CheckupRules, patch: &Value) -> Result<CheckupRules, String> {
+    let Some(patch) = patch.as_object() else {
+        return Err("checkup must be an object of thresholds".into());
+    };
+    let mut merged = json!(rules);
+    for (key, value) in patch {
+        match (merged.get_mut(key), value) {
+            (None, _) => return Err(format!("Unknown checkup rule {:?}", key)),
+            (Some(Value::Object(current)), Value::Object(changes)) => {
+                for (k, v) in changes {
+                    match (current.get_mut(k), v) {
+                        (Some(Value::Object(states)), Value::Object(new)) => {
+                            states.extend(new.clone());
+                        }
+                        _ => {
+                            current.insert(k.clone(), v.clone());
+                        }
+                    }
+                }
+            }
+            (Some(slot), _) => *slot = value.clone(),
+        }
+    }
+    serde_json::from_value(merged).map_err(|e| format!("Invalid checkup rules: {}", e))
+}
+
+/// The profile and maintenance log, saved to `garage.json` after every
+/// change. Without a path (demo and tests) changes stay in memory.
+#[derive(Debug, Default)]
+pub struct Garage {
+    path: Option<PathBuf>,
+    pub(super) profile: VehicleProfile,
+    pub(super) log: Vec<ServiceEntry>,
+    /// The VIN the car last reported; not saved.
+    pub(super) detected_vin: Option<String>,
+}
+
+/// Who the car is, for labelling results: the profile's fields, filled in
+/// from the decoded VIN where the profile leaves them blank.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(super) struct Identity {
+    pub label: String,
+    pub vin: Option<String>,
+    pub make: Option<String>,
+    pub model: Option<String>,
+    pub year: Option<u16>,
+    pub manufacturer: Option<String>,
+    pub country: Option<&'static str>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct GarageFile {
+    #[serde(default)]
+    profile: VehicleProfile,
+    #[serde(default)]
+    log: Vec<ServiceEntry>,
+}
+
+impl Garage {
+    /// Load a garage file. A missing file yields an empty profile that is
+    /// created on the first save.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let mut garage = Garage {
+            path: Some(path.to_path_buf()),
+            ..Default::default()
+        };
+        if !path.exists() {
+            return Ok(garage);
+        }
+        let raw = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read garage {}: {}", path.display(), e))?;
+        let file: GarageFile = serde_json::from_str(&raw)
+            .map_err(|e| format!("Invalid garage {}: {}", path.display(), e))?;
+        garage.profile = file.profile;
+        garage.log = file.log;
+        Ok(garage)
+    }
+
+    pub(super) fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let file = GarageFile {
+            profile: self.profile.clone(),
+            log: self.log.clone(),
+        };
+        let raw = serde_json::to_string_pretty(&file).map_err(|e| e.to_string())?;
+        std::fs::write(path, raw)
+            .map_err(|e| format!("Failed to save garage {}: {}", path.display(), e))
+    }
+
+    /// The profile's VIN, else the one the car reported.
+    pub(super) fn vin(&self) -> Option<&str> {
+        self.profile.vin.as_deref().or(self.detected_vin.as_deref())
+    }
+
+    /// `None` until the profile is filled in or the car reports a VIN.
+    pub(super) fn identity(&self) -> Option<Identity> {
+        let decoded = self.vin().and_then(|v| vin::decode(v).ok());
+        let decoded = decoded.as_ref();
+        let make = self
+            .profile
+            .make
+            .clone()
+            .or_else(|| decoded.and_then(|d| d.make.clone()));
+        let year = self
+            .profile
+            .year
+            .or_else(|| decoded.and_then(|d| d.model_year));
+        let model = self.profile.model.clone();
+        let parts: Vec<String> = [year.map(|y| y.to_string()), make.clone(), model.clone()]
+            .into_iter()
+            .flatten()
+            .collect();
+        let vin = self.vin().map(String::from);
+        let label = match (parts.is_empty(), &vin) {
+            (false, _) => parts.join(" "),
+            (true, Some(vin)) => format!("VIN {}", vin),
+            (true, None) => return None,
+        };
+        Some(Identity {
+            label,
+            vin,
+            make,
+            model,
+            year,
+            manufacturer: decoded.and_then(|d| d.manufacturer.clone()),
+            country: decoded.and_then(|d| d.country),
+        })
+    }
+}
+
+fn profile_data(garage: &Garage) -> Value {
+    let mut data = json!(garage.profile);
+    data["configured"] = json!(garage.profile.configured());
+    data["identity"] = json!(garage.identity());
+    data
+}
+
+/// The profile, plus the VIN the car reports so a mismatch (wrong car, or a
+/// profile never filled in) is visible.
+pub(super) fn get_vehicle_profile(
+    source: &mut dyn VehicleSource,
+    garage: &mut Garage,
+) -> ToolResult {
+    let reported = source.read_vin();
+    if let Ok(Some(vin)) = &reported {
+        garage.detected_vin = Some(vin.clone());
+    }
+    let mut data = profile_data(garage);
+    match reported {
+        Ok(vin) => {
+            data["vin_matches"] = json!(vin
+                .as_ref()
+                .zip(garage.profile.vin.as_ref())
+                .map(|(car, profile)| car == profile));
+            data["vin_from_vehicle"] = json!(vin);
+        }
+        Err(e) => {
+            data["vin_from_vehicle"] = Value::Null;
+            data["vin_error"] = json!(e);
+        }
+    }
+    ToolResult {
+        success: true,
+        data,
+        error: None,
+    }
+}
+
+/// Update the profile from the arguments; `read_vin` takes the VIN from the
+/// car. Nothing is saved unless every field is valid.
+pub(super) fn set_vehicle_profile(
+    source: &mut dyn VehicleSource,
+    garage: &mut Garage,
+    args: &Value,
+) -> ToolResult {
+    let mut profile = garage.profile.clone();
+    if let Err(e) = profile.update(args) {
+        return failure(e);
+    }
+    if args.get("read_vin").and_then(|v| v.as_bool()) == Some(true) {
+        match source.read_vin() {
+            Ok(Some(vin)) => {
+                garage.detected_vin = Some(vin.clone());
+                profile.vin = Some(vin);
+            }
+            Ok(None) => return failure("Vehicle did not report a VIN (mode 09)".into()),
+            Err(e) => return failure(e),
+        }
+    }
+    let previous = std::mem::replace(&mut garage.profile, profile);
+    if let Err(e) = garage.save() {
+        garage.profile = previous;
+        return failure(e);
+    }
+    ToolResult {
+        success: true,
+        data: profile_data(garage),
+        error: None,
+    }
+}
+
+/// Decode the VIN given, else the profile's, else the one the car reports.
+pub(super) fn decode_vin(
+    source: &mut dyn VehicleSource,
+    garage: &mut Garage,
+    args: &Value,
+) -> ToolResult {
+    let given = args
+        .get("vin")
+        .and_then(|v| v.as_str())
+        .filter(|v| !v.trim().is_empty());
+    let (raw, from) = if let Some(vin) = given {
+        (vin.to_string(), "input")
+    } else if let Some(vin) = &garage.profile.vin {
+        (vin.clone(), "profile")
+    } else {
+        match source.read_vin() {
+            Ok(Some(vin)) => {
+                garage.detected_vin = Some(vin.clone());
+                (vin, "vehicle")
+            }
+            Ok(None) => return failure(
+                "Vehicle did not report a VIN (mode 09); read it from the windshield or door jamb"
+                    .into(),
+            ),
+            Err(e) => return failure(e),
+        }
+    };
+    let info = match vin::decode(&raw) {
+        Ok(info) => info,
+        Err(e) => return failure(e),
+    };
+    let mut data = json!(info);
+    data["valid"] = json!(info.valid());
+    data["vin_source"] = json!(from);
+    if !info.valid() {
+        data["warning"] = json!(format!(
+            "Check digit is {} but should be {}; the VIN is probably mistyped",
+            info.check_digit.actual, info.check_digit.expected
+        ));
+    }
+    ToolResult {
+        success: true,
+        data,
+        error: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::source::DemoSource;
+    use super::*;
+
+    #[test]
+    fn test_profile_update_and_persist() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("garage.json");
+        let mut garage = Garage::load(&path).unwrap();
+        assert!(!garage.profile.configured());
+        assert_eq!(garage.profile.tire_pressure.front_psi, 35.0);
+
+        garage
+            .profile
+            .update(&json!({
+                "vin": "1hgcv1f37la012345",
+                "make": "Honda",
+                "model": "Accord",
+                "year": 2020,
+                "front_psi": 33,
+                "rear_psi": 33,
+                "oil": "0W-20 full synthetic, 4.4 qt"
+            }))
+            .unwrap();
+        garage.save().unwrap();
+
+        let reloaded = Garage::load(&path).unwrap();
+        assert_eq!(reloaded.profile, garage.profile);
+        assert_eq!(reloaded.profile.vin.as_deref(), Some("1HGCV1F37LA012345"));
+        assert_eq!(reloaded.identity().unwrap().label, "2020 Honda Accord");
+        assert_eq!(reloaded.profile.tire_pressure.rear_psi, 33.0);
+        assert!(reloaded.profile.fluids.coolant.is_none());
+
+        assert!(garage.profile.update(&json!({"front_psi": 350})).is_err());
+        assert!(garage.profile.update(&json!({"year": 1920})).is_err());
+        let rules =
+            json!({"checkup": {"tread_min_mm": 2.0, "fluids": {"coolant": {"low": "critical"}}}});
+        garage.profile.update(&rules).unwrap();
+        assert_eq!(garage.profile.checkup.tread_min_mm, 2.0);
+        assert_eq!(garage.profile.checkup.fluids.len(), 5);
+        assert_eq!(garage.profile.checkup.fluids["oil"].len(), 2);
+        assert!(garage
+            .profile
+            .update(&json!({"checkup": {"tread": 2}}))
+            .is_err());
+        assert!(garage
+            .profile
+            .update(&json!({"checkup": {"dtc": {"low": "bad"}}}))
+            .is_err());
+        // A mistyped VIN fails its check digit and is not saved.
+        assert!(garage
+            .profile
+            .update(&json!({"vin": "1HGCV1F36LA012345"}))
+            .unwrap_err()
+            .contains("check digit"));
+        assert_eq!(garage.profile.vin.as_deref(), Some("1HGCV1F37LA012345"));
+    }
+
+    #[test]
+    fn test_profile_tools_read_vin() {
+        let mut source = DemoSource::default();
+        let mut garage = Garage::default();
+        let data = get_vehicle_profile(&mut source, &mut garage).data;
+        assert_eq!(data["configured"], false);
+        assert_eq!(data["vin_from_vehicle"], "1HGCV1F37LA012345");
+        assert_eq!(data["vin_matches"], Value::Null);
+        // The car's VIN identifies it before the profile is filled in.
+        assert_eq!(data["identity"]["label"], "2020 Honda");
+
+        let result = set_vehicle_profile(
+            &mut source,
+            &mut garage,
+            &json!({"read_vin": true, "make": "Honda", "rear_psi": 80.5}),
+        );
+        assert!(!result.success);
+        assert!(garage.profile.make.is_none());
+
+        let result = set_vehicle_profile(
+            &mut source,
+            &mut garage,
+            &json!({"read_vin": true, "make": "Honda"}),
+        );
+        assert!(result.success);
+        assert_eq!(result.data["vin"], "1HGCV1F37LA012345");
+        assert_eq!(
+            get_vehicle_profile(&mut source, &mut garage).data["vin_matches"],
+            true
+        );
+    }
+
+    #[test]
+    fn test_decode_vin_sources() {
+        let mut source = DemoSource::default();
+        let mut garage = Garage::default();
+        let data = decode_vin(&mut source, &mut garage, &json!({})).data;
+        assert_eq!(data["vin_source"], "vehicle");
+        assert_eq!(data["model_year"], 2020);
+        assert_eq!(garage.detected_vin.as_deref(), Some("1HGCV1F37LA012345"));
+
+        let data = decode_vin(
+            &mut source,
+            &mut garage,
+            &json!({"vin": "1HGCV1F36LA012345"}),
+        )
+        .data;
+        assert_eq!(data["vin_source"], "input");
+        assert_eq!(data["valid"], false);
+        assert!(data["warning"].is_string());
+        assert!(!decode_vin(&mut source, &mut garage, &json!({"vin": "12345"})).success);
+    }
+}
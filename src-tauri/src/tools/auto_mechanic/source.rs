@@ -0,0 +1,249 @@
+//! Where vehicle data comes from: canned demo values or a live ELM327.
+
+use super::elm327::Elm327;
+use super::obd::{self, DtcKind};
+use serde_json::{json, Value};
+
+pub(super) trait VehicleSource: Send {
+    /// "demo", "serial" or "tcp".
+    fn kind(&self) -> &'static str;
+
+    /// Raw mode 01 payload for a PID, or `None` when the vehicle does not
+    /// support it.
+    fn read_pid(&mut self, pid: u8) -> Result<Option<Vec<u8>>, String>;
+
+    /// Stored (mode 03), pending (07) or permanent (0A) DTCs.
+    fn read_dtcs(&mut self, kind: DtcKind) -> Result<Vec<String>, String>;
+
+    /// Raw mode 02 payload for a PID in a freeze frame, or `None` when the
+    /// frame does not hold it.
+    fn read_freeze_pid(&mut self, frame: u8, pid: u8) -> Result<Option<Vec<u8>>, String>;
+
+    /// Mode 09: the VIN, or `None` when the ECU does not report it.
+    fn read_vin(&mut self) -> Result<Option<String>, String>;
+
+    /// Mode 04: clear stored and pending DTCs, freeze frames and readiness
+    /// monitors, and turn the MIL off.
+    fn clear_dtcs(&mut self) -> Result<(), String>;
+
+    /// Adapter details for tool output.
+    fn describe(&self) -> Value {
+        json!({ "source": self.kind() })
+    }
+}
+
+/// A 2020 Honda Accord.
+pub(super) const DEMO_VIN: &str = "1HGCV1F37LA012345";
+
+/// Canned data for demos and for running without an adapter. Clearing codes
+/// behaves like a real ECU until the app restarts.
+pub(super) struct DemoSource {
+    stored: Vec<String>,
+    pending: Vec<String>,
+    permanent: Vec<String>,
+    /// Freeze frame 0, stored with the first code.
+    freeze_frame: bool,
+}
+
+impl Default for DemoSource {
+    fn default() -> Self {
+        DemoSource {
+            stored: vec!["P0171".into(), "P0420".into()],
+            pending: vec!["P0300".into()],
+            permanent: vec!["P0420".into()],
+            freeze_frame: true,
+        }
+    }
+}
+
+impl VehicleSource for DemoSource {
+    fn kind(&self) -> &'static str {
+        "demo"
+    }
+
+    fn read_pid(&mut self, pid: u8) -> Result<Option<Vec<u8>>, String> {
+        let payload: &[u8] = match pid {
+            obd::MONITOR_STATUS => {
+                let mil = if self.stored.is_empty() { 0x00 } else { 0x80 };
+                return Ok(Some(vec![mil | self.stored.len() as u8, 0x07, 0x65, 0x00]));
+            }
+            obd::ENGINE_LOAD => &[0x33],
+            obd::COOLANT_TEMP => &[0x82],
+            obd::STFT_BANK1 => &[0x8C],
+            obd::LTFT_BANK1 => &[0x8F],
+            obd::RPM => &[0x0D, 0x48],
+            obd::SPEED => &[0x00],
+            obd::CONTROL_MODULE_VOLTAGE => &[0x30, 0x70],
+            // 72,420.5 km (45,000 miles).
+            obd::ODOMETER => &[0x00, 0x0B, 0x0C, 0xED],
+            _ => return Ok(None),
+        };
+        Ok(Some(payload.to_vec()))
+    }
+
+    fn read_dtcs(&mut self, kind: DtcKind) -> Result<Vec<String>, String> {
+        Ok(match kind {
+            DtcKind::Stored => self.stored.clone(),
+            DtcKind::Pending => self.pending.clone(),
+            DtcKind::Permanent => self.permanent.clone(),
+        })
+    }
+
+    fn read_freeze_pid(&mut self, frame: u8, pid: u8) -> Result<Option<Vec<u8>>, String> {
+        if frame != 0 || !self.freeze_frame {
+            return Ok(None);
+        }
+        // Lean under load at highway speed, when P0171 set.
+        let payload: &[u8] = match pid {
+            obd::FREEZE_DTC => &[0x01, 0x71],
+            obd::ENGINE_LOAD => &[0x59],
+            obd::COOLANT_TEMP => &[0x80],
+            obd::STFT_BANK1 => &[0x9A],
+            obd::LTFT_BANK1 => &[0x94],
+            obd::RPM => &[0x22, 0x60],
+            obd::SPEED => &[0x48],
+            _ => return Ok(None),
+        };
+        Ok(Some(payload.to_vec()))
+    }
+
+    fn read_vin(&mut self) -> Result<Option<String>, String> {
+        Ok(Some(DEMO_VIN.into()))
+    }
+
+    fn clear_dtcs(&mut self) -> Result<(), String> {
+        self.stored.clear();
+        self.pending.clear();
+        self.freeze_frame = false;
+        Ok(())
+    }
+}
+
+type Connector = Box<dyn Fn() -> Result<Elm327, String> + Send>;
+
+/// A live ELM327. Connects and initialises on first use; any failure drops
+/// the connection so the next call starts over (dongle power-cycled, car
+/// turned off and on).
+pub(super) struct Elm327Source {
+    kind: &'static str,
+    connect: Connector,
+    conn: Option<Elm327>,
+}
+
+impl Elm327Source {
+    pub(super) fn new(
+        kind: &'static str,
+        connect: impl Fn() -> Result<Elm327, String> + Send + 'static,
+    ) -> Self {
+        Elm327Source {
+            kind,
+            connect: Box::new(connect),
+            conn: None,
+        }
+    }
+
+    fn with_adapter<T>(
+        &mut self,
+        f: impl FnOnce(&mut Elm327) -> Result<T, String>,
+    ) -> Result<T, String> {
+        if self.conn.is_none() {
+            let mut elm = (self.connect)()?;
+            elm.initialize()?;
+            self.conn = Some(elm);
+        }
+        let result = f(self.conn.as_mut().expect("connected above"));
+        if result.is_err() {
+            self.conn = None;
+        }
+        result
+    }
+}
+
+impl VehicleSource for Elm327Source {
+    fn kind(&self) -> &'static str {
+        self.kind
+    }
+
+    fn read_pid(&mut self, pid: u8) -> Result<Option<Vec<u8>>, String> {
+        self.with_adapter(|elm| {
+            let frames = elm.query(&[0x01, pid])?;
+            Ok(obd::pid_payload(&frames, pid).map(|p| p.to_vec()))
+        })
+    }
+
+    fn read_dtcs(&mut self, kind: DtcKind) -> Result<Vec<String>, String> {
+        self.with_adapter(|elm| {
+            let frames = elm.query(&[kind.mode()])?;
+            Ok(obd::parse_dtcs(&frames, kind.mode() + 0x40))
+        })
+    }
+
+    fn read_freeze_pid(&mut self, frame: u8, pid: u8) -> Result<Option<Vec<u8>>, String> {
+        self.with_adapter(|elm| {
+            let frames = elm.query(&[0x02, pid, frame])?;
+            Ok(obd::freeze_payload(&frames, pid, frame).map(|p| p.to_vec()))
+        })
+    }
+
+    fn read_vin(&mut self) -> Result<Option<String>, String> {
+        self.with_adapter(|elm| Ok(obd::parse_vin(&elm.query(&[0x09, obd::VIN])?)))
+    }
+
+    fn clear_dtcs(&mut self) -> Result<(), String> {
+        self.with_adapter(|elm| obd::check_clear_reply(&elm.query(&[0x04])?))
+    }
+
+    fn describe(&self) -> Value {
+        let conn = self.conn.as_ref();
+        json!({
+            "source": self.kind,
+            "adapter": conn.and_then(|c| c.version.clone()),
+            "protocol": conn.and_then(|c| c.protocol.clone()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::elm327::sim::SimElm;
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_elm327_source_connects_lazily_and_reconnects() {
+        let port = SimElm::spawn_tcp(SimElm::car);
+        let mut source = Elm327Source::new("tcp", move || {
+            Elm327::connect_tcp("127.0.0.1", port, Duration::from_secs(2))
+        });
+        assert!(source.describe()["adapter"].is_null());
+
+        assert_eq!(source.read_pid(obd::RPM).unwrap(), Some(vec![0x0D, 0x48]));
+        assert_eq!(source.read_pid(obd::STFT_BANK2).unwrap(), None);
+        assert_eq!(
+            source.read_dtcs(DtcKind::Stored).unwrap(),
+            vec!["P0171", "P0420"]
+        );
+        assert_eq!(source.read_dtcs(DtcKind::Pending).unwrap(), vec!["P0300"]);
+        assert_eq!(source.read_dtcs(DtcKind::Permanent).unwrap(), vec!["P0420"]);
+        assert_eq!(
+            source.read_freeze_pid(0, obd::FREEZE_DTC).unwrap(),
+            Some(vec![0x01, 0x71])
+        );
+        assert_eq!(source.read_freeze_pid(1, obd::RPM).unwrap(), None);
+        assert_eq!(
+            source.read_vin().unwrap().as_deref(),
+            Some("1HGCV1F37LA012345")
+        );
+        assert_eq!(source.describe()["adapter"], "ELM327 v1.5");
+
+        // Dropped connection: the next call reconnects.
+        source.conn = None;
+        assert_eq!(source.read_pid(obd::ENGINE_LOAD).unwrap(), Some(vec![0x40]));
+    }
+
+    #[test]
+    fn test_elm327_source_reports_connect_failure() {
+        let mut source = Elm327Source::new("tcp", || Err("Cannot connect".into()));
+        assert_eq!(source.read_pid(obd::RPM).unwrap_err(), "Cannot connect");
+    }
+}
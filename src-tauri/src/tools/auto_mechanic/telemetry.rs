@@ -0,0 +1,612 @@
+//! Live telemetry: poll a set of PIDs at a fixed rate, hand each sample to a
+//! callback (the app forwards them as Tauri events), record the session, and
+//! replay a recording as a vehicle source.
+//!
+//! A recording is written twice: `<stem>.csv` with decoded values for
+//! spreadsheets, and `<stem>.jsonl` with the raw PID payloads. The JSONL file
+//! starts with a header line and has one sample per line; it is the one
+//! [`ReplaySource`] reads, so a replay decodes exactly what the car sent.
+
+use super::obd::{self, DtcKind, Reading};
+use super::source::VehicleSource;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Polled when neither the request nor `vehicle.json` names any PIDs.
+const DEFAULT_PIDS: &[&str] = &[
+    "rpm",
+    "speed",
+    "coolant_temp",
+    "engine_load",
+    "throttle",
+    "control_module_voltage",
+];
+
+const DEFAULT_INTERVAL_MS: u64 = 500;
+
+/// An ELM327 answers roughly one PID per 50 ms; faster polling only queues.
+const MIN_INTERVAL_MS: u64 = 100;
+
+const FORMAT: &str = "sentinel-telemetry";
+
+/// What to poll and how often. The `telemetry` section of `vehicle.json`
+/// sets the defaults; a start request overrides any field it sets.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TelemetryOptions {
+    /// PID keys (`rpm`, `coolant_temp`) or hex ids (`0C`).
+    #[serde(default)]
+    pub pids: Vec<String>,
+    #[serde(default)]
+    pub interval_ms: Option<u64>,
+    /// Record to `<record>.csv` and `<record>.jsonl`.
+    #[serde(default)]
+    pub record: Option<PathBuf>,
+}
+
+impl TelemetryOptions {
+    /// Fill unset fields from `defaults`. Recording is never inherited.
+    pub(super) fn or(self, defaults: &TelemetryOptions) -> Self {
+        TelemetryOptions {
+            pids: if self.pids.is_empty() {
+                defaults.pids.clone()
+            } else {
+                self.pids
+            },
+            interval_ms: self.interval_ms.or(defaults.interval_ms),
+            record: self.record,
+        }
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_millis(
+            self.interval_ms
+                .unwrap_or(DEFAULT_INTERVAL_MS)
+                .max(MIN_INTERVAL_MS),
+        )
+    }
+
+    /// Resolve PID names to ids, rejecting ones the decoder does not know.
+    fn resolve_pids(&self) -> Result<Vec<u8>, String> {
+        let names: Vec<&str> = if self.pids.is_empty() {
+            DEFAULT_PIDS.to_vec()
+        } else {
+            self.pids.iter().map(String::as_str).collect()
+        };
+        let mut ids = Vec::new();
+        for name in names {
+            let id = obd::PIDS
+                .iter()
+                .find(|p| p.key == name)
+                .map(|p| p.id)
+                .or_else(|| {
+                    u8::from_str_radix(name.trim_start_matches("0x"), 16)
+                        .ok()
+                        .filter(|id| obd::pid(*id).is_some())
+                })
+                .ok_or_else(|| format!("Unknown PID for telemetry: {}", name))?;
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+        Ok(ids)
+    }
+}
+
+/// One poll of every PID. `raw` holds the payloads that were answered.
+#[derive(Debug, Clone, Serialize)]
+pub struct Sample {
+    /// Milliseconds since the session started.
+    pub t_ms: u64,
+    /// Wall-clock time, Unix milliseconds.
+    pub timestamp_ms: u64,
+    pub readings: Vec<Reading>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip)]
+    raw: Vec<(u8, Vec<u8>)>,
+}
+
+/// How a finished session went.
+#[derive(Debug, Clone, Serialize)]
+pub struct TelemetrySummary {
+    pub samples: u64,
+    pub errors: u64,
+    pub duration_ms: u64,
+    pub recording: Vec<PathBuf>,
+}
+
+fn unix_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+fn poll(source: &mut dyn VehicleSource, pids: &[u8], started: Instant) -> Sample {
+    let mut sample = Sample {
+        t_ms: started.elapsed().as_millis() as u64,
+        timestamp_ms: unix_ms(),
+        readings: Vec::new(),
+        error: None,
+        raw: Vec::new(),
+    };
+    for &pid in pids {
+        match source.read_pid(pid) {
+            Ok(Some(data)) => {
+                sample.readings.extend(obd::decode_pid(pid, &data));
+                sample.raw.push((pid, data));
+            }
+            Ok(None) => {}
+            Err(e) => {
+                // The adapter dropped; the next poll reconnects.
+                sample.error = Some(e);
+                break;
+            }
+        }
+    }
+    sample
+}
+
+/// Writes the CSV and JSONL files for one session.
+struct Recorder {
+    pids: Vec<u8>,
+    csv: BufWriter<File>,
+    jsonl: BufWriter<File>,
+    paths: Vec<PathBuf>,
+}
+
+impl Recorder {
+    fn create(stem: &Path, pids: &[u8], header: Value) -> Result<Self, String> {
+        if let Some(dir) = stem.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Cannot create {}: {}", dir.display(), e))?;
+        }
+        let open = |ext: &str| -> Result<(BufWriter<File>, PathBuf), String> {
+            let path = stem.with_extension(ext);
+            let file = File::create(&path)
+                .map_err(|e| format!("Cannot create {}: {}", path.display(), e))?;
+            Ok((BufWriter::new(file), path))
+        };
+        let (mut csv, csv_path) = open("csv")?;
+        let (mut jsonl, jsonl_path) = open("jsonl")?;
+
+        let mut columns = vec!["t_ms".to_string(), "timestamp_ms".to_string()];
+        for &pid in pids {
+            let p = obd::pid(pid).expect("resolved PIDs are known");
+            columns.push(if p.unit.is_empty() {
+                p.key.to_string()
+            } else {
+                format!("{} ({})", p.key, p.unit)
+            });
+        }
+        let write_err = |e: std::io::Error| format!("Failed to write recording: {}", e);
+        writeln!(csv, "{}", columns.join(",")).map_err(write_err)?;
+        writeln!(jsonl, "{}", header).map_err(write_err)?;
+        Ok(Recorder {
+            pids: pids.to_vec(),
+            csv,
+            jsonl,
+            paths: vec![csv_path, jsonl_path],
+        })
+    }
+
+    fn write(&mut self, sample: &Sample) -> std::io::Result<()> {
+        let mut row = vec![sample.t_ms.to_string(), sample.timestamp_ms.to_string()];
+        for pid in &self.pids {
+            row.push(
+                sample
+                    .readings
+                    .iter()
+                    .find(|r| r.pid == *pid)
+                    .map(|r| r.value.to_string())
+                    .unwrap_or_default(),
+            );
+        }
+        writeln!(self.csv, "{}", row.join(","))?;
+
+        let raw: BTreeMap<String, String> = sample
+            .raw
+            .iter()
+            .map(|(pid, data)| (format!("{:02X}", pid), hex(data)))
+            .collect();
+        let mut line = json!({ "t_ms": sample.t_ms, "ts": sample.timestamp_ms, "pids": raw });
+        if let Some(e) = &sample.error {
+            line["error"] = json!(e);
+        }
+        writeln!(self.jsonl, "{}", line)
+    }
+
+    fn finish(mut self) -> std::io::Result<Vec<PathBuf>> {
+        self.csv.flush()?;
+        self.jsonl.flush()?;
+        Ok(self.paths)
+    }
+}
+
+/// A running telemetry session. Dropping it without [`stop`](Self::stop)
+/// leaves the poller running.
+pub struct TelemetrySession {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<Result<TelemetrySummary, String>>,
+}
+
+impl TelemetrySession {
+    /// Start polling on a background thread. The source lock is held only
+    /// for one poll at a time, so tools keep working while a session runs.
+    pub(super) fn start(
+        source: Arc<Mutex<Box<dyn VehicleSource>>>,
+        options: &TelemetryOptions,
+        mut on_sample: impl FnMut(&Sample) + Send + 'static,
+    ) -> Result<Self, String> {
+        let pids = options.resolve_pids()?;
+        let interval = options.interval();
+        let mut recorder = match &options.record {
+            Some(stem) => {
+                let header = {
+                    let mut source = source.lock().unwrap_or_else(|e| e.into_inner());
+                    session_header(source.as_mut(), &pids, interval)
+                };
+                Some(Recorder::create(stem, &pids, header)?)
+            }
+            None => None,
+        };
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        let thread = std::thread::spawn(move || {
+            let started = Instant::now();
+            let (mut samples, mut errors) = (0, 0);
+            while !stopped.load(Ordering::Relaxed) {
+                let tick = Instant::now();
+                let sample = {
+                    let mut source = source.lock().unwrap_or_else(|e| e.into_inner());
+                    poll(source.as_mut(), &pids, started)
+                };
+                samples += 1;
+                errors += sample.error.is_some() as u64;
+                on_sample(&sample);
+                if let Some(recorder) = recorder.as_mut() {
+                    recorder
+                        .write(&sample)
+                        .map_err(|e| format!("Failed to write recording: {}", e))?;
+                }
+                // Sleep in short steps so stop() returns promptly.
+                while !stopped.load(Ordering::Relaxed) && tick.elapsed() < interval {
+                    std::thread::sleep((interval - tick.elapsed()).min(Duration::from_millis(50)));
+                }
+            }
+            let recording = match recorder {
+                Some(r) => r
+                    .finish()
+                    .map_err(|e| format!("Failed to write recording: {}", e))?,
+                None => Vec::new(),
+            };
+            Ok(TelemetrySummary {
+                samples,
+                errors,
+                duration_ms: started.elapsed().as_millis() as u64,
+                recording,
+            })
+        });
+        Ok(TelemetrySession { stop, thread })
+    }
+
+    /// Stop polling, flush the recording and report the session.
+    pub fn stop(self) -> Result<TelemetrySummary, String> {
+        self.stop.store(true, Ordering::Relaxed);
+        self.thread
+            .join()
+            .map_err(|_| "Telemetry poller panicked".to_string())?
+    }
+}
+
+/// First line of a JSONL recording. Stored codes and the VIN are captured
+/// so a replay can show them; failing to read them does not stop the session.
+fn session_header(source: &mut dyn VehicleSource, pids: &[u8], interval: Duration) -> Value {
+    json!({
+        "format": FORMAT,
+        "version": 1,
+        "source": source.kind(),
+        "started_ms": unix_ms(),
+        "interval_ms": interval.as_millis() as u64,
+        "pids": pids.iter().map(|p| format!("{:02X}", p)).collect::<Vec<_>>(),
+        "stored": source.read_dtcs(DtcKind::Stored).unwrap_or_default(),
+        "vin": source.read_vin().ok().flatten(),
+    })
+}
+
+/// One recorded poll, as raw payloads.
+#[derive(Debug, Clone, PartialEq)]
+struct Frame {
+    t_ms: u64,
+    pids: BTreeMap<u8, Vec<u8>>,
+}
+
+/// A JSONL recording played back in real time. Each read returns the sample
+/// due at the time elapsed since the first read, looping at the end, so the
+/// tools and the poller see the session as it was driven.
+pub(super) struct ReplaySource {
+    path: PathBuf,
+    header: Value,
+    frames: Vec<Frame>,
+    started: Option<Instant>,
+}
+
+impl ReplaySource {
+    pub(super) fn open(path: &Path) -> Result<Self, String> {
+        let file = File::open(path)
+            .map_err(|e| format!("Cannot open recording {}: {}", path.display(), e))?;
+        Self::parse(path, BufReader::new(file))
+    }
+
+    fn parse(path: &Path, reader: impl BufRead) -> Result<Self, String> {
+        let bad =
+            |n: usize, why: &str| format!("Bad recording {} line {}: {}", path.display(), n, why);
+        let mut header = Value::Null;
+        let mut frames = Vec::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line.map_err(|e| bad(i + 1, &e.to_string()))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let value: Value =
+                serde_json::from_str(&line).map_err(|e| bad(i + 1, &e.to_string()))?;
+            if value["format"] == FORMAT {
+                header = value;
+                continue;
+            }
+            let t_ms = value["t_ms"]
+                .as_u64()
+                .ok_or_else(|| bad(i + 1, "missing t_ms"))?;
+            let mut pids = BTreeMap::new();
+            for (id, payload) in value["pids"].as_object().into_iter().flatten() {
+                let id = u8::from_str_radix(id, 16).map_err(|_| bad(i + 1, "bad PID id"))?;
+                let data = payload
+                    .as_str()
+                    .and_then(obd::hex_bytes)
+                    .ok_or_else(|| bad(i + 1, "bad PID payload"))?;
+                pids.insert(id, data);
+            }
+            frames.push(Frame { t_ms, pids });
+        }
+        if frames.is_empty() {
+            return Err(format!("Recording {} has no samples", path.display()));
+        }
+        if header["interval_ms"].as_u64() == Some(0) {
+            return Err(format!(
+                "Bad recording {}: interval_ms must be positive",
+                path.display()
+            ));
+        }
+        // Playback looks frames up by time, so they must be in order.
+        frames.sort_by_key(|f| f.t_ms);
+        Ok(ReplaySource {
+            path: path.to_path_buf(),
+            header,
+            frames,
+            started: None,
+        })
+    }
+
+    /// The frame due `t_ms` into the replay.
+    fn frame_at(&self, t_ms: u64) -> &Frame {
+        let first = self.frames[0].t_ms;
+        let interval = self.header["interval_ms"]
+            .as_u64()
+            .unwrap_or(DEFAULT_INTERVAL_MS);
+        let length = self.frames[self.frames.len() - 1].t_ms - first + interval;
+        let t = first + t_ms % length;
+        let i = self.frames.partition_point(|f| f.t_ms <= t);
+        &self.frames[i.saturating_sub(1)]
+    }
+
+    fn current(&mut self) -> &Frame {
+        let started = *self.started.get_or_insert_with(Instant::now);
+        self.frame_at(started.elapsed().as_millis() as u64)
+    }
+
+    fn stored(&self) -> Vec<String> {
+        self.header["stored"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|c| c.as_str().map(String::from))
+            .collect()
+    }
+}
+
+impl VehicleSource for ReplaySource {
+    fn kind(&self) -> &'static str {
+        "replay"
+    }
+
+    fn read_pid(&mut self, pid: u8) -> Result<Option<Vec<u8>>, String> {
+        if let Some(data) = self.current().pids.get(&pid) {
+            return Ok(Some(data.clone()));
+        }
+        if pid == obd::MONITOR_STATUS {
+            // Not polled: derive the MIL from the codes in the header.
+            let stored = self.stored().len() as u8;
+            let mil = if stored > 0 { 0x80 } else { 0x00 };
+            return Ok(Some(vec![mil | stored, 0x00, 0x00, 0x00]));
+        }
+        Ok(None)
+    }
+
+    fn read_dtcs(&mut self, kind: DtcKind) -> Result<Vec<String>, String> {
+        Ok(match kind {
+            DtcKind::Stored => self.stored(),
+            DtcKind::Pending | DtcKind::Permanent => Vec::new(),
+        })
+    }
+
+    fn read_freeze_pid(&mut self, _frame: u8, _pid: u8) -> Result<Option<Vec<u8>>, String> {
+        Ok(None)
+    }
+
+    fn read_vin(&mut self) -> Result<Option<String>, String> {
+        Ok(self.header["vin"].as_str().map(String::from))
+    }
+
+    fn clear_dtcs(&mut self) -> Result<(), String> {
+        Err("A replayed recording cannot be changed; connect to the car to clear codes".into())
+    }
+
+    fn describe(&self) -> Value {
+        json!({
+            "source": "replay",
+            "recording": self.path.display().to_string(),
+            "recorded_from": self.header["source"],
+            "recorded_at_ms": self.header["started_ms"],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::source::DemoSource;
+    use super::*;
+
+    fn demo() -> Arc<Mutex<Box<dyn VehicleSource>>> {
+        Arc::new(Mutex::new(Box::new(DemoSource::default())))
+    }
+
+    #[test]
+    fn test_options_resolve_pids() {
+        let options = TelemetryOptions {
+            pids: vec!["rpm".into(), "0D".into(), "0x0c".into()],
+            ..Default::default()
+        };
+        assert_eq!(options.resolve_pids().unwrap(), vec![obd::RPM, obd::SPEED]);
+        assert_eq!(TelemetryOptions::default().resolve_pids().unwrap().len(), 6);
+
+        let unknown = TelemetryOptions {
+            pids: vec!["boost".into()],
+            ..Default::default()
+        };
+        assert!(unknown.resolve_pids().is_err());
+
+        let defaults = TelemetryOptions {
+            pids: vec!["speed".into()],
+            interval_ms: Some(20),
+            record: Some("ignored".into()),
+        };
+        let merged = TelemetryOptions::default().or(&defaults);
+        assert_eq!(merged.pids, vec!["speed"]);
+        assert_eq!(merged.interval(), Duration::from_millis(MIN_INTERVAL_MS));
+        assert!(merged.record.is_none());
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        let stem = dir.path().join("drive");
+        let options = TelemetryOptions {
+            pids: vec!["rpm".into(), "coolant_temp".into(), "maf".into()],
+            interval_ms: Some(100),
+            record: Some(stem.clone()),
+        };
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let session = TelemetrySession::start(demo(), &options, move |s| {
+            sink.lock().unwrap().push(s.clone());
+        })
+        .unwrap();
+        std::thread::sleep(Duration::from_millis(250));
+        let summary = session.stop().unwrap();
+
+        let seen = seen.lock().unwrap();
+        assert!(summary.samples >= 2);
+        assert_eq!(summary.samples, seen.len() as u64);
+        assert_eq!(summary.errors, 0);
+        // The demo car has no MAF sensor.
+        assert_eq!(seen[0].readings.len(), 2);
+        assert_eq!(seen[0].readings[0].key, "rpm");
+        assert!(seen[1].t_ms >= 100);
+
+        let csv = std::fs::read_to_string(stem.with_extension("csv")).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next().unwrap(),
+            "t_ms,timestamp_ms,rpm (rpm),coolant_temp (°C),maf (g/s)"
+        );
+        assert!(lines.next().unwrap().ends_with(",850,90,"));
+
+        let mut replay = ReplaySource::open(&stem.with_extension("jsonl")).unwrap();
+        assert_eq!(replay.frames.len() as u64, summary.samples);
+        assert_eq!(replay.read_pid(obd::RPM).unwrap(), Some(vec![0x0D, 0x48]));
+        assert_eq!(replay.read_pid(obd::SPEED).unwrap(), None);
+        assert_eq!(
+            replay.read_dtcs(DtcKind::Stored).unwrap(),
+            vec!["P0171", "P0420"]
+        );
+        assert!(obd::mil_on(
+            &replay.read_pid(obd::MONITOR_STATUS).unwrap().unwrap()
+        ));
+        assert_eq!(
+            replay.read_vin().unwrap().as_deref(),
+            Some("1HGCV1F37LA012345")
+        );
+        assert!(replay.clear_dtcs().is_err());
+        assert_eq!(replay.describe()["recorded_from"], "demo");
+    }
+
+    #[test]
+    fn test_replay_follows_recorded_time() {
+        let raw = concat!(
+            r#"{"format":"sentinel-telemetry","version":1,"interval_ms":500,"pids":["0D"]}"#,
+            "\n",
+            r#"{"t_ms":0,"ts":0,"pids":{"0D":"00"}}"#,
+            "\n",
+            r#"{"t_ms":500,"ts":0,"pids":{"0D":"30"}}"#,
+            "\n",
+            r#"{"t_ms":1000,"ts":0,"pids":{"0D":"60"}}"#,
+            "\n",
+        );
+        let replay = ReplaySource::parse(Path::new("drive.jsonl"), raw.as_bytes()).unwrap();
+        let speed = |t| replay.frame_at(t).pids[&obd::SPEED][0];
+        assert_eq!(speed(0), 0x00);
+        assert_eq!(speed(499), 0x00);
+        assert_eq!(speed(700), 0x30);
+        assert_eq!(speed(1200), 0x60);
+        // Loops after the last sample's interval.
+        assert_eq!(speed(1500), 0x00);
+
+        // Samples written out of order play back in time order.
+        let shuffled = concat!(
+            r#"{"t_ms":1000,"pids":{"0D":"60"}}"#,
+            "\n",
+            r#"{"t_ms":0,"pids":{"0D":"00"}}"#,
+        );
+        let replay = ReplaySource::parse(Path::new("x"), shuffled.as_bytes()).unwrap();
+        assert_eq!(replay.frame_at(200).pids[&obd::SPEED][0], 0x00);
+        assert_eq!(replay.frame_at(1200).pids[&obd::SPEED][0], 0x60);
+
+        let no_interval = concat!(
+            r#"{"format":"sentinel-telemetry","version":1,"interval_ms":0}"#,
+            "\n",
+            r#"{"t_ms":0,"pids":{"0D":"00"}}"#,
+        );
+        assert!(ReplaySource::parse(Path::new("x"), no_interval.as_bytes()).is_err());
+
+        let empty = r#"{"format":"sentinel-telemetry","version":1}"#;
+        assert!(ReplaySource::parse(Path::new("x"), empty.as_bytes()).is_err());
+        assert!(ReplaySource::parse(
+            Path::new("x"),
+            "{\"t_ms\":0,\"pids\":{\"0D\":\"zz\"}}".as_bytes()
+        )
+        .is_err());
+    }
+}
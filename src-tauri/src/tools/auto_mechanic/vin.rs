@@ -0,0 +1,304 @@
+//! Offline VIN decoding (ISO 3779 / 49 CFR 565).
+//!
+//! A 17-character VIN is three sections: the world manufacturer identifier
+//! (positions 1-3), the vehicle descriptor (4-8, plus the check digit at 9),
+//! and the vehicle identifier (10-17: model year, plant, serial). Country and
+//! region come from the first two characters; manufacturers and the plant
+//! codes of a few makes come from `data/vin.json`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+const VIN_TABLE: &str = include_str!("data/vin.json");
+
+#[derive(Debug, Deserialize)]
+struct Manufacturer {
+    manufacturer: String,
+    make: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlantTable {
+    makes: Vec<String>,
+    codes: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VinTable {
+    wmi: HashMap<String, Manufacturer>,
+    plants: HashMap<String, PlantTable>,
+}
+
+fn table() -> &'static VinTable {
+    static TABLE: OnceLock<VinTable> = OnceLock::new();
+    TABLE.get_or_init(|| serde_json::from_str(VIN_TABLE).expect("bundled VIN table is valid"))
+}
+
+/// ISO 3779 orders VIN characters A-Z, then 1-9, then 0.
+fn rank(c: char) -> u32 {
+    match c {
+        'A'..='Z' => c as u32 - 'A' as u32,
+        '1'..='9' => 26 + c as u32 - '1' as u32,
+        _ => 35,
+    }
+}
+
+/// Countries by the range their first two characters fall in.
+const COUNTRIES: &[(char, char, char, &str)] = &[
+    ('A', 'A', 'H', "South Africa"),
+    ('J', 'A', '0', "Japan"),
+    ('K', 'L', 'R', "South Korea"),
+    ('L', 'A', '0', "China"),
+    ('M', 'A', 'E', "India"),
+    ('M', 'F', 'K', "Indonesia"),
+    ('M', 'L', 'R', "Thailand"),
+    ('N', 'L', 'R', "Turkey"),
+    ('P', 'L', 'R', "Malaysia"),
+    ('S', 'A', 'M', "United Kingdom"),
+    ('S', 'U', 'Z', "Poland"),
+    ('T', 'A', 'H', "Switzerland"),
+    ('T', 'J', 'P', "Czech Republic"),
+    ('T', 'R', 'V', "Hungary"),
+    ('V', 'A', 'E', "Austria"),
+    ('V', 'F', 'R', "France"),
+    ('V', 'S', 'W', "Spain"),
+    ('W', 'A', '0', "Germany"),
+    ('X', 'L', 'R', "Netherlands"),
+    ('X', 'S', 'W', "Russia"),
+    ('Y', 'A', 'E', "Belgium"),
+    ('Y', 'F', 'K', "Finland"),
+    ('Y', 'S', 'W', "Sweden"),
+    ('Z', 'A', 'R', "Italy"),
+    ('1', 'A', '0', "United States"),
+    ('2', 'A', '0', "Canada"),
+    ('3', 'A', 'W', "Mexico"),
+    ('4', 'A', '0', "United States"),
+    ('5', 'A', '0', "United States"),
+    ('6', 'A', 'W', "Australia"),
+    ('7', 'A', 'E', "New Zealand"),
+    ('7', 'F', '0', "United States"),
+    ('8', 'A', 'E', "Argentina"),
+    ('9', 'A', 'E', "Brazil"),
+    ('9', '3', '9', "Brazil"),
+];
+
+fn country(first: char, second: char) -> Option<&'static str> {
+    COUNTRIES
+        .iter()
+        .find(|(c, lo, hi, _)| *c == first && (rank(*lo)..=rank(*hi)).contains(&rank(second)))
+        .map(|(_, _, _, name)| *name)
+}
+
+fn region(first: char) -> &'static str {
+    match first {
+        'A'..='C' => "Africa",
+        'H'..='R' => "Asia",
+        'S'..='Z' => "Europe",
+        '1'..='5' => "North America",
+        '6' | '7' => "Oceania",
+        '8' | '9' | '0' => "South America",
+        _ => "Unknown",
+    }
+}
+
+/// Year codes for position 10; the cycle repeats every 30 years.
+const YEAR_CODES: &str = "ABCDEFGHJKLMNPRSTVWXY123456789";
+
+/// Position 10 names a year in each 30-year cycle (L is 1990 or 2020). In
+/// North America a letter at position 7 means the later cycle; elsewhere
+/// nothing in the VIN picks the cycle, so only the candidates are given.
+fn model_years(vin: &[char]) -> (Option<u16>, Vec<u16>) {
+    let Some(i) = YEAR_CODES.find(vin[9]) else {
+        return (None, Vec::new());
+    };
+    let candidates = vec![1980 + i as u16, 2010 + i as u16];
+    if !matches!(vin[0], '1'..='5') {
+        return (None, candidates);
+    }
+    let likely = if vin[6].is_ascii_alphabetic() {
+        candidates[1]
+    } else {
+        candidates[0]
+    };
+    (Some(likely), candidates)
+}
+
+const WEIGHTS: [u32; 17] = [8, 7, 6, 5, 4, 3, 2, 10, 0, 9, 8, 7, 6, 5, 4, 3, 2];
+
+/// Letters transliterate to 1-9 (I, O and Q never appear).
+fn value(c: char) -> u32 {
+    match c {
+        '0'..='9' => c as u32 - '0' as u32,
+        'A' | 'J' => 1,
+        'B' | 'K' | 'S' => 2,
+        'C' | 'L' | 'T' => 3,
+        'D' | 'M' | 'U' => 4,
+        'E' | 'N' | 'V' => 5,
+        'F' | 'W' => 6,
+        'G' | 'P' | 'X' => 7,
+        'H' | 'Y' => 8,
+        'R' | 'Z' => 9,
+        _ => 0,
+    }
+}
+
+/// The check digit position 9 should hold: weighted sum mod 11, 10 is `X`.
+fn expected_check_digit(vin: &[char]) -> char {
+    let sum: u32 = vin.iter().zip(WEIGHTS).map(|(c, w)| value(*c) * w).sum();
+    match sum % 11 {
+        10 => 'X',
+        d => char::from_digit(d, 10).expect("below 10"),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(super) struct CheckDigit {
+    pub expected: char,
+    pub actual: char,
+    pub valid: bool,
+    /// Mandatory in North America and China; elsewhere position 9 may be
+    /// anything.
+    pub required: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(super) struct VinInfo {
+    pub vin: String,
+    pub wmi: String,
+    pub manufacturer: Option<String>,
+    pub make: Option<String>,
+    pub country: Option<&'static str>,
+    pub region: &'static str,
+    /// Positions 4-8: model, body, engine, restraints, per manufacturer.
+    pub vds: String,
+    pub check_digit: CheckDigit,
+    pub model_year: Option<u16>,
+    pub model_year_candidates: Vec<u16>,
+    pub plant_code: char,
+    pub plant: Option<String>,
+    pub serial: String,
+}
+
+impl VinInfo {
+    /// A VIN whose check digit is wrong where one is required is almost
+    /// always a typo.
+    pub(super) fn valid(&self) -> bool {
+        self.check_digit.valid || !self.check_digit.required
+    }
+}
+
+/// Normalise and decode a VIN. Fails on the wrong length or characters a
+/// VIN never uses; a bad check digit is reported, not rejected.
+pub(super) fn decode(raw: &str) -> Result<VinInfo, String> {
+    let text: String = raw
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect::<String>()
+        .to_uppercase();
+    if text.len() != 17 {
+        return Err(format!(
+            "A VIN has 17 characters; {:?} has {} (vehicles before 1981 used shorter numbers)",
+            text,
+            text.len()
+        ));
+    }
+    if let Some(bad) = text.chars().find(|c| !c.is_ascii_alphanumeric()) {
+        return Err(format!("{:?} is not valid in a VIN", bad));
+    }
+    if let Some(bad) = text.chars().find(|c| matches!(c, 'I' | 'O' | 'Q')) {
+        return Err(format!("VINs never use {}; check for a 1 or 0", bad));
+    }
+    let vin: Vec<char> = text.chars().collect();
+
+    let wmi = &text[..3];
+    let manufacturer = table().wmi.get(wmi);
+    let make = manufacturer.map(|m| m.make.clone());
+    let plant = make.as_deref().and_then(|make| {
+        let make = make.to_lowercase();
+        table()
+            .plants
+            .values()
+            .find(|t| t.makes.contains(&make))
+            .and_then(|t| t.codes.get(&vin[10].to_string()).cloned())
+    });
+    let country = country(vin[0], vin[1]);
+    let expected = expected_check_digit(&vin);
+    let (model_year, model_year_candidates) = model_years(&vin);
+    Ok(VinInfo {
+        wmi: wmi.to_string(),
+        manufacturer: manufacturer.map(|m| m.manufacturer.clone()),
+        make,
+        country,
+        region: region(vin[0]),
+        vds: text[3..8].to_string(),
+        check_digit: CheckDigit {
+            expected,
+            actual: vin[8],
+            valid: expected == vin[8],
+            required: matches!(vin[0], '1'..='5' | 'L'),
+        },
+        model_year,
+        model_year_candidates,
+        plant_code: vin[10],
+        plant,
+        serial: text[11..].to_string(),
+        vin: text,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_vin() {
+        let info = decode("1hgcv1f37la012345").unwrap();
+        assert_eq!(info.vin, "1HGCV1F37LA012345");
+        assert_eq!(info.make.as_deref(), Some("Honda"));
+        assert_eq!(info.country, Some("United States"));
+        assert_eq!(info.region, "North America");
+        assert_eq!(info.vds, "CV1F3");
+        assert!(info.check_digit.valid);
+        assert!(info.valid());
+        assert_eq!(info.model_year, Some(2020));
+        assert_eq!(info.model_year_candidates, vec![1990, 2020]);
+        assert_eq!(info.plant.as_deref(), Some("Marysville, Ohio"));
+        assert_eq!(info.serial, "012345");
+
+        // The textbook example: check digit X, numeric position 7.
+        let info = decode("1M8GDM9AXKP042788").unwrap();
+        assert_eq!(info.check_digit.expected, 'X');
+        assert!(info.check_digit.valid);
+        assert_eq!(info.model_year, Some(1989));
+        assert!(info.make.is_none());
+
+        let typo = decode("1HGCV1F36LA012345").unwrap();
+        assert!(!typo.check_digit.valid);
+        assert!(!typo.valid());
+        // European VINs need not carry a check digit.
+        let bmw = decode("WBA8E9G50GNT12345").unwrap();
+        assert_eq!(bmw.country, Some("Germany"));
+        assert_eq!(bmw.make.as_deref(), Some("BMW"));
+        assert!(bmw.valid());
+        // Outside North America the year cycle is ambiguous.
+        assert_eq!(bmw.model_year, None);
+        assert_eq!(bmw.model_year_candidates, vec![1986, 2016]);
+
+        assert!(decode("1HGCV1F37LA01234").is_err());
+        assert!(decode("1HGCV1F37LAO12345")
+            .unwrap_err()
+            .contains("never use O"));
+    }
+
+    #[test]
+    fn test_country_ranges() {
+        assert_eq!(country('J', 'T'), Some("Japan"));
+        assert_eq!(country('K', 'M'), Some("South Korea"));
+        assert_eq!(country('S', 'A'), Some("United Kingdom"));
+        assert_eq!(country('V', 'F'), Some("France"));
+        assert_eq!(country('3', 'V'), Some("Mexico"));
+        assert_eq!(country('9', '5'), Some("Brazil"));
+        assert_eq!(country('K', 'A'), None);
+    }
+}
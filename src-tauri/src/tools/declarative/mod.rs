@@ -0,0 +1,442 @@
+//! Declarative modules: tools described in a TOML file instead of Rust.
+//!
+//! Each file in the modules directory is one module. A tool runs a command
+//! (an argv array, never a shell) with `{param}` placeholders filled from
+//! arguments validated against its JSON Schema, then parses stdout with one
+//! of the [`parsers`]. `keywords` route matching phrases to the tool without
+//! a model.
+//!
+//! ```toml
+//! name = "system_info"
+//! description = "macOS version and uptime"
+//!
+//! [[tools]]
+//! name = "get_os_version"
+//! description = "Show the macOS product name, version and build"
+//! keywords = ["macos version", "os version"]
+//! command = ["sw_vers"]
+//! parser = { kind = "key_value" }
+//! ```
+
+mod parsers;
+mod template;
+
+use super::{RiskLevel, ToolDefinition, ToolModule, ToolResult};
+use parsers::Parser;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+fn default_parameters() -> Value {
+    json!({"type": "object", "properties": {}, "required": []})
+}
+
+fn default_timeout() -> u64 {
+    10
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ToolSpec {
+    name: String,
+    description: String,
+    #[serde(default = "default_parameters")]
+    parameters: Value,
+    #[serde(default)]
+    risk: RiskLevel,
+    command: Vec<String>,
+    #[serde(default)]
+    parser: Parser,
+    #[serde(default)]
+    keywords: Vec<String>,
+    #[serde(default = "default_timeout")]
+    timeout_secs: u64,
+    /// Exit codes that still count as success (0 always does).
+    #[serde(default)]
+    ok_exit_codes: Vec<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ModuleFile {
+    name: String,
+    #[serde(default)]
+    description: String,
+    tools: Vec<ToolSpec>,
+}
+
+/// A module loaded from a TOML definition.
+#[derive(Debug)]
+pub struct DeclarativeModule {
+    name: String,
+    description: String,
+    tools: Vec<ToolSpec>,
+}
+
+impl DeclarativeModule {
+    /// Parse and validate a module definition.
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        let file: ModuleFile = toml::from_str(raw).map_err(|e| e.to_string())?;
+        if file.name.trim().is_empty() {
+            return Err("module name is empty".into());
+        }
+        if file.tools.is_empty() {
+            return Err(format!("module {:?} defines no tools", file.name));
+        }
+        for tool in &file.tools {
+            let context = |e: String| format!("tool {:?}: {}", tool.name, e);
+            if tool
+                .command
+                .first()
+                .is_none_or(|program| program.contains('{'))
+            {
+                return Err(context("command must start with a fixed program".into()));
+            }
+            template::check_schema(&tool.parameters).map_err(context)?;
+            let declared = tool.parameters["properties"].as_object();
+            for name in template::placeholders(&tool.command) {
+                if !declared.is_some_and(|p| p.contains_key(&name)) {
+                    return Err(context(format!("{{{}}} is not a declared parameter", name)));
+                }
+            }
+        }
+        Ok(DeclarativeModule {
+            name: file.name,
+            description: file.description,
+            tools: file.tools,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let raw = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read module {}: {}", path.display(), e))?;
+        Self::parse(&raw).map_err(|e| format!("Invalid module {}: {}", path.display(), e))
+    }
+
+    fn tool(&self, tool_name: &str) -> Option<&ToolSpec> {
+        self.tools.iter().find(|t| t.name == tool_name)
+    }
+}
+
+/// Load every `*.toml` in `dir`, in name order. A missing directory has no
+/// modules; a bad file is reported without stopping the others.
+pub fn load_dir(dir: &Path) -> Vec<Result<DeclarativeModule, String>> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+    paths.sort();
+    paths.iter().map(|p| DeclarativeModule::load(p)).collect()
+}
+
+struct Output {
+    code: Option<i32>,
+    stdout: String,
+    stderr: String,
+}
+
+/// Run argv with a deadline, killing the process if it overruns.
+fn run(argv: &[String], timeout: Duration) -> Result<Output, String> {
+    let mut child = Command::new(&argv[0])
+        .args(&argv[1..])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", argv[0], e))?;
+    // Drain both pipes on threads so a chatty command cannot block on a
+    // full pipe while we wait for it.
+    let drain = |pipe: Option<Box<dyn Read + Send>>| {
+        std::thread::spawn(move || {
+            let mut text = String::new();
+            if let Some(mut pipe) = pipe {
+                let mut bytes = Vec::new();
+                let _ = pipe.read_to_end(&mut bytes);
+                text = String::from_utf8_lossy(&bytes).into_owned();
+            }
+            text
+        })
+    };
+    let stdout = drain(
+        child
+            .stdout
+            .take()
+            .map(|p| Box::new(p) as Box<dyn Read + Send>),
+    );
+    let stderr = drain(
+        child
+            .stderr
+            .take()
+            .map(|p| Box::new(p) as Box<dyn Read + Send>),
+    );
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!(
+                    "{} timed out after {}s",
+                    argv[0],
+                    timeout.as_secs()
+                ));
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(20)),
+            Err(e) => return Err(format!("Failed to wait for {}: {}", argv[0], e)),
+        }
+    };
+    Ok(Output {
+        code: status.code(),
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+fn failure(error: String) -> ToolResult {
+    ToolResult {
+        success: false,
+        data: Value::Null,
+        error: Some(error),
+    }
+}
+
+impl ToolModule for DeclarativeModule {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn tools(&self) -> Vec<ToolDefinition> {
+        self.tools
+            .iter()
+            .map(|t| ToolDefinition {
+                name: t.name.clone(),
+                description: t.description.clone(),
+                parameters: t.parameters.clone(),
+                risk: t.risk,
+            })
+            .collect()
+    }
+
+    fn execute(&self, tool_name: &str, args: Value) -> ToolResult {
+        let Some(tool) = self.tool(tool_name) else {
+            return failure(format!("Unknown tool: {}", tool_name));
+        };
+        let argv = match template::bind(&tool.parameters, &args)
+            .and_then(|bound| template::render(&tool.command, &bound))
+        {
+            Ok(argv) => argv,
+            Err(e) => return failure(e),
+        };
+        // Only a tool that declares `dry_run` is previewed without
+        // confirmation, so an undeclared one must not skip the command.
+        let declares_dry_run = tool.parameters.pointer("/properties/dry_run").is_some();
+        if declares_dry_run && args.get("dry_run").and_then(|v| v.as_bool()) == Some(true) {
+            return ToolResult {
+                success: true,
+                data: json!({"dry_run": true, "command": argv}),
+                error: None,
+            };
+        }
+        let output = match run(&argv, Duration::from_secs(tool.timeout_secs.max(1))) {
+            Ok(output) => output,
+            Err(e) => return failure(e),
+        };
+        let ok = output
+            .code
+            .is_some_and(|code| code == 0 || tool.ok_exit_codes.contains(&code));
+        if !ok {
+            let reason = output.stderr.lines().find(|l| !l.trim().is_empty());
+            return failure(format!(
+                "{} exited with {}{}",
+                argv[0],
+                output
+                    .code
+                    .map_or("a signal".to_string(), |c| format!("status {}", c)),
+                reason
+                    .map(|r| format!(": {}", r.trim()))
+                    .unwrap_or_default()
+            ));
+        }
+        match tool.parser.parse(&output.stdout) {
+            Ok(data) => ToolResult {
+                success: true,
+                data,
+                error: None,
+            },
+            Err(e) => failure(e),
+        }
+    }
+
+    fn preview(&self, tool_name: &str, args: &Value) -> Option<Value> {
+        let tool = self.tool(tool_name)?;
+        let bound = template::bind(&tool.parameters, args).ok()?;
+        let argv = template::render(&tool.command, &bound).ok()?;
+        Some(json!({"command": argv}))
+    }
+
+    fn keywords(&self, tool_name: &str) -> Vec<String> {
+        self.tool(tool_name)
+            .map(|t| t.keywords.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::ModuleRegistry;
+    use std::sync::Arc;
+
+    const ECHO: &str = r#"
+name = "echo_tools"
+description = "Test module"
+
+[[tools]]
+name = "greet"
+description = "Say hello"
+keywords = ["say hello"]
+command = ["echo", "hello {name}"]
+parser = { kind = "regex", pattern = 'hello (?P<who>\w+)' }
+
+[tools.parameters]
+type = "object"
+required = ["name"]
+
+[tools.parameters.properties.name]
+type = "string"
+pattern = "[a-z]+"
+
+[[tools]]
+name = "env_table"
+description = "Print a table"
+command = ["printf", "NAME SIZE\na 1\nb 22\n"]
+parser = { kind = "table" }
+
+[[tools]]
+name = "remove"
+description = "Pretend to delete"
+risk = "destructive"
+command = ["true"]
+
+[[tools]]
+name = "fail"
+description = "Always fails"
+command = ["sh", "-c", "echo broken >&2; exit 3"]
+
+[[tools]]
+name = "slow"
+description = "Sleeps past its timeout"
+command = ["sleep", "5"]
+timeout_secs = 1
+"#;
+
+    #[test]
+    fn test_declarative_tools_run_and_parse() {
+        let module = DeclarativeModule::parse(ECHO).unwrap();
+        assert_eq!(module.tools().len(), 5);
+        assert_eq!(module.keywords("greet"), vec!["say hello"]);
+
+        let result = module.execute("greet", json!({"name": "sam"}));
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(result.data, json!({"who": "sam"}));
+        assert!(!module.execute("greet", json!({"name": "Sam; ls"})).success);
+
+        let table = module.execute("env_table", json!({}));
+        assert_eq!(table.data["rows"][1], json!({"name": "b", "size": 22}));
+
+        let failed = module.execute("fail", json!({}));
+        assert_eq!(
+            failed.error.as_deref(),
+            Some("sh exited with status 3: broken")
+        );
+
+        let started = Instant::now();
+        let slow = module.execute("slow", json!({}));
+        assert!(slow.error.unwrap().contains("timed out"));
+        assert!(started.elapsed() < Duration::from_secs(4));
+    }
+
+    #[test]
+    fn test_registered_like_native_modules() {
+        let mut registry = ModuleRegistry::new();
+        registry
+            .register(Arc::new(DeclarativeModule::parse(ECHO).unwrap()))
+            .unwrap();
+        assert!(registry.has_tool("greet"));
+        assert_eq!(
+            registry.routing_keywords(),
+            vec![("greet".to_string(), vec!["say hello".to_string()])]
+        );
+        // Destructive tools still go through confirmation, with the command
+        // as the preview.
+        let parked = registry.execute("remove", json!({}));
+        assert_eq!(parked.data["requires_confirmation"], true);
+        assert_eq!(
+            parked.data["confirmation"]["preview"]["command"],
+            json!(["true"])
+        );
+        // `dry_run` on a tool that does not declare it is confirmed and run.
+        let parked = registry.execute("remove", json!({"dry_run": true}));
+        let token = parked.data["confirmation"]["token"].as_str().unwrap();
+        let confirmed = registry.confirm(token);
+        assert!(confirmed.success, "{:?}", confirmed.error);
+        assert!(confirmed.data.get("dry_run").is_none());
+
+        let declared = DeclarativeModule::parse(
+            r#"
+name = "x"
+
+[[tools]]
+name = "wipe"
+description = "Pretend to delete, with a preview"
+risk = "destructive"
+command = ["false"]
+
+[tools.parameters]
+type = "object"
+
+[tools.parameters.properties.dry_run]
+type = "boolean"
+"#,
+        )
+        .unwrap();
+        let preview = declared.execute("wipe", json!({"dry_run": true}));
+        assert_eq!(preview.data, json!({"dry_run": true, "command": ["false"]}));
+    }
+
+    #[test]
+    fn test_invalid_definitions_rejected() {
+        let bad = [
+            "name = \"x\"\ntools = []",
+            "name = \"x\"\n[[tools]]\nname = \"t\"\ndescription = \"d\"\ncommand = [\"echo\", \"{missing}\"]",
+            "name = \"x\"\n[[tools]]\nname = \"t\"\ndescription = \"d\"\ncommand = []",
+            "name = \"x\"\n[[tools]]\nname = \"t\"\ndescription = \"d\"\ncommand = [\"ls\"]\nshell = true",
+            "name = \"x\"\n[[tools]]\nname = \"t\"\ndescription = \"d\"\ncommand = [\"ls\"]\nparser = { kind = \"regex\", pattern = \"(\" }",
+        ];
+        for raw in bad {
+            assert!(DeclarativeModule::parse(raw).is_err(), "{}", raw);
+        }
+    }
+
+    #[test]
+    fn test_bundled_modules_load() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../modules");
+        let modules = load_dir(&dir);
+        assert!(!modules.is_empty());
+        for module in modules {
+            module.unwrap();
+        }
+    }
+}
@@ -0,0 +1,291 @@
+//! Output parsers: turn a command's stdout into the tool's JSON result.
+//!
+//! - `text`: the trimmed output as `output` (the default).
+//! - `regex`: named capture groups of the first match, or of every match
+//!   as `matches` with `all = true`.
+//! - `key_value`: one `key: value` pair per line, keys in snake_case.
+//! - `json`: the parsed output, or selected `fields` by path
+//!   (`$.interfaces[0].name`).
+//! - `table`: whitespace-separated columns as `rows`, named by `columns` or
+//!   by the header line.
+
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+enum ParserSpec {
+    Text,
+    Regex {
+        pattern: String,
+        #[serde(default)]
+        all: bool,
+    },
+    KeyValue {
+        #[serde(default = "default_separator")]
+        separator: String,
+    },
+    Json {
+        #[serde(default)]
+        fields: BTreeMap<String, String>,
+    },
+    Table {
+        #[serde(default)]
+        columns: Vec<String>,
+        /// Lines to drop before the header or first row.
+        #[serde(default)]
+        skip: usize,
+    },
+}
+
+fn default_separator() -> String {
+    ":".into()
+}
+
+/// A parser with its regex compiled once, when the module loads.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(try_from = "ParserSpec")]
+pub(super) enum Parser {
+    #[default]
+    Text,
+    Regex {
+        regex: Regex,
+        all: bool,
+    },
+    KeyValue {
+        separator: String,
+    },
+    Json {
+        fields: BTreeMap<String, String>,
+    },
+    Table {
+        columns: Vec<String>,
+        skip: usize,
+    },
+}
+
+impl TryFrom<ParserSpec> for Parser {
+    type Error = String;
+
+    fn try_from(spec: ParserSpec) -> Result<Self, String> {
+        Ok(match spec {
+            ParserSpec::Text => Parser::Text,
+            ParserSpec::Regex { pattern, all } => {
+                let regex = Regex::new(&pattern).map_err(|e| format!("invalid regex: {}", e))?;
+                if regex.capture_names().flatten().next().is_none() {
+                    return Err(
+                        "regex parser needs at least one named group, e.g. (?P<name>...)".into(),
+                    );
+                }
+                Parser::Regex { regex, all }
+            }
+            ParserSpec::KeyValue { separator } => {
+                if separator.is_empty() {
+                    return Err("key_value separator may not be empty".into());
+                }
+                Parser::KeyValue { separator }
+            }
+            ParserSpec::Json { fields } => Parser::Json { fields },
+            ParserSpec::Table { columns, skip } => Parser::Table {
+                columns: columns.iter().map(|c| snake_case(c)).collect(),
+                skip,
+            },
+        })
+    }
+}
+
+/// "Product Version" → "product_version".
+fn snake_case(key: &str) -> String {
+    key.trim()
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// Numbers become JSON numbers; everything else stays text.
+fn scalar(text: &str) -> Value {
+    let text = text.trim();
+    if let Ok(n) = text.parse::<i64>() {
+        return json!(n);
+    }
+    match text.parse::<f64>() {
+        Ok(n) if n.is_finite() => json!(n),
+        _ => json!(text),
+    }
+}
+
+fn captures(regex: &Regex, caps: &regex::Captures) -> Value {
+    let fields: Map<String, Value> = regex
+        .capture_names()
+        .flatten()
+        .filter_map(|name| {
+            caps.name(name)
+                .map(|m| (name.to_string(), scalar(m.as_str())))
+        })
+        .collect();
+    Value::Object(fields)
+}
+
+/// Follow a path like `$.a.b[0].c` (or `a.b.0.c`) into a JSON value.
+fn select<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    let path = path.trim_start_matches('$');
+    let mut current = value;
+    for segment in path.split(['.', '[', ']']).filter(|s| !s.is_empty()) {
+        current = match current {
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?)?,
+            Value::Object(map) => map.get(segment.trim_matches(['"', '\'']))?,
+            _ => return None,
+        };
+    }
+    Some(current)
+}
+
+impl Parser {
+    pub(super) fn parse(&self, output: &str) -> Result<Value, String> {
+        match self {
+            Parser::Text => Ok(json!({ "output": output.trim() })),
+            Parser::Regex { regex, all: false } => regex
+                .captures(output)
+                .map(|caps| captures(regex, &caps))
+                .ok_or_else(|| format!("Output did not match /{}/", regex.as_str())),
+            Parser::Regex { regex, all: true } => {
+                let matches: Vec<Value> = regex
+                    .captures_iter(output)
+                    .map(|caps| captures(regex, &caps))
+                    .collect();
+                Ok(json!({ "matches": matches, "count": matches.len() }))
+            }
+            Parser::KeyValue { separator } => {
+                let fields: Map<String, Value> = output
+                    .lines()
+                    .filter_map(|line| line.split_once(separator.as_str()))
+                    .map(|(k, v)| (snake_case(k), scalar(v)))
+                    .filter(|(k, _)| !k.is_empty())
+                    .collect();
+                Ok(Value::Object(fields))
+            }
+            Parser::Json { fields } => {
+                let value: Value = serde_json::from_str(output)
+                    .map_err(|e| format!("Output is not JSON: {}", e))?;
+                if fields.is_empty() {
+                    return Ok(value);
+                }
+                let selected: Map<String, Value> = fields
+                    .iter()
+                    .map(|(name, path)| {
+                        (
+                            name.clone(),
+                            select(&value, path).cloned().unwrap_or(Value::Null),
+                        )
+                    })
+                    .collect();
+                Ok(Value::Object(selected))
+            }
+            Parser::Table { columns, skip } => {
+                let mut lines = output.lines().skip(*skip).filter(|l| !l.trim().is_empty());
+                let columns = if columns.is_empty() {
+                    match lines.next() {
+                        Some(header) => header.split_whitespace().map(snake_case).collect(),
+                        None => Vec::new(),
+                    }
+                } else {
+                    columns.clone()
+                };
+                let rows: Vec<Value> = lines
+                    .map(|line| {
+                        // The last column takes the rest of the line, so a
+                        // path or name with spaces stays whole.
+                        let mut row = Map::new();
+                        let mut rest = line.trim();
+                        for (i, column) in columns.iter().enumerate() {
+                            let cell = if i + 1 == columns.len() {
+                                std::mem::take(&mut rest)
+                            } else {
+                                let (cell, tail) =
+                                    rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+                                rest = tail.trim_start();
+                                cell
+                            };
+                            row.insert(column.clone(), scalar(cell));
+                        }
+                        Value::Object(row)
+                    })
+                    .collect();
+                Ok(json!({ "rows": rows, "count": rows.len() }))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parser(value: Value) -> Parser {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_parsers() {
+        let regex =
+            parser(json!({"kind": "regex", "pattern": r"load averages?: (?P<load_1m>[\d.]+)"}));
+        assert_eq!(
+            regex
+                .parse("up 3 days, load averages: 1.52 1.40 1.38")
+                .unwrap(),
+            json!({"load_1m": 1.52})
+        );
+        assert!(regex.parse("nothing here").is_err());
+
+        let all = parser(json!({"kind": "regex", "pattern": r"(?P<port>\d+)/tcp", "all": true}));
+        assert_eq!(all.parse("22/tcp open\n80/tcp open").unwrap()["count"], 2);
+
+        let kv = parser(json!({"kind": "key_value"}));
+        assert_eq!(
+            kv.parse("ProductName:\tmacOS\nProductVersion:\t14.5\nnoise")
+                .unwrap(),
+            json!({"productname": "macOS", "productversion": 14.5})
+        );
+
+        let json_fields = parser(
+            json!({"kind": "json", "fields": {"first": "$.items[0].name", "gone": "$.x.y"}}),
+        );
+        assert_eq!(
+            json_fields
+                .parse(r#"{"items": [{"name": "en0"}]}"#)
+                .unwrap(),
+            json!({"first": "en0", "gone": null})
+        );
+
+        let table = parser(json!({"kind": "table"}));
+        let rows = table
+            .parse("Filesystem 1K-blocks Mounted on\n/dev/disk1 1000 /Volumes/My Disk\n")
+            .unwrap();
+        // A header with spaces splits into extra columns; name them instead.
+        assert_eq!(
+            rows["rows"][0],
+            json!({"filesystem": "/dev/disk1", "1k_blocks": 1000, "mounted": "/Volumes/My", "on": "Disk"})
+        );
+        let table = parser(
+            json!({"kind": "table", "columns": ["device", "blocks", "Mounted On"], "skip": 1}),
+        );
+        let rows = table
+            .parse("Filesystem 1K-blocks Mounted on\n/dev/disk1 1000 /Volumes/My Disk\n")
+            .unwrap();
+        assert_eq!(rows["rows"][0]["mounted_on"], "/Volumes/My Disk");
+
+        assert_eq!(
+            Parser::default().parse(" hi \n").unwrap(),
+            json!({"output": "hi"})
+        );
+        assert!(
+            serde_json::from_value::<Parser>(json!({"kind": "regex", "pattern": "(unnamed)"}))
+                .is_err()
+        );
+        assert!(serde_json::from_value::<Parser>(json!({"kind": "xml"})).is_err());
+    }
+}
@@ -0,0 +1,232 @@
+//! Command templates: `{name}` placeholders inside argv elements, filled from
+//! arguments checked against the tool's JSON Schema. Commands never go
+//! through a shell, so a value is always exactly one argument; the schema
+//! decides which values are allowed at all.
+
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::sync::OnceLock;
+
+fn placeholder() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\{([A-Za-z_][A-Za-z0-9_]*)\}").expect("valid placeholder regex"))
+}
+
+/// Placeholder names used in a command, in order of appearance.
+pub(super) fn placeholders(command: &[String]) -> Vec<String> {
+    command
+        .iter()
+        .flat_map(|part| placeholder().captures_iter(part))
+        .map(|c| c[1].to_string())
+        .collect()
+}
+
+fn properties(schema: &Value) -> Option<&Map<String, Value>> {
+    schema.get("properties").and_then(|p| p.as_object())
+}
+
+/// Check a schema is one the validator understands: an object schema whose
+/// properties have a supported type and compilable patterns.
+pub(super) fn check_schema(schema: &Value) -> Result<(), String> {
+    if schema.get("type").and_then(|t| t.as_str()) != Some("object") {
+        return Err("parameters must be a JSON Schema with type \"object\"".into());
+    }
+    for (name, prop) in properties(schema).into_iter().flatten() {
+        let kind = prop.get("type").and_then(|t| t.as_str()).unwrap_or("");
+        if !matches!(kind, "string" | "integer" | "number" | "boolean") {
+            return Err(format!(
+                "parameter {:?} must be a string, integer, number or boolean",
+                name
+            ));
+        }
+        if let Some(pattern) = prop.get("pattern").and_then(|p| p.as_str()) {
+            Regex::new(pattern).map_err(|e| format!("parameter {:?} pattern: {}", name, e))?;
+        }
+    }
+    Ok(())
+}
+
+fn check_value(name: &str, prop: &Value, value: &Value) -> Result<String, String> {
+    let kind = prop
+        .get("type")
+        .and_then(|t| t.as_str())
+        .unwrap_or("string");
+    let text = match (kind, value) {
+        ("string", Value::String(s)) => s.clone(),
+        ("integer", v) if v.is_i64() || v.is_u64() => v.to_string(),
+        ("number", Value::Number(n)) => n.to_string(),
+        ("boolean", Value::Bool(b)) => b.to_string(),
+        _ => return Err(format!("{} must be a {}", name, kind)),
+    };
+    if let Some(allowed) = prop.get("enum").and_then(|e| e.as_array()) {
+        if !allowed.contains(value) {
+            return Err(format!(
+                "{} must be one of {}",
+                name,
+                Value::from(allowed.clone())
+            ));
+        }
+    }
+    if let Some(n) = value.as_f64() {
+        if prop
+            .get("minimum")
+            .and_then(|m| m.as_f64())
+            .is_some_and(|min| n < min)
+        {
+            return Err(format!(
+                "{} is below the minimum of {}",
+                name, prop["minimum"]
+            ));
+        }
+        if prop
+            .get("maximum")
+            .and_then(|m| m.as_f64())
+            .is_some_and(|max| n > max)
+        {
+            return Err(format!(
+                "{} is above the maximum of {}",
+                name, prop["maximum"]
+            ));
+        }
+    }
+    if kind == "string" {
+        if let Some(max) = prop.get("maxLength").and_then(|m| m.as_u64()) {
+            if text.chars().count() as u64 > max {
+                return Err(format!("{} is longer than {} characters", name, max));
+            }
+        }
+        if let Some(pattern) = prop.get("pattern").and_then(|p| p.as_str()) {
+            let anchored = Regex::new(&format!("^(?:{})$", pattern)).map_err(|e| e.to_string())?;
+            if !anchored.is_match(&text) {
+                return Err(format!("{} {:?} does not match {}", name, text, pattern));
+            }
+        }
+        // A value is one argument, but a leading dash would still be read
+        // as an option by most commands.
+        if text.starts_with('-') {
+            return Err(format!("{} may not start with '-'", name));
+        }
+        if text.contains('\0') {
+            return Err(format!("{} contains a NUL byte", name));
+        }
+    }
+    Ok(text)
+}
+
+/// Validate `args` against `schema`, filling in defaults. Returns the text
+/// to substitute for each parameter that has a value.
+pub(super) fn bind(schema: &Value, args: &Value) -> Result<Map<String, Value>, String> {
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(|r| r.as_array())
+        .map(|r| r.iter().filter_map(|v| v.as_str()).collect())
+        .unwrap_or_default();
+    let mut bound = Map::new();
+    for (name, prop) in properties(schema).into_iter().flatten() {
+        let value = args
+            .get(name)
+            .filter(|v| !v.is_null())
+            .or_else(|| prop.get("default"));
+        match value {
+            Some(value) => {
+                let text = check_value(name, prop, value)?;
+                bound.insert(name.clone(), Value::String(text));
+            }
+            None if required.contains(&name.as_str()) => {
+                return Err(format!("Missing required parameter {:?}", name));
+            }
+            None => {}
+        }
+    }
+    Ok(bound)
+}
+
+/// Substitute bound values into the command. An element that is only a
+/// placeholder for an unset optional parameter is dropped.
+pub(super) fn render(
+    command: &[String],
+    bound: &Map<String, Value>,
+) -> Result<Vec<String>, String> {
+    let mut argv = Vec::with_capacity(command.len());
+    for part in command {
+        if let Some(c) = placeholder().captures(part) {
+            if c[0].len() == part.len() && !bound.contains_key(&c[1]) {
+                continue;
+            }
+        }
+        let mut missing = None;
+        let rendered = placeholder().replace_all(part, |c: &regex::Captures| {
+            match bound.get(&c[1]).and_then(|v| v.as_str()) {
+                Some(text) => text.to_string(),
+                None => {
+                    missing = Some(c[1].to_string());
+                    String::new()
+                }
+            }
+        });
+        if let Some(name) = missing {
+            return Err(format!("No value for {{{}}} in {:?}", name, part));
+        }
+        argv.push(rendered.into_owned());
+    }
+    Ok(argv)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_bind_and_render() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "host": {"type": "string", "pattern": "[A-Za-z0-9.-]+", "maxLength": 253},
+                "count": {"type": "integer", "minimum": 1, "maximum": 10, "default": 3},
+                "family": {"type": "string", "enum": ["inet", "inet6"]}
+            },
+            "required": ["host"]
+        });
+        check_schema(&schema).unwrap();
+        let command: Vec<String> = ["ping", "-c", "{count}", "{family}", "{host}"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(placeholders(&command), vec!["count", "family", "host"]);
+
+        let bound = bind(&schema, &json!({"host": "example.com"})).unwrap();
+        assert_eq!(
+            render(&command, &bound).unwrap(),
+            vec!["ping", "-c", "3", "example.com"]
+        );
+
+        // Injection attempts fail validation rather than reaching argv.
+        for args in [
+            json!({"host": "example.com; rm -rf ~"}),
+            json!({"host": "-oProxyCommand=evil"}),
+            json!({"host": "a", "count": 50}),
+            json!({"host": "a", "count": "3"}),
+            json!({"host": "a", "family": "unix"}),
+            json!({}),
+        ] {
+            assert!(bind(&schema, &args).is_err(), "{}", args);
+        }
+    }
+
+    #[test]
+    fn test_unconstrained_string_is_one_argument() {
+        let schema = json!({"type": "object", "properties": {"name": {"type": "string"}}});
+        let command = vec!["echo".to_string(), "name={name}".to_string()];
+        let bound = bind(&schema, &json!({"name": "$(whoami) && ls"})).unwrap();
+        assert_eq!(
+            render(&command, &bound).unwrap(),
+            vec!["echo", "name=$(whoami) && ls"]
+        );
+        assert!(render(&command, &Map::new()).is_err());
+        assert!(
+            check_schema(&json!({"type": "object", "properties": {"x": {"type": "array"}}}))
+                .is_err()
+        );
+    }
+}
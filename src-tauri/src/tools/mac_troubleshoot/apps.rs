@@ -0,0 +1,427 @@
+//! Application inventory for `list_apps`.
+//!
+//! Reads each bundle's `Info.plist` for name, version and bundle id, and the
+//! Mach-O header of its main executable for the architectures it was built
+//! for. Intel-only apps that are running on an Apple silicon Mac are running
+//! under Rosetta. Homebrew formulae and casks are listed when `brew` exists,
+//! along with whatever `brew outdated` reports.
+
+use super::process::{self, ProcessInfo};
+use super::startup::{check_signature, Signature};
+use super::{home_dir, run_cmd, ToolResult};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+const CPU_TYPE_X86: i32 = 7;
+const CPU_TYPE_ARM: i32 = 12;
+const CPU_ARCH_ABI64: i32 = 0x0100_0000;
+
+const BREW_PATHS: &[&str] = &["/opt/homebrew/bin/brew", "/usr/local/bin/brew"];
+
+// ----- Mach-O -----
+
+/// Architecture names from a Mach-O header: thin (32/64-bit, either byte
+/// order) or fat/universal. Anything else yields an empty list.
+pub(super) fn parse_macho_archs(header: &[u8]) -> Vec<&'static str> {
+    let word = |at: usize, big: bool| -> Option<i32> {
+        let bytes: [u8; 4] = header.get(at..at + 4)?.try_into().ok()?;
+        Some(if big {
+            i32::from_be_bytes(bytes)
+        } else {
+            i32::from_le_bytes(bytes)
+        })
+    };
+    let name = |cputype: i32| match cputype {
+        t if t == (CPU_TYPE_X86 | CPU_ARCH_ABI64) => "x86_64",
+        t if t == CPU_TYPE_X86 => "i386",
+        t if t == (CPU_TYPE_ARM | CPU_ARCH_ABI64) => "arm64",
+        t if t == CPU_TYPE_ARM => "arm",
+        _ => "unknown",
+    };
+
+    let Some(magic) = word(0, true) else {
+        return Vec::new();
+    };
+    match magic as u32 {
+        // fat_header, big-endian; fat_arch is 20 bytes, fat_arch_64 is 32.
+        0xcafe_babe | 0xcafe_babf => {
+            let entry_size = if magic as u32 == 0xcafe_babe { 20 } else { 32 };
+            let count = word(4, true).unwrap_or(0);
+            // Java class files share the magic; their "count" is huge.
+            if !(1..=16).contains(&count) {
+                return Vec::new();
+            }
+            (0..count as usize)
+                .filter_map(|i| word(8 + i * entry_size, true))
+                .map(name)
+                .collect()
+        }
+        0xfeed_face | 0xfeed_facf => word(4, true).map(name).into_iter().collect(),
+        0xcefa_edfe | 0xcffa_edfe => word(4, false).map(name).into_iter().collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum Architecture {
+    Universal,
+    AppleSilicon,
+    Intel,
+    Unknown,
+}
+
+impl Architecture {
+    pub(super) fn from_archs(archs: &[&str]) -> Self {
+        let arm = archs.iter().any(|a| a.starts_with("arm"));
+        let intel = archs.iter().any(|a| *a == "x86_64" || *a == "i386");
+        match (arm, intel) {
+            (true, true) => Architecture::Universal,
+            (true, false) => Architecture::AppleSilicon,
+            (false, true) => Architecture::Intel,
+            (false, false) => Architecture::Unknown,
+        }
+    }
+}
+
+fn read_header(path: &Path) -> Vec<u8> {
+    let mut buf = vec![0u8; 512];
+    match File::open(path).and_then(|mut f| f.read(&mut buf)) {
+        Ok(n) => {
+            buf.truncate(n);
+            buf
+        }
+        Err(_) => Vec::new(),
+    }
+}
+
+// ----- Bundles -----
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(super) struct AppBundle {
+    pub name: String,
+    pub path: String,
+    pub bundle_id: Option<String>,
+    pub version: Option<String>,
+    pub build: Option<String>,
+    pub executable: Option<String>,
+    pub archs: Vec<&'static str>,
+    pub architecture: Architecture,
+    pub signature: Option<Signature>,
+    pub running: bool,
+    pub rosetta: bool,
+}
+
+/// Read a `.app` bundle. Returns `None` when it has no readable `Info.plist`.
+pub(super) fn read_bundle(path: &Path) -> Option<AppBundle> {
+    let contents = path.join("Contents");
+    let info = plist::Value::from_file(contents.join("Info.plist")).ok()?;
+    let dict = info.as_dictionary()?;
+    let string = |key: &str| dict.get(key).and_then(|v| v.as_string()).map(String::from);
+
+    let file_stem = path.file_stem()?.to_string_lossy().to_string();
+    let executable = string("CFBundleExecutable");
+    let archs = executable
+        .as_ref()
+        .map(|exe| parse_macho_archs(&read_header(&contents.join("MacOS").join(exe))))
+        .unwrap_or_default();
+
+    Some(AppBundle {
+        name: string("CFBundleDisplayName")
+            .or_else(|| string("CFBundleName"))
+            .unwrap_or(file_stem),
+        path: path.display().to_string(),
+        bundle_id: string("CFBundleIdentifier"),
+        // Apps that only set CFBundleVersion use it as their version.
+        version: string("CFBundleShortVersionString").or_else(|| string("CFBundleVersion")),
+        build: string("CFBundleVersion"),
+        executable,
+        architecture: Architecture::from_archs(&archs),
+        archs,
+        signature: None,
+        running: false,
+        rosetta: false,
+    })
+}
+
+/// `.app` bundles directly inside `dirs` and one level of subfolders
+/// (`/Applications/Utilities`), sorted by name.
+pub(super) fn scan_bundles(dirs: &[PathBuf]) -> Vec<AppBundle> {
+    let is_app = |p: &Path| p.extension().is_some_and(|e| e == "app");
+    let mut bundles: Vec<AppBundle> = Vec::new();
+    for dir in dirs {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        for path in entries.flatten().map(|e| e.path()) {
+            if is_app(&path) {
+                bundles.extend(read_bundle(&path));
+            } else if path.is_dir() {
+                let nested = fs::read_dir(&path).into_iter().flatten().flatten();
+                bundles.extend(
+                    nested
+                        .map(|e| e.path())
+                        .filter(|p| is_app(p))
+                        .filter_map(|p| read_bundle(&p)),
+                );
+            }
+        }
+    }
+    bundles.sort_by_key(|b| b.name.to_lowercase());
+    bundles
+}
+
+/// Mark bundles with a running process; on Apple silicon, running Intel-only
+/// bundles are translated by Rosetta.
+pub(super) fn mark_running(bundles: &mut [AppBundle], processes: &[ProcessInfo], arm_host: bool) {
+    for bundle in bundles.iter_mut() {
+        let prefix = format!("{}/", bundle.path);
+        bundle.running = processes.iter().any(|p| p.command.starts_with(&prefix));
+        bundle.rosetta = arm_host && bundle.running && bundle.architecture == Architecture::Intel;
+    }
+}
+
+// ----- Homebrew -----
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(super) struct BrewPackage {
+    pub name: String,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(super) struct BrewOutdated {
+    pub name: String,
+    pub kind: &'static str,
+    pub installed: String,
+    pub latest: String,
+}
+
+/// `brew list --versions`: "name v1 [v2 ...]"; the last version is newest.
+pub(super) fn parse_brew_list(output: &str) -> Vec<BrewPackage> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let name = parts.next()?;
+            Some(BrewPackage {
+                name: name.to_string(),
+                version: parts.last()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// `brew outdated --json=v2`.
+pub(super) fn parse_brew_outdated(output: &str) -> Vec<BrewOutdated> {
+    let Ok(root) = serde_json::from_str::<Value>(output) else {
+        return Vec::new();
+    };
+    let mut outdated = Vec::new();
+    for (key, kind) in [("formulae", "formula"), ("casks", "cask")] {
+        for pkg in root
+            .get(key)
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+        {
+            let text = |v: Option<&Value>| match v {
+                Some(Value::String(s)) => Some(s.clone()),
+                Some(Value::Array(a)) => a.last().and_then(|s| s.as_str()).map(String::from),
+                _ => None,
+            };
+            let (Some(name), Some(installed), Some(latest)) = (
+                text(pkg.get("name")),
+                text(pkg.get("installed_versions")),
+                text(pkg.get("current_version")),
+            ) else {
+                continue;
+            };
+            outdated.push(BrewOutdated {
+                name,
+                kind,
+                installed,
+                latest,
+            });
+        }
+    }
+    outdated
+}
+
+fn homebrew(check_outdated: bool) -> Option<Value> {
+    let brew = BREW_PATHS.iter().find(|p| Path::new(p).exists())?;
+    let formulae = parse_brew_list(&run_cmd(brew, &["list", "--formula", "--versions"]));
+    let casks = parse_brew_list(&run_cmd(brew, &["list", "--cask", "--versions"]));
+    let outdated = if check_outdated {
+        parse_brew_outdated(&run_cmd(brew, &["outdated", "--json=v2"]))
+    } else {
+        Vec::new()
+    };
+    Some(json!({
+        "brew": brew,
+        "formulae": formulae,
+        "casks": casks,
+        "outdated": outdated,
+    }))
+}
+
+pub(super) fn list_apps(args: &Value) -> ToolResult {
+    let name = args
+        .get("name")
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim().to_lowercase())
+        .filter(|s| !s.is_empty());
+    let include_homebrew = args
+        .get("include_homebrew")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(name.is_none());
+    let check_outdated = args
+        .get("check_outdated")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true);
+
+    let dirs = vec![
+        PathBuf::from("/Applications"),
+        home_dir().join("Applications"),
+    ];
+    let mut bundles = scan_bundles(&dirs);
+    if let Some(name) = &name {
+        bundles.retain(|b| {
+            b.name.to_lowercase().contains(name)
+                || b.bundle_id
+                    .as_deref()
+                    .is_some_and(|id| id.to_lowercase().contains(name))
+        });
+    }
+    for bundle in bundles.iter_mut() {
+        bundle.signature = Some(check_signature(Path::new(&bundle.path)));
+    }
+
+    let arm_host = run_cmd("uname", &["-m"]) == "arm64";
+    mark_running(&mut bundles, &process::snapshot(), arm_host);
+
+    let homebrew = if include_homebrew {
+        homebrew(check_outdated)
+    } else {
+        None
+    };
+    let names = |keep: fn(&AppBundle) -> bool| -> Vec<&str> {
+        bundles
+            .iter()
+            .filter(|b| keep(b))
+            .map(|b| b.name.as_str())
+            .collect()
+    };
+
+    ToolResult {
+        success: true,
+        data: json!({
+            "apple_silicon": arm_host,
+            "app_count": bundles.len(),
+            "intel_only": names(|b| b.architecture == Architecture::Intel),
+            "running_under_rosetta": names(|b| b.rosetta),
+            "unsigned": names(|b| matches!(b.signature, Some(Signature::Unsigned))),
+            "invalid_signature": names(|b| matches!(b.signature, Some(Signature::Invalid(_)))),
+            "apps": bundles,
+            "homebrew": homebrew,
+        }),
+        error: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixtures() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/apps")
+    }
+
+    #[test]
+    fn test_parse_macho_archs() {
+        let thin_arm = [0xcf, 0xfa, 0xed, 0xfe, 0x0c, 0x00, 0x00, 0x01];
+        assert_eq!(parse_macho_archs(&thin_arm), vec!["arm64"]);
+        let fat = [
+            0xca, 0xfe, 0xba, 0xbe, 0, 0, 0, 2, //
+            1, 0, 0, 7, 0, 0, 0, 3, 0, 0, 0x10, 0, 0, 0, 0, 0, 0, 0, 0, 12, //
+            1, 0, 0, 12, 0, 0, 0, 0, 0, 0, 0x20, 0, 0, 0, 0, 0, 0, 0, 0, 12,
+        ];
+        assert_eq!(parse_macho_archs(&fat), vec!["x86_64", "arm64"]);
+        // A Java class file: same magic, nonsense arch count.
+        assert!(parse_macho_archs(&[0xca, 0xfe, 0xba, 0xbe, 0, 0, 0, 52]).is_empty());
+        assert!(parse_macho_archs(b"#!/bin/sh\n").is_empty());
+    }
+
+    #[test]
+    fn test_read_bundles() {
+        let bundles = scan_bundles(&[fixtures()]);
+        // Broken.app has no Info.plist.
+        let names: Vec<&str> = bundles.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, vec!["Legacy Intel", "Silicon", "Universal"]);
+
+        // Binary plist, executable name differs from the bundle name.
+        let legacy = &bundles[0];
+        assert_eq!(legacy.bundle_id.as_deref(), Some("com.example.legacy"));
+        assert_eq!(legacy.version.as_deref(), Some("1.0"));
+        assert_eq!(legacy.architecture, Architecture::Intel);
+
+        // No short version string: falls back to the build number.
+        let silicon = &bundles[1];
+        assert_eq!(silicon.version.as_deref(), Some("42"));
+        assert_eq!(silicon.architecture, Architecture::AppleSilicon);
+
+        let universal = &bundles[2];
+        assert_eq!(universal.version.as_deref(), Some("2.3.1"));
+        assert_eq!(universal.build.as_deref(), Some("231"));
+        assert_eq!(universal.archs, vec!["x86_64", "arm64"]);
+        assert_eq!(universal.architecture, Architecture::Universal);
+    }
+
+    #[test]
+    fn test_rosetta_detection() {
+        let mut bundles = scan_bundles(&[fixtures()]);
+        let process = |command: String| ProcessInfo {
+            pid: 1,
+            name: String::new(),
+            user: String::new(),
+            uid: 501,
+            cpu_percent: 0.0,
+            mem_percent: 0.0,
+            rss_kb: 0,
+            command,
+        };
+        let running = vec![
+            process(format!("{}/Contents/MacOS/LegacyBin", bundles[0].path)),
+            process(format!("{}/Contents/MacOS/Universal", bundles[2].path)),
+        ];
+
+        mark_running(&mut bundles, &running, true);
+        assert!(bundles[0].running && bundles[0].rosetta);
+        assert!(!bundles[1].running);
+        assert!(bundles[2].running && !bundles[2].rosetta);
+
+        // No Rosetta on an Intel Mac.
+        mark_running(&mut bundles, &running, false);
+        assert!(!bundles[0].rosetta);
+    }
+
+    #[test]
+    fn test_parse_brew() {
+        let list = parse_brew_list("wget 1.21.3\nopenssl@3 3.1.0 3.1.1\n");
+        assert_eq!(list[1].name, "openssl@3");
+        assert_eq!(list[1].version, "3.1.1");
+
+        let outdated = parse_brew_outdated(
+            r#"{"formulae":[{"name":"wget","installed_versions":["1.21.3"],"current_version":"1.21.4","pinned":false}],
+                "casks":[{"name":"firefox","installed_versions":"120.0","current_version":"121.0"}]}"#,
+        );
+        assert_eq!(outdated.len(), 2);
+        assert_eq!(outdated[0].kind, "formula");
+        assert_eq!(outdated[0].latest, "1.21.4");
+        assert_eq!(outdated[1].installed, "120.0");
+        assert!(parse_brew_outdated("").is_empty());
+    }
+}
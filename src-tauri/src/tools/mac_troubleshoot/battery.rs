@@ -0,0 +1,414 @@
+//! Battery health for `diagnose_battery`.
+//!
+//! Combines three sources: `system_profiler SPPowerDataType -json` (cycle
+//! count, condition, maximum capacity, charger), the `AppleSmartBattery`
+//! registry entry from `ioreg` (design vs. full-charge capacity, temperature,
+//! time remaining), and `pmset` (charge state and sleep assertions).
+
+use super::{run_cmd, ToolResult};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// Health below this percentage of design capacity needs service.
+const HEALTH_SERVICE_PCT: f64 = 80.0;
+/// Modern MacBooks are rated for 1000 cycles.
+const RATED_CYCLES: u64 = 1000;
+const HOT_TEMPERATURE_C: f64 = 40.0;
+/// `ioreg` reports 65535 when a time estimate is not available.
+const NO_ESTIMATE: u64 = 65535;
+
+// ----- system_profiler -----
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub(super) struct ProfilerBattery {
+    pub cycle_count: Option<u64>,
+    pub condition: Option<String>,
+    pub maximum_capacity_pct: Option<f64>,
+    pub charge_pct: Option<u64>,
+    pub charger_connected: Option<bool>,
+    pub adapter_watts: Option<u64>,
+}
+
+/// system_profiler encodes booleans as "TRUE"/"FALSE" and numbers as either
+/// JSON numbers or strings like "89%".
+fn sp_bool(v: &Value) -> Option<bool> {
+    v.as_str().map(|s| s.eq_ignore_ascii_case("TRUE"))
+}
+
+pub(super) fn sp_number(v: &Value) -> Option<f64> {
+    v.as_f64()
+        .or_else(|| v.as_str()?.trim().trim_end_matches('%').trim().parse().ok())
+}
+
+pub(super) fn parse_power_profile(raw: &str) -> ProfilerBattery {
+    let mut battery = ProfilerBattery::default();
+    let Ok(root) = serde_json::from_str::<Value>(raw) else {
+        return battery;
+    };
+    for section in root["SPPowerDataType"].as_array().into_iter().flatten() {
+        match section["_name"].as_str() {
+            Some("spbattery_information") => {
+                let health = &section["sppower_battery_health_info"];
+                let charge = &section["sppower_battery_charge_info"];
+                battery.cycle_count =
+                    sp_number(&health["sppower_battery_cycle_count"]).map(|n| n as u64);
+                battery.condition = health["sppower_battery_health"].as_str().map(String::from);
+                battery.maximum_capacity_pct =
+                    sp_number(&health["sppower_battery_health_maximum_capacity"]);
+                battery.charge_pct =
+                    sp_number(&charge["sppower_battery_state_of_charge"]).map(|n| n as u64);
+            }
+            Some("sppower_ac_charger_information") => {
+                battery.charger_connected = sp_bool(&section["sppower_battery_charger_connected"]);
+                battery.adapter_watts =
+                    sp_number(&section["sppower_ac_charger_watts"]).map(|n| n as u64);
+            }
+            _ => {}
+        }
+    }
+    battery
+}
+
+// ----- ioreg -----
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub(super) struct SmartBattery {
+    pub design_capacity_mah: Option<u64>,
+    pub full_charge_capacity_mah: Option<u64>,
+    pub cycle_count: Option<u64>,
+    pub temperature_c: Option<f64>,
+    pub time_to_empty_min: Option<u64>,
+    pub time_to_full_min: Option<u64>,
+    pub adapter_watts: Option<u64>,
+}
+
+/// Top-level `"Key" = 123` integer from `ioreg -rn AppleSmartBattery`.
+fn ioreg_int(raw: &str, key: &str) -> Option<u64> {
+    let needle = format!("\"{}\" = ", key);
+    raw.lines().find_map(|line| {
+        line.trim()
+            .strip_prefix(&needle)
+            .and_then(|v| v.trim().parse().ok())
+    })
+}
+
+/// `"Watts"=96` inside the `AdapterDetails` dictionary.
+fn ioreg_adapter_watts(raw: &str) -> Option<u64> {
+    let line = raw
+        .lines()
+        .find(|l| l.trim().starts_with("\"AdapterDetails\""))?;
+    let rest = &line[line.find("\"Watts\"=")? + "\"Watts\"=".len()..];
+    rest.split(|c: char| !c.is_ascii_digit())
+        .next()?
+        .parse()
+        .ok()
+}
+
+pub(super) fn parse_ioreg_battery(raw: &str) -> SmartBattery {
+    // Apple silicon reports MaxCapacity as a percentage; the mAh value is AppleRawMaxCapacity
+    let full_charge = ioreg_int(raw, "AppleRawMaxCapacity")
+        .or_else(|| ioreg_int(raw, "MaxCapacity").filter(|&c| c > 100));
+    let minutes = |key| ioreg_int(raw, key).filter(|&m| m != NO_ESTIMATE && m != 0);
+    SmartBattery {
+        design_capacity_mah: ioreg_int(raw, "DesignCapacity"),
+        full_charge_capacity_mah: full_charge,
+        cycle_count: ioreg_int(raw, "CycleCount"),
+        // Reported in hundredths of a degree Celsius
+        temperature_c: ioreg_int(raw, "Temperature").map(|t| t as f64 / 100.0),
+        time_to_empty_min: minutes("AvgTimeToEmpty"),
+        time_to_full_min: minutes("AvgTimeToFull"),
+        adapter_watts: ioreg_adapter_watts(raw),
+    }
+}
+
+// ----- pmset -----
+
+/// Charge percentage, state and remaining-time text from `pmset -g batt`.
+pub(super) fn parse_pmset_batt(raw: &str) -> (Option<u64>, &'static str, Option<String>) {
+    let Some(line) = raw.lines().find(|l| l.contains('%')) else {
+        return (None, "unknown", None);
+    };
+    let pct_pos = line.find('%').unwrap_or(0);
+    let start = line[..pct_pos]
+        .rfind(|c: char| !c.is_ascii_digit())
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    let percentage = line[start..pct_pos].parse().ok();
+
+    // Match the state field exactly: "discharging" also contains "charging"
+    let fields: Vec<&str> = line[pct_pos..].split(';').map(str::trim).collect();
+    let state = match fields.get(1).copied() {
+        Some("discharging") => "discharging",
+        Some("charging") => "charging",
+        Some("charged") => "charged",
+        Some(s) if s.contains("AC attached") => "ac_attached",
+        Some("finishing charge") => "charging",
+        _ => "unknown",
+    };
+    let remaining = fields
+        .get(2)
+        .and_then(|f| f.split_whitespace().next())
+        .filter(|t| t.contains(':'))
+        .map(String::from);
+    (percentage, state, remaining)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(super) struct SleepBlocker {
+    pub pid: u32,
+    pub process: String,
+    pub assertion: String,
+    pub reason: String,
+}
+
+/// Assertions that keep the machine or display awake, from `pmset -g assertions`:
+///
+/// ```text
+///    pid 1234(zoom.us): [0x000a1] 00:05:00 PreventUserIdleDisplaySleep named: "Zoom meeting"
+/// ```
+pub(super) fn parse_assertions(raw: &str) -> Vec<SleepBlocker> {
+    raw.lines()
+        .filter_map(|line| {
+            let rest = line.trim().strip_prefix("pid ")?;
+            let (pid, rest) = rest.split_once('(')?;
+            let (process, rest) = rest.split_once("):")?;
+            let after_id = rest.split_once(']').map(|(_, r)| r).unwrap_or(rest);
+            let (head, reason) = after_id.split_once("named:").unwrap_or((after_id, ""));
+            let assertion = head.split_whitespace().find(|t| {
+                t.starts_with("Prevent") || t.starts_with("NoIdle") || t.starts_with("NoDisplay")
+            })?;
+            Some(SleepBlocker {
+                pid: pid.trim().parse().ok()?,
+                process: process.to_string(),
+                assertion: assertion.to_string(),
+                reason: reason.trim().trim_matches('"').to_string(),
+            })
+        })
+        .collect()
+}
+
+// ----- Verdict -----
+
+/// Health as full-charge / design capacity, falling back to system_profiler's
+/// "Maximum Capacity" percentage.
+pub(super) fn health_pct(profile: &ProfilerBattery, smart: &SmartBattery) -> Option<f64> {
+    match (smart.full_charge_capacity_mah, smart.design_capacity_mah) {
+        (Some(full), Some(design)) if design > 0 => {
+            Some((full as f64 / design as f64 * 1000.0).round() / 10.0)
+        }
+        _ => profile.maximum_capacity_pct,
+    }
+}
+
+pub(super) fn verdict(
+    condition: Option<&str>,
+    health: Option<f64>,
+    cycles: Option<u64>,
+    temperature_c: Option<f64>,
+) -> (&'static str, Vec<String>) {
+    let mut reasons = Vec::new();
+    let mut level = "good";
+
+    if let Some(c) = condition.filter(|c| !matches!(*c, "Normal" | "Good")) {
+        reasons.push(format!("macOS reports battery condition \"{}\"", c));
+        level = "service_recommended";
+    }
+    if let Some(h) = health.filter(|h| *h < HEALTH_SERVICE_PCT) {
+        reasons.push(format!("Holds {:.0}% of its design capacity", h));
+        level = "service_recommended";
+    }
+    if let Some(n) = cycles.filter(|n| *n >= RATED_CYCLES) {
+        reasons.push(format!("{} cycles is past the rated {}", n, RATED_CYCLES));
+        if level == "good" {
+            level = "fair";
+        }
+    }
+    if let Some(t) = temperature_c.filter(|t| *t >= HOT_TEMPERATURE_C) {
+        reasons.push(format!("Battery is hot ({:.1}°C)", t));
+        if level == "good" {
+            level = "fair";
+        }
+    }
+    (level, reasons)
+}
+
+pub(super) fn diagnose_battery() -> ToolResult {
+    let batt = run_cmd("pmset", &["-g", "batt"]);
+    let profile = parse_power_profile(&run_cmd("system_profiler", &["SPPowerDataType", "-json"]));
+    let smart = parse_ioreg_battery(&run_cmd("ioreg", &["-rn", "AppleSmartBattery"]));
+    let blockers = parse_assertions(&run_cmd("pmset", &["-g", "assertions"]));
+
+    let (percentage, status, remaining) = parse_pmset_batt(&batt);
+    let has_battery = profile.cycle_count.is_some() || smart.design_capacity_mah.is_some();
+    let health = health_pct(&profile, &smart);
+    let cycles = profile.cycle_count.or(smart.cycle_count);
+    let (level, reasons) = verdict(
+        profile.condition.as_deref(),
+        health,
+        cycles,
+        smart.temperature_c,
+    );
+
+    let summary = if !has_battery {
+        "No battery found (desktop Mac?)".to_string()
+    } else if reasons.is_empty() {
+        "Battery is healthy".to_string()
+    } else {
+        reasons.join("; ")
+    };
+
+    ToolResult {
+        success: true,
+        data: json!({
+            "has_battery": has_battery,
+            "percentage": percentage.or(profile.charge_pct),
+            "status": status,
+            "time_remaining": remaining,
+            "time_to_empty_min": smart.time_to_empty_min,
+            "time_to_full_min": smart.time_to_full_min,
+            "cycle_count": cycles,
+            "condition": profile.condition,
+            "design_capacity_mah": smart.design_capacity_mah,
+            "full_charge_capacity_mah": smart.full_charge_capacity_mah,
+            "health_percent": health,
+            "temperature_c": smart.temperature_c,
+            "charger_connected": profile.charger_connected,
+            "adapter_watts": profile.adapter_watts.or(smart.adapter_watts),
+            "sleep_blockers": blockers,
+            "verdict": if has_battery { level } else { "no_battery" },
+            "summary": summary,
+        }),
+        error: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POWER_PROFILE: &str = r#"{
+      "SPPowerDataType" : [
+        {
+          "_name" : "spbattery_information",
+          "sppower_battery_charge_info" : {
+            "sppower_battery_at_warn_level" : "FALSE",
+            "sppower_battery_fully_charged" : "FALSE",
+            "sppower_battery_is_charging" : "TRUE",
+            "sppower_battery_state_of_charge" : 76
+          },
+          "sppower_battery_health_info" : {
+            "sppower_battery_cycle_count" : 412,
+            "sppower_battery_health" : "Good",
+            "sppower_battery_health_maximum_capacity" : "89%"
+          },
+          "sppower_battery_model_info" : {
+            "sppower_battery_device_name" : "bq40z651"
+          }
+        },
+        {
+          "_name" : "sppower_ac_charger_information",
+          "sppower_ac_charger_watts" : "96",
+          "sppower_battery_charger_connected" : "TRUE",
+          "sppower_battery_is_charging" : "TRUE"
+        }
+      ]
+    }"#;
+
+    const IOREG: &str = r#"+-o AppleSmartBattery  <class AppleSmartBattery, id 0x100000253, registered, matched, active, busy 0 (0 ms), retain 7>
+    {
+      "AppleRawMaxCapacity" = 4484
+      "MaxCapacity" = 100
+      "DesignCapacity" = 5103
+      "CycleCount" = 412
+      "Temperature" = 3120
+      "AvgTimeToEmpty" = 65535
+      "AvgTimeToFull" = 47
+      "AdapterDetails" = {"AdapterVoltage"=20000,"Watts"=96,"Current"=4700,"Description"="pd charger"}
+    }"#;
+
+    const PMSET_BATT: &str = "Now drawing from 'AC Power'\n -InternalBattery-0 (id=23068771)\t76%; charging; 0:47 remaining present: true\n";
+
+    const ASSERTIONS: &str = r#"2024-05-01 10:00:00 +0200
+Assertion status system-wide:
+   BackgroundTask                 0
+   PreventUserIdleDisplaySleep    1
+   PreventUserIdleSystemSleep     1
+Listed by owning process:
+   pid 392(coreaudiod): [0x0000a5e8000193a7] 00:11:05 PreventUserIdleSystemSleep named: "com.apple.audio.AppleUSBAudioEngine:Built-in.context.preventuseridlesleep"
+	Created for PID: 1234.
+   pid 1234(zoom.us): [0x0000a5e8000193a9] 00:05:00 PreventUserIdleDisplaySleep named: "Zoom meeting"
+   pid 88(powerd): [0x0000a5e8000193b0] 00:00:10 BackgroundTask named: "com.apple.powerd.check"
+Kernel Assertions: 0x4=USB
+"#;
+
+    #[test]
+    fn test_parse_power_profile() {
+        let p = parse_power_profile(POWER_PROFILE);
+        assert_eq!(p.cycle_count, Some(412));
+        assert_eq!(p.condition.as_deref(), Some("Good"));
+        assert_eq!(p.maximum_capacity_pct, Some(89.0));
+        assert_eq!(p.charge_pct, Some(76));
+        assert_eq!(p.charger_connected, Some(true));
+        assert_eq!(p.adapter_watts, Some(96));
+        assert_eq!(parse_power_profile("not json"), ProfilerBattery::default());
+    }
+
+    #[test]
+    fn test_parse_ioreg_battery() {
+        let b = parse_ioreg_battery(IOREG);
+        assert_eq!(b.design_capacity_mah, Some(5103));
+        assert_eq!(b.full_charge_capacity_mah, Some(4484));
+        assert_eq!(b.temperature_c, Some(31.2));
+        assert_eq!(b.time_to_empty_min, None);
+        assert_eq!(b.time_to_full_min, Some(47));
+        assert_eq!(b.adapter_watts, Some(96));
+    }
+
+    #[test]
+    fn test_parse_pmset_batt() {
+        assert_eq!(
+            parse_pmset_batt(PMSET_BATT),
+            (Some(76), "charging", Some("0:47".to_string()))
+        );
+        let discharging =
+            " -InternalBattery-0 (id=1)\t54%; discharging; (no estimate) present: true";
+        assert_eq!(
+            parse_pmset_batt(discharging),
+            (Some(54), "discharging", None)
+        );
+        assert_eq!(
+            parse_pmset_batt("Now drawing from 'AC Power'"),
+            (None, "unknown", None)
+        );
+    }
+
+    #[test]
+    fn test_parse_assertions() {
+        let blockers = parse_assertions(ASSERTIONS);
+        assert_eq!(blockers.len(), 2);
+        assert_eq!(blockers[0].process, "coreaudiod");
+        assert_eq!(blockers[0].assertion, "PreventUserIdleSystemSleep");
+        assert_eq!(blockers[1].pid, 1234);
+        assert_eq!(blockers[1].reason, "Zoom meeting");
+    }
+
+    #[test]
+    fn test_health_and_verdict() {
+        let profile = parse_power_profile(POWER_PROFILE);
+        let smart = parse_ioreg_battery(IOREG);
+        assert_eq!(health_pct(&profile, &smart), Some(87.9));
+        assert_eq!(health_pct(&profile, &SmartBattery::default()), Some(89.0));
+
+        assert_eq!(
+            verdict(Some("Good"), Some(87.9), Some(412), Some(31.2)).0,
+            "good"
+        );
+        assert_eq!(
+            verdict(Some("Normal"), Some(92.0), Some(1040), None).0,
+            "fair"
+        );
+        let (level, reasons) = verdict(Some("Service Recommended"), Some(74.0), Some(1200), None);
+        assert_eq!(level, "service_recommended");
+        assert_eq!(reasons.len(), 3);
+    }
+}
@@ -0,0 +1,470 @@
+//! Previewable cache cleanup.
+//!
+//! Candidates are the top-level folders of `~/Library/Caches`, listed with
+//! their sizes before anything is touched. A run can be narrowed to specific
+//! apps, honours exclusion rules, and moves folders to the Trash instead of
+//! unlinking them. Every real run writes a manifest recording what moved where
+//! and how many bytes were reclaimed.
+
+use super::disk::path_size;
+use super::{home_dir, ToolResult};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Caches that hold live state for system services (iCloud sync, in-flight
+/// downloads, account auth) and must never be moved.
+const DEFAULT_EXCLUDES: &[&str] = &[
+    "com.apple.bird",
+    "CloudKit",
+    "com.apple.nsurlsessiond",
+    "com.apple.akd",
+    "com.apple.HomeKit",
+];
+
+#[derive(Debug, Clone)]
+pub(super) struct CleanupOptions {
+    pub cache_root: PathBuf,
+    pub trash_dir: PathBuf,
+    pub manifest_dir: PathBuf,
+    /// When non-empty, only cache folders matching one of these app names.
+    pub apps: Vec<String>,
+    /// Extra exclusion patterns (`*` wildcards), on top of `DEFAULT_EXCLUDES`.
+    pub exclude: Vec<String>,
+    pub dry_run: bool,
+}
+
+impl CleanupOptions {
+    /// Options for the current user, taken from tool arguments.
+    fn from_args(args: &Value) -> Self {
+        let strings = |key: &str| -> Vec<String> {
+            args.get(key)
+                .and_then(|v| v.as_array())
+                .map(|a| {
+                    a.iter()
+                        .filter_map(|s| s.as_str())
+                        .map(|s| s.to_string())
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let home = home_dir();
+        Self {
+            cache_root: home.join("Library/Caches"),
+            trash_dir: home.join(".Trash"),
+            manifest_dir: home.join("Library/Logs/Sentinel"),
+            apps: strings("apps"),
+            exclude: strings("exclude"),
+            dry_run: args
+                .get("dry_run")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum EntryStatus {
+    /// Would be moved (dry run).
+    Planned,
+    Moved,
+    Excluded,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(super) struct CleanupEntry {
+    pub name: String,
+    pub path: PathBuf,
+    pub bytes: u64,
+    pub status: EntryStatus,
+    pub trashed_to: Option<PathBuf>,
+    /// Exclusion rule or error message, when relevant.
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(super) struct CleanupManifest {
+    pub timestamp: u64,
+    pub dry_run: bool,
+    pub cache_root: PathBuf,
+    pub entries: Vec<CleanupEntry>,
+    /// Bytes moved to the Trash (or that would be, on a dry run).
+    pub bytes_reclaimed: u64,
+    pub moved: usize,
+    pub failed: usize,
+    pub excluded: usize,
+    pub manifest_path: Option<PathBuf>,
+}
+
+/// Case-insensitive match with `*` wildcards.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    let text = text.to_lowercase();
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == text;
+    }
+    let mut rest = text.as_str();
+    for (i, part) in parts.iter().enumerate() {
+        if i == 0 {
+            match rest.strip_prefix(part) {
+                Some(r) => rest = r,
+                None => return false,
+            }
+        } else if i == parts.len() - 1 {
+            return rest.ends_with(part);
+        } else {
+            match rest.find(part) {
+                Some(pos) => rest = &rest[pos + part.len()..],
+                None => return false,
+            }
+        }
+    }
+    true
+}
+
+/// Return the exclusion rule that matches `name`, if any.
+fn excluded_by<'a>(name: &str, extra: &'a [String]) -> Option<&'a str> {
+    DEFAULT_EXCLUDES
+        .iter()
+        .copied()
+        .chain(extra.iter().map(|s| s.as_str()))
+        .find(|rule| glob_match(rule, name))
+}
+
+/// List candidate cache folders, largest first, without touching anything.
+pub(super) fn plan(opts: &CleanupOptions) -> Vec<CleanupEntry> {
+    let entries = match fs::read_dir(&opts.cache_root) {
+        Ok(e) => e,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut out: Vec<CleanupEntry> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !opts.apps.is_empty() {
+                let lower = name.to_lowercase();
+                if !opts.apps.iter().any(|a| lower.contains(&a.to_lowercase())) {
+                    return None;
+                }
+            }
+            let path = entry.path();
+            let (status, reason) = match excluded_by(&name, &opts.exclude) {
+                Some(rule) => (
+                    EntryStatus::Excluded,
+                    Some(format!("matches exclusion rule '{}'", rule)),
+                ),
+                None => (EntryStatus::Planned, None),
+            };
+            Some(CleanupEntry {
+                bytes: path_size(&path),
+                name,
+                path,
+                status,
+                trashed_to: None,
+                reason,
+            })
+        })
+        .collect();
+    out.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.name.cmp(&b.name)));
+    out
+}
+
+/// Move `path` into `trash_dir`, picking a free name if one is already taken.
+fn move_to_trash(path: &Path, trash_dir: &Path) -> std::io::Result<PathBuf> {
+    fs::create_dir_all(trash_dir)?;
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "item".into());
+    let mut target = trash_dir.join(&name);
+    let mut n = 1;
+    while target.exists() {
+        n += 1;
+        target = trash_dir.join(format!("{} {}", name, n));
+    }
+    fs::rename(path, &target)?;
+    Ok(target)
+}
+
+/// Plan, then (unless dry-running) move each planned folder to the Trash and
+/// write the manifest.
+pub(super) fn run(opts: &CleanupOptions) -> CleanupManifest {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let mut entries = plan(opts);
+    if !opts.dry_run {
+        for entry in entries
+            .iter_mut()
+            .filter(|e| e.status == EntryStatus::Planned)
+        {
+            match move_to_trash(&entry.path, &opts.trash_dir) {
+                Ok(dest) => {
+                    entry.status = EntryStatus::Moved;
+                    entry.trashed_to = Some(dest);
+                }
+                Err(e) => {
+                    entry.status = EntryStatus::Failed;
+                    entry.reason = Some(e.to_string());
+                }
+            }
+        }
+    }
+
+    let count = |s: EntryStatus| entries.iter().filter(|e| e.status == s).count();
+    let reclaim_status = if opts.dry_run {
+        EntryStatus::Planned
+    } else {
+        EntryStatus::Moved
+    };
+    let mut manifest = CleanupManifest {
+        timestamp,
+        dry_run: opts.dry_run,
+        cache_root: opts.cache_root.clone(),
+        bytes_reclaimed: entries
+            .iter()
+            .filter(|e| e.status == reclaim_status)
+            .map(|e| e.bytes)
+            .sum(),
+        moved: count(EntryStatus::Moved),
+        failed: count(EntryStatus::Failed),
+        excluded: count(EntryStatus::Excluded),
+        entries,
+        manifest_path: None,
+    };
+
+    if !opts.dry_run {
+        let path = opts
+            .manifest_dir
+            .join(format!("cleanup-{}.json", timestamp));
+        let written = fs::create_dir_all(&opts.manifest_dir).is_ok()
+            && serde_json::to_vec_pretty(&manifest)
+                .map(|bytes| fs::write(&path, bytes).is_ok())
+                .unwrap_or(false);
+        if written {
+            manifest.manifest_path = Some(path);
+        }
+    }
+
+    manifest
+}
+
+// ---------------------------------------------------------------------------
+// Tool
+// ---------------------------------------------------------------------------
+
+pub(super) fn clear_caches(args: &Value) -> ToolResult {
+    let target = args
+        .get("target")
+        .and_then(|v| v.as_str())
+        .unwrap_or("both");
+    if !matches!(target, "disk" | "memory" | "both") {
+        return ToolResult {
+            success: false,
+            data: json!({"target": target}),
+            error: Some(format!(
+                "Unknown target {:?}; use disk, memory or both",
+                target
+            )),
+        };
+    }
+    let opts = CleanupOptions::from_args(args);
+
+    let mut results = serde_json::Map::new();
+    let mut errors = Vec::new();
+
+    if target == "disk" || target == "both" {
+        let manifest = run(&opts);
+        if manifest.failed > 0 {
+            errors.push(format!(
+                "{} of {} cache folders could not be moved to the Trash",
+                manifest.failed,
+                manifest.failed + manifest.moved
+            ));
+        }
+        results.insert("disk".to_string(), json!(manifest));
+    }
+
+    if target == "memory" || target == "both" {
+        if opts.dry_run {
+            results.insert(
+                "memory".to_string(),
+                json!({"purged": false, "dry_run": true}),
+            );
+        } else {
+            // `purge` requires root; -n makes sudo fail instead of prompting.
+            let output = Command::new("sudo").args(["-n", "purge"]).output();
+            let (purged, detail) = match output {
+                Ok(o) => (
+                    o.status.success(),
+                    String::from_utf8_lossy(&o.stderr).trim().to_string(),
+                ),
+                Err(e) => (false, e.to_string()),
+            };
+            if !purged {
+                errors.push("Memory purge requires administrator privileges".to_string());
+            }
+            results.insert(
+                "memory".to_string(),
+                json!({"purged": purged, "output": detail}),
+            );
+        }
+    }
+
+    results.insert("target".to_string(), json!(target));
+    results.insert("dry_run".to_string(), json!(opts.dry_run));
+
+    ToolResult {
+        success: errors.is_empty(),
+        data: Value::Object(results),
+        error: if errors.is_empty() {
+            None
+        } else {
+            Some(errors.join("; "))
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixture {
+        _tmp: tempfile::TempDir,
+        opts: CleanupOptions,
+    }
+
+    /// Caches for three apps plus one excluded-by-default system cache.
+    fn fixture() -> Fixture {
+        let tmp = tempfile::tempdir().unwrap();
+        let caches = tmp.path().join("Caches");
+        for (dir, bytes) in [
+            ("com.google.Chrome", 64 * 1024),
+            ("com.spotify.client", 32 * 1024),
+            ("com.tinyspeck.slackmacgap", 16 * 1024),
+            ("CloudKit", 8 * 1024),
+        ] {
+            fs::create_dir_all(caches.join(dir)).unwrap();
+            fs::write(caches.join(dir).join("data"), vec![1u8; bytes]).unwrap();
+        }
+        let opts = CleanupOptions {
+            cache_root: caches,
+            trash_dir: tmp.path().join("Trash"),
+            manifest_dir: tmp.path().join("Logs"),
+            apps: Vec::new(),
+            exclude: Vec::new(),
+            dry_run: true,
+        };
+        Fixture { _tmp: tmp, opts }
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("com.apple.*", "com.apple.Safari"));
+        assert!(glob_match("*slack*", "com.tinyspeck.slackmacgap"));
+        assert!(glob_match("CloudKit", "cloudkit"));
+        assert!(!glob_match("com.apple.*", "com.google.Chrome"));
+        assert!(!glob_match("*.log", "logs"));
+    }
+
+    #[test]
+    fn test_unknown_target_is_rejected() {
+        for target in ["disks", "cache", ""] {
+            let result = clear_caches(&json!({"target": target, "dry_run": true}));
+            assert!(!result.success);
+            assert!(result.error.unwrap().contains("Unknown target"));
+        }
+    }
+
+    #[test]
+    fn test_dry_run_lists_without_touching() {
+        let f = fixture();
+        let manifest = run(&f.opts);
+
+        assert!(manifest.dry_run);
+        assert_eq!(manifest.entries.len(), 4);
+        assert_eq!(manifest.entries[0].name, "com.google.Chrome");
+        assert_eq!(manifest.excluded, 1);
+        assert_eq!(manifest.moved, 0);
+        assert!(manifest.bytes_reclaimed >= (64 + 32 + 16) * 1024);
+        assert!(manifest.manifest_path.is_none());
+        assert!(f.opts.cache_root.join("com.google.Chrome").exists());
+        assert!(!f.opts.trash_dir.exists());
+    }
+
+    #[test]
+    fn test_moves_selected_apps_to_trash_with_manifest() {
+        let mut f = fixture();
+        f.opts.dry_run = false;
+        f.opts.apps = vec!["chrome".into(), "spotify".into()];
+        let manifest = run(&f.opts);
+
+        assert_eq!(manifest.entries.len(), 2);
+        assert_eq!(manifest.moved, 2);
+        assert_eq!(manifest.failed, 0);
+        assert!(!f.opts.cache_root.join("com.google.Chrome").exists());
+        assert!(f.opts.trash_dir.join("com.google.Chrome/data").exists());
+        // Unselected apps are left alone.
+        assert!(f.opts.cache_root.join("com.tinyspeck.slackmacgap").exists());
+
+        let path = manifest.manifest_path.clone().unwrap();
+        let saved: Value = serde_json::from_slice(&fs::read(path).unwrap()).unwrap();
+        assert_eq!(saved["moved"], 2);
+        assert_eq!(saved["bytes_reclaimed"], manifest.bytes_reclaimed);
+    }
+
+    #[test]
+    fn test_exclusions_are_never_moved() {
+        let mut f = fixture();
+        f.opts.dry_run = false;
+        f.opts.exclude = vec!["*slack*".into()];
+        let manifest = run(&f.opts);
+
+        assert_eq!(manifest.moved, 2);
+        assert_eq!(manifest.excluded, 2);
+        assert!(f.opts.cache_root.join("CloudKit").exists());
+        assert!(f.opts.cache_root.join("com.tinyspeck.slackmacgap").exists());
+        let slack = manifest
+            .entries
+            .iter()
+            .find(|e| e.name == "com.tinyspeck.slackmacgap")
+            .unwrap();
+        assert_eq!(slack.status, EntryStatus::Excluded);
+        assert!(slack.reason.as_deref().unwrap().contains("*slack*"));
+    }
+
+    #[test]
+    fn test_trash_name_collision_and_failure_reporting() {
+        let mut f = fixture();
+        f.opts.dry_run = false;
+        f.opts.apps = vec!["spotify".into()];
+        fs::create_dir_all(f.opts.trash_dir.join("com.spotify.client")).unwrap();
+        let manifest = run(&f.opts);
+        assert_eq!(
+            manifest.entries[0].trashed_to.as_deref(),
+            Some(f.opts.trash_dir.join("com.spotify.client 2").as_path())
+        );
+
+        // A trash location that cannot be created reports a per-path failure.
+        f.opts.apps = vec!["chrome".into()];
+        f.opts.trash_dir = f
+            .opts
+            .cache_root
+            .join("com.tinyspeck.slackmacgap/data/trash");
+        let manifest = run(&f.opts);
+        assert_eq!(manifest.failed, 1);
+        assert_eq!(manifest.bytes_reclaimed, 0);
+        assert_eq!(manifest.entries[0].status, EntryStatus::Failed);
+        assert!(manifest.entries[0].reason.is_some());
+        assert!(f.opts.cache_root.join("com.google.Chrome").exists());
+    }
+}
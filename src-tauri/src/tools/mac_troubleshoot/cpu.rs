@@ -0,0 +1,331 @@
+//! Time-sampled CPU profiling for `monitor_cpu`.
+//!
+//! A single `top -l 1` sample has no previous sample to diff against, so its
+//! %CPU column is meaningless. Each sample here is the second frame of a
+//! `top -l 2 -s <interval>` run, which covers exactly that interval. Samples
+//! are aggregated per process into average and peak CPU and energy impact,
+//! and processes above the threshold are split into sustained hogs and
+//! short spikes.
+
+use super::sampler::{keyed_series, Sampler, SeriesStats};
+use super::{run_cmd, ToolResult};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::Duration;
+
+const DEFAULT_SAMPLES: usize = 3;
+const DEFAULT_DURATION_SECS: f64 = 3.0;
+/// %CPU (of one core) above which a process counts as busy in a sample.
+const DEFAULT_THRESHOLD_PCT: f64 = 50.0;
+/// Busy in at least this share of samples makes a hog sustained.
+const SUSTAINED_SHARE: f64 = 0.75;
+/// Processes `top` is asked for per sample.
+const TOP_ROWS: &str = "40";
+/// Processes reported.
+const MAX_REPORTED: usize = 15;
+
+/// One row of `top -stats pid,command,cpu,power,th`.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct ProcessSample {
+    pub pid: u32,
+    pub command: String,
+    pub cpu: f64,
+    pub power: f64,
+    pub threads: u32,
+}
+
+/// The last frame of a `top` run: overall usage and its process rows.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(super) struct TopFrame {
+    /// (user, sys, idle) percentages from the "CPU usage" line.
+    pub usage: Option<(f64, f64, f64)>,
+    pub processes: Vec<ProcessSample>,
+}
+
+/// "CPU usage: 5.12% user, 3.40% sys, 91.47% idle"
+fn parse_usage_line(line: &str) -> Option<(f64, f64, f64)> {
+    let rest = line.strip_prefix("CPU usage:")?;
+    let mut values = HashMap::new();
+    for part in rest.split(',') {
+        let mut it = part.split_whitespace();
+        let pct = it.next()?.trim_end_matches('%').parse::<f64>().ok()?;
+        values.insert(it.next()?.to_string(), pct);
+    }
+    Some((
+        *values.get("user")?,
+        *values.get("sys")?,
+        *values.get("idle")?,
+    ))
+}
+
+/// "4312  Google Chrome He 45.2  30.1  24/2" -- the command may contain
+/// spaces, so the numeric columns are taken from the end.
+fn parse_process_row(line: &str) -> Option<ProcessSample> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    if parts.len() < 5 {
+        return None;
+    }
+    let n = parts.len();
+    Some(ProcessSample {
+        pid: parts[0].parse().ok()?,
+        command: parts[1..n - 3].join(" "),
+        cpu: parts[n - 3].parse().ok()?,
+        power: parts[n - 2].parse().ok()?,
+        // "24/2" is threads/running; a trailing "+" marks a changed count.
+        threads: parts[n - 1]
+            .split('/')
+            .next()?
+            .trim_end_matches(['+', '-'])
+            .parse()
+            .ok()?,
+    })
+}
+
+/// Parse `top -l N` output, keeping only the last frame.
+pub(super) fn parse_top_frame(output: &str) -> TopFrame {
+    let frame = output
+        .rfind("Processes:")
+        .map_or(output, |start| &output[start..]);
+    let mut parsed = TopFrame::default();
+    let mut in_table = false;
+    for line in frame.lines() {
+        let line = line.trim();
+        if let Some(usage) = parse_usage_line(line) {
+            parsed.usage = Some(usage);
+        } else if line.starts_with("PID") {
+            in_table = true;
+        } else if in_table {
+            if let Some(p) = parse_process_row(line) {
+                parsed.processes.push(p);
+            }
+        }
+    }
+    parsed
+}
+
+fn sample_top(interval: Duration) -> TopFrame {
+    // top's delay is whole seconds; the sampler is fitted to that.
+    let secs = interval.as_secs().max(1).to_string();
+    parse_top_frame(&run_cmd(
+        "top",
+        &[
+            "-l",
+            "2",
+            "-s",
+            &secs,
+            "-o",
+            "cpu",
+            "-n",
+            TOP_ROWS,
+            "-stats",
+            "pid,command,cpu,power,th",
+        ],
+    ))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum Load {
+    Sustained,
+    Spike,
+    Normal,
+}
+
+/// Busy in most samples is sustained; busy in only some is a spike.
+pub(super) fn classify(cpu: &[f64], threshold: f64) -> Load {
+    let busy = cpu.iter().filter(|c| **c >= threshold).count();
+    if busy == 0 {
+        Load::Normal
+    } else if busy as f64 >= (cpu.len() as f64 * SUSTAINED_SHARE).ceil() {
+        Load::Sustained
+    } else {
+        Load::Spike
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(super) struct ProcessProfile {
+    pub pid: u32,
+    pub command: String,
+    pub cpu: SeriesStats,
+    pub energy: SeriesStats,
+    pub threads: u32,
+    pub load: Load,
+}
+
+/// Aggregate per-sample rows into one profile per process, busiest first.
+pub(super) fn profile(frames: &[TopFrame], threshold: f64) -> Vec<ProcessProfile> {
+    let keyed = |value: fn(&ProcessSample) -> f64| -> Vec<Vec<((u32, String), f64)>> {
+        frames
+            .iter()
+            .map(|f| {
+                f.processes
+                    .iter()
+                    .map(|p| ((p.pid, p.command.clone()), value(p)))
+                    .collect()
+            })
+            .collect()
+    };
+    let energy: HashMap<(u32, String), Vec<f64>> =
+        keyed_series(&keyed(|p| p.power)).into_iter().collect();
+    let threads: HashMap<(u32, String), f64> = keyed_series(&keyed(|p| p.threads as f64))
+        .into_iter()
+        .map(|(k, v)| (k, v.into_iter().fold(0.0, f64::max)))
+        .collect();
+
+    let mut profiles: Vec<ProcessProfile> = keyed_series(&keyed(|p| p.cpu))
+        .into_iter()
+        .map(|(key, cpu)| ProcessProfile {
+            pid: key.0,
+            command: key.1.clone(),
+            cpu: SeriesStats::of(&cpu),
+            energy: SeriesStats::of(energy.get(&key).map_or(&[][..], |v| v)),
+            threads: threads.get(&key).copied().unwrap_or(0.0) as u32,
+            load: classify(&cpu, threshold),
+        })
+        .collect();
+    profiles.sort_by(|a, b| {
+        b.cpu
+            .avg
+            .total_cmp(&a.cpu.avg)
+            .then(b.cpu.peak.total_cmp(&a.cpu.peak))
+    });
+    profiles
+}
+
+pub(super) fn monitor_cpu(args: &Value) -> ToolResult {
+    let sampler = Sampler::from_args(args, DEFAULT_SAMPLES, DEFAULT_DURATION_SECS).whole_seconds();
+    let threshold = args
+        .get("threshold_pct")
+        .and_then(|v| v.as_f64())
+        .unwrap_or(DEFAULT_THRESHOLD_PCT);
+
+    let ncpu = run_cmd("sysctl", &["-n", "hw.ncpu"]);
+    let brand = run_cmd("sysctl", &["-n", "machdep.cpu.brand_string"]);
+
+    let frames = sampler.windows(sample_top);
+    let profiles = profile(&frames, threshold);
+
+    let usage: Vec<(f64, f64, f64)> = frames.iter().filter_map(|f| f.usage).collect();
+    let overall = (!usage.is_empty()).then(|| {
+        let column = |pick: fn(&(f64, f64, f64)) -> f64| {
+            SeriesStats::of(&usage.iter().map(pick).collect::<Vec<_>>())
+        };
+        json!({
+            "user": column(|u| u.0),
+            "sys": column(|u| u.1),
+            "idle": column(|u| u.2),
+        })
+    });
+
+    let names = |load: Load| -> Vec<&str> {
+        profiles
+            .iter()
+            .filter(|p| p.load == load)
+            .map(|p| p.command.as_str())
+            .collect()
+    };
+
+    ToolResult {
+        success: true,
+        data: json!({
+            "cpu_brand": brand,
+            "core_count": ncpu.parse::<u32>().unwrap_or(0),
+            "samples": frames.len(),
+            "duration_secs": sampler.duration.as_secs_f64(),
+            "interval_secs": sampler.interval().as_secs_f64(),
+            "threshold_pct": threshold,
+            "overall": overall,
+            "sustained_hogs": names(Load::Sustained),
+            "spikes": names(Load::Spike),
+            "processes": profiles.iter().take(MAX_REPORTED).collect::<Vec<_>>(),
+        }),
+        error: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOP_OUTPUT: &str = "\
+Processes: 512 total, 3 running, 509 sleeping, 2468 threads
+2024/05/01 10:00:00
+Load Avg: 2.15, 1.98, 1.75
+CPU usage: 0.00% user, 0.00% sys, 100.00% idle
+
+PID    COMMAND          %CPU POWER #TH
+4312   Safari           0.0  0.0   24/2
+Processes: 512 total, 3 running, 509 sleeping, 2470 threads
+2024/05/01 10:00:01
+Load Avg: 2.15, 1.98, 1.75
+CPU usage: 12.50% user, 6.25% sys, 81.25% idle
+SharedLibs: 500M resident, 90M data, 40M linkedit.
+
+PID    COMMAND          %CPU POWER #TH
+4312   Safari           45.2 30.1  24/2
+881    Google Chrome He 101.0 88.4 31+
+0      kernel_task      8.1  0.0   512/8
+";
+
+    fn frame(rows: &[(u32, &str, f64, f64, u32)]) -> TopFrame {
+        TopFrame {
+            usage: None,
+            processes: rows
+                .iter()
+                .map(|&(pid, command, cpu, power, threads)| ProcessSample {
+                    pid,
+                    command: command.into(),
+                    cpu,
+                    power,
+                    threads,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_parse_top_frame_uses_last_frame() {
+        let f = parse_top_frame(TOP_OUTPUT);
+        assert_eq!(f.usage, Some((12.5, 6.25, 81.25)));
+        assert_eq!(f.processes.len(), 3);
+        assert_eq!(f.processes[0].cpu, 45.2);
+        assert_eq!(f.processes[1].command, "Google Chrome He");
+        assert_eq!(f.processes[1].threads, 31);
+        assert_eq!(f.processes[2].threads, 512);
+        assert!(parse_top_frame("").processes.is_empty());
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify(&[80.0, 90.0, 70.0, 10.0], 50.0), Load::Sustained);
+        assert_eq!(classify(&[5.0, 95.0, 4.0, 3.0], 50.0), Load::Spike);
+        assert_eq!(classify(&[5.0, 5.0], 50.0), Load::Normal);
+    }
+
+    #[test]
+    fn test_profile_aggregates_samples() {
+        let frames = vec![
+            frame(&[(1, "hog", 90.0, 40.0, 10), (2, "blip", 2.0, 0.5, 3)]),
+            frame(&[(1, "hog", 110.0, 60.0, 12), (2, "blip", 99.0, 20.0, 4)]),
+            frame(&[(1, "hog", 100.0, 50.0, 11)]),
+        ];
+        let profiles = profile(&frames, 50.0);
+        assert_eq!(profiles.len(), 2);
+
+        let hog = &profiles[0];
+        assert_eq!(hog.command, "hog");
+        assert_eq!(hog.cpu.avg, 100.0);
+        assert_eq!(hog.cpu.peak, 110.0);
+        assert_eq!(hog.energy.avg, 50.0);
+        assert_eq!(hog.threads, 12);
+        assert_eq!(hog.load, Load::Sustained);
+
+        // Missing from the last sample: counted as idle there.
+        let blip = &profiles[1];
+        assert_eq!(blip.cpu.avg, 33.7);
+        assert_eq!(blip.cpu.peak, 99.0);
+        assert_eq!(blip.load, Load::Spike);
+    }
+}
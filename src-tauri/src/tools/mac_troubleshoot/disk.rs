@@ -0,0 +1,693 @@
+//! Disk usage monitoring and space analysis.
+//!
+//! `monitor_disk` is the quick overview (mounted volumes plus a few well-known
+//! folders). `analyze_disk` walks a directory tree in-process with depth and
+//! time budgets, ranks the largest directories and files, and attributes space
+//! to known categories (caches, DerivedData, node_modules, ...) so it can
+//! suggest what is safe to reclaim.
+
+use super::{home_dir, run_cmd, run_shell, ToolResult};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Default number of directory levels below the root to descend into.
+const DEFAULT_MAX_DEPTH: usize = 8;
+const MAX_MAX_DEPTH: u64 = 64;
+/// Default wall-clock budget for a walk.
+const DEFAULT_TIME_BUDGET_SECS: u64 = 10;
+const MAX_TIME_BUDGET_SECS: u64 = 120;
+/// Default number of largest directories / files to report.
+const DEFAULT_TOP_N: usize = 10;
+const MAX_TOP_N: u64 = 100;
+/// Categories smaller than this do not produce a suggestion.
+const SUGGESTION_MIN_BYTES: u64 = 100 * 1024 * 1024;
+
+// ---------------------------------------------------------------------------
+// Volumes
+// ---------------------------------------------------------------------------
+
+/// One mounted volume from `df -kP`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(super) struct Volume {
+    pub filesystem: String,
+    pub mount_point: String,
+    pub size_bytes: u64,
+    pub used_bytes: u64,
+    pub available_bytes: u64,
+    pub capacity_pct: u32,
+}
+
+/// Parse `df -kP` into volumes, skipping pseudo filesystems (devfs, autofs maps)
+/// and zero-sized mounts.
+pub(super) fn parse_df_volumes(raw: &str) -> Vec<Volume> {
+    raw.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            // Locate the capacity column; everything after it is the mount
+            // point (which may contain spaces), the three numbers before it
+            // are size / used / available.
+            let cap_idx = tokens
+                .iter()
+                .position(|t| t.ends_with('%') && t.trim_end_matches('%').parse::<u32>().is_ok())?;
+            if cap_idx < 4 || cap_idx + 1 >= tokens.len() {
+                return None;
+            }
+            let kb = |i: usize| tokens[i].parse::<u64>().ok();
+            let size = kb(cap_idx - 3)?;
+            let used = kb(cap_idx - 2)?;
+            let avail = kb(cap_idx - 1)?;
+            let filesystem = tokens[..cap_idx - 3].join(" ");
+            if size == 0 || filesystem == "devfs" || filesystem.starts_with("map ") {
+                return None;
+            }
+            Some(Volume {
+                filesystem,
+                mount_point: tokens[cap_idx + 1..].join(" "),
+                size_bytes: size * 1024,
+                used_bytes: used * 1024,
+                available_bytes: avail * 1024,
+                capacity_pct: tokens[cap_idx].trim_end_matches('%').parse().unwrap_or(0),
+            })
+        })
+        .collect()
+}
+
+/// Parse `du -sh` lines into a JSON object of path -> size.
+fn parse_du(raw: &str) -> Value {
+    let mut map = serde_json::Map::new();
+    for line in raw.lines() {
+        if let Some((size, path)) = line.split_once(char::is_whitespace) {
+            map.insert(path.trim().to_string(), json!(size));
+        }
+    }
+    Value::Object(map)
+}
+
+// ---------------------------------------------------------------------------
+// Categories
+// ---------------------------------------------------------------------------
+
+/// Well-known space consumers that the analyzer attributes bytes to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum Category {
+    Caches,
+    Downloads,
+    XcodeDerivedData,
+    NodeModules,
+    DockerImages,
+    Trash,
+}
+
+impl Category {
+    const ALL: [Category; 6] = [
+        Category::Caches,
+        Category::Downloads,
+        Category::XcodeDerivedData,
+        Category::NodeModules,
+        Category::DockerImages,
+        Category::Trash,
+    ];
+
+    /// Match a directory path against the category rules.
+    fn classify(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?;
+        let ends_with = |suffix: &[&str]| {
+            let comps: Vec<&str> = path
+                .components()
+                .rev()
+                .take(suffix.len())
+                .filter_map(|c| c.as_os_str().to_str())
+                .collect();
+            comps.iter().rev().eq(suffix.iter())
+        };
+
+        match name {
+            "node_modules" => Some(Self::NodeModules),
+            ".Trash" => Some(Self::Trash),
+            "Downloads" => Some(Self::Downloads),
+            ".cache" => Some(Self::Caches),
+            "Caches" if ends_with(&["Library", "Caches"]) => Some(Self::Caches),
+            "DerivedData" if ends_with(&["Developer", "Xcode", "DerivedData"]) => {
+                Some(Self::XcodeDerivedData)
+            }
+            "com.docker.docker" if ends_with(&["Containers", "com.docker.docker"]) => {
+                Some(Self::DockerImages)
+            }
+            _ => None,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Caches => "Application caches",
+            Self::Downloads => "Downloads",
+            Self::XcodeDerivedData => "Xcode DerivedData",
+            Self::NodeModules => "node_modules folders",
+            Self::DockerImages => "Docker images and volumes",
+            Self::Trash => "Trash",
+        }
+    }
+
+    /// Whether the contents can be deleted without losing user data.
+    fn reclaimable(self) -> bool {
+        matches!(
+            self,
+            Self::Caches | Self::XcodeDerivedData | Self::NodeModules | Self::Trash
+        )
+    }
+
+    fn suggestion(self) -> &'static str {
+        match self {
+            Self::Caches => "Caches are rebuilt on demand; clear them with clear_caches",
+            Self::Downloads => "Review Downloads for installers and archives you no longer need",
+            Self::XcodeDerivedData => "DerivedData is regenerated by Xcode on the next build",
+            Self::NodeModules => "node_modules can be reinstalled with npm install in each project",
+            Self::DockerImages => "Run `docker system prune` to remove unused images and volumes",
+            Self::Trash => "Empty the Trash",
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Walker
+// ---------------------------------------------------------------------------
+
+/// Budgets and limits for a single walk.
+#[derive(Debug, Clone)]
+pub(super) struct WalkOptions {
+    pub max_depth: usize,
+    pub time_budget: Duration,
+    pub top_n: usize,
+}
+
+impl Default for WalkOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_MAX_DEPTH,
+            time_budget: Duration::from_secs(DEFAULT_TIME_BUDGET_SECS),
+            top_n: DEFAULT_TOP_N,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub(super) struct SizedPath {
+    pub bytes: u64,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(super) struct CategoryUsage {
+    pub category: Category,
+    pub label: &'static str,
+    pub bytes: u64,
+    pub reclaimable: bool,
+    pub paths: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(super) struct DiskReport {
+    pub root: PathBuf,
+    pub total_bytes: u64,
+    pub files_scanned: u64,
+    pub dirs_scanned: u64,
+    pub unreadable_dirs: u64,
+    /// True if the time budget ran out before the walk finished.
+    pub timed_out: bool,
+    /// True if some directories were left unvisited because of `max_depth`.
+    pub depth_limited: bool,
+    pub largest_dirs: Vec<SizedPath>,
+    pub largest_files: Vec<SizedPath>,
+    pub categories: Vec<CategoryUsage>,
+    pub reclaimable_bytes: u64,
+    pub suggestions: Vec<String>,
+}
+
+/// Keeps the N largest entries seen so far.
+struct TopN {
+    n: usize,
+    heap: BinaryHeap<Reverse<SizedPath>>,
+}
+
+impl TopN {
+    fn new(n: usize) -> Self {
+        Self {
+            n,
+            heap: BinaryHeap::new(),
+        }
+    }
+
+    fn push(&mut self, bytes: u64, path: &Path) {
+        if self.n == 0 {
+            return;
+        }
+        if self.heap.len() < self.n {
+            self.heap.push(Reverse(SizedPath {
+                bytes,
+                path: path.to_path_buf(),
+            }));
+        } else if self.heap.peek().is_some_and(|min| bytes > min.0.bytes) {
+            self.heap.pop();
+            self.heap.push(Reverse(SizedPath {
+                bytes,
+                path: path.to_path_buf(),
+            }));
+        }
+    }
+
+    fn into_sorted(self) -> Vec<SizedPath> {
+        let mut v: Vec<SizedPath> = self.heap.into_iter().map(|r| r.0).collect();
+        v.sort_by(|a, b| b.cmp(a));
+        v
+    }
+}
+
+/// Bytes actually allocated on disk (what `du` reports), so sparse files such
+/// as Docker.raw are not overstated.
+fn disk_usage(meta: &fs::Metadata) -> u64 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        meta.blocks() * 512
+    }
+    #[cfg(not(unix))]
+    {
+        meta.len()
+    }
+}
+
+/// Total allocated size of a file or directory tree, without following symlinks.
+pub(super) fn path_size(path: &Path) -> u64 {
+    let meta = match fs::symlink_metadata(path) {
+        Ok(m) => m,
+        Err(_) => return 0,
+    };
+    if !meta.is_dir() {
+        return disk_usage(&meta);
+    }
+    fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|e| path_size(&e.path())).sum())
+        .unwrap_or(0)
+}
+
+#[cfg(unix)]
+fn device_id(meta: &fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(meta.dev())
+}
+
+#[cfg(not(unix))]
+fn device_id(_meta: &fs::Metadata) -> Option<u64> {
+    None
+}
+
+struct Walker {
+    deadline: Instant,
+    max_depth: usize,
+    root_dev: Option<u64>,
+    files_scanned: u64,
+    dirs_scanned: u64,
+    unreadable_dirs: u64,
+    timed_out: bool,
+    depth_limited: bool,
+    largest_dirs: TopN,
+    largest_files: TopN,
+    category_bytes: HashMap<Category, u64>,
+    category_paths: HashMap<Category, Vec<PathBuf>>,
+}
+
+impl Walker {
+    /// Sum the size of `dir`, recording files, directories and categories as
+    /// it goes. Stays on the root's filesystem and never follows symlinks.
+    fn walk(&mut self, dir: &Path, depth: usize, category: Option<Category>) -> u64 {
+        let entries = match fs::read_dir(dir) {
+            Ok(e) => e,
+            Err(_) => {
+                self.unreadable_dirs += 1;
+                return 0;
+            }
+        };
+        self.dirs_scanned += 1;
+
+        let mut total = 0;
+        for entry in entries.flatten() {
+            if Instant::now() >= self.deadline {
+                self.timed_out = true;
+                break;
+            }
+            let path = entry.path();
+            let meta = match fs::symlink_metadata(&path) {
+                Ok(m) => m,
+                Err(_) => continue,
+            };
+
+            if meta.is_dir() {
+                if self.root_dev.is_some() && device_id(&meta) != self.root_dev {
+                    continue;
+                }
+                if depth >= self.max_depth {
+                    self.depth_limited = true;
+                    continue;
+                }
+                let child_category = category.or_else(|| {
+                    let c = Category::classify(&path)?;
+                    self.category_paths.entry(c).or_default().push(path.clone());
+                    Some(c)
+                });
+                let size = self.walk(&path, depth + 1, child_category);
+                self.largest_dirs.push(size, &path);
+                total += size;
+            } else {
+                let size = disk_usage(&meta);
+                self.files_scanned += 1;
+                self.largest_files.push(size, &path);
+                if let Some(c) = category {
+                    *self.category_bytes.entry(c).or_default() += size;
+                }
+                total += size;
+            }
+        }
+        total
+    }
+}
+
+/// Walk `root` within the given budgets and build a report.
+pub(super) fn analyze(root: &Path, opts: &WalkOptions) -> DiskReport {
+    let root_meta = fs::symlink_metadata(root).ok();
+    let mut walker = Walker {
+        deadline: Instant::now() + opts.time_budget,
+        max_depth: opts.max_depth,
+        root_dev: root_meta.as_ref().and_then(device_id),
+        files_scanned: 0,
+        dirs_scanned: 0,
+        unreadable_dirs: 0,
+        timed_out: false,
+        depth_limited: false,
+        largest_dirs: TopN::new(opts.top_n),
+        largest_files: TopN::new(opts.top_n),
+        category_bytes: HashMap::new(),
+        category_paths: HashMap::new(),
+    };
+
+    let root_category = Category::classify(root);
+    if let Some(c) = root_category {
+        walker
+            .category_paths
+            .entry(c)
+            .or_default()
+            .push(root.to_path_buf());
+    }
+    let total_bytes = walker.walk(root, 0, root_category);
+
+    let mut categories: Vec<CategoryUsage> = Category::ALL
+        .iter()
+        .filter_map(|&c| {
+            let bytes = *walker.category_bytes.get(&c)?;
+            Some(CategoryUsage {
+                category: c,
+                label: c.label(),
+                bytes,
+                reclaimable: c.reclaimable(),
+                paths: walker.category_paths.remove(&c).unwrap_or_default(),
+            })
+        })
+        .collect();
+    categories.sort_by_key(|c| Reverse(c.bytes));
+
+    let reclaimable_bytes = categories
+        .iter()
+        .filter(|c| c.reclaimable)
+        .map(|c| c.bytes)
+        .sum();
+
+    let suggestions = categories
+        .iter()
+        .filter(|c| c.bytes >= SUGGESTION_MIN_BYTES)
+        .map(|c| {
+            format!(
+                "{} use {}. {}.",
+                c.label,
+                format_size(c.bytes),
+                c.category.suggestion()
+            )
+        })
+        .collect();
+
+    DiskReport {
+        root: root.to_path_buf(),
+        total_bytes,
+        files_scanned: walker.files_scanned,
+        dirs_scanned: walker.dirs_scanned,
+        unreadable_dirs: walker.unreadable_dirs,
+        timed_out: walker.timed_out,
+        depth_limited: walker.depth_limited,
+        largest_dirs: walker.largest_dirs.into_sorted(),
+        largest_files: walker.largest_files.into_sorted(),
+        categories,
+        reclaimable_bytes,
+        suggestions,
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    const GB: f64 = 1024.0 * 1024.0 * 1024.0;
+    const MB: f64 = 1024.0 * 1024.0;
+    let b = bytes as f64;
+    if b >= GB {
+        format!("{:.1} GB", b / GB)
+    } else {
+        format!("{:.0} MB", b / MB)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tools
+// ---------------------------------------------------------------------------
+
+pub(super) fn monitor_disk() -> ToolResult {
+    let df_raw = run_cmd("df", &["-kP"]);
+    let du_raw =
+        run_shell("du -sh ~/Desktop ~/Downloads ~/Documents ~/Library/Caches ~/.Trash 2>/dev/null");
+
+    let volumes = parse_df_volumes(&df_raw);
+    let root_volume = volumes.iter().find(|v| v.mount_point == "/").cloned();
+    let dir_sizes = parse_du(&du_raw);
+
+    ToolResult {
+        success: true,
+        data: json!({
+            "root_volume": root_volume,
+            "volumes": volumes,
+            "directory_sizes": dir_sizes,
+        }),
+        error: None,
+    }
+}
+
+/// Walk options from tool arguments, each clamped to its maximum.
+fn options_from_args(args: &Value) -> WalkOptions {
+    let defaults = WalkOptions::default();
+    let arg = |name: &str, max: u64| args.get(name).and_then(|v| v.as_u64()).map(|v| v.min(max));
+    WalkOptions {
+        max_depth: arg("max_depth", MAX_MAX_DEPTH).map_or(defaults.max_depth, |d| d as usize),
+        time_budget: arg("time_budget_secs", MAX_TIME_BUDGET_SECS)
+            .map_or(defaults.time_budget, Duration::from_secs),
+        top_n: arg("top_n", MAX_TOP_N).map_or(defaults.top_n, |n| n as usize),
+    }
+}
+
+pub(super) fn analyze_disk(args: &Value) -> ToolResult {
+    let root = args
+        .get("path")
+        .and_then(|v| v.as_str())
+        .map(|p| match p.strip_prefix("~") {
+            Some(rest) => home_dir().join(rest.trim_start_matches('/')),
+            None => PathBuf::from(p),
+        })
+        .unwrap_or_else(home_dir);
+
+    if !root.is_dir() {
+        return ToolResult {
+            success: false,
+            data: json!({"path": root}),
+            error: Some(format!("Not a directory: {}", root.display())),
+        };
+    }
+
+    let report = analyze(&root, &options_from_args(args));
+    let volumes = parse_df_volumes(&run_cmd("df", &["-kP"]));
+
+    let mut data = serde_json::to_value(&report).unwrap_or(Value::Null);
+    if let Some(obj) = data.as_object_mut() {
+        obj.insert("volumes".into(), json!(volumes));
+    }
+
+    ToolResult {
+        success: true,
+        data,
+        error: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_file(path: &Path, bytes: usize) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, vec![0xA5u8; bytes]).unwrap();
+    }
+
+    /// Build a small home-like tree:
+    ///
+    /// ```text
+    /// Downloads/installer.dmg          300 KB
+    /// Library/Caches/com.app/blob      200 KB
+    /// Library/Developer/Xcode/DerivedData/Proj/Build/obj   150 KB
+    /// code/web/node_modules/dep/index.js                    120 KB
+    /// code/web/src/main.ts                                    4 KB
+    /// .Trash/old.zip                                         80 KB
+    /// ```
+    fn sample_tree() -> tempfile::TempDir {
+        let tmp = tempfile::tempdir().unwrap();
+        let r = tmp.path();
+        write_file(&r.join("Downloads/installer.dmg"), 300 * 1024);
+        write_file(&r.join("Library/Caches/com.app/blob"), 200 * 1024);
+        write_file(
+            &r.join("Library/Developer/Xcode/DerivedData/Proj/Build/obj"),
+            150 * 1024,
+        );
+        write_file(&r.join("code/web/node_modules/dep/index.js"), 120 * 1024);
+        write_file(&r.join("code/web/src/main.ts"), 4 * 1024);
+        write_file(&r.join(".Trash/old.zip"), 80 * 1024);
+        tmp
+    }
+
+    fn category(report: &DiskReport, c: Category) -> Option<&CategoryUsage> {
+        report.categories.iter().find(|u| u.category == c)
+    }
+
+    #[test]
+    fn test_largest_files_and_dirs() {
+        let tmp = sample_tree();
+        let report = analyze(tmp.path(), &WalkOptions::default());
+
+        assert_eq!(report.files_scanned, 6);
+        assert!(!report.timed_out);
+        assert!(!report.depth_limited);
+        assert_eq!(report.largest_files.len(), 6);
+        assert!(report.largest_files[0].path.ends_with("installer.dmg"));
+        assert!(report.largest_files[0].bytes >= 300 * 1024);
+        // Library holds caches + DerivedData, so it outranks Downloads.
+        assert!(report.largest_dirs[0].path.ends_with("Library"));
+    }
+
+    #[test]
+    fn test_categories_and_reclaimable() {
+        let tmp = sample_tree();
+        let report = analyze(tmp.path(), &WalkOptions::default());
+
+        for c in Category::ALL {
+            if c == Category::DockerImages {
+                assert!(category(&report, c).is_none());
+            } else {
+                assert!(category(&report, c).is_some(), "missing {:?}", c);
+            }
+        }
+        let nm = category(&report, Category::NodeModules).unwrap();
+        assert!(nm.paths[0].ends_with("code/web/node_modules"));
+
+        let downloads = category(&report, Category::Downloads).unwrap().bytes;
+        assert!(!category(&report, Category::Downloads).unwrap().reclaimable);
+        // Everything except Downloads and the project source is reclaimable.
+        let src = report
+            .largest_files
+            .iter()
+            .find(|f| f.path.ends_with("main.ts"))
+            .unwrap()
+            .bytes;
+        assert_eq!(
+            report.reclaimable_bytes,
+            report.total_bytes - downloads - src
+        );
+    }
+
+    #[test]
+    fn test_top_n_and_depth_limit() {
+        let tmp = sample_tree();
+        let opts = WalkOptions {
+            max_depth: 1,
+            top_n: 2,
+            ..WalkOptions::default()
+        };
+        let report = analyze(tmp.path(), &opts);
+        assert!(report.depth_limited);
+        assert_eq!(report.largest_files.len(), 2);
+        assert!(report.largest_dirs.len() <= 2);
+        // Only the top two levels are visited, so deep files are not counted.
+        assert!(report
+            .largest_files
+            .iter()
+            .all(|f| !f.path.ends_with("obj")));
+    }
+
+    #[test]
+    fn test_time_budget_exhausted() {
+        let tmp = sample_tree();
+        let opts = WalkOptions {
+            time_budget: Duration::ZERO,
+            ..WalkOptions::default()
+        };
+        let report = analyze(tmp.path(), &opts);
+        assert!(report.timed_out);
+        assert_eq!(report.files_scanned, 0);
+    }
+
+    #[test]
+    fn test_options_are_clamped() {
+        let opts = options_from_args(&json!({
+            "max_depth": u64::MAX,
+            "time_budget_secs": u64::MAX,
+            "top_n": u64::MAX,
+        }));
+        assert_eq!(opts.max_depth, MAX_MAX_DEPTH as usize);
+        assert_eq!(opts.time_budget, Duration::from_secs(MAX_TIME_BUDGET_SECS));
+        assert_eq!(opts.top_n, MAX_TOP_N as usize);
+
+        let tmp = sample_tree();
+        let root = tmp.path().to_str().unwrap();
+        let result = analyze_disk(&json!({"path": root, "time_budget_secs": u64::MAX}));
+        assert!(result.success);
+    }
+
+    #[test]
+    fn test_parse_df_volumes() {
+        let raw = "\
+Filesystem     1024-blocks      Used Available Capacity  Mounted on
+/dev/disk3s1s1   482797652  10503424 219367808     5%    /
+devfs                  205       205         0   100%    /dev
+/dev/disk3s5     482797652 248812924 219367808    54%    /System/Volumes/Data
+map auto_home            0         0         0   100%    /System/Volumes/Data/home
+/dev/disk5s1     976730112 512000000 464730112    53%    /Volumes/Backup Drive";
+        let vols = parse_df_volumes(raw);
+        assert_eq!(vols.len(), 3);
+        assert_eq!(vols[0].mount_point, "/");
+        assert_eq!(vols[0].size_bytes, 482797652 * 1024);
+        assert_eq!(vols[1].capacity_pct, 54);
+        assert_eq!(vols[2].mount_point, "/Volumes/Backup Drive");
+    }
+
+    #[test]
+    fn test_analyze_disk_rejects_missing_root() {
+        let result = analyze_disk(&json!({"path": "/definitely/not/here"}));
+        assert!(!result.success);
+        assert!(result.error.unwrap().contains("Not a directory"));
+    }
+}
@@ -0,0 +1,485 @@
+//! Peripheral and hardware inventory for `check_hardware`.
+//!
+//! One `system_profiler -json` call covers the requested data types; each
+//! section is parsed on its own so a missing or reshaped section (they vary
+//! between macOS releases) only empties that part of the report. Problems a
+//! user would ask about -- failing SMART status, low peripheral batteries,
+//! USB devices drawing more power than the port offers -- are collected into
+//! `issues`.
+
+use super::battery::sp_number;
+use super::{run_cmd, ToolResult};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+/// Bluetooth batteries at or below this are reported.
+const LOW_BATTERY_PCT: f64 = 20.0;
+
+/// Tool categories and the system_profiler data types behind them.
+const CATEGORIES: &[(&str, &str)] = &[
+    ("usb", "SPUSBDataType"),
+    ("thunderbolt", "SPThunderboltDataType"),
+    ("displays", "SPDisplaysDataType"),
+    ("bluetooth", "SPBluetoothDataType"),
+    ("storage", "SPStorageDataType"),
+];
+
+fn text(v: &Value, key: &str) -> Option<String> {
+    v.get(key)
+        .and_then(|s| s.as_str())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+}
+
+/// system_profiler's "spdisplays_yes" / "attrib_on" / "yes" style flags.
+fn flag(v: &Value, key: &str) -> bool {
+    text(v, key).is_some_and(|s| s.ends_with("_yes") || s.ends_with("_on") || s == "yes")
+}
+
+/// "spdisplays_displayport" -> "displayport", "up_to_480_Mb_sec" -> "up to 480 Mb/sec"
+fn humanize(raw: &str) -> String {
+    raw.trim_start_matches("spdisplays_")
+        .trim_start_matches("receptacle_")
+        .replace("_sec", "/sec")
+        .replace('_', " ")
+}
+
+fn items(v: &Value) -> impl Iterator<Item = &Value> {
+    v.get("_items")
+        .and_then(|i| i.as_array())
+        .into_iter()
+        .flatten()
+}
+
+// ----- USB -----
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(super) struct UsbDevice {
+    pub name: String,
+    pub manufacturer: Option<String>,
+    pub vendor_id: Option<String>,
+    pub product_id: Option<String>,
+    pub speed: Option<String>,
+    /// The hub this device hangs off, if any.
+    pub hub: Option<String>,
+    pub power_available_ma: Option<f64>,
+    pub power_used_ma: Option<f64>,
+    /// BSD names of storage media on the device.
+    pub media: Vec<String>,
+}
+
+/// Flatten the bus → hub → device tree. Buses themselves are skipped.
+pub(super) fn parse_usb(section: &Value) -> Vec<UsbDevice> {
+    fn walk(node: &Value, hub: Option<&str>, out: &mut Vec<UsbDevice>) {
+        let name = text(node, "_name").unwrap_or_else(|| "Unknown device".into());
+        out.push(UsbDevice {
+            name: name.clone(),
+            manufacturer: text(node, "manufacturer"),
+            // "0x0781  (SanDisk Corporation)" -> "0x0781"
+            vendor_id: text(node, "vendor_id")
+                .map(|v| v.split_whitespace().next().unwrap_or_default().to_string()),
+            product_id: text(node, "product_id"),
+            speed: text(node, "speed").map(|s| humanize(&s)),
+            hub: hub.map(String::from),
+            power_available_ma: node.get("bus_power").and_then(sp_number),
+            power_used_ma: node.get("bus_power_used").and_then(sp_number),
+            media: node
+                .get("Media")
+                .and_then(|m| m.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|m| text(m, "bsd_name"))
+                .collect(),
+        });
+        for child in items(node) {
+            walk(child, Some(&name), out);
+        }
+    }
+
+    let mut devices = Vec::new();
+    for bus in section.as_array().into_iter().flatten() {
+        for device in items(bus) {
+            walk(device, None, &mut devices);
+        }
+    }
+    devices
+}
+
+// ----- Thunderbolt -----
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(super) struct ThunderboltPort {
+    pub port: Option<String>,
+    pub status: String,
+    pub speed: Option<String>,
+    pub devices: Vec<ThunderboltDevice>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(super) struct ThunderboltDevice {
+    pub name: String,
+    pub vendor: Option<String>,
+    pub mode: Option<String>,
+}
+
+pub(super) fn parse_thunderbolt(section: &Value) -> Vec<ThunderboltPort> {
+    section
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|bus| {
+            let receptacle = bus.get("receptacle_1_tag").unwrap_or(&Value::Null);
+            ThunderboltPort {
+                port: text(receptacle, "receptacle_id_key"),
+                status: text(receptacle, "receptacle_status_key")
+                    .map(|s| humanize(&s))
+                    .unwrap_or_else(|| "unknown".into()),
+                speed: text(receptacle, "current_speed_key"),
+                devices: items(bus)
+                    .map(|d| ThunderboltDevice {
+                        name: text(d, "device_name_key")
+                            .or_else(|| text(d, "_name"))
+                            .unwrap_or_default(),
+                        vendor: text(d, "vendor_name_key"),
+                        mode: text(d, "mode_key").map(|m| humanize(&m)),
+                    })
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+// ----- Displays -----
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(super) struct Display {
+    pub name: String,
+    pub gpu: Option<String>,
+    pub resolution: Option<String>,
+    pub native_pixels: Option<String>,
+    pub connection: Option<String>,
+    pub internal: bool,
+    pub main: bool,
+    pub online: bool,
+    pub mirrored: bool,
+}
+
+pub(super) fn parse_displays(section: &Value) -> Vec<Display> {
+    let mut displays = Vec::new();
+    for gpu in section.as_array().into_iter().flatten() {
+        let gpu_name = text(gpu, "sppci_model").or_else(|| text(gpu, "_name"));
+        let screens = gpu.get("spdisplays_ndrvs").and_then(|d| d.as_array());
+        for screen in screens.into_iter().flatten() {
+            let connection = text(screen, "spdisplays_connection_type");
+            displays.push(Display {
+                name: text(screen, "_name").unwrap_or_else(|| "Unknown display".into()),
+                gpu: gpu_name.clone(),
+                resolution: text(screen, "_spdisplays_resolution"),
+                native_pixels: text(screen, "_spdisplays_pixels"),
+                internal: connection.as_deref() == Some("spdisplays_internal"),
+                connection: connection.map(|c| humanize(&c)),
+                main: flag(screen, "spdisplays_main"),
+                // Absent on some releases; a listed display is connected.
+                online: text(screen, "spdisplays_online")
+                    .is_none_or(|_| flag(screen, "spdisplays_online")),
+                mirrored: flag(screen, "spdisplays_mirror"),
+            });
+        }
+    }
+    displays
+}
+
+// ----- Bluetooth -----
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(super) struct BluetoothDevice {
+    pub name: String,
+    pub kind: Option<String>,
+    pub address: Option<String>,
+    pub connected: bool,
+    /// Battery levels by part: main, left, right, case.
+    pub battery_pct: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub(super) struct Bluetooth {
+    pub powered: Option<bool>,
+    pub devices: Vec<BluetoothDevice>,
+}
+
+pub(super) fn parse_bluetooth(section: &Value) -> Bluetooth {
+    let mut bt = Bluetooth::default();
+    for controller in section.as_array().into_iter().flatten() {
+        if let Some(props) = controller.get("controller_properties") {
+            bt.powered = text(props, "controller_state").map(|s| s == "attrib_on");
+        }
+        for (key, connected) in [("device_connected", true), ("device_not_connected", false)] {
+            // Each entry is a one-key object: {"AirPods Pro": {...}}
+            let entries = controller.get(key).and_then(|d| d.as_array());
+            for (name, info) in entries
+                .into_iter()
+                .flatten()
+                .filter_map(|e| e.as_object())
+                .flatten()
+            {
+                let battery_pct = info
+                    .as_object()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|(k, v)| {
+                        let part = k.strip_prefix("device_batteryLevel")?.to_lowercase();
+                        Some((part, json!(sp_number(v)?)))
+                    })
+                    .collect();
+                bt.devices.push(BluetoothDevice {
+                    name: name.clone(),
+                    kind: text(info, "device_minorType"),
+                    address: text(info, "device_address"),
+                    connected,
+                    battery_pct,
+                });
+            }
+        }
+    }
+    bt
+}
+
+// ----- Storage -----
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(super) struct StorageVolume {
+    pub name: String,
+    pub mount_point: Option<String>,
+    pub file_system: Option<String>,
+    pub size_bytes: Option<u64>,
+    pub free_bytes: Option<u64>,
+    pub device: Option<String>,
+    pub internal: bool,
+    pub medium: Option<String>,
+    pub protocol: Option<String>,
+    /// "Verified", "Failing", or absent when the drive doesn't report SMART
+    /// (most USB enclosures).
+    pub smart_status: Option<String>,
+}
+
+pub(super) fn parse_storage(section: &Value) -> Vec<StorageVolume> {
+    section
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|v| {
+            let drive = v.get("physical_drive").unwrap_or(&Value::Null);
+            StorageVolume {
+                name: text(v, "_name").unwrap_or_default(),
+                mount_point: text(v, "mount_point"),
+                file_system: text(v, "file_system"),
+                size_bytes: v.get("size_in_bytes").and_then(|n| n.as_u64()),
+                free_bytes: v.get("free_space_in_bytes").and_then(|n| n.as_u64()),
+                device: text(drive, "device_name"),
+                internal: flag(drive, "is_internal_disk"),
+                medium: text(drive, "medium_type"),
+                protocol: text(drive, "protocol"),
+                smart_status: text(drive, "smart_status"),
+            }
+        })
+        .collect()
+}
+
+// ----- Report -----
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub(super) struct HardwareReport {
+    pub usb: Option<Vec<UsbDevice>>,
+    pub thunderbolt: Option<Vec<ThunderboltPort>>,
+    pub displays: Option<Vec<Display>>,
+    pub bluetooth: Option<Bluetooth>,
+    pub storage: Option<Vec<StorageVolume>>,
+}
+
+/// Parse a combined `system_profiler -json` document. Sections that are not
+/// present stay `None`.
+pub(super) fn parse_system_profiler(raw: &str) -> HardwareReport {
+    let Ok(root) = serde_json::from_str::<Value>(raw) else {
+        return HardwareReport::default();
+    };
+    let section = |key: &str| root.get(key);
+    HardwareReport {
+        usb: section("SPUSBDataType").map(parse_usb),
+        thunderbolt: section("SPThunderboltDataType").map(parse_thunderbolt),
+        displays: section("SPDisplaysDataType").map(parse_displays),
+        bluetooth: section("SPBluetoothDataType").map(parse_bluetooth),
+        storage: section("SPStorageDataType").map(parse_storage),
+    }
+}
+
+pub(super) fn issues(report: &HardwareReport) -> Vec<String> {
+    let mut issues = Vec::new();
+    for v in report.storage.iter().flatten() {
+        if let Some(status) = v.smart_status.as_deref().filter(|s| *s != "Verified") {
+            issues.push(format!(
+                "{} ({}) SMART status is {}: back it up and replace the drive",
+                v.device.as_deref().unwrap_or(&v.name),
+                v.name,
+                status
+            ));
+        }
+    }
+    for d in report.usb.iter().flatten() {
+        if let (Some(available), Some(used)) = (d.power_available_ma, d.power_used_ma) {
+            if used > available {
+                issues.push(format!(
+                    "{} draws {} mA but its port supplies {} mA; use a powered hub",
+                    d.name, used, available
+                ));
+            }
+        }
+    }
+    for d in report.bluetooth.iter().flat_map(|b| &b.devices) {
+        for (part, pct) in &d.battery_pct {
+            if let Some(pct) = pct.as_f64().filter(|p| *p <= LOW_BATTERY_PCT) {
+                issues.push(format!("{} {} battery is at {:.0}%", d.name, part, pct));
+            }
+        }
+    }
+    if report.bluetooth.as_ref().and_then(|b| b.powered) == Some(false) {
+        issues.push("Bluetooth is turned off".into());
+    }
+    if report
+        .displays
+        .as_ref()
+        .is_some_and(|d| d.iter().any(|d| !d.online))
+    {
+        issues.push("A display is connected but offline".into());
+    }
+    issues
+}
+
+pub(super) fn check_hardware(args: &Value) -> ToolResult {
+    let requested: Vec<String> = args
+        .get("categories")
+        .and_then(|v| v.as_array())
+        .map(|c| {
+            c.iter()
+                .filter_map(|s| s.as_str())
+                .map(str::to_lowercase)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut cmd_args = vec!["-json"];
+    for (category, data_type) in CATEGORIES {
+        if requested.is_empty() || requested.iter().any(|r| r == category) {
+            cmd_args.push(*data_type);
+        }
+    }
+    if cmd_args.len() == 1 {
+        return ToolResult {
+            success: false,
+            data: json!({"categories": CATEGORIES.iter().map(|(c, _)| *c).collect::<Vec<_>>()}),
+            error: Some(format!("Unknown categories: {}", requested.join(", "))),
+        };
+    }
+
+    let report = parse_system_profiler(&run_cmd("system_profiler", &cmd_args));
+    let issues = issues(&report);
+
+    ToolResult {
+        success: true,
+        data: json!({
+            "usb": report.usb,
+            "thunderbolt": report.thunderbolt,
+            "displays": report.displays,
+            "bluetooth": report.bluetooth,
+            "storage": report.storage,
+            "issues": issues,
+        }),
+        error: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn fixture() -> HardwareReport {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/hardware/system_profiler.json");
+        parse_system_profiler(&std::fs::read_to_string(path).unwrap())
+    }
+
+    #[test]
+    fn test_parse_usb_tree() {
+        let usb = fixture().usb.unwrap();
+        let names: Vec<&str> = usb.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, vec!["USB2.0 Hub", "Ultra Fit", "USB Receiver"]);
+
+        let drive = &usb[1];
+        assert_eq!(drive.hub.as_deref(), Some("USB2.0 Hub"));
+        assert_eq!(drive.vendor_id.as_deref(), Some("0x0781"));
+        assert_eq!(drive.speed.as_deref(), Some("up to 480 Mb/sec"));
+        assert_eq!(drive.media, vec!["disk4"]);
+        assert_eq!(drive.power_used_ma, Some(224.0));
+    }
+
+    #[test]
+    fn test_parse_thunderbolt_and_displays() {
+        let report = fixture();
+        let tb = report.thunderbolt.unwrap();
+        assert_eq!(tb.len(), 2);
+        assert_eq!(tb[0].status, "connected");
+        assert_eq!(tb[0].devices[0].name, "TS3 Plus");
+        assert_eq!(tb[0].devices[0].mode.as_deref(), Some("thunderbolt three"));
+        assert_eq!(tb[1].status, "no devices connected");
+
+        let displays = report.displays.unwrap();
+        assert_eq!(displays.len(), 2);
+        assert!(displays[0].internal && displays[0].main);
+        assert_eq!(displays[1].name, "DELL U2720Q");
+        assert_eq!(displays[1].connection.as_deref(), Some("displayport"));
+        assert_eq!(displays[1].gpu.as_deref(), Some("Apple M1"));
+        assert!(!displays[1].internal && !displays[1].main && displays[1].online);
+    }
+
+    #[test]
+    fn test_parse_bluetooth_batteries() {
+        let bt = fixture().bluetooth.unwrap();
+        assert_eq!(bt.powered, Some(true));
+        assert_eq!(bt.devices.len(), 3);
+
+        let airpods = &bt.devices[0];
+        assert_eq!(airpods.name, "AirPods Pro");
+        assert_eq!(airpods.kind.as_deref(), Some("Headphones"));
+        assert_eq!(airpods.battery_pct["left"], 85.0);
+        assert_eq!(airpods.battery_pct["case"], 40.0);
+        assert_eq!(bt.devices[1].battery_pct["main"], 60.0);
+        assert!(!bt.devices[2].connected);
+        assert!(bt.devices[2].battery_pct.is_empty());
+    }
+
+    #[test]
+    fn test_parse_storage_smart() {
+        let storage = fixture().storage.unwrap();
+        assert_eq!(storage[0].smart_status.as_deref(), Some("Verified"));
+        assert!(storage[0].internal);
+        assert_eq!(storage[1].smart_status.as_deref(), Some("Failing"));
+        assert_eq!(storage[2].smart_status, None);
+    }
+
+    #[test]
+    fn test_issues() {
+        let issues = issues(&fixture());
+        assert_eq!(issues.len(), 2, "{:?}", issues);
+        assert!(issues[0].starts_with("WD Elements 25A2 (Backup) SMART status is Failing"));
+        assert_eq!(issues[1], "AirPods Pro right battery is at 12%");
+    }
+
+    #[test]
+    fn test_missing_sections() {
+        let report = parse_system_profiler(r#"{"SPUSBDataType": []}"#);
+        assert_eq!(report.usb, Some(vec![]));
+        assert!(report.displays.is_none());
+        assert_eq!(parse_system_profiler("not json"), HardwareReport::default());
+    }
+}
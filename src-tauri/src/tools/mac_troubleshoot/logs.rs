@@ -0,0 +1,904 @@
+//! Unified log search and crash/hang report analysis.
+//!
+//! `search_logs` wraps `log show --style ndjson` with a predicate assembled
+//! from typed filters. `analyze_crashes` reads the `.ips` (JSON) and legacy
+//! `.crash` (text) reports in `DiagnosticReports` and groups them per app.
+
+use super::{home_dir, ToolResult};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant, SystemTime};
+
+const DEFAULT_WINDOW: &str = "1h";
+/// Longest window `log show` is asked for; a day of logs is already
+/// hundreds of megabytes on a busy Mac.
+const MAX_WINDOW_SECS: u64 = 24 * 3600;
+/// Longest window for info/debug messages without a narrowing filter.
+const MAX_VERBOSE_WINDOW_SECS: u64 = 3600;
+/// `log show` is killed after this long; what was read so far is returned.
+const SEARCH_TIMEOUT: Duration = Duration::from_secs(20);
+const DEFAULT_LOG_LIMIT: usize = 100;
+const MAX_LOG_LIMIT: usize = 1000;
+const DEFAULT_DAYS: u64 = 7;
+const MAX_DAYS: u64 = 365;
+const DEFAULT_REPORT_LIMIT: usize = 20;
+/// Frames kept from the crashing thread's backtrace.
+const MAX_FRAMES: usize = 8;
+
+/// Unified log levels accepted by the `level` filter, mapped to `messageType`.
+const LEVELS: &[(&str, &str)] = &[
+    ("fault", "fault"),
+    ("error", "error"),
+    ("default", "default"),
+    ("info", "info"),
+    ("debug", "debug"),
+];
+
+// ----- Unified log -----
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(super) struct LogEntry {
+    pub timestamp: String,
+    pub level: String,
+    pub process: String,
+    pub pid: Option<u64>,
+    pub subsystem: Option<String>,
+    pub category: Option<String>,
+    pub message: String,
+}
+
+/// Parse one line of `log show --style ndjson` output. Non-JSON lines (the
+/// trailing summary, warnings) are skipped.
+pub(super) fn parse_log_line(line: &str) -> Option<LogEntry> {
+    let v = serde_json::from_str::<Value>(line.trim()).ok()?;
+    v.get("eventMessage")?;
+    let text = |key: &str| {
+        v.get(key)
+            .and_then(|s| s.as_str())
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+    let process = text("processImagePath")
+        .map(|p| p.rsplit('/').next().unwrap_or(&p).to_string())
+        .unwrap_or_default();
+    Some(LogEntry {
+        timestamp: text("timestamp").unwrap_or_default(),
+        level: text("messageType")
+            .unwrap_or_else(|| "Default".into())
+            .to_lowercase(),
+        process,
+        pid: v.get("processID").and_then(|p| p.as_u64()),
+        subsystem: text("subsystem"),
+        category: text("category"),
+        message: text("eventMessage").unwrap_or_default(),
+    })
+}
+
+#[cfg(test)]
+fn parse_log_ndjson(output: &str) -> Vec<LogEntry> {
+    output.lines().filter_map(parse_log_line).collect()
+}
+
+/// The most recent `limit` entries of a stream, plus counts over all of it.
+#[derive(Debug, Default)]
+pub(super) struct LogTail {
+    limit: usize,
+    recent: VecDeque<LogEntry>,
+    total: usize,
+    by_process: HashMap<String, usize>,
+    timed_out: bool,
+}
+
+impl LogTail {
+    fn new(limit: usize) -> Self {
+        LogTail {
+            limit,
+            ..Default::default()
+        }
+    }
+
+    fn push(&mut self, entry: LogEntry) {
+        self.total += 1;
+        *self.by_process.entry(entry.process.clone()).or_default() += 1;
+        if self.recent.len() == self.limit {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(entry);
+    }
+}
+
+/// Run a command and keep the tail of its ndjson log output, one line at a
+/// time, killing it at `timeout`.
+pub(super) fn stream_log(
+    program: &str,
+    args: &[String],
+    limit: usize,
+    timeout: Duration,
+) -> Result<LogTail, String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    let stdout = child.stdout.take().ok_or("log output unavailable")?;
+    let (tx, entries) = mpsc::sync_channel(256);
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines() {
+            let Ok(line) = line else { break };
+            if let Some(entry) = parse_log_line(&line) {
+                if tx.send(entry).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+
+    let mut tail = LogTail::new(limit);
+    let deadline = Instant::now() + timeout;
+    loop {
+        match entries.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(entry) => tail.push(entry),
+            Err(RecvTimeoutError::Timeout) => {
+                tail.timed_out = true;
+                let _ = child.kill();
+                break;
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    let _ = child.wait();
+    Ok(tail)
+}
+
+/// Quote a value as an NSPredicate string literal.
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Seconds in a `log show --last` window: a number followed by s, m, h or d.
+fn window_secs(window: &str) -> Option<u64> {
+    let unit = window.chars().last()?;
+    let digits = &window[..window.len() - unit.len_utf8()];
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let scale = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 3600,
+        'd' => 86_400,
+        _ => return None,
+    };
+    digits.parse::<u64>().ok()?.checked_mul(scale)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct LogQuery {
+    pub window: String,
+    pub process: Option<String>,
+    pub subsystem: Option<String>,
+    pub contains: Option<String>,
+    pub level: Option<String>,
+    pub predicate: Option<String>,
+    pub limit: usize,
+}
+
+impl LogQuery {
+    pub(super) fn from_args(args: &Value) -> Result<Self, String> {
+        let text = |key: &str| {
+            args.get(key)
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+        };
+
+        let window = text("last").unwrap_or_else(|| DEFAULT_WINDOW.into());
+        let Some(secs) = window_secs(&window) else {
+            return Err(format!(
+                "Invalid time window '{}': use a number followed by s, m, h or d (e.g. 30m, 2h)",
+                window
+            ));
+        };
+        if secs > MAX_WINDOW_SECS {
+            return Err(format!(
+                "Time window '{}' is too long: search at most 24h at a time",
+                window
+            ));
+        }
+
+        let level = match text("level") {
+            Some(l) => {
+                let l = l.to_lowercase();
+                match LEVELS.iter().find(|(name, _)| *name == l) {
+                    Some((_, message_type)) => Some(message_type.to_string()),
+                    None => {
+                        return Err(format!(
+                            "Unknown log level '{}': use fault, error, default, info or debug",
+                            l
+                        ))
+                    }
+                }
+            }
+            None => None,
+        };
+
+        let limit = args
+            .get("limit")
+            .and_then(|v| v.as_u64())
+            .map(|n| (n as usize).clamp(1, MAX_LOG_LIMIT))
+            .unwrap_or(DEFAULT_LOG_LIMIT);
+
+        let query = LogQuery {
+            window,
+            process: text("process"),
+            subsystem: text("subsystem"),
+            contains: text("contains"),
+            level,
+            predicate: text("predicate"),
+            limit,
+        };
+        let narrowed = query.process.is_some()
+            || query.subsystem.is_some()
+            || query.contains.is_some()
+            || query.predicate.is_some();
+        let verbose = matches!(query.level.as_deref(), Some("info" | "debug"));
+        if verbose && !narrowed && secs > MAX_VERBOSE_WINDOW_SECS {
+            return Err(format!(
+                "{} messages over '{}' are too many: add a process, subsystem, contains or \
+                 predicate filter, or search 1h or less",
+                query.level.as_deref().unwrap_or_default(),
+                query.window
+            ));
+        }
+        Ok(query)
+    }
+
+    /// The combined NSPredicate. Typed filters are quoted; a raw `predicate`
+    /// is parenthesised and ANDed with them.
+    pub(super) fn predicate(&self) -> Option<String> {
+        let mut clauses = Vec::new();
+        if let Some(p) = &self.process {
+            clauses.push(format!("process == {}", quote(p)));
+        }
+        if let Some(s) = &self.subsystem {
+            clauses.push(format!("subsystem == {}", quote(s)));
+        }
+        if let Some(c) = &self.contains {
+            clauses.push(format!("eventMessage CONTAINS[c] {}", quote(c)));
+        }
+        if let Some(l) = &self.level {
+            clauses.push(format!("messageType == {}", l));
+        }
+        if let Some(raw) = &self.predicate {
+            clauses.push(format!("({})", raw));
+        }
+        if clauses.is_empty() {
+            None
+        } else {
+            Some(clauses.join(" AND "))
+        }
+    }
+
+    fn command_args(&self) -> Vec<String> {
+        let mut args: Vec<String> = vec![
+            "show".into(),
+            "--style".into(),
+            "ndjson".into(),
+            "--last".into(),
+            self.window.clone(),
+        ];
+        // info and debug messages are hidden unless asked for.
+        match self.level.as_deref() {
+            Some("info") => args.push("--info".into()),
+            Some("debug") => {
+                args.push("--info".into());
+                args.push("--debug".into());
+            }
+            _ => {}
+        }
+        if let Some(p) = self.predicate() {
+            args.push("--predicate".into());
+            args.push(p);
+        }
+        args
+    }
+}
+
+pub(super) fn search_logs(args: &Value) -> ToolResult {
+    let query = match LogQuery::from_args(args) {
+        Ok(q) => q,
+        Err(e) => {
+            return ToolResult {
+                success: false,
+                data: json!(null),
+                error: Some(e),
+            }
+        }
+    };
+
+    // `log show` prints oldest first; the tail keeps the most recent entries.
+    let tail = match stream_log("log", &query.command_args(), query.limit, SEARCH_TIMEOUT) {
+        Ok(tail) => tail,
+        Err(e) => {
+            return ToolResult {
+                success: false,
+                data: json!(null),
+                error: Some(e),
+            }
+        }
+    };
+
+    let mut top_processes: Vec<(&str, usize)> = tail
+        .by_process
+        .iter()
+        .map(|(p, n)| (p.as_str(), *n))
+        .collect();
+    top_processes.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    top_processes.truncate(10);
+
+    ToolResult {
+        success: true,
+        data: json!({
+            "window": query.window,
+            "predicate": query.predicate(),
+            "match_count": tail.total,
+            "truncated": tail.total > tail.recent.len(),
+            // Counts cover only what was read before the search was stopped.
+            "timed_out": tail.timed_out,
+            "top_processes": top_processes
+                .iter()
+                .map(|(p, n)| json!({"process": p, "count": n}))
+                .collect::<Vec<_>>(),
+            "entries": tail.recent,
+        }),
+        error: None,
+    }
+}
+
+// ----- Crash and hang reports -----
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum ReportKind {
+    Crash,
+    Hang,
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(super) struct CrashThread {
+    pub index: usize,
+    pub name: Option<String>,
+    pub queue: Option<String>,
+    pub frames: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(super) struct CrashReport {
+    pub app: String,
+    pub bundle_id: Option<String>,
+    pub version: Option<String>,
+    pub timestamp: String,
+    pub kind: ReportKind,
+    pub exception_type: Option<String>,
+    pub signal: Option<String>,
+    pub subtype: Option<String>,
+    /// Termination indicator for crashes, the hang reason for hangs.
+    pub reason: Option<String>,
+    pub crashing_thread: Option<CrashThread>,
+    pub file: String,
+}
+
+fn str_field(v: &Value, key: &str) -> Option<String> {
+    v.get(key)
+        .and_then(|s| s.as_str())
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+/// Parse an `.ips` report: a one-line JSON header followed by a JSON body.
+pub(super) fn parse_ips(text: &str, file: &str) -> Option<CrashReport> {
+    let (header_line, body_text) = text.split_once('\n').unwrap_or((text, ""));
+    let header: Value = serde_json::from_str(header_line.trim()).ok()?;
+    let body: Value = serde_json::from_str(body_text.trim()).unwrap_or(Value::Null);
+
+    let app = str_field(&header, "app_name")
+        .or_else(|| str_field(&header, "name"))
+        .or_else(|| str_field(&body, "procName"))?;
+    let exception = body.get("exception");
+
+    let bug_type = str_field(&header, "bug_type").unwrap_or_default();
+    let kind = if bug_type == "298" || bug_type == "288" || file.contains(".hang") {
+        ReportKind::Hang
+    } else if bug_type == "309" || exception.is_some() {
+        ReportKind::Crash
+    } else {
+        ReportKind::Other
+    };
+
+    let reason = body
+        .get("termination")
+        .and_then(|t| str_field(t, "indicator"))
+        .or_else(|| str_field(&body, "reason"));
+
+    Some(CrashReport {
+        app,
+        bundle_id: str_field(&header, "bundleID"),
+        version: str_field(&header, "app_version"),
+        timestamp: str_field(&header, "timestamp").unwrap_or_default(),
+        kind,
+        exception_type: exception.and_then(|e| str_field(e, "type")),
+        signal: exception.and_then(|e| str_field(e, "signal")),
+        subtype: exception.and_then(|e| str_field(e, "subtype")),
+        reason,
+        crashing_thread: ips_crashing_thread(&body),
+        file: file.to_string(),
+    })
+}
+
+fn ips_crashing_thread(body: &Value) -> Option<CrashThread> {
+    let threads = body.get("threads")?.as_array()?;
+    let index = body
+        .get("faultingThread")
+        .and_then(|i| i.as_u64())
+        .map(|i| i as usize)
+        .or_else(|| {
+            threads
+                .iter()
+                .position(|t| t.get("triggered").and_then(|b| b.as_bool()) == Some(true))
+        })?;
+    let thread = threads.get(index)?;
+
+    let images = body.get("usedImages").and_then(|i| i.as_array());
+    let frames = thread
+        .get("frames")
+        .and_then(|f| f.as_array())
+        .map(|frames| {
+            frames
+                .iter()
+                .take(MAX_FRAMES)
+                .map(|f| {
+                    let image = f
+                        .get("imageIndex")
+                        .and_then(|i| i.as_u64())
+                        .and_then(|i| images?.get(i as usize))
+                        .and_then(|img| str_field(img, "name"));
+                    match str_field(f, "symbol") {
+                        Some(sym) => {
+                            let offset = f.get("symbolLocation").and_then(|o| o.as_u64());
+                            match offset {
+                                Some(o) => format!("{} + {}", sym, o),
+                                None => sym,
+                            }
+                        }
+                        None => format!(
+                            "{} + {}",
+                            image.unwrap_or_else(|| "???".into()),
+                            f.get("imageOffset").and_then(|o| o.as_u64()).unwrap_or(0)
+                        ),
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some(CrashThread {
+        index,
+        name: str_field(thread, "name"),
+        queue: str_field(thread, "queue"),
+        frames,
+    })
+}
+
+/// Parse a pre-Monterey plain-text `.crash` report.
+pub(super) fn parse_legacy_crash(text: &str, file: &str) -> Option<CrashReport> {
+    let field = |key: &str| {
+        text.lines().find_map(|l| {
+            let (k, v) = l.split_once(':')?;
+            (k.trim() == key)
+                .then(|| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        })
+    };
+
+    // "Legacy [321]" -> "Legacy"
+    let app = field("Process")?
+        .split(" [")
+        .next()
+        .unwrap_or_default()
+        .to_string();
+
+    // "EXC_BAD_INSTRUCTION (SIGILL)" -> type and signal
+    let (exception_type, signal) = match field("Exception Type") {
+        Some(e) => match e.split_once(" (") {
+            Some((t, s)) => (
+                Some(t.to_string()),
+                Some(s.trim_end_matches(')').to_string()),
+            ),
+            None => (Some(e), None),
+        },
+        None => (None, None),
+    };
+
+    let crashing_thread = field("Crashed Thread").and_then(|t| {
+        let index: usize = t.split_whitespace().next()?.parse().ok()?;
+        let queue = t
+            .split_once("Dispatch queue:")
+            .map(|(_, q)| q.trim().to_string());
+        let marker = format!("Thread {} Crashed:", index);
+        let frames = text
+            .lines()
+            .skip_while(|l| !l.starts_with(&marker))
+            .skip(1)
+            .take_while(|l| !l.trim().is_empty())
+            .take(MAX_FRAMES)
+            .filter_map(legacy_frame)
+            .collect();
+        Some(CrashThread {
+            index,
+            name: None,
+            queue,
+            frames,
+        })
+    });
+
+    Some(CrashReport {
+        app,
+        bundle_id: field("Identifier"),
+        version: field("Version"),
+        timestamp: field("Date/Time").unwrap_or_default(),
+        kind: ReportKind::Crash,
+        exception_type,
+        signal,
+        subtype: None,
+        reason: field("Termination Reason"),
+        crashing_thread,
+        file: file.to_string(),
+    })
+}
+
+/// "0   com.example.legacy   0x0000000100001234 -[LegacyController load] + 52"
+/// -> "-[LegacyController load] + 52"
+fn legacy_frame(line: &str) -> Option<String> {
+    let mut parts = line.split_whitespace();
+    parts.next()?.parse::<usize>().ok()?;
+    let image = parts.next()?;
+    let rest: Vec<&str> = parts.collect();
+    let symbol_start = rest
+        .iter()
+        .position(|p| p.starts_with("0x"))
+        .map_or(0, |i| i + 1);
+    let symbol = rest[symbol_start..].join(" ");
+    Some(if symbol.is_empty() {
+        image.to_string()
+    } else {
+        symbol
+    })
+}
+
+pub(super) fn parse_report(path: &Path) -> Option<CrashReport> {
+    let text = fs::read_to_string(path).ok()?;
+    let file = path.file_name()?.to_string_lossy().to_string();
+    match path.extension()?.to_str()? {
+        "ips" => parse_ips(&text, &file),
+        "crash" => parse_legacy_crash(&text, &file),
+        // Older spindump hangs use .hang/.spin; newer ones are JSON.
+        "hang" | "spin" => parse_ips(&text, &file),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(super) struct AppSummary {
+    pub app: String,
+    pub bundle_id: Option<String>,
+    pub crashes: usize,
+    pub hangs: usize,
+    pub last_seen: String,
+    pub top_exception: Option<String>,
+}
+
+/// Group reports per app, most frequent first.
+pub(super) fn summarize(reports: &[CrashReport]) -> Vec<AppSummary> {
+    let mut by_app: HashMap<&str, Vec<&CrashReport>> = HashMap::new();
+    for r in reports {
+        by_app.entry(r.app.as_str()).or_default().push(r);
+    }
+
+    let mut summaries: Vec<AppSummary> = by_app
+        .into_iter()
+        .map(|(app, reports)| {
+            let mut exceptions: HashMap<&str, usize> = HashMap::new();
+            for r in &reports {
+                if let Some(e) = &r.exception_type {
+                    *exceptions.entry(e.as_str()).or_default() += 1;
+                }
+            }
+            let top_exception = exceptions
+                .into_iter()
+                .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(a.0)))
+                .map(|(e, _)| e.to_string());
+            AppSummary {
+                app: app.to_string(),
+                bundle_id: reports.iter().find_map(|r| r.bundle_id.clone()),
+                crashes: reports
+                    .iter()
+                    .filter(|r| r.kind == ReportKind::Crash)
+                    .count(),
+                hangs: reports
+                    .iter()
+                    .filter(|r| r.kind == ReportKind::Hang)
+                    .count(),
+                last_seen: reports
+                    .iter()
+                    .map(|r| r.timestamp.as_str())
+                    .max()
+                    .unwrap_or_default()
+                    .to_string(),
+                top_exception,
+            }
+        })
+        .collect();
+
+    summaries.sort_by(|a, b| {
+        (b.crashes + b.hangs)
+            .cmp(&(a.crashes + a.hangs))
+            .then(b.last_seen.cmp(&a.last_seen))
+    });
+    summaries
+}
+
+/// Report files in `dirs` modified within `max_age`.
+fn report_files(dirs: &[PathBuf], max_age: Duration) -> Vec<PathBuf> {
+    let cutoff = SystemTime::now().checked_sub(max_age);
+    dirs.iter()
+        .filter_map(|d| fs::read_dir(d).ok())
+        .flatten()
+        .flatten()
+        .filter(|entry| {
+            let recent = match (cutoff, entry.metadata().and_then(|m| m.modified())) {
+                (Some(cutoff), Ok(modified)) => modified >= cutoff,
+                _ => true,
+            };
+            recent && entry.path().is_file()
+        })
+        .map(|entry| entry.path())
+        .collect()
+}
+
+pub(super) fn collect_reports(dirs: &[PathBuf], days: u64, app: Option<&str>) -> Vec<CrashReport> {
+    let app = app.map(str::to_lowercase);
+    let max_age = Duration::from_secs(days.saturating_mul(86_400));
+    let mut reports: Vec<CrashReport> = report_files(dirs, max_age)
+        .iter()
+        .filter_map(|p| parse_report(p))
+        .filter(|r| r.kind != ReportKind::Other)
+        .filter(|r| {
+            app.as_deref().is_none_or(|a| {
+                r.app.to_lowercase().contains(a)
+                    || r.bundle_id
+                        .as_deref()
+                        .is_some_and(|b| b.to_lowercase().contains(a))
+            })
+        })
+        .collect();
+    reports.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    reports
+}
+
+pub(super) fn analyze_crashes(args: &Value) -> ToolResult {
+    let app = args
+        .get("app")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|s| !s.is_empty());
+    let days = args
+        .get("days")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(DEFAULT_DAYS)
+        .clamp(1, MAX_DAYS);
+    let limit = args
+        .get("limit")
+        .and_then(|v| v.as_u64())
+        .map(|n| n as usize)
+        .unwrap_or(DEFAULT_REPORT_LIMIT);
+
+    let dirs = vec![
+        home_dir().join("Library/Logs/DiagnosticReports"),
+        PathBuf::from("/Library/Logs/DiagnosticReports"),
+    ];
+    let reports = collect_reports(&dirs, days, app);
+    let apps = summarize(&reports);
+
+    ToolResult {
+        success: true,
+        data: json!({
+            "days": days,
+            "app": app,
+            "report_count": reports.len(),
+            "crash_count": reports.iter().filter(|r| r.kind == ReportKind::Crash).count(),
+            "hang_count": reports.iter().filter(|r| r.kind == ReportKind::Hang).count(),
+            "apps": apps,
+            "reports": reports.iter().take(limit).collect::<Vec<_>>(),
+            "truncated": reports.len() > limit,
+        }),
+        error: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixtures() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/crash")
+    }
+
+    fn report(name: &str) -> CrashReport {
+        parse_report(&fixtures().join(name)).expect("fixture should parse")
+    }
+
+    #[test]
+    fn test_parse_ips_crash() {
+        let r = report("Safari-2024-05-01-100000.ips");
+        assert_eq!(r.app, "Safari");
+        assert_eq!(r.bundle_id.as_deref(), Some("com.apple.Safari"));
+        assert_eq!(r.version.as_deref(), Some("17.4.1"));
+        assert_eq!(r.kind, ReportKind::Crash);
+        assert_eq!(r.exception_type.as_deref(), Some("EXC_BAD_ACCESS"));
+        assert_eq!(r.signal.as_deref(), Some("SIGSEGV"));
+        assert_eq!(r.reason.as_deref(), Some("Segmentation fault: 11"));
+
+        let t = r.crashing_thread.unwrap();
+        assert_eq!(t.index, 2);
+        assert_eq!(t.name.as_deref(), Some("WebContent.Networking"));
+        assert_eq!(t.frames[0], "objc_msgSend + 16");
+        // Frames without a symbol fall back to image + offset.
+        assert_eq!(t.frames[2], "SafariShared + 8000");
+    }
+
+    #[test]
+    fn test_parse_ips_hang() {
+        let r = report("Xcode_2024-05-03-140000_MacBook.hang.ips");
+        assert_eq!(r.kind, ReportKind::Hang);
+        assert_eq!(r.app, "Xcode");
+        assert!(r.exception_type.is_none());
+        assert!(r.reason.unwrap().contains("hung"));
+    }
+
+    #[test]
+    fn test_parse_legacy_crash() {
+        let r = report("Legacy_2023-01-01-120000_MacBook.crash");
+        assert_eq!(r.app, "Legacy");
+        assert_eq!(r.version.as_deref(), Some("2.1 (210)"));
+        assert_eq!(r.exception_type.as_deref(), Some("EXC_BAD_INSTRUCTION"));
+        assert_eq!(r.signal.as_deref(), Some("SIGILL"));
+        let t = r.crashing_thread.unwrap();
+        assert_eq!(t.index, 0);
+        assert_eq!(t.queue.as_deref(), Some("com.apple.main-thread"));
+        assert_eq!(
+            t.frames,
+            vec![
+                "-[LegacyController load] + 52",
+                "-[NSApplication run] + 600",
+                "start + 1903",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_garbage_report_is_skipped() {
+        assert!(parse_report(&fixtures().join("not-a-report.ips")).is_none());
+    }
+
+    #[test]
+    fn test_summarize_per_app() {
+        let reports = collect_reports(&[fixtures()], 36_500, None);
+        assert_eq!(reports.len(), 4);
+        // Newest first.
+        assert_eq!(reports[0].app, "Xcode");
+
+        let apps = summarize(&reports);
+        assert_eq!(apps[0].app, "Safari");
+        assert_eq!(apps[0].crashes, 2);
+        assert_eq!(apps[0].last_seen, "2024-05-02 09:30:00.00 +0200");
+        let xcode = apps.iter().find(|a| a.app == "Xcode").unwrap();
+        assert_eq!((xcode.crashes, xcode.hangs), (0, 1));
+
+        let safari_only = collect_reports(&[fixtures()], 36_500, Some("com.apple.safari"));
+        assert_eq!(safari_only.len(), 2);
+    }
+
+    #[test]
+    fn test_huge_day_counts_are_capped() {
+        assert_eq!(collect_reports(&[fixtures()], u64::MAX, None).len(), 4);
+        let result = analyze_crashes(&json!({"days": u64::MAX}));
+        assert!(result.success);
+        assert_eq!(result.data["days"], MAX_DAYS);
+    }
+
+    #[test]
+    fn test_parse_log_ndjson() {
+        let output = r#"Filtering the log data using "process == \"Safari\""
+{"timestamp":"2024-05-01 10:00:00.123456+0200","messageType":"Error","processImagePath":"/Applications/Safari.app/Contents/MacOS/Safari","processID":4312,"subsystem":"com.apple.WebKit","category":"Loading","eventMessage":"Failed to load resource"}
+{"timestamp":"2024-05-01 10:00:01.000000+0200","messageType":"Default","processImagePath":"/usr/libexec/trustd","processID":99,"subsystem":"","category":"","eventMessage":"ok"}
+{"count":2,"finished":1}
+"#;
+        let entries = parse_log_ndjson(output);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].process, "Safari");
+        assert_eq!(entries[0].level, "error");
+        assert_eq!(entries[0].pid, Some(4312));
+        assert_eq!(entries[1].subsystem, None);
+    }
+
+    #[test]
+    fn test_log_query_predicate() {
+        let q = LogQuery::from_args(&json!({
+            "process": "Safari",
+            "contains": "say \"hi\"",
+            "level": "Error",
+            "predicate": "category == \"Loading\"",
+        }))
+        .unwrap();
+        assert_eq!(q.window, "1h");
+        assert_eq!(
+            q.predicate().unwrap(),
+            "process == \"Safari\" AND eventMessage CONTAINS[c] \"say \\\"hi\\\"\" \
+             AND messageType == error AND (category == \"Loading\")"
+        );
+        assert!(LogQuery::from_args(&json!({}))
+            .unwrap()
+            .predicate()
+            .is_none());
+    }
+
+    #[test]
+    fn test_log_query_validation() {
+        assert!(LogQuery::from_args(&json!({"last": "30m"})).is_ok());
+        assert!(LogQuery::from_args(&json!({"last": "2 hours"})).is_err());
+        assert!(LogQuery::from_args(&json!({"last": "h"})).is_err());
+        assert!(LogQuery::from_args(&json!({"level": "loud"})).is_err());
+        assert!(LogQuery::from_args(&json!({"last": "24h"})).is_ok());
+        assert!(LogQuery::from_args(&json!({"last": "365d"})).is_err());
+        assert!(LogQuery::from_args(&json!({"last": "99999999999999999999d"})).is_err());
+
+        let q = LogQuery::from_args(&json!({"level": "debug", "limit": 5000})).unwrap();
+        assert_eq!(q.limit, MAX_LOG_LIMIT);
+        assert!(q.command_args().contains(&"--debug".to_string()));
+        // Debug over a long window needs a filter.
+        assert!(LogQuery::from_args(&json!({"level": "debug", "last": "6h"})).is_err());
+        assert!(
+            LogQuery::from_args(&json!({"level": "debug", "last": "6h", "process": "Safari"}))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_stream_log_keeps_tail_and_stops_at_deadline() {
+        let line = r#"{"processImagePath":"/usr/libexec/trustd","messageType":"Default","eventMessage":"n"}"#;
+        let script = format!("for i in 1 2 3 4 5; do echo '{}'; done; echo summary", line);
+        let tail = stream_log("sh", &["-c".into(), script], 2, Duration::from_secs(5)).unwrap();
+        assert_eq!((tail.total, tail.recent.len()), (5, 2));
+        assert_eq!(tail.by_process["trustd"], 5);
+        assert!(!tail.timed_out);
+
+        // An endless stream is cut off, keeping memory at `limit` entries.
+        let script = format!("while :; do echo '{}'; done", line);
+        let started = Instant::now();
+        let tail = stream_log("sh", &["-c".into(), script], 3, Duration::from_millis(300)).unwrap();
+        assert!(tail.timed_out);
+        assert!(tail.total > 3);
+        assert_eq!(tail.recent.len(), 3);
+        assert!(started.elapsed() < Duration::from_secs(3));
+    }
+}
@@ -0,0 +1,477 @@
+//! Memory pressure analysis.
+//!
+//! Turns the raw `vm_stat` page counts into byte figures using the real page
+//! size, combines them with swap usage and the kernel's own pressure level,
+//! and names the processes holding most of the resident set.
+
+use super::{run_cmd, ToolResult};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+// ---------------------------------------------------------------------------
+// Thresholds
+// ---------------------------------------------------------------------------
+
+/// Available memory (free + cached) below this share of RAM is "warning".
+const AVAILABLE_WARN_PCT: f64 = 20.0;
+/// Available memory below this share of RAM is "critical".
+const AVAILABLE_CRITICAL_PCT: f64 = 8.0;
+/// Compressor holding more than this share of RAM indicates sustained pressure.
+const COMPRESSED_WARN_PCT: f64 = 25.0;
+/// Swap in use beyond this means RAM has run out at some point.
+const SWAP_WARN_BYTES: u64 = 1024 * 1024 * 1024;
+/// Top consumers are listed until together they cover this share of the resident set.
+const TOP_CONSUMER_SHARE_PCT: f64 = 60.0;
+/// Upper bound on the number of top consumers reported.
+const TOP_CONSUMER_MAX: usize = 10;
+
+/// Page size assumed when neither `vm_stat` nor sysctl report one.
+const DEFAULT_PAGE_SIZE: u64 = 16384;
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(super) enum PressureLevel {
+    Normal,
+    Warning,
+    Critical,
+}
+
+impl PressureLevel {
+    /// Map `kern.memorystatus_vm_pressure_level` (1 / 2 / 4) to a level.
+    fn from_kernel(raw: &str) -> Option<Self> {
+        match raw.trim().parse::<u32>().ok()? {
+            1 => Some(Self::Normal),
+            2 => Some(Self::Warning),
+            4 => Some(Self::Critical),
+            _ => None,
+        }
+    }
+}
+
+/// Memory broken down into Activity Monitor-style buckets, all in bytes.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub(super) struct MemoryBreakdown {
+    pub page_size: u64,
+    pub total: u64,
+    pub used: u64,
+    pub app: u64,
+    pub wired: u64,
+    pub compressed: u64,
+    pub cached: u64,
+    pub free: u64,
+    pub available: u64,
+}
+
+/// Parsed `vm.swapusage`, in bytes.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub(super) struct SwapUsage {
+    pub total: u64,
+    pub used: u64,
+    pub free: u64,
+    pub encrypted: bool,
+}
+
+/// One process from the resident-set listing.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(super) struct MemoryConsumer {
+    pub pid: u32,
+    pub name: String,
+    pub command: String,
+    pub rss_bytes: u64,
+    pub pct_of_resident: f64,
+}
+
+// ---------------------------------------------------------------------------
+// Parsers
+// ---------------------------------------------------------------------------
+
+/// Parse `vm_stat` output into a JSON object of page counts.
+pub(super) fn parse_vm_stat(raw: &str) -> Value {
+    let mut map = serde_json::Map::new();
+    for line in raw.lines() {
+        if let Some((key, val)) = line.split_once(':') {
+            let key = key
+                .trim()
+                .trim_matches('"')
+                .replace(' ', "_")
+                .to_lowercase();
+            let val = val.trim().trim_end_matches('.').trim();
+            if let Ok(n) = val.parse::<u64>() {
+                map.insert(key, json!(n));
+            } else {
+                map.insert(key, json!(val));
+            }
+        }
+    }
+    Value::Object(map)
+}
+
+/// Extract the page size from the `vm_stat` header
+/// ("Mach Virtual Memory Statistics: (page size of 16384 bytes)").
+pub(super) fn parse_page_size(raw: &str) -> Option<u64> {
+    let header = raw.lines().next()?;
+    let rest = &header[header.find("page size of")? + "page size of".len()..];
+    rest.split_whitespace().next()?.parse().ok()
+}
+
+/// Convert a swap figure such as `1034.25M` into bytes.
+fn parse_swap_amount(s: &str) -> u64 {
+    let s = s.trim();
+    let (num, mult) = match s.chars().last() {
+        Some('K') => (&s[..s.len() - 1], 1024.0),
+        Some('M') => (&s[..s.len() - 1], 1024.0 * 1024.0),
+        Some('G') => (&s[..s.len() - 1], 1024.0 * 1024.0 * 1024.0),
+        _ => (s, 1.0),
+    };
+    (num.parse::<f64>().unwrap_or(0.0) * mult) as u64
+}
+
+/// Parse `sysctl -n vm.swapusage`
+/// ("total = 2048.00M  used = 1034.25M  free = 1013.75M  (encrypted)").
+pub(super) fn parse_swapusage(raw: &str) -> SwapUsage {
+    let tokens: Vec<&str> = raw.split_whitespace().collect();
+    let value_of = |key: &str| -> u64 {
+        tokens
+            .windows(3)
+            .find(|w| w[0] == key && w[1] == "=")
+            .map(|w| parse_swap_amount(w[2]))
+            .unwrap_or(0)
+    };
+    SwapUsage {
+        total: value_of("total"),
+        used: value_of("used"),
+        free: value_of("free"),
+        encrypted: raw.contains("(encrypted)"),
+    }
+}
+
+/// Parse `ps -axo pid=,rss=,comm=` into consumers sorted by resident size.
+/// `pct_of_resident` is filled in against the sum of all listed processes.
+pub(super) fn parse_ps_rss(raw: &str) -> Vec<MemoryConsumer> {
+    let mut procs: Vec<MemoryConsumer> = raw
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let pid = parts.next()?.parse().ok()?;
+            let rss_kb: u64 = parts.next()?.parse().ok()?;
+            let command = parts.collect::<Vec<_>>().join(" ");
+            if command.is_empty() {
+                return None;
+            }
+            let name = command.rsplit('/').next().unwrap_or(&command).to_string();
+            Some(MemoryConsumer {
+                pid,
+                name,
+                command,
+                rss_bytes: rss_kb * 1024,
+                pct_of_resident: 0.0,
+            })
+        })
+        .collect();
+
+    let total: u64 = procs.iter().map(|p| p.rss_bytes).sum();
+    if total > 0 {
+        for p in &mut procs {
+            p.pct_of_resident = round2(p.rss_bytes as f64 / total as f64 * 100.0);
+        }
+    }
+    procs.sort_by_key(|p| std::cmp::Reverse(p.rss_bytes));
+    procs
+}
+
+// ---------------------------------------------------------------------------
+// Analysis
+// ---------------------------------------------------------------------------
+
+/// Compute the byte breakdown from parsed `vm_stat` pages.
+///
+/// Mirrors Activity Monitor: used = app + wired + compressed, where app memory
+/// is anonymous pages minus purgeable; cached = file-backed + purgeable.
+pub(super) fn compute_breakdown(vm: &Value, page_size: u64, total: u64) -> MemoryBreakdown {
+    let pages = |key: &str| vm.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+
+    let purgeable = pages("pages_purgeable");
+    let app = pages("anonymous_pages").saturating_sub(purgeable) * page_size;
+    let wired = pages("pages_wired_down") * page_size;
+    let compressed = pages("pages_occupied_by_compressor") * page_size;
+    let cached = (pages("file-backed_pages") + purgeable) * page_size;
+    let free = (pages("pages_free") + pages("pages_speculative")) * page_size;
+    let used = app + wired + compressed;
+
+    MemoryBreakdown {
+        page_size,
+        total,
+        used,
+        app,
+        wired,
+        compressed,
+        cached,
+        free,
+        available: total.saturating_sub(used),
+    }
+}
+
+/// Derive the pressure level from the breakdown and swap, taking the kernel's
+/// own level into account when it reports a worse one.
+pub(super) fn pressure_level(
+    mem: &MemoryBreakdown,
+    swap: &SwapUsage,
+    kernel: Option<PressureLevel>,
+) -> PressureLevel {
+    let mut level = PressureLevel::Normal;
+    if mem.total > 0 {
+        let available_pct = pct(mem.available, mem.total);
+        let compressed_pct = pct(mem.compressed, mem.total);
+        if available_pct < AVAILABLE_CRITICAL_PCT {
+            level = PressureLevel::Critical;
+        } else if available_pct < AVAILABLE_WARN_PCT || compressed_pct > COMPRESSED_WARN_PCT {
+            level = PressureLevel::Warning;
+        }
+    }
+    if swap.used > SWAP_WARN_BYTES {
+        level = level.max(PressureLevel::Warning);
+    }
+    kernel.map_or(level, |k| level.max(k))
+}
+
+/// The smallest prefix of the (sorted) consumers that covers
+/// `TOP_CONSUMER_SHARE_PCT` of the resident set, capped at `TOP_CONSUMER_MAX`.
+pub(super) fn top_consumers(procs: &[MemoryConsumer]) -> Vec<MemoryConsumer> {
+    let mut covered = 0.0;
+    let mut out = Vec::new();
+    for p in procs.iter().take(TOP_CONSUMER_MAX) {
+        if covered >= TOP_CONSUMER_SHARE_PCT {
+            break;
+        }
+        covered += p.pct_of_resident;
+        out.push(p.clone());
+    }
+    out
+}
+
+/// One-line human-readable summary.
+pub(super) fn verdict(
+    mem: &MemoryBreakdown,
+    swap: &SwapUsage,
+    level: PressureLevel,
+    top: &[MemoryConsumer],
+) -> String {
+    let usage = format!(
+        "{} of {} in use, {} available",
+        format_gb(mem.used),
+        format_gb(mem.total),
+        format_gb(mem.available)
+    );
+    let mut text = match level {
+        PressureLevel::Normal => format!("Memory pressure is normal: {}.", usage),
+        PressureLevel::Warning => format!("Memory pressure is elevated: {}.", usage),
+        PressureLevel::Critical => format!("Memory pressure is critical: {}.", usage),
+    };
+    if swap.used > SWAP_WARN_BYTES {
+        text.push_str(&format!(" {} swapped to disk.", format_gb(swap.used)));
+    }
+    if level != PressureLevel::Normal {
+        if let Some(p) = top.first() {
+            text.push_str(&format!(
+                " Largest consumer: {} ({}).",
+                p.name,
+                format_gb(p.rss_bytes)
+            ));
+        }
+    }
+    text
+}
+
+fn pct(part: u64, whole: u64) -> f64 {
+    part as f64 / whole as f64 * 100.0
+}
+
+fn round2(x: f64) -> f64 {
+    (x * 100.0).round() / 100.0
+}
+
+fn format_gb(bytes: u64) -> String {
+    format!("{:.1} GB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+}
+
+// ---------------------------------------------------------------------------
+// Tool
+// ---------------------------------------------------------------------------
+
+pub(super) fn monitor_memory() -> ToolResult {
+    let vm_raw = run_cmd("vm_stat", &[]);
+    let memsize = run_cmd("sysctl", &["-n", "hw.memsize"]);
+    let swap_raw = run_cmd("sysctl", &["-n", "vm.swapusage"]);
+    let kernel_raw = run_cmd("sysctl", &["-n", "kern.memorystatus_vm_pressure_level"]);
+    let ps_raw = run_cmd("ps", &["-axo", "pid=,rss=,comm="]);
+
+    let page_size = parse_page_size(&vm_raw)
+        .or_else(|| run_cmd("sysctl", &["-n", "hw.pagesize"]).parse().ok())
+        .unwrap_or(DEFAULT_PAGE_SIZE);
+    let total_bytes: u64 = memsize.parse().unwrap_or(0);
+
+    let vm = parse_vm_stat(&vm_raw);
+    let mem = compute_breakdown(&vm, page_size, total_bytes);
+    let swap = parse_swapusage(&swap_raw);
+    let kernel_level = PressureLevel::from_kernel(&kernel_raw);
+    let level = pressure_level(&mem, &swap, kernel_level);
+    let procs = parse_ps_rss(&ps_raw);
+    let top = top_consumers(&procs);
+    let summary = verdict(&mem, &swap, level, &top);
+
+    let total_gb = total_bytes as f64 / (1024.0 * 1024.0 * 1024.0);
+
+    ToolResult {
+        success: true,
+        data: json!({
+            "total_memory_gb": round2(total_gb),
+            "memory_bytes": mem,
+            "swap_bytes": swap,
+            "pressure": {
+                "level": level,
+                "kernel_level": kernel_level,
+                "available_pct": if mem.total > 0 { round2(pct(mem.available, mem.total)) } else { 0.0 },
+                "compressed_pct": if mem.total > 0 { round2(pct(mem.compressed, mem.total)) } else { 0.0 },
+            },
+            "thresholds": {
+                "available_warn_pct": AVAILABLE_WARN_PCT,
+                "available_critical_pct": AVAILABLE_CRITICAL_PCT,
+                "compressed_warn_pct": COMPRESSED_WARN_PCT,
+                "swap_warn_bytes": SWAP_WARN_BYTES,
+                "top_consumer_share_pct": TOP_CONSUMER_SHARE_PCT,
+            },
+            "top_memory_consumers": top,
+            "verdict": summary,
+            "vm_stat": vm,
+        }),
+        error: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VM_STAT: &str = "\
+Mach Virtual Memory Statistics: (page size of 16384 bytes)
+Pages free:                               20000.
+Pages active:                            345678.
+Pages inactive:                          340000.
+Pages speculative:                         5000.
+Pages throttled:                              0.
+Pages wired down:                        150000.
+Pages purgeable:                          10000.
+\"Translation faults\":                 123456789.
+File-backed pages:                       300000.
+Anonymous pages:                         390000.
+Pages stored in compressor:              400000.
+Pages occupied by compressor:            100000.
+Swapins:                                      0.
+Swapouts:                                     0.";
+
+    const GIB: u64 = 1024 * 1024 * 1024;
+
+    #[test]
+    fn test_parse_page_size_and_keys() {
+        assert_eq!(parse_page_size(VM_STAT), Some(16384));
+        let vm = parse_vm_stat(VM_STAT);
+        assert_eq!(vm["pages_wired_down"], 150000);
+        assert_eq!(vm["file-backed_pages"], 300000);
+        assert_eq!(vm["translation_faults"], 123456789);
+    }
+
+    #[test]
+    fn test_compute_breakdown() {
+        let vm = parse_vm_stat(VM_STAT);
+        let mem = compute_breakdown(&vm, 16384, 16 * GIB);
+        assert_eq!(mem.app, 380000 * 16384);
+        assert_eq!(mem.wired, 150000 * 16384);
+        assert_eq!(mem.compressed, 100000 * 16384);
+        assert_eq!(mem.cached, 310000 * 16384);
+        assert_eq!(mem.free, 25000 * 16384);
+        assert_eq!(mem.used, mem.app + mem.wired + mem.compressed);
+        assert_eq!(mem.available, 16 * GIB - mem.used);
+    }
+
+    #[test]
+    fn test_parse_swapusage() {
+        let swap =
+            parse_swapusage("total = 2048.00M  used = 1536.00M  free = 512.00M  (encrypted)");
+        assert_eq!(swap.total, 2048 * 1024 * 1024);
+        assert_eq!(swap.used, 1536 * 1024 * 1024);
+        assert_eq!(swap.free, 512 * 1024 * 1024);
+        assert!(swap.encrypted);
+    }
+
+    #[test]
+    fn test_pressure_levels() {
+        let mut mem = MemoryBreakdown {
+            total: 16 * GIB,
+            used: 8 * GIB,
+            available: 8 * GIB,
+            ..Default::default()
+        };
+        let no_swap = SwapUsage::default();
+        assert_eq!(pressure_level(&mem, &no_swap, None), PressureLevel::Normal);
+
+        mem.available = 2 * GIB;
+        assert_eq!(pressure_level(&mem, &no_swap, None), PressureLevel::Warning);
+
+        mem.available = GIB;
+        assert_eq!(
+            pressure_level(&mem, &no_swap, None),
+            PressureLevel::Critical
+        );
+
+        mem.available = 8 * GIB;
+        let swapping = SwapUsage {
+            used: 2 * GIB,
+            ..Default::default()
+        };
+        assert_eq!(
+            pressure_level(&mem, &swapping, None),
+            PressureLevel::Warning
+        );
+
+        // The kernel's level wins when it is worse.
+        assert_eq!(
+            pressure_level(&mem, &no_swap, Some(PressureLevel::Critical)),
+            PressureLevel::Critical
+        );
+    }
+
+    #[test]
+    fn test_top_consumers_cover_share() {
+        let ps = "\
+  101 4000000 /Applications/Google Chrome.app/Contents/MacOS/Google Chrome
+  202 3000000 /Applications/Xcode.app/Contents/MacOS/Xcode
+  303 2000000 /usr/libexec/thing
+  404 1000000 /usr/sbin/other";
+        let procs = parse_ps_rss(ps);
+        assert_eq!(procs[0].name, "Google Chrome");
+        assert_eq!(procs[0].pct_of_resident, 40.0);
+
+        // 40% + 30% crosses the 60% share, so only two are flagged.
+        let top = top_consumers(&procs);
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[1].pid, 202);
+    }
+
+    #[test]
+    fn test_verdict_names_largest_consumer_under_pressure() {
+        let mem = MemoryBreakdown {
+            total: 16 * GIB,
+            used: 15 * GIB,
+            available: GIB,
+            ..Default::default()
+        };
+        let top = parse_ps_rss("1 4000000 /usr/bin/hog");
+        let text = verdict(&mem, &SwapUsage::default(), PressureLevel::Critical, &top);
+        assert!(text.starts_with("Memory pressure is critical"));
+        assert!(text.contains("hog"));
+    }
+}
@@ -0,0 +1,539 @@
+//! macOS troubleshooting tools.
+//!
+//! Each tool wraps real shell commands via `std::process::Command` and parses
+//! the output into structured JSON.
+
+mod apps;
+mod battery;
+mod cleanup;
+mod cpu;
+mod disk;
+mod hardware;
+mod logs;
+mod memory;
+mod network;
+mod process;
+mod sampler;
+mod security;
+mod sockets;
+mod startup;
+mod thermal;
+
+use super::{RiskLevel, ToolDefinition, ToolModule, ToolResult};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::process::Command;
+
+/// A module providing 19 macOS diagnostic / troubleshooting tools.
+pub struct MacTroubleshootModule;
+
+impl MacTroubleshootModule {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+// ---------------------------------------------------------------------------
+// ToolModule implementation
+// ---------------------------------------------------------------------------
+
+impl ToolModule for MacTroubleshootModule {
+    fn name(&self) -> &str {
+        "mac_troubleshoot"
+    }
+
+    fn description(&self) -> &str {
+        "macOS system diagnostics, monitoring, and troubleshooting tools"
+    }
+
+    fn tools(&self) -> Vec<ToolDefinition> {
+        vec![
+            ToolDefinition {
+                name: "monitor_cpu".into(),
+                description: "Profile CPU usage over a few seconds: per-process average and peak CPU, energy impact and threads, separating sustained hogs from spikes".into(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "samples": {
+                            "type": "integer",
+                            "description": "Number of samples to take (default 3, max 30; each takes at least 1s, so fewer are taken over a short window)"
+                        },
+                        "duration_secs": {
+                            "type": "number",
+                            "description": "Total time to sample over in whole seconds (default 3, max 60)"
+                        },
+                        "threshold_pct": {
+                            "type": "number",
+                            "description": "CPU % (of one core) that counts as busy in a sample (default 50)"
+                        }
+                    },
+                    "required": []
+                }),
+                risk: RiskLevel::ReadOnly,
+            },
+            ToolDefinition {
+                name: "check_thermals".into(),
+                description: "Check if the Mac is hot or thermally throttled: CPU speed limit, thermal pressure, temperature, fan, load average and per-core usage".into(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "sample_secs": {
+                            "type": "number",
+                            "description": "Seconds to sample per-core usage over (default 1, max 10)"
+                        }
+                    },
+                    "required": []
+                }),
+                risk: RiskLevel::ReadOnly,
+            },
+            ToolDefinition {
+                name: "monitor_memory".into(),
+                description: "Analyze memory usage and pressure: used/wired/compressed/cached, swap, and top memory consumers".into(),
+                parameters: json!({"type": "object", "properties": {}, "required": []}),
+                risk: RiskLevel::ReadOnly,
+            },
+            ToolDefinition {
+                name: "monitor_disk".into(),
+                description: "Check disk usage for all mounted volumes and common user directories".into(),
+                parameters: json!({"type": "object", "properties": {}, "required": []}),
+                risk: RiskLevel::ReadOnly,
+            },
+            ToolDefinition {
+                name: "analyze_disk".into(),
+                description: "Find the largest folders and files and how much space can be reclaimed (caches, downloads, Xcode, node_modules, Docker, trash)".into(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "Directory to analyze (defaults to the home folder)"
+                        },
+                        "max_depth": {
+                            "type": "integer",
+                            "description": "How many folder levels to descend (default 8, at most 64)"
+                        },
+                        "time_budget_secs": {
+                            "type": "integer",
+                            "description": "Stop scanning after this many seconds (default 10, at most 120)"
+                        },
+                        "top_n": {
+                            "type": "integer",
+                            "description": "Number of largest folders and files to report (default 10, at most 100)"
+                        }
+                    },
+                    "required": []
+                }),
+                risk: RiskLevel::ReadOnly,
+            },
+            ToolDefinition {
+                name: "monitor_network".into(),
+                description: "Inventory network sockets: listening ports with the owning app, connections per process, and which apps talk to the internet".into(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "port": {
+                            "type": "integer",
+                            "description": "Only sockets using this local or remote port"
+                        },
+                        "process": {
+                            "type": "string",
+                            "description": "Only sockets owned by processes whose name contains this"
+                        },
+                        "state": {
+                            "type": "string",
+                            "description": "Only sockets in this state, e.g. listen or established"
+                        },
+                        "reverse_dns": {
+                            "type": "boolean",
+                            "description": "Resolve remote addresses to hostnames"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Maximum sockets to list (default 50)"
+                        }
+                    },
+                    "required": []
+                }),
+                risk: RiskLevel::ReadOnly,
+            },
+            ToolDefinition {
+                name: "diagnose_network".into(),
+                description: "Diagnose network layer by layer (link, DHCP/gateway, DNS, TCP, TLS, captive portal) and name the failing layer".into(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "dns_hosts": {
+                            "type": "array",
+                            "items": {"type": "string"},
+                            "description": "Hostnames to resolve (default apple.com, google.com)"
+                        },
+                        "endpoints": {
+                            "type": "array",
+                            "items": {"type": "string"},
+                            "description": "Endpoints to connect to: https://host[:port] for TCP + TLS, host:port for TCP only"
+                        },
+                        "captive_portal_url": {
+                            "type": "string",
+                            "description": "http:// URL whose body should contain captive_portal_expect"
+                        },
+                        "captive_portal_expect": {
+                            "type": "string",
+                            "description": "Expected body text when there is no portal (default Success)"
+                        },
+                        "layers": {
+                            "type": "array",
+                            "items": {"type": "string"},
+                            "description": "Subset of link, gateway, dns, tcp, tls, captive_portal to check"
+                        },
+                        "timeout_secs": {
+                            "type": "number",
+                            "description": "Per-probe timeout in seconds (default 5)"
+                        }
+                    },
+                    "required": []
+                }),
+                risk: RiskLevel::ReadOnly,
+            },
+            ToolDefinition {
+                name: "check_hardware".into(),
+                description: "List connected hardware: USB and Thunderbolt devices, displays, Bluetooth devices with battery levels, and drives with SMART status".into(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "categories": {
+                            "type": "array",
+                            "items": {"type": "string"},
+                            "description": "Only these: usb, thunderbolt, displays, bluetooth, storage (default all)"
+                        }
+                    },
+                    "required": []
+                }),
+                risk: RiskLevel::ReadOnly,
+            },
+            ToolDefinition {
+                name: "diagnose_battery".into(),
+                description: "Check battery health: cycle count, capacity vs. design, condition, temperature, charger, and apps preventing sleep".into(),
+                parameters: json!({"type": "object", "properties": {}, "required": []}),
+                risk: RiskLevel::ReadOnly,
+            },
+            ToolDefinition {
+                name: "kill_process".into(),
+                description: "Quit a process by name or pid (SIGTERM, then SIGKILL after a timeout). Returns candidates when the name is ambiguous".into(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "process_name": {
+                            "type": "string",
+                            "description": "Executable or app name of the process to kill"
+                        },
+                        "pid": {
+                            "type": "integer",
+                            "description": "Exact process id to kill"
+                        },
+                        "all": {
+                            "type": "boolean",
+                            "description": "Kill every matching process instead of asking when several match"
+                        },
+                        "timeout_secs": {
+                            "type": "number",
+                            "description": "Seconds to wait after SIGTERM before sending SIGKILL (default 5)"
+                        },
+                        "dry_run": {
+                            "type": "boolean",
+                            "description": "Only list the processes that would be killed, and which are protected"
+                        }
+                    },
+                    "required": []
+                }),
+                risk: RiskLevel::Destructive,
+            },
+            ToolDefinition {
+                name: "clear_caches".into(),
+                description: "Clear disk caches (moved to Trash, previewable with dry_run), purge memory, or both".into(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "target": {
+                            "type": "string",
+                            "description": "What to clear: memory, disk, or both"
+                        },
+                        "dry_run": {
+                            "type": "boolean",
+                            "description": "Only list the cache folders and sizes that would be cleared"
+                        },
+                        "apps": {
+                            "type": "array",
+                            "items": {"type": "string"},
+                            "description": "Only clear caches for these apps (e.g. chrome, spotify)"
+                        },
+                        "exclude": {
+                            "type": "array",
+                            "items": {"type": "string"},
+                            "description": "Cache folder patterns to keep (supports * wildcards)"
+                        }
+                    },
+                    "required": ["target"]
+                }),
+                risk: RiskLevel::Destructive,
+            },
+            ToolDefinition {
+                name: "list_apps".into(),
+                description: "List installed apps with version, bundle id, architecture (Intel/Apple silicon/universal) and signing, Homebrew packages and outdated ones, and Intel apps running under Rosetta".into(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "name": {
+                            "type": "string",
+                            "description": "Only apps whose name or bundle id contains this (e.g. to answer what version of X is installed)"
+                        },
+                        "include_homebrew": {
+                            "type": "boolean",
+                            "description": "Also list Homebrew formulae and casks (default true unless name is given)"
+                        },
+                        "check_outdated": {
+                            "type": "boolean",
+                            "description": "Ask Homebrew which packages are outdated (default true)"
+                        }
+                    },
+                    "required": []
+                }),
+                risk: RiskLevel::ReadOnly,
+            },
+            ToolDefinition {
+                name: "check_startup_items".into(),
+                description: "Audit login items, LaunchAgents and LaunchDaemons: what they run, whether the program exists and is signed, and which look orphaned or suspicious".into(),
+                parameters: json!({"type": "object", "properties": {}, "required": []}),
+                risk: RiskLevel::ReadOnly,
+            },
+            ToolDefinition {
+                name: "disable_startup_item".into(),
+                description: "Disable a LaunchAgent or LaunchDaemon by label so it no longer starts".into(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "label": {
+                            "type": "string",
+                            "description": "launchd Label of the item, as listed by check_startup_items"
+                        },
+                        "dry_run": {
+                            "type": "boolean",
+                            "description": "Only show the launchctl commands that would run"
+                        }
+                    },
+                    "required": ["label"]
+                }),
+                risk: RiskLevel::Destructive,
+            },
+            ToolDefinition {
+                name: "check_security".into(),
+                description: "Audit security posture (FileVault, SIP, Gatekeeper, updates, firewall, screen lock, sharing, XProtect, admins) with a score and fixes".into(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "check_updates": {
+                            "type": "boolean",
+                            "description": "Query Apple for pending software updates (slower; default true)"
+                        }
+                    },
+                    "required": []
+                }),
+                risk: RiskLevel::ReadOnly,
+            },
+            ToolDefinition {
+                name: "search_logs".into(),
+                description: "Search the macOS unified log by process, subsystem, text and level over a recent time window".into(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "last": {
+                            "type": "string",
+                            "description": "Time window to search, e.g. 30m, 2h (default 1h, at most 24h; info/debug over more than 1h need a filter)"
+                        },
+                        "process": {
+                            "type": "string",
+                            "description": "Only entries from this process name"
+                        },
+                        "subsystem": {
+                            "type": "string",
+                            "description": "Only entries from this subsystem (e.g. com.apple.WebKit)"
+                        },
+                        "contains": {
+                            "type": "string",
+                            "description": "Only entries whose message contains this text (case-insensitive)"
+                        },
+                        "level": {
+                            "type": "string",
+                            "description": "Only entries of this level: fault, error, default, info or debug"
+                        },
+                        "predicate": {
+                            "type": "string",
+                            "description": "Raw NSPredicate combined with the other filters"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Most recent entries to return (default 100, max 1000)"
+                        }
+                    },
+                    "required": []
+                }),
+                risk: RiskLevel::ReadOnly,
+            },
+            ToolDefinition {
+                name: "analyze_crashes".into(),
+                description: "Summarise recent crash and hang reports: exception type, crashing thread and how often each app crashes".into(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "app": {
+                            "type": "string",
+                            "description": "Only reports for this app name or bundle id"
+                        },
+                        "days": {
+                            "type": "integer",
+                            "description": "How many days back to look (default 7, at most 365)"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Most recent reports to include in detail (default 20)"
+                        }
+                    },
+                    "required": []
+                }),
+                risk: RiskLevel::ReadOnly,
+            },
+            ToolDefinition {
+                name: "run_full_checkup".into(),
+                description: "Run a comprehensive system health check (CPU + memory + disk + network + security)".into(),
+                parameters: json!({"type": "object", "properties": {}, "required": []}),
+                risk: RiskLevel::ReadOnly,
+            },
+            ToolDefinition {
+                name: "troubleshoot".into(),
+                description: "Cloud-assisted troubleshooting for complex problems".into(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "problem": {
+                            "type": "string",
+                            "description": "Description of the problem to troubleshoot"
+                        }
+                    },
+                    "required": ["problem"]
+                }),
+                risk: RiskLevel::ReadOnly,
+            },
+        ]
+    }
+
+    fn execute(&self, tool_name: &str, args: Value) -> ToolResult {
+        match tool_name {
+            "monitor_cpu" => cpu::monitor_cpu(&args),
+            "check_thermals" => thermal::check_thermals(&args),
+            "monitor_memory" => memory::monitor_memory(),
+            "monitor_disk" => disk::monitor_disk(),
+            "analyze_disk" => disk::analyze_disk(&args),
+            "monitor_network" => sockets::monitor_network(&args),
+            "diagnose_network" => network::diagnose_network(&args),
+            "check_hardware" => hardware::check_hardware(&args),
+            "diagnose_battery" => battery::diagnose_battery(),
+            "kill_process" => process::kill_process(&args),
+            "clear_caches" => cleanup::clear_caches(&args),
+            "list_apps" => apps::list_apps(&args),
+            "check_startup_items" => startup::check_startup_items(),
+            "disable_startup_item" => startup::disable_startup_item(&args),
+            "check_security" => security::check_security(&args),
+            "search_logs" => logs::search_logs(&args),
+            "analyze_crashes" => logs::analyze_crashes(&args),
+            "run_full_checkup" => run_full_checkup(),
+            "troubleshoot" => troubleshoot(&args),
+            _ => ToolResult {
+                success: false,
+                data: Value::Null,
+                error: Some(format!("Unknown tool: {}", tool_name)),
+            },
+        }
+    }
+
+    fn preview(&self, tool_name: &str, args: &Value) -> Option<Value> {
+        match tool_name {
+            "clear_caches" => {
+                let mut dry_args = args.clone();
+                dry_args.as_object_mut()?.insert("dry_run".into(), json!(true));
+                Some(cleanup::clear_caches(&dry_args).data)
+            }
+            "kill_process" => process::preview(args),
+            "disable_startup_item" => startup::preview_disable(args),
+            _ => None,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Run a shell command and return its stdout as a `String`.
+/// Returns an empty string on failure.
+fn run_cmd(program: &str, args: &[&str]) -> String {
+    Command::new(program)
+        .args(args)
+        .output()
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Run a command through `sh -c` for pipelines / shell features.
+fn run_shell(cmd: &str) -> String {
+    Command::new("sh")
+        .args(["-c", cmd])
+        .output()
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .unwrap_or_default()
+}
+
+/// The current user's home directory.
+fn home_dir() -> PathBuf {
+    std::env::var("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("/"))
+}
+
+// ---------------------------------------------------------------------------
+// Tool implementations
+// ---------------------------------------------------------------------------
+
+fn run_full_checkup() -> ToolResult {
+    let cpu = cpu::monitor_cpu(&json!({"samples": 2, "duration_secs": 2}));
+    let mem = memory::monitor_memory();
+    let disk = disk::monitor_disk();
+    let net = sockets::monitor_network(&json!({"limit": 20}));
+    let sec = security::check_security(&json!({"check_updates": false}));
+
+    ToolResult {
+        success: true,
+        data: json!({
+            "cpu": cpu.data,
+            "memory": mem.data,
+            "disk": disk.data,
+            "network": net.data,
+            "security": sec.data,
+        }),
+        error: None,
+    }
+}
+
+fn troubleshoot(args: &Value) -> ToolResult {
+    let problem = args
+        .get("problem")
+        .and_then(|v| v.as_str())
+        .unwrap_or("unspecified");
+
+    ToolResult {
+        success: true,
+        data: json!({
+            "requires_cloud": true,
+            "problem": problem,
+        }),
+        error: None,
+    }
+}
@@ -0,0 +1,812 @@
+//! Layered network diagnosis.
+//!
+//! `diagnose_network` walks the stack bottom-up — link, DHCP/gateway, DNS,
+//! TCP, TLS, captive portal — and reports each layer separately, so the
+//! verdict can name the first layer that is actually broken instead of
+//! inferring everything from a single ping. Every target is an argument, so
+//! the upper layers can be exercised against local listeners.
+
+use super::{run_cmd, ToolResult};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const DEFAULT_DNS_HOSTS: &[&str] = &["apple.com", "google.com"];
+const DEFAULT_ENDPOINTS: &[&str] = &["https://www.apple.com", "https://www.google.com"];
+const DEFAULT_CAPTIVE_URL: &str = "http://captive.apple.com/hotspot-detect.html";
+const DEFAULT_CAPTIVE_EXPECT: &str = "Success";
+const DEFAULT_TIMEOUT_SECS: f64 = 5.0;
+
+/// Lookups slower than this are reported as a warning.
+const SLOW_DNS_MS: f64 = 500.0;
+/// Connects or handshakes slower than this are reported as a warning.
+const SLOW_CONNECT_MS: f64 = 1000.0;
+
+/// Layers in the order they are checked and blamed.
+const LAYERS: &[&str] = &["link", "gateway", "dns", "tcp", "tls", "captive_portal"];
+
+// ----- Layer results -----
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum LayerStatus {
+    Ok,
+    Warn,
+    Fail,
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(super) struct Layer {
+    pub name: &'static str,
+    pub status: LayerStatus,
+    pub detail: String,
+    pub data: Value,
+}
+
+impl Layer {
+    fn new(
+        name: &'static str,
+        status: LayerStatus,
+        detail: impl Into<String>,
+        data: Value,
+    ) -> Self {
+        Self {
+            name,
+            status,
+            detail: detail.into(),
+            data,
+        }
+    }
+}
+
+fn ms(d: Duration) -> f64 {
+    (d.as_secs_f64() * 10_000.0).round() / 10.0
+}
+
+/// Worst status among a set of probes: any failure fails the layer and slow
+/// probes only warn.
+fn combine(statuses: &[LayerStatus]) -> LayerStatus {
+    if statuses.is_empty() {
+        LayerStatus::Skipped
+    } else if statuses.contains(&LayerStatus::Fail) {
+        LayerStatus::Fail
+    } else if statuses.contains(&LayerStatus::Warn) {
+        LayerStatus::Warn
+    } else {
+        LayerStatus::Ok
+    }
+}
+
+// ----- Parsers -----
+
+/// `key: value` lines from `route -n get default` / `ifconfig` style output.
+fn field<'a>(raw: &'a str, key: &str) -> Option<&'a str> {
+    raw.lines().find_map(|line| {
+        let (k, v) = line.trim().split_once(':')?;
+        (k.trim() == key).then(|| v.trim())
+    })
+}
+
+/// Default gateway and interface from `route -n get default`.
+pub(super) fn parse_default_route(raw: &str) -> (Option<String>, Option<String>) {
+    (
+        field(raw, "gateway").map(String::from),
+        field(raw, "interface").map(String::from),
+    )
+}
+
+/// `status: active` and the IPv4 address from `ifconfig <iface>`.
+pub(super) fn parse_ifconfig(raw: &str) -> (bool, Option<String>) {
+    let active = field(raw, "status").map(|s| s == "active").unwrap_or(false);
+    let inet = raw.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        (parts.next()? == "inet").then(|| parts.next().map(String::from))?
+    });
+    (active, inet)
+}
+
+/// Lease fields from `ipconfig getpacket <iface>`.
+pub(super) fn parse_dhcp_packet(raw: &str) -> Value {
+    let mut lease = serde_json::Map::new();
+    for line in raw.lines() {
+        let Some((key, value)) = line.split_once(" = ").or_else(|| line.split_once("): ")) else {
+            continue;
+        };
+        let key = key.split_whitespace().next().unwrap_or("");
+        let value = value.trim().trim_start_matches('{').trim_end_matches('}');
+        match key {
+            "yiaddr" => lease.insert("ip_address".into(), json!(value)),
+            "router" => lease.insert("router".into(), json!(value)),
+            "domain_name_server" => lease.insert(
+                "dns_servers".into(),
+                json!(value.split(',').map(|s| s.trim()).collect::<Vec<_>>()),
+            ),
+            "lease_time" => lease.insert(
+                "lease_secs".into(),
+                json!(u64::from_str_radix(value.trim_start_matches("0x"), 16).ok()),
+            ),
+            _ => None,
+        };
+    }
+    Value::Object(lease)
+}
+
+/// Packet loss percentage from a `ping` summary line.
+pub(super) fn parse_packet_loss(raw: &str) -> Option<f64> {
+    let line = raw.lines().find(|l| l.contains("packet loss"))?;
+    line.split(',')
+        .find(|part| part.contains("packet loss"))?
+        .split_whitespace()
+        .next()?
+        .trim_end_matches('%')
+        .parse()
+        .ok()
+}
+
+/// Nameservers from `scutil --dns` (`nameserver[0] : 1.1.1.1`) or
+/// `/etc/resolv.conf` (`nameserver 1.1.1.1`), deduplicated in order.
+pub(super) fn parse_nameservers(raw: &str) -> Vec<String> {
+    let mut servers: Vec<String> = Vec::new();
+    for line in raw.lines() {
+        let line = line.trim();
+        let server = if let Some(rest) = line.strip_prefix("nameserver[") {
+            rest.split_once(':').map(|(_, v)| v.trim())
+        } else {
+            line.strip_prefix("nameserver ").map(str::trim)
+        };
+        if let Some(s) = server.filter(|s| !s.is_empty()) {
+            if !servers.iter().any(|x| x == s) {
+                servers.push(s.to_string());
+            }
+        }
+    }
+    servers
+}
+
+/// `curl -w "%{time_connect} %{time_appconnect} %{http_code}"` → (tcp ms, tls ms).
+/// `time_appconnect` is 0 when the handshake never completed.
+pub(super) fn parse_curl_timing(raw: &str) -> Option<(f64, Option<f64>)> {
+    let mut parts = raw.split_whitespace();
+    let connect: f64 = parts.next()?.parse().ok()?;
+    let appconnect: f64 = parts.next()?.parse().ok()?;
+    let tls = (appconnect > 0.0).then(|| ((appconnect - connect) * 10_000.0).round() / 10.0);
+    Some(((connect * 10_000.0).round() / 10.0, tls))
+}
+
+// ----- Targets -----
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Endpoint {
+    pub host: String,
+    pub port: u16,
+    pub tls: bool,
+}
+
+impl Endpoint {
+    /// `https://host[:port]` probes TCP and TLS; `host:port` or `tcp://host:port`
+    /// probes TCP only.
+    pub(super) fn parse(spec: &str) -> Option<Self> {
+        let (tls, rest) = if let Some(rest) = spec.strip_prefix("https://") {
+            (true, rest)
+        } else {
+            (false, spec.strip_prefix("tcp://").unwrap_or(spec))
+        };
+        let authority = rest.split('/').next()?;
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((h, p)) => (h, p.parse().ok()?),
+            None if tls => (authority, 443),
+            None => return None,
+        };
+        if host.is_empty() {
+            return None;
+        }
+        Some(Self {
+            host: host.trim_matches(|c| c == '[' || c == ']').to_string(),
+            port,
+            tls,
+        })
+    }
+
+    fn label(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+}
+
+/// Tunables for a diagnosis run, read from the tool arguments.
+#[derive(Debug, Clone)]
+pub(super) struct NetworkTargets {
+    pub layers: Vec<String>,
+    pub dns_hosts: Vec<String>,
+    pub endpoints: Vec<Endpoint>,
+    pub captive_portal_url: String,
+    pub captive_portal_expect: String,
+    pub timeout: Duration,
+}
+
+fn string_list(args: &Value, key: &str, default: &[&str]) -> Vec<String> {
+    match args.get(key).and_then(|v| v.as_array()) {
+        Some(items) => items
+            .iter()
+            .filter_map(|v| v.as_str().map(String::from))
+            .collect(),
+        None => default.iter().map(|s| s.to_string()).collect(),
+    }
+}
+
+impl NetworkTargets {
+    pub(super) fn from_args(args: &Value) -> Self {
+        Self {
+            layers: string_list(args, "layers", LAYERS),
+            dns_hosts: string_list(args, "dns_hosts", DEFAULT_DNS_HOSTS),
+            endpoints: string_list(args, "endpoints", DEFAULT_ENDPOINTS)
+                .iter()
+                .filter_map(|s| Endpoint::parse(s))
+                .collect(),
+            captive_portal_url: args
+                .get("captive_portal_url")
+                .and_then(|v| v.as_str())
+                .unwrap_or(DEFAULT_CAPTIVE_URL)
+                .to_string(),
+            captive_portal_expect: args
+                .get("captive_portal_expect")
+                .and_then(|v| v.as_str())
+                .unwrap_or(DEFAULT_CAPTIVE_EXPECT)
+                .to_string(),
+            timeout: Duration::from_secs_f64(
+                args.get("timeout_secs")
+                    .and_then(|v| v.as_f64())
+                    .unwrap_or(DEFAULT_TIMEOUT_SECS)
+                    .clamp(0.5, 30.0),
+            ),
+        }
+    }
+
+    fn wants(&self, layer: &str) -> bool {
+        self.layers.iter().any(|l| l == layer)
+    }
+}
+
+// ----- Probes -----
+
+/// Interface and default route. Returns the layer plus (gateway, interface).
+fn check_link() -> (Layer, Option<String>, Option<String>) {
+    let (gateway, iface) = parse_default_route(&run_cmd("route", &["-n", "get", "default"]));
+    let Some(iface_name) = iface.clone() else {
+        let layer = Layer::new(
+            "link",
+            LayerStatus::Fail,
+            "No default route — not connected to any network",
+            json!({}),
+        );
+        return (layer, None, None);
+    };
+    let (active, ip) = parse_ifconfig(&run_cmd("ifconfig", &[&iface_name]));
+    let ssid = run_cmd("networksetup", &["-getairportnetwork", &iface_name])
+        .strip_prefix("Current Wi-Fi Network: ")
+        .map(String::from);
+    let data =
+        json!({"interface": iface_name, "active": active, "ip_address": ip, "wifi_network": ssid});
+    let layer = match (active, &ip) {
+        (true, Some(ip)) => Layer::new(
+            "link",
+            LayerStatus::Ok,
+            format!("{} is up with {}", iface_name, ip),
+            data,
+        ),
+        (true, None) => Layer::new(
+            "link",
+            LayerStatus::Fail,
+            format!("{} is up but has no IPv4 address", iface_name),
+            data,
+        ),
+        (false, _) => Layer::new(
+            "link",
+            LayerStatus::Fail,
+            format!("{} is not active", iface_name),
+            data,
+        ),
+    };
+    (layer, gateway, iface)
+}
+
+fn check_gateway(gateway: Option<&str>, iface: Option<&str>) -> Layer {
+    let lease = iface
+        .map(|i| parse_dhcp_packet(&run_cmd("ipconfig", &["getpacket", i])))
+        .unwrap_or_else(|| json!({}));
+    let Some(gateway) = gateway else {
+        return Layer::new(
+            "gateway",
+            LayerStatus::Fail,
+            "No gateway (DHCP lease missing?)",
+            json!({"dhcp": lease}),
+        );
+    };
+    let ping = run_cmd("ping", &["-c", "3", "-t", "5", gateway]);
+    let loss = parse_packet_loss(&ping);
+    let data = json!({"gateway": gateway, "dhcp": lease, "packet_loss_percent": loss});
+    match loss {
+        Some(l) if l <= 0.0 => Layer::new(
+            "gateway",
+            LayerStatus::Ok,
+            format!("Gateway {} reachable", gateway),
+            data,
+        ),
+        Some(l) if l < 100.0 => Layer::new(
+            "gateway",
+            LayerStatus::Warn,
+            format!("{}% packet loss to gateway {}", l, gateway),
+            data,
+        ),
+        _ => Layer::new(
+            "gateway",
+            LayerStatus::Fail,
+            format!("Gateway {} unreachable", gateway),
+            data,
+        ),
+    }
+}
+
+/// Resolve `host` on a helper thread so a hung resolver can't outlast `timeout`.
+fn resolve_host(host: &str, timeout: Duration) -> (Result<Vec<SocketAddr>, String>, Duration) {
+    let (tx, rx) = mpsc::channel();
+    let owned = host.to_string();
+    let start = Instant::now();
+    thread::spawn(move || {
+        let result = (owned.as_str(), 0)
+            .to_socket_addrs()
+            .map(|a| a.collect::<Vec<_>>());
+        let _ = tx.send(result.map_err(|e| e.to_string()));
+    });
+    let result = rx
+        .recv_timeout(timeout)
+        .unwrap_or_else(|_| Err("timed out".into()));
+    (result, start.elapsed())
+}
+
+fn check_dns(targets: &NetworkTargets) -> Layer {
+    let mut resolver = parse_nameservers(&run_cmd("scutil", &["--dns"]));
+    if resolver.is_empty() {
+        resolver =
+            parse_nameservers(&std::fs::read_to_string("/etc/resolv.conf").unwrap_or_default());
+    }
+
+    let mut statuses = Vec::new();
+    let lookups: Vec<Value> = targets
+        .dns_hosts
+        .iter()
+        .map(|host| {
+            let (result, elapsed) = resolve_host(host, targets.timeout);
+            let status = match &result {
+                Ok(addrs) if addrs.is_empty() => LayerStatus::Fail,
+                Ok(_) if ms(elapsed) > SLOW_DNS_MS => LayerStatus::Warn,
+                Ok(_) => LayerStatus::Ok,
+                Err(_) => LayerStatus::Fail,
+            };
+            statuses.push(status);
+            json!({
+                "host": host,
+                "status": status,
+                "time_ms": ms(elapsed),
+                "addresses": result.as_ref().map(|a| a.iter().map(|s| s.ip().to_string()).collect::<Vec<_>>()).unwrap_or_default(),
+                "error": result.err(),
+            })
+        })
+        .collect();
+
+    let status = combine(&statuses);
+    let detail = match status {
+        LayerStatus::Ok => format!("{} host(s) resolved", lookups.len()),
+        LayerStatus::Warn => "DNS is slow".into(),
+        LayerStatus::Fail if resolver.is_empty() => {
+            "DNS lookups failed and no resolver is configured".into()
+        }
+        LayerStatus::Fail => "DNS lookups failed".into(),
+        LayerStatus::Skipped => "No hosts to resolve".into(),
+    };
+    Layer::new(
+        "dns",
+        status,
+        detail,
+        json!({"resolvers": resolver, "lookups": lookups}),
+    )
+}
+
+fn tcp_connect(endpoint: &Endpoint, timeout: Duration) -> Result<Duration, String> {
+    let addr = (endpoint.host.as_str(), endpoint.port)
+        .to_socket_addrs()
+        .map_err(|e| e.to_string())?
+        .next()
+        .ok_or_else(|| "no address".to_string())?;
+    let start = Instant::now();
+    TcpStream::connect_timeout(&addr, timeout).map_err(|e| e.to_string())?;
+    Ok(start.elapsed())
+}
+
+fn check_tcp(targets: &NetworkTargets) -> Layer {
+    let mut statuses = Vec::new();
+    let probes: Vec<Value> = targets
+        .endpoints
+        .iter()
+        .map(|ep| {
+            let result = tcp_connect(ep, targets.timeout);
+            let status = match &result {
+                Ok(t) if ms(*t) > SLOW_CONNECT_MS => LayerStatus::Warn,
+                Ok(_) => LayerStatus::Ok,
+                Err(_) => LayerStatus::Fail,
+            };
+            statuses.push(status);
+            json!({
+                "endpoint": ep.label(),
+                "status": status,
+                "connect_ms": result.as_ref().ok().map(|t| ms(*t)),
+                "error": result.err(),
+            })
+        })
+        .collect();
+    let status = combine(&statuses);
+    let failed = statuses.iter().filter(|s| **s == LayerStatus::Fail).count();
+    let detail = match status {
+        LayerStatus::Fail => format!(
+            "{} of {} endpoint(s) refused or timed out",
+            failed,
+            probes.len()
+        ),
+        LayerStatus::Warn => "TCP connects are slow".into(),
+        LayerStatus::Ok => format!("Connected to {} endpoint(s)", probes.len()),
+        LayerStatus::Skipped => "No endpoints to connect to".into(),
+    };
+    Layer::new("tcp", status, detail, json!({"endpoints": probes}))
+}
+
+fn check_tls(targets: &NetworkTargets) -> Layer {
+    let timeout = format!("{}", targets.timeout.as_secs_f64());
+    let mut statuses = Vec::new();
+    let probes: Vec<Value> = targets
+        .endpoints
+        .iter()
+        .filter(|ep| ep.tls)
+        .map(|ep| {
+            let url = format!("https://{}/", ep.label());
+            let raw = run_cmd(
+                "curl",
+                &[
+                    "-s",
+                    "-o",
+                    "/dev/null",
+                    "-m",
+                    &timeout,
+                    "-w",
+                    "%{time_connect} %{time_appconnect} %{http_code}",
+                    &url,
+                ],
+            );
+            let handshake_ms = parse_curl_timing(&raw).and_then(|(_, tls)| tls);
+            let status = match handshake_ms {
+                Some(t) if t > SLOW_CONNECT_MS => LayerStatus::Warn,
+                Some(_) => LayerStatus::Ok,
+                None => LayerStatus::Fail,
+            };
+            statuses.push(status);
+            json!({"endpoint": ep.label(), "status": status, "handshake_ms": handshake_ms})
+        })
+        .collect();
+    let status = combine(&statuses);
+    let detail = match status {
+        LayerStatus::Fail => "TLS handshake failed (proxy, clock skew or interception?)".into(),
+        LayerStatus::Warn => "TLS handshakes are slow".into(),
+        LayerStatus::Ok => format!("TLS handshake OK with {} endpoint(s)", probes.len()),
+        LayerStatus::Skipped => "No https:// endpoints".into(),
+    };
+    Layer::new("tls", status, detail, json!({"endpoints": probes}))
+}
+
+/// Minimal HTTP/1.0 GET returning (status code, body). Redirects are not followed
+/// on purpose: a portal answering with a 30x is exactly what we want to see.
+fn http_get(url: &str, timeout: Duration) -> Result<(u16, String), String> {
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or("captive portal URL must be http://")?;
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((h, p)) => (h, p.parse::<u16>().map_err(|e| e.to_string())?),
+        None => (authority, 80),
+    };
+    let addr = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| e.to_string())?
+        .next()
+        .ok_or("no address")?;
+    let mut stream = TcpStream::connect_timeout(&addr, timeout).map_err(|e| e.to_string())?;
+    stream
+        .set_read_timeout(Some(timeout))
+        .map_err(|e| e.to_string())?;
+    let request = format!(
+        "GET {} HTTP/1.0\r\nHost: {}\r\nUser-Agent: CaptiveNetworkSupport\r\n\r\n",
+        path, host
+    );
+    stream
+        .write_all(request.as_bytes())
+        .map_err(|e| e.to_string())?;
+    let mut response = String::new();
+    stream
+        .read_to_string(&mut response)
+        .map_err(|e| e.to_string())?;
+    let code = response
+        .lines()
+        .next()
+        .and_then(|l| l.split_whitespace().nth(1))
+        .and_then(|c| c.parse().ok())
+        .ok_or("malformed HTTP response")?;
+    let body = response
+        .split_once("\r\n\r\n")
+        .map(|(_, b)| b.to_string())
+        .unwrap_or_default();
+    Ok((code, body))
+}
+
+fn check_captive_portal(targets: &NetworkTargets) -> Layer {
+    let url = &targets.captive_portal_url;
+    match http_get(url, targets.timeout) {
+        Ok((200, body)) if body.contains(&targets.captive_portal_expect) => Layer::new(
+            "captive_portal",
+            LayerStatus::Ok,
+            "No captive portal",
+            json!({"url": url, "status_code": 200}),
+        ),
+        Ok((code, _)) => Layer::new(
+            "captive_portal",
+            LayerStatus::Fail,
+            "Captive portal detected — sign in to the network in a browser",
+            json!({"url": url, "status_code": code}),
+        ),
+        Err(e) => Layer::new(
+            "captive_portal",
+            LayerStatus::Fail,
+            format!("Captive portal probe failed: {}", e),
+            json!({"url": url, "error": e}),
+        ),
+    }
+}
+
+// ----- Verdict + tool -----
+
+/// The first failing layer is the one to fix; warnings only matter when
+/// nothing failed outright.
+pub(super) fn verdict(layers: &[Layer]) -> (Option<&'static str>, String) {
+    if let Some(failed) = layers.iter().find(|l| l.status == LayerStatus::Fail) {
+        return (
+            Some(failed.name),
+            format!("Problem at the {} layer: {}", failed.name, failed.detail),
+        );
+    }
+    if let Some(warn) = layers.iter().find(|l| l.status == LayerStatus::Warn) {
+        return (
+            Some(warn.name),
+            format!(
+                "Working, but degraded at the {} layer: {}",
+                warn.name, warn.detail
+            ),
+        );
+    }
+    (None, "Network is healthy at every checked layer".into())
+}
+
+pub(super) fn diagnose(targets: &NetworkTargets) -> Vec<Layer> {
+    let mut layers = Vec::new();
+    let (mut gateway, mut iface) = (None, None);
+
+    if targets.wants("link") {
+        let (layer, gw, ifc) = check_link();
+        layers.push(layer);
+        gateway = gw;
+        iface = ifc;
+    }
+    if targets.wants("gateway") {
+        if gateway.is_none() && iface.is_none() && !targets.wants("link") {
+            let (_, gw, ifc) = check_link();
+            gateway = gw;
+            iface = ifc;
+        }
+        layers.push(check_gateway(gateway.as_deref(), iface.as_deref()));
+    }
+    if targets.wants("dns") {
+        layers.push(check_dns(targets));
+    }
+    if targets.wants("tcp") {
+        layers.push(check_tcp(targets));
+    }
+    if targets.wants("tls") {
+        layers.push(check_tls(targets));
+    }
+    if targets.wants("captive_portal") {
+        layers.push(check_captive_portal(targets));
+    }
+    layers
+}
+
+pub(super) fn diagnose_network(args: &Value) -> ToolResult {
+    let targets = NetworkTargets::from_args(args);
+    let layers = diagnose(&targets);
+    let (failing_layer, verdict) = verdict(&layers);
+
+    ToolResult {
+        success: true,
+        data: json!({
+            "healthy": failing_layer.is_none(),
+            "failing_layer": failing_layer,
+            "verdict": verdict,
+            "layers": layers,
+        }),
+        error: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_parse_default_route_and_ifconfig() {
+        let route = "   route to: default\ndestination: default\n    gateway: 192.168.1.1\n  interface: en0\n      flags: <UP,GATEWAY,DONE,STATIC,PRCLONING>\n";
+        assert_eq!(
+            parse_default_route(route),
+            (Some("192.168.1.1".into()), Some("en0".into()))
+        );
+        let ifconfig = "en0: flags=8863<UP,BROADCAST,SMART,RUNNING,SIMPLEX,MULTICAST> mtu 1500\n\tinet6 fe80::1%en0 prefixlen 64\n\tinet 192.168.1.23 netmask 0xffffff00 broadcast 192.168.1.255\n\tstatus: active\n";
+        assert_eq!(
+            parse_ifconfig(ifconfig),
+            (true, Some("192.168.1.23".into()))
+        );
+        assert_eq!(
+            parse_ifconfig("en0: flags=8822\n\tstatus: inactive\n"),
+            (false, None)
+        );
+    }
+
+    #[test]
+    fn test_parse_dhcp_packet() {
+        let raw = "op = BOOTREPLY\nyiaddr = 192.168.1.23\nOptions count is 8\nlease_time (uint32): 0x15180\nrouter (ip_mult): {192.168.1.1}\ndomain_name_server (ip_mult): {192.168.1.1, 1.1.1.1}\n";
+        let lease = parse_dhcp_packet(raw);
+        assert_eq!(lease["ip_address"], "192.168.1.23");
+        assert_eq!(lease["router"], "192.168.1.1");
+        assert_eq!(lease["lease_secs"], 86400);
+        assert_eq!(lease["dns_servers"], json!(["192.168.1.1", "1.1.1.1"]));
+    }
+
+    #[test]
+    fn test_parse_packet_loss() {
+        assert_eq!(
+            parse_packet_loss("3 packets transmitted, 3 packets received, 0.0% packet loss"),
+            Some(0.0)
+        );
+        assert_eq!(
+            parse_packet_loss("3 packets transmitted, 1 received, 66% packet loss, time 2003ms"),
+            Some(66.0)
+        );
+        assert_eq!(parse_packet_loss(""), None);
+    }
+
+    #[test]
+    fn test_parse_nameservers() {
+        let scutil = "resolver #1\n  nameserver[0] : 192.168.1.1\n  nameserver[1] : 1.1.1.1\nresolver #2\n  nameserver[0] : 192.168.1.1\n";
+        assert_eq!(parse_nameservers(scutil), vec!["192.168.1.1", "1.1.1.1"]);
+        assert_eq!(
+            parse_nameservers("# generated\nnameserver 8.8.8.8\n"),
+            vec!["8.8.8.8"]
+        );
+    }
+
+    #[test]
+    fn test_parse_curl_timing() {
+        assert_eq!(
+            parse_curl_timing("0.020 0.085 200"),
+            Some((20.0, Some(65.0)))
+        );
+        assert_eq!(parse_curl_timing("0.020 0.000 000"), Some((20.0, None)));
+        assert_eq!(parse_curl_timing(""), None);
+    }
+
+    #[test]
+    fn test_endpoint_parse() {
+        assert_eq!(
+            Endpoint::parse("https://apple.com"),
+            Some(Endpoint {
+                host: "apple.com".into(),
+                port: 443,
+                tls: true
+            })
+        );
+        assert_eq!(
+            Endpoint::parse("127.0.0.1:5432"),
+            Some(Endpoint {
+                host: "127.0.0.1".into(),
+                port: 5432,
+                tls: false
+            })
+        );
+        assert_eq!(Endpoint::parse("tcp://localhost"), None);
+    }
+
+    #[test]
+    fn test_verdict_names_first_failing_layer() {
+        let layers = vec![
+            Layer::new("link", LayerStatus::Ok, "up", json!({})),
+            Layer::new("dns", LayerStatus::Warn, "slow", json!({})),
+            Layer::new("tcp", LayerStatus::Fail, "refused", json!({})),
+            Layer::new("tls", LayerStatus::Fail, "failed", json!({})),
+        ];
+        assert_eq!(verdict(&layers).0, Some("tcp"));
+        assert_eq!(verdict(&layers[..2]).0, Some("dns"));
+        assert_eq!(verdict(&layers[..1]).0, None);
+    }
+
+    /// Serve a single canned HTTP response on a local port.
+    fn serve_once(response: &'static str) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            if let Ok((mut stream, _)) = listener.accept() {
+                // Read the whole request before answering
+                let mut request = Vec::new();
+                let mut buf = [0u8; 256];
+                while !request.ends_with(b"\r\n\r\n") {
+                    match stream.read(&mut buf) {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => request.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+        port
+    }
+
+    fn localhost_args(tcp_port: u16, captive_port: u16) -> Value {
+        json!({
+            "layers": ["dns", "tcp", "captive_portal"],
+            "dns_hosts": ["localhost"],
+            "endpoints": [format!("127.0.0.1:{}", tcp_port)],
+            "captive_portal_url": format!("http://127.0.0.1:{}/hotspot-detect.html", captive_port),
+            "timeout_secs": 2,
+        })
+    }
+
+    #[test]
+    fn test_diagnose_against_local_listeners() {
+        let tcp = TcpListener::bind("127.0.0.1:0").unwrap();
+        let tcp_port = tcp.local_addr().unwrap().port();
+        let captive = serve_once(
+            "HTTP/1.0 200 OK\r\nContent-Type: text/html\r\n\r\n<HTML><BODY>Success</BODY></HTML>",
+        );
+
+        let result = diagnose_network(&localhost_args(tcp_port, captive));
+        assert_eq!(result.data["healthy"], true, "{}", result.data);
+        assert_eq!(result.data["layers"][1]["status"], "ok");
+    }
+
+    #[test]
+    fn test_diagnose_detects_refused_tcp_and_captive_portal() {
+        // Bind then drop to get a port nothing listens on
+        let closed = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let captive =
+            serve_once("HTTP/1.0 302 Found\r\nLocation: http://login.hotel.example/\r\n\r\n");
+
+        let result = diagnose_network(&localhost_args(closed, captive));
+        assert_eq!(result.data["failing_layer"], "tcp");
+        assert_eq!(result.data["layers"][2]["status"], "fail");
+        assert_eq!(result.data["layers"][2]["data"]["status_code"], 302);
+    }
+}
@@ -0,0 +1,634 @@
+//! Process targeting and termination for `kill_process`.
+//!
+//! Targets are resolved against a `ps` snapshot before anything is signalled:
+//! a pid is matched exactly, a name is matched against the executable name
+//! (never the full command line), and anything owned by root or another user
+//! is refused. When several processes match, the candidates are handed back
+//! so the user can pick one. Termination sends SIGTERM, waits, and only then
+//! escalates to SIGKILL.
+
+use super::{run_cmd, ToolResult};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Grace period between SIGTERM and SIGKILL.
+const DEFAULT_TIMEOUT_SECS: f64 = 5.0;
+const MAX_TIMEOUT_SECS: f64 = 30.0;
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Processes that must never be signalled, even when owned by the user.
+const CRITICAL_PROCESSES: &[&str] = &[
+    "kernel_task",
+    "launchd",
+    "WindowServer",
+    "loginwindow",
+    "Finder",
+    "Dock",
+    "SystemUIServer",
+];
+
+// ----- Snapshot -----
+
+#[derive(Debug, Clone, Serialize)]
+pub(super) struct ProcessInfo {
+    pub pid: u32,
+    pub name: String,
+    pub user: String,
+    pub uid: u32,
+    pub cpu_percent: f64,
+    pub mem_percent: f64,
+    pub rss_kb: u64,
+    pub command: String,
+}
+
+/// Parse `ps -axo pid=,comm=` into pid → executable name (basename of the path).
+fn parse_ps_names(raw: &str) -> HashMap<u32, String> {
+    raw.lines()
+        .filter_map(|line| {
+            let line = line.trim_start();
+            let (pid, comm) = line.split_once(char::is_whitespace)?;
+            let comm = comm.trim();
+            let name = comm.rsplit('/').next().unwrap_or(comm);
+            Some((pid.parse().ok()?, name.to_string()))
+        })
+        .collect()
+}
+
+/// Parse `ps -axww -o pid=,uid=,user=,%cpu=,%mem=,rss=,args=`.
+///
+/// The executable name is taken from `names` when available, because `args`
+/// may contain spaces anywhere (e.g. `/Applications/Google Chrome.app/...`).
+fn parse_ps_stats(raw: &str, names: &HashMap<u32, String>) -> Vec<ProcessInfo> {
+    raw.lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let pid: u32 = fields.next()?.parse().ok()?;
+            let uid: u32 = fields.next()?.parse().ok()?;
+            let user = fields.next()?.to_string();
+            let cpu_percent = fields.next()?.parse().ok()?;
+            let mem_percent = fields.next()?.parse().ok()?;
+            let rss_kb = fields.next()?.parse().ok()?;
+            let command = fields.collect::<Vec<_>>().join(" ");
+            let name = names.get(&pid).cloned().unwrap_or_else(|| {
+                let exe = command.split_whitespace().next().unwrap_or("");
+                exe.rsplit('/').next().unwrap_or(exe).to_string()
+            });
+            Some(ProcessInfo {
+                pid,
+                name,
+                user,
+                uid,
+                cpu_percent,
+                mem_percent,
+                rss_kb,
+                command,
+            })
+        })
+        .collect()
+}
+
+pub(super) fn snapshot() -> Vec<ProcessInfo> {
+    let names = parse_ps_names(&run_cmd("ps", &["-axo", "pid=,comm="]));
+    parse_ps_stats(
+        &run_cmd(
+            "ps",
+            &["-axww", "-o", "pid=,uid=,user=,%cpu=,%mem=,rss=,args="],
+        ),
+        &names,
+    )
+}
+
+fn current_uid() -> Option<u32> {
+    run_cmd("id", &["-u"]).parse().ok()
+}
+
+// ----- Resolution -----
+
+/// What the user asked to kill.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Target {
+    Pid(u32),
+    Name(String),
+}
+
+impl Target {
+    /// Read `pid` or `process_name` from the tool arguments. A numeric
+    /// `process_name` is treated as a pid. Pids outside 1..=u32::MAX are
+    /// rejected rather than wrapped onto some other process.
+    fn from_args(args: &Value) -> Result<Self, String> {
+        let invalid_pid = |pid: &dyn std::fmt::Display| {
+            format!("Invalid pid {}: must be between 1 and {}", pid, u32::MAX)
+        };
+        if let Some(pid) = args.get("pid").filter(|v| !v.is_null()) {
+            return pid
+                .as_u64()
+                .and_then(|p| u32::try_from(p).ok())
+                .filter(|&p| p > 0)
+                .map(Target::Pid)
+                .ok_or_else(|| invalid_pid(pid));
+        }
+        let name = args
+            .get("process_name")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .ok_or("Missing required parameter: process_name or pid")?;
+        match name.parse::<u32>() {
+            Ok(0) => Err(invalid_pid(&0)),
+            Ok(pid) => Ok(Target::Pid(pid)),
+            Err(_) => Ok(Target::Name(name.to_string())),
+        }
+    }
+
+    fn label(&self) -> String {
+        match self {
+            Target::Pid(pid) => format!("pid {}", pid),
+            Target::Name(name) => format!("'{}'", name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(super) struct Candidate {
+    #[serde(flatten)]
+    pub process: ProcessInfo,
+    /// Why this process may not be killed, if it is protected.
+    pub refused: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(super) struct Resolution {
+    /// How the candidates matched: pid, exact_name, app_bundle, partial_name or none.
+    pub match_kind: &'static str,
+    pub candidates: Vec<Candidate>,
+}
+
+impl Resolution {
+    fn killable(&self) -> Vec<&ProcessInfo> {
+        self.candidates
+            .iter()
+            .filter(|c| c.refused.is_none())
+            .map(|c| &c.process)
+            .collect()
+    }
+}
+
+/// Name of the `.app` bundle a process was launched from, if any.
+pub(super) fn app_bundle(command: &str) -> Option<&str> {
+    let end = command.find(".app/")?;
+    let start = command[..end].rfind('/').map(|i| i + 1).unwrap_or(0);
+    Some(&command[start..end])
+}
+
+fn refusal(process: &ProcessInfo, own_uid: Option<u32>, own_pid: u32) -> Option<String> {
+    if process.pid == own_pid {
+        return Some("this is Sentinel itself".into());
+    }
+    if CRITICAL_PROCESSES.contains(&process.name.as_str()) {
+        return Some("system-critical process".into());
+    }
+    if process.uid == 0 {
+        return Some("owned by root".into());
+    }
+    match own_uid {
+        Some(uid) if uid == process.uid => None,
+        Some(_) => Some(format!("owned by another user ({})", process.user)),
+        None => Some("could not determine the current user".into()),
+    }
+}
+
+fn matches(match_kind: &str, process: &ProcessInfo, target: &Target) -> bool {
+    match (match_kind, target) {
+        ("pid", Target::Pid(pid)) => process.pid == *pid,
+        ("exact_name", Target::Name(name)) => process.name.eq_ignore_ascii_case(name),
+        ("app_bundle", Target::Name(name)) => {
+            app_bundle(&process.command).is_some_and(|b| b.eq_ignore_ascii_case(name))
+        }
+        ("partial_name", Target::Name(name)) => {
+            process.name.to_lowercase().contains(&name.to_lowercase())
+        }
+        _ => false,
+    }
+}
+
+/// Match `target` against a process snapshot, most precise match first:
+/// pid, exact executable name, `.app` bundle name, then partial executable name.
+pub(super) fn resolve(
+    processes: &[ProcessInfo],
+    target: &Target,
+    own_uid: Option<u32>,
+    own_pid: u32,
+) -> Resolution {
+    for match_kind in ["pid", "exact_name", "app_bundle", "partial_name"] {
+        let mut candidates: Vec<Candidate> = processes
+            .iter()
+            .filter(|p| matches(match_kind, p, target))
+            .map(|p| Candidate {
+                process: p.clone(),
+                refused: refusal(p, own_uid, own_pid),
+            })
+            .collect();
+        if !candidates.is_empty() {
+            candidates.sort_by(|a, b| b.process.cpu_percent.total_cmp(&a.process.cpu_percent));
+            return Resolution {
+                match_kind,
+                candidates,
+            };
+        }
+    }
+
+    Resolution {
+        match_kind: "none",
+        candidates: Vec::new(),
+    }
+}
+
+// ----- Termination -----
+
+#[derive(Debug, Clone, Serialize)]
+pub(super) struct KillOutcome {
+    pub pid: u32,
+    pub name: String,
+    /// The last signal sent: SIGTERM, or SIGKILL after the grace period.
+    pub signal: &'static str,
+    pub exited: bool,
+    pub error: Option<String>,
+}
+
+fn send_signal(pid: u32, signal: &str) -> Result<(), String> {
+    let out = Command::new("kill")
+        .args([signal, &pid.to_string()])
+        .output()
+        .map_err(|e| format!("Failed to run kill: {}", e))?;
+    if out.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&out.stderr).trim().to_string())
+    }
+}
+
+fn is_alive(pid: u32) -> bool {
+    send_signal(pid, "-0").is_ok()
+}
+
+fn wait_for_exit(pids: &[u32], timeout: Duration) {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline && pids.iter().any(|&p| is_alive(p)) {
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// SIGTERM every target, wait up to `timeout`, then SIGKILL the survivors.
+pub(super) fn terminate(targets: &[(u32, String)], timeout: Duration) -> Vec<KillOutcome> {
+    let mut outcomes: Vec<KillOutcome> = targets
+        .iter()
+        .map(|(pid, name)| {
+            let error = send_signal(*pid, "-TERM").err();
+            KillOutcome {
+                pid: *pid,
+                name: name.clone(),
+                signal: "SIGTERM",
+                exited: false,
+                error,
+            }
+        })
+        .collect();
+
+    let signalled: Vec<u32> = outcomes
+        .iter()
+        .filter(|o| o.error.is_none())
+        .map(|o| o.pid)
+        .collect();
+    wait_for_exit(&signalled, timeout);
+
+    let mut escalated = Vec::new();
+    for outcome in outcomes.iter_mut().filter(|o| o.error.is_none()) {
+        if is_alive(outcome.pid) {
+            outcome.signal = "SIGKILL";
+            match send_signal(outcome.pid, "-KILL") {
+                Ok(()) => escalated.push(outcome.pid),
+                Err(e) => outcome.error = Some(e),
+            }
+        }
+    }
+    wait_for_exit(&escalated, Duration::from_secs(1));
+
+    for outcome in &mut outcomes {
+        outcome.exited = !is_alive(outcome.pid);
+        if outcome.exited {
+            // A process that vanished between the snapshot and the signal is fine
+            outcome.error = None;
+        }
+    }
+    outcomes
+}
+
+// ----- Tool -----
+
+fn timeout_from_args(args: &Value) -> Duration {
+    let secs = args
+        .get("timeout_secs")
+        .and_then(|v| v.as_f64())
+        .unwrap_or(DEFAULT_TIMEOUT_SECS)
+        .clamp(0.0, MAX_TIMEOUT_SECS);
+    Duration::from_secs_f64(secs)
+}
+
+fn resolve_args(args: &Value) -> Result<(Target, Resolution), String> {
+    let target = Target::from_args(args)?;
+    let resolution = resolve(&snapshot(), &target, current_uid(), std::process::id());
+    Ok((target, resolution))
+}
+
+fn preview_data(target: &Target, resolution: &Resolution, all: bool) -> Value {
+    let killable = resolution.killable();
+    json!({
+        "target": target.label(),
+        "match_kind": resolution.match_kind,
+        "ambiguous": killable.len() > 1 && !all,
+        "would_kill": killable.iter().map(|p| p.pid).collect::<Vec<_>>(),
+        "candidates": resolution.candidates,
+    })
+}
+
+/// Candidates the call would affect, for the confirmation prompt.
+pub(super) fn preview(args: &Value) -> Option<Value> {
+    let (target, resolution) = resolve_args(args).ok()?;
+    let all = args.get("all").and_then(|v| v.as_bool()).unwrap_or(false);
+    Some(preview_data(&target, &resolution, all))
+}
+
+pub(super) fn kill_process(args: &Value) -> ToolResult {
+    let (target, resolution) = match resolve_args(args) {
+        Ok(r) => r,
+        Err(e) => {
+            return ToolResult {
+                success: false,
+                data: Value::Null,
+                error: Some(e),
+            }
+        }
+    };
+    let all = args.get("all").and_then(|v| v.as_bool()).unwrap_or(false);
+    if args.get("dry_run").and_then(|v| v.as_bool()) == Some(true) {
+        // Candidates carry their refusals; nothing is signalled.
+        let mut data = preview_data(&target, &resolution, all);
+        data["dry_run"] = json!(true);
+        data["affected_pids"] = json!([]);
+        return ToolResult {
+            success: true,
+            data,
+            error: None,
+        };
+    }
+    let killable = resolution.killable();
+
+    let refuse = |error: String| ToolResult {
+        success: false,
+        data: json!({
+            "target": target.label(),
+            "match_kind": resolution.match_kind,
+            "ambiguous": killable.len() > 1,
+            "candidates": resolution.candidates,
+            "affected_pids": [],
+        }),
+        error: Some(error),
+    };
+
+    if resolution.candidates.is_empty() {
+        return refuse(format!("No process matches {}", target.label()));
+    }
+    if killable.is_empty() {
+        let reasons: Vec<String> = resolution
+            .candidates
+            .iter()
+            .filter_map(|c| {
+                c.refused
+                    .as_ref()
+                    .map(|r| format!("{} ({}): {}", c.process.name, c.process.pid, r))
+            })
+            .collect();
+        return refuse(format!(
+            "Refusing to kill {}: {}",
+            target.label(),
+            reasons.join("; ")
+        ));
+    }
+    if killable.len() > 1 && !all {
+        return refuse(format!(
+            "{} processes match {}; pick a pid or set all=true",
+            killable.len(),
+            target.label()
+        ));
+    }
+
+    let targets: Vec<(u32, String)> = killable.iter().map(|p| (p.pid, p.name.clone())).collect();
+    let outcomes = terminate(&targets, timeout_from_args(args));
+    let affected: Vec<u32> = outcomes
+        .iter()
+        .filter(|o| o.exited)
+        .map(|o| o.pid)
+        .collect();
+    let failed: Vec<String> = outcomes
+        .iter()
+        .filter(|o| !o.exited)
+        .map(|o| match &o.error {
+            Some(e) => format!("{} ({}): {}", o.name, o.pid, e),
+            None => format!("{} ({}) is still running", o.name, o.pid),
+        })
+        .collect();
+
+    ToolResult {
+        success: failed.is_empty(),
+        data: json!({
+            "target": target.label(),
+            "match_kind": resolution.match_kind,
+            "killed": !affected.is_empty(),
+            "affected_pids": affected,
+            "outcomes": outcomes,
+        }),
+        error: if failed.is_empty() {
+            None
+        } else {
+            Some(format!("Could not kill {}", failed.join("; ")))
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PS_NAMES: &str = "\
+    1 /sbin/launchd
+  412 /System/Library/CoreServices/WindowServer
+  901 /Applications/Safari.app/Contents/MacOS/Safari
+  950 /Applications/Google Chrome.app/Contents/MacOS/Google Chrome
+  951 /Applications/Google Chrome.app/Contents/Frameworks/Google Chrome Helper.app/Contents/MacOS/Google Chrome Helper
+ 1200 /Applications/Visual Studio Code.app/Contents/MacOS/Electron
+ 1300 /usr/local/bin/node
+ 1500 /usr/sbin/cupsd
+ 1600 /usr/local/bin/node
+";
+
+    const PS_STATS: &str = "\
+    1     0 root       0.0  0.1  12000 /sbin/launchd
+  412   501 alice     12.5  1.2 180000 /System/Library/CoreServices/WindowServer -daemon
+  901   501 alice      3.0  4.0 400000 /Applications/Safari.app/Contents/MacOS/Safari
+  950   501 alice     25.0  6.0 600000 /Applications/Google Chrome.app/Contents/MacOS/Google Chrome
+  951   501 alice     40.0  3.0 300000 /Applications/Google Chrome.app/Contents/Frameworks/Google Chrome Helper.app/Contents/MacOS/Google Chrome Helper --type=renderer
+ 1200   501 alice      8.0  5.0 500000 /Applications/Visual Studio Code.app/Contents/MacOS/Electron
+ 1300   501 alice     90.0  2.0 200000 node /Users/alice/code/server.js
+ 1500     0 root       0.0  0.1   8000 /usr/sbin/cupsd -l
+ 1600   502 bob        1.0  1.0 100000 node /Users/bob/code/app.js
+";
+
+    fn processes() -> Vec<ProcessInfo> {
+        parse_ps_stats(PS_STATS, &parse_ps_names(PS_NAMES))
+    }
+
+    fn pids(r: &Resolution) -> Vec<u32> {
+        r.candidates.iter().map(|c| c.process.pid).collect()
+    }
+
+    #[test]
+    fn test_parse_ps_keeps_names_with_spaces() {
+        let procs = processes();
+        assert_eq!(procs.len(), 9);
+        let chrome = procs.iter().find(|p| p.pid == 950).unwrap();
+        assert_eq!(chrome.name, "Google Chrome");
+        assert_eq!(chrome.user, "alice");
+        assert_eq!(chrome.uid, 501);
+        assert_eq!(chrome.cpu_percent, 25.0);
+        assert_eq!(chrome.rss_kb, 600000);
+        assert!(chrome.command.ends_with("MacOS/Google Chrome"));
+    }
+
+    #[test]
+    fn test_exact_name_beats_command_line() {
+        // "code" appears in several command lines but is no process's name
+        let r = resolve(&processes(), &Target::Name("code".into()), Some(501), 1);
+        assert_eq!(r.match_kind, "none");
+        assert!(r.candidates.is_empty());
+
+        let r = resolve(&processes(), &Target::Name("safari".into()), Some(501), 1);
+        assert_eq!(r.match_kind, "exact_name");
+        assert_eq!(pids(&r), vec![901]);
+    }
+
+    #[test]
+    fn test_app_bundle_match() {
+        let r = resolve(
+            &processes(),
+            &Target::Name("Visual Studio Code".into()),
+            Some(501),
+            1,
+        );
+        assert_eq!(r.match_kind, "app_bundle");
+        assert_eq!(pids(&r), vec![1200]);
+    }
+
+    #[test]
+    fn test_partial_name_is_ambiguous() {
+        let r = resolve(&processes(), &Target::Name("chrome".into()), Some(501), 1);
+        assert_eq!(r.match_kind, "partial_name");
+        // Sorted by CPU, highest first
+        assert_eq!(pids(&r), vec![951, 950]);
+        assert_eq!(r.killable().len(), 2);
+    }
+
+    #[test]
+    fn test_refuses_root_other_users_and_critical() {
+        let r = resolve(&processes(), &Target::Name("node".into()), Some(501), 1);
+        assert_eq!(
+            r.killable().iter().map(|p| p.pid).collect::<Vec<_>>(),
+            vec![1300]
+        );
+        let bob = r.candidates.iter().find(|c| c.process.pid == 1600).unwrap();
+        assert!(bob.refused.as_ref().unwrap().contains("another user"));
+
+        let r = resolve(&processes(), &Target::Pid(1500), Some(501), 1);
+        assert_eq!(r.candidates[0].refused.as_deref(), Some("owned by root"));
+
+        let r = resolve(
+            &processes(),
+            &Target::Name("WindowServer".into()),
+            Some(501),
+            1,
+        );
+        assert_eq!(
+            r.candidates[0].refused.as_deref(),
+            Some("system-critical process")
+        );
+
+        let r = resolve(&processes(), &Target::Pid(901), Some(501), 901);
+        assert!(r.killable().is_empty());
+    }
+
+    #[test]
+    fn test_target_from_args() {
+        assert_eq!(Target::from_args(&json!({"pid": 42})), Ok(Target::Pid(42)));
+        assert_eq!(
+            Target::from_args(&json!({"process_name": "1234"})),
+            Ok(Target::Pid(1234))
+        );
+        assert_eq!(
+            Target::from_args(&json!({"process_name": "Safari"})),
+            Ok(Target::Name("Safari".into()))
+        );
+        assert!(Target::from_args(&json!({"process_name": "  "})).is_err());
+        // Out-of-range pids must not wrap onto another process.
+        for pid in [
+            json!(-1),
+            json!(0),
+            json!(4_294_967_296u64 + 42),
+            json!(1.5),
+        ] {
+            let err = Target::from_args(&json!({"pid": pid})).unwrap_err();
+            assert!(err.contains("Invalid pid"), "{}", err);
+        }
+        assert!(Target::from_args(&json!({"process_name": "0"})).is_err());
+    }
+
+    #[test]
+    fn test_dry_run_does_not_signal() {
+        let pid = spawn_reaped("exec sleep 30");
+        let result = kill_process(&json!({"pid": pid, "dry_run": true}));
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(result.data["dry_run"], true);
+        // Under root (CI) the candidate is listed with its refusal instead.
+        assert_eq!(result.data["candidates"][0]["pid"], pid);
+        assert!(is_alive(pid));
+        terminate(&[(pid, "sleep".into())], Duration::from_secs(2));
+    }
+
+    /// Spawn a child and reap it in the background so it doesn't linger as a zombie.
+    fn spawn_reaped(script: &str) -> u32 {
+        let mut child = Command::new("sh").args(["-c", script]).spawn().unwrap();
+        let pid = child.id();
+        thread::spawn(move || child.wait());
+        // Give the shell time to install its trap before we signal it
+        thread::sleep(Duration::from_millis(200));
+        pid
+    }
+
+    #[test]
+    fn test_terminate_graceful() {
+        let pid = spawn_reaped("exec sleep 30");
+        let outcomes = terminate(&[(pid, "sleep".into())], Duration::from_secs(5));
+        assert!(outcomes[0].exited);
+        assert_eq!(outcomes[0].signal, "SIGTERM");
+    }
+
+    #[test]
+    fn test_terminate_escalates_to_sigkill() {
+        let pid = spawn_reaped("trap '' TERM; exec sleep 30");
+        let outcomes = terminate(&[(pid, "sleep".into())], Duration::from_millis(300));
+        assert!(outcomes[0].exited);
+        assert_eq!(outcomes[0].signal, "SIGKILL");
+    }
+}
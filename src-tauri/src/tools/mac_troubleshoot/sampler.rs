@@ -0,0 +1,214 @@
+//! Repeated sampling over a time window.
+//!
+//! A [`Sampler`] takes a fixed number of samples spread over a duration and
+//! hands back the raw readings; [`keyed_series`] lines keyed readings (per
+//! process, per interface) up into one series per key so [`SeriesStats`] can
+//! summarise them. Probes come in two shapes: instantaneous readings
+//! (`vm_stat`, tick counters) go through [`Sampler::snapshots`], which sleeps
+//! between calls, while probes that measure over a window themselves
+//! (`top -s`) go through [`Sampler::windows`].
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::Duration;
+
+const MAX_SAMPLES: usize = 30;
+const MAX_DURATION_SECS: f64 = 60.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct Sampler {
+    pub samples: usize,
+    pub duration: Duration,
+}
+
+impl Sampler {
+    pub(super) fn new(samples: usize, duration: Duration) -> Self {
+        Sampler {
+            samples: samples.clamp(1, MAX_SAMPLES),
+            duration: duration.min(Duration::from_secs_f64(MAX_DURATION_SECS)),
+        }
+    }
+
+    /// Read `samples` and `duration_secs` from tool args, falling back to the
+    /// given defaults.
+    pub(super) fn from_args(args: &Value, default_samples: usize, default_secs: f64) -> Self {
+        let samples = args
+            .get("samples")
+            .and_then(|v| v.as_u64())
+            .map(|n| n as usize)
+            .unwrap_or(default_samples);
+        let secs = args
+            .get("duration_secs")
+            .and_then(|v| v.as_f64())
+            .filter(|s| s.is_finite() && *s >= 0.0)
+            .unwrap_or(default_secs)
+            .min(MAX_DURATION_SECS);
+        Sampler::new(samples, Duration::from_secs_f64(secs))
+    }
+
+    /// Fit the window to probes that only take whole-second intervals
+    /// (`top -s`): at least one second per sample, dropping samples rather
+    /// than overrunning, so `duration` is the time actually spent.
+    pub(super) fn whole_seconds(self) -> Self {
+        let total = (self.duration.as_secs_f64().round() as u64).max(1);
+        let samples = self.samples.min(total as usize);
+        let interval = total / samples as u64;
+        Sampler {
+            samples,
+            duration: Duration::from_secs(interval * samples as u64),
+        }
+    }
+
+    /// Time covered by each sample.
+    pub(super) fn interval(&self) -> Duration {
+        self.duration / self.samples as u32
+    }
+
+    /// Call an instantaneous probe `samples + 1` times, one interval apart,
+    /// so consecutive readings bracket every interval (for rate/delta
+    /// probes). The first reading is taken immediately.
+    pub(super) fn snapshots<T>(&self, mut probe: impl FnMut() -> T) -> Vec<T> {
+        let mut readings = Vec::with_capacity(self.samples + 1);
+        readings.push(probe());
+        for _ in 0..self.samples {
+            std::thread::sleep(self.interval());
+            readings.push(probe());
+        }
+        readings
+    }
+
+    /// Call a probe that blocks for the interval it is given, `samples` times.
+    pub(super) fn windows<T>(&self, mut probe: impl FnMut(Duration) -> T) -> Vec<T> {
+        (0..self.samples).map(|_| probe(self.interval())).collect()
+    }
+}
+
+/// Summary of one series of readings.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub(super) struct SeriesStats {
+    pub avg: f64,
+    pub peak: f64,
+    pub min: f64,
+    pub last: f64,
+}
+
+impl SeriesStats {
+    pub(super) fn of(values: &[f64]) -> Self {
+        if values.is_empty() {
+            return SeriesStats {
+                avg: 0.0,
+                peak: 0.0,
+                min: 0.0,
+                last: 0.0,
+            };
+        }
+        let round = |v: f64| (v * 10.0).round() / 10.0;
+        SeriesStats {
+            avg: round(values.iter().sum::<f64>() / values.len() as f64),
+            peak: round(values.iter().cloned().fold(f64::MIN, f64::max)),
+            min: round(values.iter().cloned().fold(f64::MAX, f64::min)),
+            last: round(values[values.len() - 1]),
+        }
+    }
+}
+
+/// Pivot per-sample keyed readings into one series per key, in order of first
+/// appearance. A key missing from a sample reads as `0.0` there (a process
+/// that dropped off `top`'s list, an interface that went away).
+pub(super) fn keyed_series<K: Eq + Hash + Clone>(samples: &[Vec<(K, f64)>]) -> Vec<(K, Vec<f64>)> {
+    let mut index: HashMap<K, usize> = HashMap::new();
+    let mut series: Vec<(K, Vec<f64>)> = Vec::new();
+    for (i, sample) in samples.iter().enumerate() {
+        for (key, value) in sample {
+            let slot = *index.entry(key.clone()).or_insert_with(|| {
+                series.push((key.clone(), vec![0.0; samples.len()]));
+                series.len() - 1
+            });
+            series[slot].1[i] = *value;
+        }
+    }
+    series
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_from_args_clamps() {
+        let s = Sampler::from_args(&json!({}), 3, 3.0);
+        assert_eq!(s.samples, 3);
+        assert_eq!(s.interval(), Duration::from_secs(1));
+
+        let s = Sampler::from_args(&json!({"samples": 500, "duration_secs": 600}), 3, 3.0);
+        assert_eq!(s.samples, MAX_SAMPLES);
+        assert_eq!(s.duration, Duration::from_secs(60));
+
+        let s = Sampler::from_args(&json!({"samples": 0, "duration_secs": -1}), 3, 3.0);
+        assert_eq!(s.samples, 1);
+        assert_eq!(s.duration, Duration::from_secs(3));
+    }
+
+    #[test]
+    fn test_whole_seconds() {
+        let s = Sampler::new(30, Duration::from_secs(3)).whole_seconds();
+        assert_eq!((s.samples, s.duration), (3, Duration::from_secs(3)));
+        assert_eq!(s.interval(), Duration::from_secs(1));
+
+        let s = Sampler::new(2, Duration::from_secs(5)).whole_seconds();
+        assert_eq!((s.samples, s.interval()), (2, Duration::from_secs(2)));
+        assert_eq!(s.duration, Duration::from_secs(4));
+
+        let s = Sampler::new(3, Duration::ZERO).whole_seconds();
+        assert_eq!((s.samples, s.duration), (1, Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_snapshots_and_windows() {
+        let s = Sampler::new(3, Duration::ZERO);
+        let mut n = 0;
+        assert_eq!(
+            s.snapshots(|| {
+                n += 1;
+                n
+            }),
+            vec![1, 2, 3, 4]
+        );
+
+        let mut seen = Vec::new();
+        let out = s.windows(|interval| {
+            seen.push(interval);
+            seen.len()
+        });
+        assert_eq!(out, vec![1, 2, 3]);
+        assert!(seen.iter().all(|d| *d == Duration::ZERO));
+    }
+
+    #[test]
+    fn test_keyed_series_and_stats() {
+        let samples = vec![
+            vec![("a", 10.0), ("b", 90.0)],
+            vec![("a", 20.0)],
+            vec![("a", 30.0), ("c", 5.0)],
+        ];
+        let series = keyed_series(&samples);
+        assert_eq!(
+            series,
+            vec![
+                ("a", vec![10.0, 20.0, 30.0]),
+                ("b", vec![90.0, 0.0, 0.0]),
+                ("c", vec![0.0, 0.0, 5.0]),
+            ]
+        );
+
+        let stats = SeriesStats::of(&series[0].1);
+        assert_eq!(stats.avg, 20.0);
+        assert_eq!(stats.peak, 30.0);
+        assert_eq!(stats.min, 10.0);
+        assert_eq!(stats.last, 30.0);
+        assert_eq!(SeriesStats::of(&[]).peak, 0.0);
+    }
+}
@@ -0,0 +1,602 @@
+//! Security posture audit for `check_security`.
+//!
+//! Each check turns one command's output into a [`Finding`] with a severity,
+//! a pass/fail status and remediation text. Passed checks earn their
+//! severity's weight; the posture score is the share of weight earned over
+//! the checks whose status could be determined.
+
+use super::{run_cmd, run_shell, ToolResult};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const XPROTECT_PLIST: &str =
+    "/Library/Apple/System/Library/CoreServices/XProtect.bundle/Contents/Info.plist";
+
+/// Longest acceptable delay before a password is required after sleep.
+const MAX_SCREEN_LOCK_DELAY_SECS: u64 = 300;
+/// More admins than this is flagged.
+const MAX_ADMINS: usize = 2;
+
+/// Sharing services that accept connections from other machines.
+const REMOTE_SERVICES: &[(&str, &str)] = &[
+    ("com.openssh.sshd", "Remote Login (SSH)"),
+    ("com.apple.screensharing", "Screen Sharing"),
+    ("com.apple.smbd", "File Sharing (SMB)"),
+    ("com.apple.AEServer", "Remote Apple Events"),
+    ("com.apple.RemoteDesktop.agent", "Remote Management"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum Severity {
+    High,
+    Medium,
+    Low,
+}
+
+impl Severity {
+    fn weight(self) -> u32 {
+        match self {
+            Severity::High => 15,
+            Severity::Medium => 10,
+            Severity::Low => 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum Status {
+    Pass,
+    Fail,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(super) struct Finding {
+    pub id: &'static str,
+    pub title: &'static str,
+    pub status: Status,
+    pub severity: Severity,
+    /// Points earned: the severity weight when passed, otherwise 0.
+    pub score: u32,
+    pub max_score: u32,
+    pub detail: String,
+    pub remediation: Option<&'static str>,
+}
+
+fn finding(
+    id: &'static str,
+    title: &'static str,
+    severity: Severity,
+    status: Status,
+    detail: impl Into<String>,
+    remediation: &'static str,
+) -> Finding {
+    let max_score = severity.weight();
+    Finding {
+        id,
+        title,
+        status,
+        severity,
+        score: if status == Status::Pass { max_score } else { 0 },
+        max_score,
+        detail: detail.into(),
+        remediation: (status != Status::Pass).then_some(remediation),
+    }
+}
+
+/// Pass when `on` matches, fail when `off` matches, otherwise unknown.
+fn on_off(raw: &str, on: &str, off: &str) -> Status {
+    let lower = raw.to_lowercase();
+    if lower.contains(on) {
+        Status::Pass
+    } else if lower.contains(off) {
+        Status::Fail
+    } else {
+        Status::Unknown
+    }
+}
+
+fn first_line(raw: &str) -> String {
+    raw.lines().next().unwrap_or("").trim().to_string()
+}
+
+// ----- Checks -----
+
+pub(super) fn check_filevault(raw: &str) -> Finding {
+    finding(
+        "filevault",
+        "FileVault disk encryption",
+        Severity::High,
+        on_off(raw, "filevault is on", "filevault is off"),
+        first_line(raw),
+        "Turn on FileVault in System Settings → Privacy & Security → FileVault.",
+    )
+}
+
+pub(super) fn check_sip(raw: &str) -> Finding {
+    finding(
+        "sip",
+        "System Integrity Protection",
+        Severity::High,
+        on_off(raw, "status: enabled", "status: disabled"),
+        first_line(raw),
+        "Boot into Recovery and run `csrutil enable`.",
+    )
+}
+
+pub(super) fn check_gatekeeper(raw: &str) -> Finding {
+    finding(
+        "gatekeeper",
+        "Gatekeeper",
+        Severity::High,
+        on_off(raw, "assessments enabled", "assessments disabled"),
+        first_line(raw),
+        "Run `sudo spctl --master-enable` and allow apps from the App Store and identified developers.",
+    )
+}
+
+pub(super) fn check_firewall(raw: &str) -> Finding {
+    finding(
+        "firewall",
+        "Application firewall",
+        Severity::Medium,
+        on_off(raw, "is enabled", "is disabled"),
+        first_line(raw),
+        "Turn on the firewall in System Settings → Network → Firewall.",
+    )
+}
+
+pub(super) fn check_stealth_mode(raw: &str) -> Finding {
+    // "Firewall stealth mode is on" (macOS 14+) or "Stealth mode enabled"
+    let lower = raw.to_lowercase();
+    let status = if lower.contains("mode is on") || lower.contains("mode enabled") {
+        Status::Pass
+    } else if lower.contains("mode is off") || lower.contains("mode disabled") {
+        Status::Fail
+    } else {
+        Status::Unknown
+    };
+    finding(
+        "stealth_mode",
+        "Firewall stealth mode",
+        Severity::Low,
+        status,
+        first_line(raw),
+        "Enable stealth mode in Firewall Options so the Mac ignores pings and probes.",
+    )
+}
+
+/// `defaults read` values from the SoftwareUpdate preferences. A missing key
+/// means macOS is using its default, which is enabled.
+pub(super) fn check_auto_updates(prefs: &HashMap<&str, String>) -> Finding {
+    let keys = [
+        ("AutomaticCheckEnabled", "check"),
+        ("AutomaticDownload", "download"),
+        ("CriticalUpdateInstall", "security responses"),
+        ("ConfigDataInstall", "system data files"),
+    ];
+    let off: Vec<&str> = keys
+        .iter()
+        .filter(|(key, _)| prefs.get(key).is_some_and(|v| v.trim() == "0"))
+        .map(|(_, label)| *label)
+        .collect();
+    let status = if off.is_empty() {
+        Status::Pass
+    } else {
+        Status::Fail
+    };
+    let detail = if off.is_empty() {
+        "Automatic updates are on".to_string()
+    } else {
+        format!("Automatic {} turned off", off.join(", "))
+    };
+    finding(
+        "auto_updates",
+        "Automatic updates",
+        Severity::Medium,
+        status,
+        detail,
+        "Turn on all options under System Settings → General → Software Update → Automatic Updates.",
+    )
+}
+
+/// Update titles from `softwareupdate -l`.
+pub(super) fn parse_pending_updates(raw: &str) -> Vec<String> {
+    raw.lines()
+        .filter_map(|l| l.trim().strip_prefix("Title:"))
+        .map(|t| t.split(',').next().unwrap_or(t).trim().to_string())
+        .collect()
+}
+
+pub(super) fn check_pending_updates(raw: Option<&str>) -> Finding {
+    let (status, detail) = match raw {
+        None => (Status::Unknown, "Skipped".to_string()),
+        Some(raw) if raw.contains("No new software available") => {
+            (Status::Pass, "Up to date".to_string())
+        }
+        Some(raw) => {
+            let updates = parse_pending_updates(raw);
+            if updates.is_empty() {
+                (Status::Unknown, first_line(raw))
+            } else {
+                (
+                    Status::Fail,
+                    format!("{} pending: {}", updates.len(), updates.join("; ")),
+                )
+            }
+        }
+    };
+    finding(
+        "pending_updates",
+        "Software updates",
+        Severity::High,
+        status,
+        detail,
+        "Install pending updates in System Settings → General → Software Update.",
+    )
+}
+
+/// Delay in seconds from `sysadminctl -screenLock status`: `Some(None)` when
+/// the lock is off, `None` when the output is not recognised.
+pub(super) fn parse_screen_lock(raw: &str) -> Option<Option<u64>> {
+    let lower = raw.to_lowercase();
+    if lower.contains("screenlock is off") {
+        return Some(None);
+    }
+    if lower.contains("immediate") {
+        return Some(Some(0));
+    }
+    let after = &lower[lower.find("delay is ")? + "delay is ".len()..];
+    Some(after.split_whitespace().next()?.parse().ok())
+}
+
+pub(super) fn check_screen_lock(raw: &str) -> Finding {
+    let (status, detail) = match parse_screen_lock(raw) {
+        Some(Some(secs)) if secs <= MAX_SCREEN_LOCK_DELAY_SECS => (
+            Status::Pass,
+            format!("Password required {}", delay_text(secs)),
+        ),
+        Some(Some(secs)) => (
+            Status::Fail,
+            format!("Password required only {}", delay_text(secs)),
+        ),
+        Some(None) => (Status::Fail, "No password required after sleep".to_string()),
+        None => (Status::Unknown, first_line(raw)),
+    };
+    finding(
+        "screen_lock",
+        "Password after sleep",
+        Severity::Medium,
+        status,
+        detail,
+        "Set \"Require password after screen saver begins or display is turned off\" to 5 minutes or less in Lock Screen settings.",
+    )
+}
+
+fn delay_text(secs: u64) -> String {
+    if secs == 0 {
+        "immediately".into()
+    } else {
+        format!("after {} s", secs)
+    }
+}
+
+/// `launchctl print-disabled system`: `"label" => enabled|disabled` (or the
+/// older `=> false|true`, where `true` means disabled).
+pub(super) fn parse_disabled_overrides(raw: &str) -> HashMap<String, bool> {
+    raw.lines()
+        .filter_map(|line| {
+            let (label, state) = line.trim().split_once("=>")?;
+            let enabled = match state.trim() {
+                "enabled" | "false" => true,
+                "disabled" | "true" => false,
+                _ => return None,
+            };
+            Some((label.trim().trim_matches('"').to_string(), enabled))
+        })
+        .collect()
+}
+
+pub(super) fn check_remote_access(overrides: &HashMap<String, bool>) -> Finding {
+    let enabled: Vec<&str> = REMOTE_SERVICES
+        .iter()
+        .filter(|(label, _)| overrides.get(*label).copied().unwrap_or(false))
+        .map(|(_, name)| *name)
+        .collect();
+    let (status, detail) = if enabled.is_empty() {
+        (
+            Status::Pass,
+            "No remote access services enabled".to_string(),
+        )
+    } else {
+        (Status::Fail, format!("Enabled: {}", enabled.join(", ")))
+    };
+    finding(
+        "remote_access",
+        "Remote login and sharing",
+        Severity::Medium,
+        status,
+        detail,
+        "Turn off sharing services you don't use in System Settings → General → Sharing.",
+    )
+}
+
+pub(super) fn xprotect_version(plist_path: &Path) -> Option<String> {
+    let value = plist::Value::from_file(plist_path).ok()?;
+    value
+        .as_dictionary()?
+        .get("CFBundleShortVersionString")?
+        .as_string()
+        .map(String::from)
+}
+
+pub(super) fn check_xprotect(version: Option<&str>) -> Finding {
+    let (status, detail) = match version {
+        Some(v) => (Status::Pass, format!("XProtect {}", v)),
+        None => (Status::Unknown, "XProtect version not found".to_string()),
+    };
+    finding(
+        "xprotect",
+        "XProtect malware definitions",
+        Severity::Low,
+        status,
+        detail,
+        "Make sure \"Install Security Responses and system files\" is on so XProtect stays current.",
+    )
+}
+
+/// Members of the admin group from `dscl . -read /Groups/admin GroupMembership`,
+/// excluding root.
+pub(super) fn parse_admins(raw: &str) -> Vec<String> {
+    raw.trim()
+        .strip_prefix("GroupMembership:")
+        .unwrap_or("")
+        .split_whitespace()
+        .filter(|u| *u != "root")
+        .map(String::from)
+        .collect()
+}
+
+pub(super) fn check_admins(admins: &[String]) -> Finding {
+    let status = match admins.len() {
+        0 => Status::Unknown,
+        n if n <= MAX_ADMINS => Status::Pass,
+        _ => Status::Fail,
+    };
+    finding(
+        "admin_accounts",
+        "Administrator accounts",
+        Severity::Low,
+        status,
+        format!("{} admin account(s): {}", admins.len(), admins.join(", ")),
+        "Use a standard account day to day and keep admin rights to as few accounts as possible.",
+    )
+}
+
+// ----- Scoring -----
+
+/// Posture score 0–100 over the checks with a known status.
+pub(super) fn posture_score(findings: &[Finding]) -> Option<u32> {
+    let known: Vec<&Finding> = findings
+        .iter()
+        .filter(|f| f.status != Status::Unknown)
+        .collect();
+    let max: u32 = known.iter().map(|f| f.max_score).sum();
+    if max == 0 {
+        return None;
+    }
+    let earned: u32 = known.iter().map(|f| f.score).sum();
+    Some((earned as f64 / max as f64 * 100.0).round() as u32)
+}
+
+pub(super) fn grade(score: u32) -> &'static str {
+    match score {
+        90.. => "A",
+        80..=89 => "B",
+        70..=79 => "C",
+        60..=69 => "D",
+        _ => "F",
+    }
+}
+
+fn defaults_read(domain: &str, key: &str) -> String {
+    run_shell(&format!("defaults read {} {} 2>/dev/null", domain, key))
+}
+
+pub(super) fn check_security(args: &Value) -> ToolResult {
+    let check_updates = args
+        .get("check_updates")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true);
+    let firewall_cmd = "/usr/libexec/ApplicationFirewall/socketfilterfw";
+
+    let update_domain = "/Library/Preferences/com.apple.SoftwareUpdate";
+    let mut update_prefs = HashMap::new();
+    for key in [
+        "AutomaticCheckEnabled",
+        "AutomaticDownload",
+        "CriticalUpdateInstall",
+        "ConfigDataInstall",
+    ] {
+        let value = defaults_read(update_domain, key);
+        if !value.is_empty() {
+            update_prefs.insert(key, value);
+        }
+    }
+    // `softwareupdate -l` talks to Apple's servers and can take a while
+    let pending = check_updates.then(|| run_shell("softwareupdate -l 2>&1"));
+
+    let mut findings = vec![
+        check_filevault(&run_cmd("fdesetup", &["status"])),
+        check_sip(&run_cmd("csrutil", &["status"])),
+        check_gatekeeper(&run_shell("spctl --status 2>&1")),
+        check_pending_updates(pending.as_deref()),
+        check_firewall(&run_cmd(firewall_cmd, &["--getglobalstate"])),
+        check_auto_updates(&update_prefs),
+        check_screen_lock(&run_shell("sysadminctl -screenLock status 2>&1")),
+        check_remote_access(&parse_disabled_overrides(&run_cmd(
+            "launchctl",
+            &["print-disabled", "system"],
+        ))),
+        check_stealth_mode(&run_cmd(firewall_cmd, &["--getstealthmode"])),
+        check_xprotect(xprotect_version(Path::new(XPROTECT_PLIST)).as_deref()),
+        check_admins(&parse_admins(&run_cmd(
+            "dscl",
+            &[".", "-read", "/Groups/admin", "GroupMembership"],
+        ))),
+    ];
+    // Failures first, most severe first
+    findings.sort_by_key(|f| (f.status != Status::Fail, f.severity as u8));
+
+    let score = posture_score(&findings);
+    let failed = findings.iter().filter(|f| f.status == Status::Fail).count();
+    let checked_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    ToolResult {
+        success: true,
+        data: json!({
+            "score": score,
+            "grade": score.map(grade),
+            "failed": failed,
+            "findings": findings,
+            "checked_at": checked_at,
+        }),
+        error: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOFTWAREUPDATE: &str = "Software Update Tool\n\nFinding available software\nSoftware Update found the following new or updated software:\n* Label: macOS Sonoma 14.5-23F79\n\tTitle: macOS Sonoma 14.5, Version: 14.5, Size: 1234567KiB, Recommended: YES, Action: restart, \n* Label: Safari17.5SonomaAuto-17.5\n\tTitle: Safari, Version: 17.5, Size: 123456KiB, Recommended: YES, \n";
+
+    const PRINT_DISABLED: &str = "disabled services = {\n\t\"com.apple.screensharing\" => disabled\n\t\"com.openssh.sshd\" => enabled\n\t\"com.apple.smbd\" => true\n\t\"com.apple.AEServer\" => false\n}\n";
+
+    #[test]
+    fn test_basic_checks() {
+        assert_eq!(check_filevault("FileVault is On.").status, Status::Pass);
+        assert_eq!(check_filevault("FileVault is Off.").status, Status::Fail);
+        assert_eq!(
+            check_sip("System Integrity Protection status: enabled.").status,
+            Status::Pass
+        );
+        assert_eq!(
+            check_gatekeeper("assessments disabled").status,
+            Status::Fail
+        );
+        assert_eq!(
+            check_firewall("Firewall is enabled. (State = 1)").status,
+            Status::Pass
+        );
+        assert_eq!(
+            check_stealth_mode("Firewall stealth mode is on").status,
+            Status::Pass
+        );
+        assert_eq!(
+            check_stealth_mode("Stealth mode disabled").status,
+            Status::Fail
+        );
+        assert_eq!(check_filevault("").status, Status::Unknown);
+    }
+
+    #[test]
+    fn test_failed_finding_has_remediation_and_no_score() {
+        let f = check_filevault("FileVault is Off.");
+        assert_eq!(f.score, 0);
+        assert_eq!(f.max_score, 15);
+        assert!(f.remediation.is_some());
+        assert!(check_filevault("FileVault is On.").remediation.is_none());
+    }
+
+    #[test]
+    fn test_pending_updates() {
+        assert_eq!(
+            parse_pending_updates(SOFTWAREUPDATE),
+            vec!["macOS Sonoma 14.5", "Safari"]
+        );
+        assert_eq!(
+            check_pending_updates(Some(SOFTWAREUPDATE)).status,
+            Status::Fail
+        );
+        assert_eq!(
+            check_pending_updates(Some("Software Update Tool\n\nNo new software available."))
+                .status,
+            Status::Pass
+        );
+        assert_eq!(check_pending_updates(None).status, Status::Unknown);
+    }
+
+    #[test]
+    fn test_auto_updates() {
+        assert_eq!(check_auto_updates(&HashMap::new()).status, Status::Pass);
+        let prefs = HashMap::from([
+            ("AutomaticDownload", "0".to_string()),
+            ("AutomaticCheckEnabled", "1".to_string()),
+        ]);
+        let f = check_auto_updates(&prefs);
+        assert_eq!(f.status, Status::Fail);
+        assert!(f.detail.contains("download"));
+    }
+
+    #[test]
+    fn test_screen_lock() {
+        assert_eq!(
+            parse_screen_lock("screenLock delay is immediate"),
+            Some(Some(0))
+        );
+        assert_eq!(
+            parse_screen_lock("screenLock delay is 3600 seconds"),
+            Some(Some(3600))
+        );
+        assert_eq!(parse_screen_lock("screenLock is off"), Some(None));
+        assert_eq!(
+            check_screen_lock("screenLock delay is 60 seconds").status,
+            Status::Pass
+        );
+        assert_eq!(
+            check_screen_lock("screenLock delay is 3600 seconds").status,
+            Status::Fail
+        );
+    }
+
+    #[test]
+    fn test_remote_access() {
+        let overrides = parse_disabled_overrides(PRINT_DISABLED);
+        assert_eq!(overrides.get("com.openssh.sshd"), Some(&true));
+        assert_eq!(overrides.get("com.apple.smbd"), Some(&false));
+        let f = check_remote_access(&overrides);
+        assert_eq!(f.status, Status::Fail);
+        assert_eq!(f.detail, "Enabled: Remote Login (SSH), Remote Apple Events");
+        assert_eq!(check_remote_access(&HashMap::new()).status, Status::Pass);
+    }
+
+    #[test]
+    fn test_admins() {
+        let admins = parse_admins("GroupMembership: root alice bob carol");
+        assert_eq!(admins, vec!["alice", "bob", "carol"]);
+        assert_eq!(check_admins(&admins).status, Status::Fail);
+        assert_eq!(check_admins(&admins[..1]).status, Status::Pass);
+    }
+
+    #[test]
+    fn test_posture_score_ignores_unknown() {
+        let findings = vec![
+            check_filevault("FileVault is On."),     // 15 / 15
+            check_firewall("Firewall is disabled."), // 0 / 10
+            check_xprotect(Some("5272")),            // 5 / 5
+            check_pending_updates(None),             // unknown
+        ];
+        assert_eq!(posture_score(&findings), Some(67));
+        assert_eq!(grade(67), "D");
+        assert_eq!(posture_score(&[check_pending_updates(None)]), None);
+    }
+}
@@ -0,0 +1,509 @@
+//! Network inventory for `monitor_network`.
+//!
+//! Sockets come from `lsof -F` field output, which is stable to parse unlike
+//! the column layout. Each socket is tied to its owning pid and process (and
+//! `.app` bundle when there is one), so the tool can answer "what's listening
+//! on port 5432" and "which app is talking to the internet".
+
+use super::{process, run_cmd, ToolResult};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::net::IpAddr;
+
+const DEFAULT_LIMIT: usize = 50;
+/// Reverse lookups are sequential shell-outs, so cap how many we do.
+const MAX_REVERSE_LOOKUPS: usize = 25;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(super) struct Socket {
+    pub protocol: String,
+    pub family: String,
+    pub local_address: String,
+    pub local_port: Option<u16>,
+    pub remote_address: Option<String>,
+    pub remote_port: Option<u16>,
+    /// TCP state (LISTEN, ESTABLISHED, ...); `None` for UDP.
+    pub state: Option<String>,
+    pub pid: u32,
+    pub process: String,
+    pub user: String,
+    /// Reverse-DNS name of the remote address, when requested.
+    pub remote_host: Option<String>,
+}
+
+impl Socket {
+    fn is_listening(&self) -> bool {
+        match self.state.as_deref() {
+            Some(state) => state == "LISTEN",
+            // A UDP socket with no peer is a bound listener
+            None => self.protocol == "udp" && self.remote_address.is_none(),
+        }
+    }
+
+    fn is_internet(&self) -> bool {
+        self.remote_address
+            .as_deref()
+            .and_then(|a| a.parse::<IpAddr>().ok())
+            .is_some_and(is_public)
+    }
+}
+
+/// Whether an address is routable on the internet (not loopback, private,
+/// link-local, unique-local or unspecified).
+pub(super) fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            !(v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || v4.is_multicast())
+        }
+        IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_public(IpAddr::V4(v4));
+            }
+            let first = v6.segments()[0];
+            !(v6.is_loopback()
+                || v6.is_unspecified()
+                || v6.is_multicast()
+                || first & 0xfe00 == 0xfc00 // unique local fc00::/7
+                || first & 0xffc0 == 0xfe80) // link local fe80::/10
+        }
+    }
+}
+
+/// Split `host:port` where host may be `*`, an IPv4 address or `[v6]`.
+fn split_host_port(endpoint: &str) -> (String, Option<u16>) {
+    match endpoint.rsplit_once(':') {
+        Some((host, port)) => (
+            host.trim_matches(|c| c == '[' || c == ']').to_string(),
+            port.parse().ok(),
+        ),
+        None => (endpoint.to_string(), None),
+    }
+}
+
+/// Parse `lsof -i -n -P -F pcLtPnT` output.
+///
+/// Lines are tagged by their first character: `p` starts a process (followed
+/// by `c` command and `L` login), `f`/`t` start a file, `P` is the protocol,
+/// `n` the `local->remote` name, and `TST=` the TCP state.
+pub(super) fn parse_lsof_fields(raw: &str) -> Vec<Socket> {
+    let mut sockets: Vec<Socket> = Vec::new();
+    let (mut pid, mut command, mut user) = (0u32, String::new(), String::new());
+    let mut current: Option<Socket> = None;
+
+    let flush = |current: &mut Option<Socket>, sockets: &mut Vec<Socket>| {
+        if let Some(s) = current.take() {
+            if !s.local_address.is_empty() {
+                sockets.push(s);
+            }
+        }
+    };
+
+    for line in raw.lines() {
+        let Some(tag) = line.chars().next() else {
+            continue;
+        };
+        let value = &line[tag.len_utf8()..];
+        match tag {
+            'p' => {
+                flush(&mut current, &mut sockets);
+                pid = value.parse().unwrap_or(0);
+                command.clear();
+                user.clear();
+            }
+            'c' => command = value.to_string(),
+            'L' => user = value.to_string(),
+            'f' => flush(&mut current, &mut sockets),
+            't' => {
+                // `t` opens a new file record when lsof omits `f`
+                if current
+                    .as_ref()
+                    .is_some_and(|s| !s.local_address.is_empty())
+                {
+                    flush(&mut current, &mut sockets);
+                }
+                let socket = current.get_or_insert_with(|| Socket {
+                    protocol: String::new(),
+                    family: String::new(),
+                    local_address: String::new(),
+                    local_port: None,
+                    remote_address: None,
+                    remote_port: None,
+                    state: None,
+                    pid,
+                    process: command.clone(),
+                    user: user.clone(),
+                    remote_host: None,
+                });
+                socket.family = value.to_lowercase();
+            }
+            'P' => {
+                if let Some(s) = current.as_mut() {
+                    s.protocol = value.to_lowercase();
+                }
+            }
+            'n' => {
+                if let Some(s) = current.as_mut() {
+                    let (local, remote) = match value.split_once("->") {
+                        Some((l, r)) => (l, Some(r)),
+                        None => (value, None),
+                    };
+                    let (addr, port) = split_host_port(local);
+                    s.local_address = addr;
+                    s.local_port = port;
+                    if let Some(remote) = remote {
+                        let (addr, port) = split_host_port(remote);
+                        s.remote_address = Some(addr);
+                        s.remote_port = port;
+                    }
+                }
+            }
+            'T' => {
+                if let (Some(s), Some(state)) = (current.as_mut(), value.strip_prefix("ST=")) {
+                    s.state = Some(state.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+    flush(&mut current, &mut sockets);
+    sockets
+}
+
+/// Reverse lookup via `dig -x`; `None` when there is no PTR record.
+fn reverse_dns(ip: &str) -> Option<String> {
+    run_cmd("dig", &["+short", "+time=1", "+tries=1", "-x", ip])
+        .lines()
+        .next()
+        .map(|name| name.trim_end_matches('.').to_string())
+        .filter(|name| !name.is_empty() && !name.starts_with(';'))
+}
+
+// ----- Aggregation -----
+
+#[derive(Debug, Clone, Serialize)]
+pub(super) struct ProcessConnections {
+    pub pid: u32,
+    pub process: String,
+    pub app: Option<String>,
+    pub established: usize,
+    pub listening: usize,
+    /// Connections to public (non-LAN, non-loopback) addresses.
+    pub internet: usize,
+    pub remote_hosts: usize,
+}
+
+pub(super) fn per_process(
+    sockets: &[Socket],
+    apps: &HashMap<u32, String>,
+) -> Vec<ProcessConnections> {
+    let mut by_pid: BTreeMap<u32, (ProcessConnections, BTreeSet<String>)> = BTreeMap::new();
+    for s in sockets {
+        let (entry, remotes) = by_pid.entry(s.pid).or_insert_with(|| {
+            (
+                ProcessConnections {
+                    pid: s.pid,
+                    process: s.process.clone(),
+                    app: apps.get(&s.pid).cloned(),
+                    established: 0,
+                    listening: 0,
+                    internet: 0,
+                    remote_hosts: 0,
+                },
+                BTreeSet::new(),
+            )
+        });
+        if s.is_listening() {
+            entry.listening += 1;
+        }
+        if s.state.as_deref() == Some("ESTABLISHED") {
+            entry.established += 1;
+        }
+        if s.is_internet() {
+            entry.internet += 1;
+        }
+        if let Some(remote) = &s.remote_address {
+            remotes.insert(remote.clone());
+        }
+    }
+    let mut rows: Vec<ProcessConnections> = by_pid
+        .into_values()
+        .map(|(mut entry, remotes)| {
+            entry.remote_hosts = remotes.len();
+            entry
+        })
+        .collect();
+    rows.sort_by_key(|r| std::cmp::Reverse((r.internet, r.established + r.listening)));
+    rows
+}
+
+/// Filters from the tool arguments.
+#[derive(Debug, Clone, Default)]
+pub(super) struct SocketFilter {
+    pub port: Option<u16>,
+    pub process: Option<String>,
+    pub state: Option<String>,
+}
+
+impl SocketFilter {
+    /// Ports above 65535 are rejected rather than wrapped onto another port.
+    fn from_args(args: &Value) -> Result<Self, String> {
+        let port = match args.get("port").filter(|v| !v.is_null()) {
+            Some(port) => Some(
+                port.as_u64()
+                    .and_then(|p| u16::try_from(p).ok())
+                    .ok_or_else(|| format!("Invalid port {}: must be between 0 and 65535", port))?,
+            ),
+            None => None,
+        };
+        Ok(Self {
+            port,
+            process: args
+                .get("process")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_lowercase()),
+            state: args
+                .get("state")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_uppercase()),
+        })
+    }
+
+    pub(super) fn matches(&self, s: &Socket) -> bool {
+        self.port
+            .is_none_or(|p| s.local_port == Some(p) || s.remote_port == Some(p))
+            && self
+                .process
+                .as_ref()
+                .is_none_or(|p| s.process.to_lowercase().contains(p))
+            && self.state.as_ref().is_none_or(|st| match st.as_str() {
+                "LISTEN" | "LISTENING" => s.is_listening(),
+                other => s.state.as_deref() == Some(other),
+            })
+    }
+}
+
+/// `.app` bundle name for each pid that has one.
+fn app_names() -> HashMap<u32, String> {
+    process::snapshot()
+        .into_iter()
+        .filter_map(|p| Some((p.pid, process::app_bundle(&p.command)?.to_string())))
+        .collect()
+}
+
+pub(super) fn monitor_network(args: &Value) -> ToolResult {
+    let filter = match SocketFilter::from_args(args) {
+        Ok(filter) => filter,
+        Err(e) => {
+            return ToolResult {
+                success: false,
+                data: Value::Null,
+                error: Some(e),
+            }
+        }
+    };
+    let limit = args
+        .get("limit")
+        .and_then(|v| v.as_u64())
+        .map(|n| n as usize)
+        .unwrap_or(DEFAULT_LIMIT);
+    let want_rdns = args
+        .get("reverse_dns")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let mut sockets: Vec<Socket> =
+        parse_lsof_fields(&run_cmd("lsof", &["-i", "-n", "-P", "-F", "pcLtPnT"]))
+            .into_iter()
+            .filter(|s| filter.matches(s))
+            .collect();
+
+    if want_rdns {
+        let remotes: BTreeSet<String> = sockets
+            .iter()
+            .filter_map(|s| s.remote_address.clone())
+            .collect();
+        let names: HashMap<String, Option<String>> = remotes
+            .into_iter()
+            .take(MAX_REVERSE_LOOKUPS)
+            .map(|ip| {
+                let name = reverse_dns(&ip);
+                (ip, name)
+            })
+            .collect();
+        for s in &mut sockets {
+            s.remote_host = s
+                .remote_address
+                .as_ref()
+                .and_then(|ip| names.get(ip).cloned().flatten());
+        }
+    }
+
+    let apps = app_names();
+    let listening: Vec<Value> = sockets
+        .iter()
+        .filter(|s| s.is_listening())
+        .map(|s| {
+            json!({
+                "protocol": s.protocol,
+                "address": s.local_address,
+                "port": s.local_port,
+                "pid": s.pid,
+                "process": s.process,
+                "app": apps.get(&s.pid),
+                // Bound to all interfaces rather than loopback only
+                "exposed": !s.local_address.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback()),
+            })
+        })
+        .collect();
+    let processes = per_process(&sockets, &apps);
+    let total = sockets.len();
+    sockets.truncate(limit);
+
+    ToolResult {
+        success: true,
+        data: json!({
+            "socket_count": total,
+            "listening": listening,
+            "processes": processes,
+            "internet_processes": processes.iter().filter(|p| p.internet > 0).count(),
+            "sockets": sockets,
+            "truncated": total > limit,
+        }),
+        error: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv6Addr;
+
+    const LSOF: &str = "\
+p412
+cpostgres
+Lalice
+f7
+tIPv6
+PTCP
+n[::1]:5432
+TST=LISTEN
+TQR=0
+TQS=0
+f8
+tIPv4
+PTCP
+n127.0.0.1:5432
+TST=LISTEN
+p901
+cSafari
+Lalice
+f21
+tIPv4
+PTCP
+n192.168.1.23:52144->17.253.144.10:443
+TST=ESTABLISHED
+f22
+tIPv4
+PTCP
+n192.168.1.23:52150->192.168.1.40:8009
+TST=ESTABLISHED
+f30
+tIPv4
+PUDP
+n*:5353
+p1300
+cnode
+Lalice
+tIPv4
+PTCP
+n*:3000
+TST=LISTEN
+";
+
+    fn sockets() -> Vec<Socket> {
+        parse_lsof_fields(LSOF)
+    }
+
+    #[test]
+    fn test_parse_lsof_fields() {
+        let s = sockets();
+        assert_eq!(s.len(), 6);
+        assert_eq!(s[0].local_address, "::1");
+        assert_eq!(s[0].local_port, Some(5432));
+        assert_eq!(s[0].family, "ipv6");
+        assert_eq!(s[0].process, "postgres");
+        assert_eq!(s[2].remote_address.as_deref(), Some("17.253.144.10"));
+        assert_eq!(s[2].remote_port, Some(443));
+        assert_eq!(s[2].state.as_deref(), Some("ESTABLISHED"));
+        assert_eq!(s[4].protocol, "udp");
+        assert_eq!(s[4].state, None);
+        // Records without an `f` line still split on `t`
+        assert_eq!(s[5].process, "node");
+        assert_eq!(s[5].local_address, "*");
+    }
+
+    #[test]
+    fn test_listening_and_port_filter() {
+        let filter = SocketFilter {
+            port: Some(5432),
+            state: Some("LISTEN".into()),
+            ..Default::default()
+        };
+        let matched: Vec<_> = sockets()
+            .into_iter()
+            .filter(|s| filter.matches(s))
+            .collect();
+        assert_eq!(matched.len(), 2);
+        assert!(matched.iter().all(|s| s.process == "postgres"));
+    }
+
+    #[test]
+    fn test_out_of_range_port_is_rejected() {
+        let filter = SocketFilter::from_args(&json!({"port": 65535})).unwrap();
+        assert_eq!(filter.port, Some(65535));
+        for port in [json!(65536), json!(65616), json!(-1), json!(80.5)] {
+            let err = SocketFilter::from_args(&json!({"port": port})).unwrap_err();
+            assert!(err.contains("Invalid port"), "{}", err);
+        }
+        assert!(!monitor_network(&json!({"port": 65616})).success);
+    }
+
+    #[test]
+    fn test_per_process_counts_internet_connections() {
+        let apps = HashMap::from([(901, "Safari".to_string())]);
+        let rows = per_process(&sockets(), &apps);
+        assert_eq!(rows[0].process, "Safari");
+        assert_eq!(rows[0].app.as_deref(), Some("Safari"));
+        assert_eq!(rows[0].established, 2);
+        assert_eq!(rows[0].internet, 1);
+        assert_eq!(rows[0].remote_hosts, 2);
+        assert_eq!(rows[0].listening, 1); // the mDNS UDP socket
+        let pg = rows.iter().find(|r| r.pid == 412).unwrap();
+        assert_eq!((pg.listening, pg.internet), (2, 0));
+    }
+
+    #[test]
+    fn test_is_public() {
+        for ip in ["17.253.144.10", "2606:4700::1111", "::ffff:8.8.8.8"] {
+            assert!(is_public(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in [
+            "127.0.0.1",
+            "192.168.1.40",
+            "10.0.0.1",
+            "169.254.1.1",
+            "::1",
+            "fe80::1",
+            "fd00::1",
+        ] {
+            assert!(!is_public(ip.parse().unwrap()), "{}", ip);
+        }
+        assert!(!is_public(IpAddr::V6(Ipv6Addr::UNSPECIFIED)));
+    }
+}
@@ -0,0 +1,610 @@
+//! Startup item audit (`check_startup_items`) and `disable_startup_item`.
+//!
+//! Every launchd plist in the user and local LaunchAgents / LaunchDaemons
+//! directories is parsed (XML or binary), the program it launches is checked
+//! for existence and a verified code signature, and entries that look orphaned or
+//! suspicious are flagged. Disabling goes through `launchctl disable`, which
+//! is persistent but reversible with `launchctl enable`.
+
+use super::{home_dir, run_cmd, run_shell, ToolResult};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Interpreters that make a launch item worth a second look when they run
+/// inline code instead of a script file.
+const INLINE_INTERPRETERS: &[&str] = &[
+    "sh",
+    "bash",
+    "zsh",
+    "python",
+    "python3",
+    "perl",
+    "ruby",
+    "osascript",
+];
+
+/// Locations malware likes because they are writable and rarely inspected.
+const SUSPICIOUS_DIRS: &[&str] = &["/tmp/", "/private/tmp/", "/var/tmp/", "/Users/Shared/"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum Scope {
+    UserAgent,
+    Agent,
+    Daemon,
+}
+
+impl Scope {
+    fn dir(self) -> PathBuf {
+        match self {
+            Scope::UserAgent => home_dir().join("Library/LaunchAgents"),
+            Scope::Agent => PathBuf::from("/Library/LaunchAgents"),
+            Scope::Daemon => PathBuf::from("/Library/LaunchDaemons"),
+        }
+    }
+}
+
+// ----- Plist parsing -----
+
+/// The launchd keys we care about.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub(super) struct LaunchdPlist {
+    pub label: Option<String>,
+    pub program: Option<String>,
+    pub program_arguments: Vec<String>,
+    pub run_at_load: bool,
+    /// `KeepAlive` may be a bool or a dictionary of conditions; either way the
+    /// job is restarted by launchd.
+    pub keep_alive: bool,
+    pub disabled: bool,
+}
+
+impl LaunchdPlist {
+    /// The executable launchd runs: `Program`, else `ProgramArguments[0]`.
+    pub(super) fn executable(&self) -> Option<&str> {
+        self.program
+            .as_deref()
+            .or_else(|| self.program_arguments.first().map(String::as_str))
+    }
+}
+
+pub(super) fn parse_launchd_plist(path: &Path) -> Result<LaunchdPlist, String> {
+    let value = plist::Value::from_file(path).map_err(|e| format!("Invalid plist: {}", e))?;
+    let dict = value
+        .as_dictionary()
+        .ok_or_else(|| "Plist root is not a dictionary".to_string())?;
+    let string = |key: &str| dict.get(key).and_then(|v| v.as_string()).map(String::from);
+    let boolean = |key: &str| dict.get(key).and_then(|v| v.as_boolean()).unwrap_or(false);
+
+    Ok(LaunchdPlist {
+        label: string("Label"),
+        program: string("Program"),
+        program_arguments: dict
+            .get("ProgramArguments")
+            .and_then(|v| v.as_array())
+            .map(|args| {
+                args.iter()
+                    .filter_map(|a| a.as_string().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        run_at_load: boolean("RunAtLoad"),
+        keep_alive: match dict.get("KeepAlive") {
+            Some(plist::Value::Boolean(b)) => *b,
+            Some(plist::Value::Dictionary(_)) => true,
+            _ => false,
+        },
+        disabled: boolean("Disabled"),
+    })
+}
+
+// ----- Code signing -----
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "status", content = "authority")]
+pub(super) enum Signature {
+    Signed(String),
+    AdHoc,
+    Unsigned,
+    /// Signed, but `codesign --verify` rejects it (modified, broken seal,
+    /// revoked); carries the reason.
+    Invalid(String),
+    Unknown,
+}
+
+/// Interpret the stderr of `codesign -dv --verbose=2 <path>`.
+pub(super) fn parse_codesign(raw: &str) -> Signature {
+    if raw.contains("not signed at all") {
+        return Signature::Unsigned;
+    }
+    if raw.contains("Signature=adhoc") {
+        return Signature::AdHoc;
+    }
+    match raw.lines().find_map(|l| l.strip_prefix("Authority=")) {
+        Some(authority) => Signature::Signed(authority.trim().to_string()),
+        None => Signature::Unknown,
+    }
+}
+
+/// Apply the outcome of `codesign --verify`: a signature that fails it is
+/// reported as invalid, whatever authority it names.
+pub(super) fn parse_verify(signature: Signature, verified: bool, stderr: &str) -> Signature {
+    match signature {
+        Signature::Signed(_) | Signature::AdHoc if !verified => {
+            let reason = stderr
+                .lines()
+                .find(|l| !l.trim().is_empty())
+                .map(|l| l.rsplit_once(": ").map_or(l, |(_, r)| r).trim().to_string())
+                .unwrap_or_else(|| "signature does not verify".into());
+            Signature::Invalid(reason)
+        }
+        other => other,
+    }
+}
+
+/// Read the signing authority, then verify the signature: `-dv` alone only
+/// displays it and would call a tampered bundle signed.
+pub(super) fn check_signature(path: &Path) -> Signature {
+    let Ok(display) = Command::new("codesign")
+        .args(["-dv", "--verbose=2"])
+        .arg(path)
+        .output()
+    else {
+        return Signature::Unknown;
+    };
+    let signature = parse_codesign(&String::from_utf8_lossy(&display.stderr));
+    if !matches!(signature, Signature::Signed(_) | Signature::AdHoc) {
+        return signature;
+    }
+    match Command::new("codesign").arg("--verify").arg(path).output() {
+        Ok(verify) => parse_verify(
+            signature,
+            verify.status.success(),
+            &String::from_utf8_lossy(&verify.stderr),
+        ),
+        Err(_) => Signature::Unknown,
+    }
+}
+
+// ----- Audit -----
+
+#[derive(Debug, Clone, Serialize)]
+pub(super) struct LaunchItem {
+    pub label: String,
+    pub plist_path: String,
+    pub scope: Scope,
+    pub program: Option<String>,
+    pub arguments: Vec<String>,
+    pub run_at_load: bool,
+    pub keep_alive: bool,
+    pub disabled: bool,
+    pub binary_exists: bool,
+    pub signature: Signature,
+    /// Why the item looks wrong; empty when it looks fine.
+    pub flags: Vec<String>,
+    pub parse_error: Option<String>,
+}
+
+impl LaunchItem {
+    fn suspicious(&self) -> bool {
+        !self.flags.is_empty()
+    }
+}
+
+/// Build the audit entry for one plist. File-system and signature checks
+/// are injected so fixtures can be audited without the real binaries.
+pub(super) fn audit_plist(
+    path: &Path,
+    scope: Scope,
+    exists: &dyn Fn(&Path) -> bool,
+    signature: &dyn Fn(&Path) -> Signature,
+) -> LaunchItem {
+    let file_label = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut item = LaunchItem {
+        label: file_label.clone(),
+        plist_path: path.display().to_string(),
+        scope,
+        program: None,
+        arguments: Vec::new(),
+        run_at_load: false,
+        keep_alive: false,
+        disabled: false,
+        binary_exists: false,
+        signature: Signature::Unknown,
+        flags: Vec::new(),
+        parse_error: None,
+    };
+
+    let plist = match parse_launchd_plist(path) {
+        Ok(p) => p,
+        Err(e) => {
+            item.flags.push("unreadable plist".into());
+            item.parse_error = Some(e);
+            return item;
+        }
+    };
+
+    if let Some(label) = &plist.label {
+        if *label != file_label {
+            item.flags
+                .push(format!("label \"{}\" does not match file name", label));
+        }
+        if label.starts_with("com.apple.") {
+            item.flags
+                .push("uses an Apple label outside /System".into());
+        }
+        item.label = label.clone();
+    } else {
+        item.flags.push("missing Label".into());
+    }
+
+    item.program = plist.executable().map(String::from);
+    item.arguments = plist.program_arguments.clone();
+    item.run_at_load = plist.run_at_load;
+    item.keep_alive = plist.keep_alive;
+    item.disabled = plist.disabled;
+
+    match item.program.clone() {
+        None => item.flags.push("no Program or ProgramArguments".into()),
+        Some(program) => {
+            let program_path = Path::new(&program);
+            item.binary_exists = exists(program_path);
+            if !item.binary_exists {
+                item.flags.push("orphaned: program no longer exists".into());
+            } else {
+                item.signature = signature(program_path);
+                match &item.signature {
+                    Signature::Unsigned => item.flags.push("program is unsigned".into()),
+                    Signature::AdHoc => item.flags.push("program is only ad-hoc signed".into()),
+                    Signature::Invalid(reason) => item
+                        .flags
+                        .push(format!("program signature is invalid: {}", reason)),
+                    _ => {}
+                }
+            }
+            if SUSPICIOUS_DIRS.iter().any(|d| program.starts_with(d))
+                || program
+                    .split('/')
+                    .any(|c| c.starts_with('.') && c.len() > 1)
+            {
+                item.flags
+                    .push("runs from a temporary or hidden location".into());
+            }
+            let name = program.rsplit('/').next().unwrap_or(&program);
+            let inline = plist
+                .program_arguments
+                .iter()
+                .any(|a| a == "-c" || a == "-e");
+            if INLINE_INTERPRETERS.contains(&name) && inline {
+                item.flags.push(format!("runs inline {} code", name));
+            }
+        }
+    }
+    item
+}
+
+fn plist_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.extension().is_some_and(|ext| ext == "plist"))
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+pub(super) fn audit_dirs(dirs: &[(Scope, PathBuf)]) -> Vec<LaunchItem> {
+    dirs.iter()
+        .flat_map(|(scope, dir)| {
+            plist_files(dir)
+                .into_iter()
+                .map(|p| audit_plist(&p, *scope, &|p: &Path| p.exists(), &check_signature))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn default_dirs() -> Vec<(Scope, PathBuf)> {
+    [Scope::UserAgent, Scope::Agent, Scope::Daemon]
+        .into_iter()
+        .map(|s| (s, s.dir()))
+        .collect()
+}
+
+fn login_items() -> Vec<String> {
+    let raw = run_shell(
+        r#"osascript -e 'tell application "System Events" to get the name of every login item' 2>/dev/null"#,
+    );
+    raw.split(", ")
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+pub(super) fn check_startup_items() -> ToolResult {
+    let items = audit_dirs(&default_dirs());
+    let flagged: Vec<&LaunchItem> = items.iter().filter(|i| i.suspicious()).collect();
+    let count = |scope: Scope| items.iter().filter(|i| i.scope == scope).count();
+
+    ToolResult {
+        success: true,
+        data: json!({
+            "login_items": login_items(),
+            "launch_items": items,
+            "counts": {
+                "user_agents": count(Scope::UserAgent),
+                "agents": count(Scope::Agent),
+                "daemons": count(Scope::Daemon),
+                "flagged": flagged.len(),
+            },
+            "flagged": flagged.iter().map(|i| json!({"label": i.label, "flags": i.flags})).collect::<Vec<_>>(),
+        }),
+        error: None,
+    }
+}
+
+// ----- Disable -----
+
+/// Find an item by label, exactly or else ignoring case (voice input loses case).
+fn find_item(label: &str) -> Option<LaunchItem> {
+    let items = audit_dirs(&default_dirs());
+    items
+        .iter()
+        .position(|i| i.label == label)
+        .or_else(|| items.iter().position(|i| i.label.eq_ignore_ascii_case(label)))
+        .map(|idx| items[idx].clone())
+}
+
+/// The launchctl commands that disable `item`. Agents are disabled in the
+/// user's GUI domain; daemons live in the system domain and need root.
+pub(super) fn disable_commands(item: &LaunchItem, uid: &str) -> Vec<Vec<String>> {
+    let (prefix, domain): (&[&str], String) = match item.scope {
+        Scope::Daemon => (&["sudo", "-n"], "system".to_string()),
+        Scope::UserAgent | Scope::Agent => (&[], format!("gui/{}", uid)),
+    };
+    let target = format!("{}/{}", domain, item.label);
+    ["disable", "bootout"]
+        .iter()
+        .map(|verb| {
+            prefix
+                .iter()
+                .map(|s| s.to_string())
+                .chain(["launchctl".to_string(), verb.to_string(), target.clone()])
+                .collect()
+        })
+        .collect()
+}
+
+fn disable_plan(args: &Value) -> Result<(LaunchItem, Vec<Vec<String>>), String> {
+    let label = args
+        .get("label")
+        .and_then(|v| v.as_str())
+        .ok_or("Missing required parameter: label")?;
+    let item = find_item(label).ok_or_else(|| format!("No launch item with label '{}'", label))?;
+    let commands = disable_commands(&item, &run_cmd("id", &["-u"]));
+    Ok((item, commands))
+}
+
+fn plan_json(item: &LaunchItem, commands: &[Vec<String>]) -> Value {
+    json!({
+        "item": item,
+        "commands": commands.iter().map(|c| c.join(" ")).collect::<Vec<_>>(),
+        "undo": format!("launchctl enable {}", commands[0].last().cloned().unwrap_or_default()),
+    })
+}
+
+pub(super) fn preview_disable(args: &Value) -> Option<Value> {
+    let (item, commands) = disable_plan(args).ok()?;
+    Some(plan_json(&item, &commands))
+}
+
+pub(super) fn disable_startup_item(args: &Value) -> ToolResult {
+    let (item, commands) = match disable_plan(args) {
+        Ok(plan) => plan,
+        Err(e) => {
+            return ToolResult {
+                success: false,
+                data: Value::Null,
+                error: Some(e),
+            }
+        }
+    };
+    let mut data = plan_json(&item, &commands);
+    if args
+        .get("dry_run")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+    {
+        data["dry_run"] = json!(true);
+        return ToolResult {
+            success: true,
+            data,
+            error: None,
+        };
+    }
+
+    // `disable` is what matters; `bootout` fails harmlessly when the job isn't loaded
+    let mut results = Vec::new();
+    let mut disabled = false;
+    for (i, cmd) in commands.iter().enumerate() {
+        let output = Command::new(&cmd[0]).args(&cmd[1..]).output();
+        let (ok, stderr) = match output {
+            Ok(o) => (
+                o.status.success(),
+                String::from_utf8_lossy(&o.stderr).trim().to_string(),
+            ),
+            Err(e) => (false, e.to_string()),
+        };
+        if i == 0 {
+            disabled = ok;
+        }
+        results.push(json!({"command": cmd.join(" "), "ok": ok, "stderr": stderr}));
+    }
+    data["results"] = json!(results);
+    data["disabled"] = json!(disabled);
+
+    ToolResult {
+        success: disabled,
+        data,
+        error: if disabled {
+            None
+        } else if item.scope == Scope::Daemon {
+            Some("Could not disable the daemon (needs administrator rights)".into())
+        } else {
+            Some("launchctl disable failed".into())
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/launchd")
+            .join(name)
+    }
+
+    fn audit(name: &str, exists: bool, signature: Signature) -> LaunchItem {
+        audit_plist(
+            &fixture(name),
+            Scope::UserAgent,
+            &move |_| exists,
+            &move |_| signature.clone(),
+        )
+    }
+
+    #[test]
+    fn test_parse_xml_plist() {
+        let p = parse_launchd_plist(&fixture("com.example.updater.plist")).unwrap();
+        assert_eq!(p.label.as_deref(), Some("com.example.updater"));
+        assert_eq!(
+            p.executable(),
+            Some("/Applications/Example.app/Contents/MacOS/updater")
+        );
+        assert_eq!(p.program_arguments.len(), 2);
+        assert!(p.run_at_load);
+        // KeepAlive given as a dictionary of conditions
+        assert!(p.keep_alive);
+    }
+
+    #[test]
+    fn test_parse_binary_plist() {
+        let p = parse_launchd_plist(&fixture("com.suspicious.helper.plist")).unwrap();
+        assert_eq!(p.label.as_deref(), Some("com.apple.helper"));
+        assert_eq!(p.executable(), Some("/bin/sh"));
+        assert!(p.keep_alive);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_launchd_plist(&fixture("broken.plist")).is_err());
+        assert!(parse_launchd_plist(&fixture("missing.plist")).is_err());
+    }
+
+    #[test]
+    fn test_signed_item_is_clean() {
+        let item = audit(
+            "com.example.updater.plist",
+            true,
+            Signature::Signed("Developer ID Application: Example Inc (ABCDE12345)".into()),
+        );
+        assert!(item.binary_exists);
+        assert!(item.flags.is_empty(), "{:?}", item.flags);
+    }
+
+    #[test]
+    fn test_flags_orphaned_and_unsigned() {
+        let orphan = audit("com.orphan.agent.plist", false, Signature::Unknown);
+        assert_eq!(orphan.program.as_deref(), Some("/opt/gone/agent"));
+        assert!(orphan.flags[0].starts_with("orphaned"));
+
+        let unsigned = audit("com.example.updater.plist", true, Signature::Unsigned);
+        assert_eq!(unsigned.flags, vec!["program is unsigned"]);
+    }
+
+    #[test]
+    fn test_flags_suspicious_helper() {
+        let item = audit(
+            "com.suspicious.helper.plist",
+            true,
+            Signature::Signed("Software Signing".into()),
+        );
+        assert_eq!(item.label, "com.apple.helper");
+        assert!(item
+            .flags
+            .iter()
+            .any(|f| f.contains("does not match file name")));
+        assert!(item.flags.iter().any(|f| f.contains("Apple label")));
+        assert!(item.flags.iter().any(|f| f == "runs inline sh code"));
+    }
+
+    #[test]
+    fn test_audit_dirs_reads_every_plist() {
+        let items = audit_dirs(&[(Scope::Agent, fixture(""))]);
+        assert_eq!(items.len(), 4);
+        let broken = items.iter().find(|i| i.label == "broken").unwrap();
+        assert!(broken.parse_error.is_some());
+    }
+
+    #[test]
+    fn test_parse_codesign() {
+        let signed = "Executable=/Applications/Example.app/Contents/MacOS/updater\nIdentifier=com.example.updater\nAuthority=Developer ID Application: Example Inc (ABCDE12345)\nAuthority=Developer ID Certification Authority\nAuthority=Apple Root CA\n";
+        assert_eq!(
+            parse_codesign(signed),
+            Signature::Signed("Developer ID Application: Example Inc (ABCDE12345)".into())
+        );
+        assert_eq!(
+            parse_codesign("/opt/x: code object is not signed at all"),
+            Signature::Unsigned
+        );
+        assert_eq!(
+            parse_codesign("Executable=/x\nSignature=adhoc\n"),
+            Signature::AdHoc
+        );
+        assert_eq!(parse_codesign(""), Signature::Unknown);
+
+        let developer = Signature::Signed("Developer ID Application: Example Inc".into());
+        assert_eq!(parse_verify(developer.clone(), true, ""), developer);
+        assert_eq!(
+            parse_verify(
+                developer,
+                false,
+                "/Applications/Example.app: a sealed resource is missing or invalid\n"
+            ),
+            Signature::Invalid("a sealed resource is missing or invalid".into())
+        );
+        assert_eq!(
+            parse_verify(Signature::Unsigned, false, "not signed at all"),
+            Signature::Unsigned
+        );
+    }
+
+    #[test]
+    fn test_disable_commands_by_scope() {
+        let mut item = audit("com.orphan.agent.plist", false, Signature::Unknown);
+        assert_eq!(
+            disable_commands(&item, "501")[0].join(" "),
+            "launchctl disable gui/501/com.orphan.agent"
+        );
+        item.scope = Scope::Daemon;
+        let cmds = disable_commands(&item, "501");
+        assert_eq!(
+            cmds[0].join(" "),
+            "sudo -n launchctl disable system/com.orphan.agent"
+        );
+        assert_eq!(
+            cmds[1].join(" "),
+            "sudo -n launchctl bootout system/com.orphan.agent"
+        );
+    }
+}
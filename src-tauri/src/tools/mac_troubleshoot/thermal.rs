@@ -0,0 +1,469 @@
+//! Thermal state and throttling verdict for `check_thermals`.
+//!
+//! `pmset -g therm` reports the CPU speed limit macOS is enforcing and the
+//! last thermal/performance warning levels. `powermetrics` adds the thermal
+//! pressure level, die temperature and fan speed, but only runs as root, so
+//! it is attempted with `sudo -n` and skipped when that would prompt.
+//! Per-core utilisation comes from two Mach `host_processor_info` snapshots
+//! taken a short window apart.
+
+use super::sampler::Sampler;
+use super::{run_cmd, ToolResult};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+const DEFAULT_SAMPLE_SECS: f64 = 1.0;
+const MAX_SAMPLE_SECS: f64 = 10.0;
+/// A core this busy over the window counts as pegged.
+const PEGGED_CORE_PCT: f64 = 90.0;
+
+// ----- pmset -g therm -----
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub(super) struct ThermState {
+    pub cpu_speed_limit: Option<u32>,
+    pub cpu_scheduler_limit: Option<u32>,
+    pub cpu_available_cpus: Option<u32>,
+    pub thermal_warning_level: Option<u32>,
+    pub performance_warning_level: Option<u32>,
+}
+
+/// Parse `pmset -g therm`. "No ... has been recorded" leaves fields unset.
+pub(super) fn parse_pmset_therm(output: &str) -> ThermState {
+    let mut state = ThermState::default();
+    for line in output.lines() {
+        let lower = line.to_lowercase();
+        let trailing_number = || {
+            lower
+                .split_whitespace()
+                .last()
+                .and_then(|n| n.trim_end_matches('.').parse::<u32>().ok())
+        };
+        if lower.contains("thermal warning level set to") {
+            state.thermal_warning_level = trailing_number();
+        } else if lower.contains("performance warning level set to") {
+            state.performance_warning_level = trailing_number();
+        } else if let Some((key, value)) = line.split_once('=') {
+            let value = value.trim().parse::<u32>().ok();
+            match key.trim() {
+                "CPU_Speed_Limit" => state.cpu_speed_limit = value,
+                "CPU_Scheduler_Limit" => state.cpu_scheduler_limit = value,
+                "CPU_Available_CPUs" => state.cpu_available_cpus = value,
+                _ => {}
+            }
+        }
+    }
+    state
+}
+
+// ----- powermetrics -----
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub(super) struct PowerMetrics {
+    /// Nominal, Moderate, Heavy, Trapping or Sleeping (Apple silicon).
+    pub thermal_pressure: Option<String>,
+    /// Intel SMC sampler only.
+    pub cpu_die_temp_c: Option<f64>,
+    pub fan_rpm: Option<f64>,
+}
+
+/// Parse the `thermal` and `smc` samplers of `powermetrics`.
+pub(super) fn parse_powermetrics(output: &str) -> Option<PowerMetrics> {
+    let mut metrics = PowerMetrics::default();
+    for line in output.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let number = || {
+            value
+                .split_whitespace()
+                .next()
+                .and_then(|n| n.parse::<f64>().ok())
+        };
+        match key.trim() {
+            "Current pressure level" => metrics.thermal_pressure = Some(value.trim().to_string()),
+            "CPU die temperature" => metrics.cpu_die_temp_c = number(),
+            "Fan" => metrics.fan_rpm = number(),
+            _ => {}
+        }
+    }
+    (metrics != PowerMetrics::default()).then_some(metrics)
+}
+
+fn read_powermetrics() -> Option<PowerMetrics> {
+    // `-n` makes sudo fail instead of prompting when no cached credential.
+    let output = run_cmd(
+        "sudo",
+        &[
+            "-n",
+            "powermetrics",
+            "-n",
+            "1",
+            "-i",
+            "500",
+            "--samplers",
+            "thermal,smc",
+        ],
+    );
+    parse_powermetrics(&output)
+}
+
+// ----- Load and per-core utilisation -----
+
+/// `sysctl -n vm.loadavg` -> "{ 2.15 1.98 1.75 }"
+pub(super) fn parse_loadavg(output: &str) -> Option<[f64; 3]> {
+    let values: Vec<f64> = output
+        .split_whitespace()
+        .filter_map(|v| v.parse().ok())
+        .collect();
+    match values[..] {
+        [one, five, fifteen, ..] => Some([one, five, fifteen]),
+        _ => None,
+    }
+}
+
+/// Cumulative scheduler ticks for one core.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(super) struct CoreTicks {
+    pub user: u64,
+    pub system: u64,
+    pub idle: u64,
+    pub nice: u64,
+}
+
+/// Busy percentage per core between two tick snapshots.
+pub(super) fn core_usage(before: &[CoreTicks], after: &[CoreTicks]) -> Vec<f64> {
+    before
+        .iter()
+        .zip(after)
+        .map(|(b, a)| {
+            let busy = (a.user + a.system + a.nice).saturating_sub(b.user + b.system + b.nice);
+            let idle = a.idle.saturating_sub(b.idle);
+            if busy + idle == 0 {
+                0.0
+            } else {
+                (busy as f64 / (busy + idle) as f64 * 1000.0).round() / 10.0
+            }
+        })
+        .collect()
+}
+
+#[cfg(target_os = "macos")]
+fn core_ticks() -> Option<Vec<CoreTicks>> {
+    use std::os::raw::{c_int, c_uint};
+
+    const PROCESSOR_CPU_LOAD_INFO: c_int = 2;
+    const CPU_STATE_MAX: usize = 4;
+
+    extern "C" {
+        static mach_task_self_: c_uint;
+        fn mach_host_self() -> c_uint;
+        fn host_processor_info(
+            host: c_uint,
+            flavor: c_int,
+            out_processor_count: *mut c_uint,
+            out_processor_info: *mut *mut c_int,
+            out_processor_info_count: *mut c_uint,
+        ) -> c_int;
+        fn vm_deallocate(target_task: c_uint, address: usize, size: usize) -> c_int;
+    }
+
+    let mut cpu_count: c_uint = 0;
+    let mut info: *mut c_int = std::ptr::null_mut();
+    let mut info_count: c_uint = 0;
+    // SAFETY: out-pointers are valid locals; on success the kernel hands us
+    // `info_count` ints which we copy out before deallocating.
+    unsafe {
+        let rc = host_processor_info(
+            mach_host_self(),
+            PROCESSOR_CPU_LOAD_INFO,
+            &mut cpu_count,
+            &mut info,
+            &mut info_count,
+        );
+        if rc != 0 || info.is_null() {
+            return None;
+        }
+        let raw = std::slice::from_raw_parts(info, info_count as usize);
+        let ticks = raw
+            .chunks_exact(CPU_STATE_MAX)
+            .take(cpu_count as usize)
+            .map(|c| CoreTicks {
+                user: c[0] as u32 as u64,
+                system: c[1] as u32 as u64,
+                idle: c[2] as u32 as u64,
+                nice: c[3] as u32 as u64,
+            })
+            .collect();
+        vm_deallocate(
+            mach_task_self_,
+            info as usize,
+            info_count as usize * std::mem::size_of::<c_int>(),
+        );
+        Some(ticks)
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn core_ticks() -> Option<Vec<CoreTicks>> {
+    None
+}
+
+fn sample_cores(window: Duration) -> Option<Vec<f64>> {
+    let mut readings = Sampler::new(1, window).snapshots(core_ticks).into_iter();
+    let (before, after) = (readings.next()??, readings.next()??);
+    Some(core_usage(&before, &after))
+}
+
+// ----- Verdict -----
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum Verdict {
+    Normal,
+    Elevated,
+    Throttled,
+}
+
+/// Combine the signals into a verdict and the reasons behind it.
+pub(super) fn verdict(
+    therm: &ThermState,
+    metrics: Option<&PowerMetrics>,
+    load: Option<[f64; 3]>,
+    cores: &[f64],
+) -> (Verdict, Vec<String>) {
+    let mut verdict = Verdict::Normal;
+    let mut reasons = Vec::new();
+    let mut raise = |level: Verdict, reason: String| {
+        if level == Verdict::Throttled || verdict == Verdict::Normal {
+            verdict = level;
+        }
+        reasons.push(reason);
+    };
+
+    if let Some(limit) = therm.cpu_speed_limit.filter(|l| *l < 100) {
+        raise(
+            Verdict::Throttled,
+            format!("macOS is limiting CPU speed to {}%", limit),
+        );
+    }
+    if let Some(level) = therm.thermal_warning_level.filter(|l| *l > 0) {
+        raise(
+            Verdict::Elevated,
+            format!("Thermal warning level {} recorded", level),
+        );
+    }
+    if let Some(level) = therm.performance_warning_level.filter(|l| *l > 0) {
+        raise(
+            Verdict::Elevated,
+            format!("Performance warning level {} recorded", level),
+        );
+    }
+
+    if let Some(pressure) = metrics.and_then(|m| m.thermal_pressure.as_deref()) {
+        match pressure {
+            "Nominal" => {}
+            "Moderate" => raise(
+                Verdict::Elevated,
+                "Thermal pressure is moderate".to_string(),
+            ),
+            other => raise(
+                Verdict::Throttled,
+                format!("Thermal pressure is {}", other.to_lowercase()),
+            ),
+        }
+    }
+    if let Some(temp) = metrics
+        .and_then(|m| m.cpu_die_temp_c)
+        .filter(|t| *t >= 95.0)
+    {
+        raise(
+            Verdict::Elevated,
+            format!("CPU die temperature is {:.0}°C", temp),
+        );
+    }
+
+    let core_count = cores.len().max(1) as f64;
+    if let Some([one, _, _]) = load.filter(|l| !cores.is_empty() && l[0] > core_count) {
+        raise(
+            Verdict::Elevated,
+            format!("Load average {:.2} exceeds {} cores", one, cores.len()),
+        );
+    }
+    let pegged = cores.iter().filter(|c| **c >= PEGGED_CORE_PCT).count();
+    if pegged > 0 {
+        raise(
+            Verdict::Elevated,
+            format!(
+                "{} of {} cores above {:.0}% busy",
+                pegged,
+                cores.len(),
+                PEGGED_CORE_PCT
+            ),
+        );
+    }
+
+    (verdict, reasons)
+}
+
+pub(super) fn check_thermals(args: &Value) -> ToolResult {
+    let sample_secs = args
+        .get("sample_secs")
+        .and_then(|v| v.as_f64())
+        .unwrap_or(DEFAULT_SAMPLE_SECS)
+        .clamp(0.1, MAX_SAMPLE_SECS);
+
+    let therm = parse_pmset_therm(&run_cmd("pmset", &["-g", "therm"]));
+    let metrics = read_powermetrics();
+    let load = parse_loadavg(&run_cmd("sysctl", &["-n", "vm.loadavg"]));
+    let cores = sample_cores(Duration::from_secs_f64(sample_secs)).unwrap_or_default();
+
+    let (verdict, reasons) = verdict(&therm, metrics.as_ref(), load, &cores);
+    let average_core_pct = if cores.is_empty() {
+        None
+    } else {
+        Some((cores.iter().sum::<f64>() / cores.len() as f64 * 10.0).round() / 10.0)
+    };
+
+    ToolResult {
+        success: true,
+        data: json!({
+            "verdict": verdict,
+            "reasons": reasons,
+            "cpu_speed_limit": therm.cpu_speed_limit,
+            "cpu_scheduler_limit": therm.cpu_scheduler_limit,
+            "cpu_available_cpus": therm.cpu_available_cpus,
+            "thermal_warning_level": therm.thermal_warning_level,
+            "performance_warning_level": therm.performance_warning_level,
+            "powermetrics": metrics,
+            "load_average": load.map(|[one, five, fifteen]| json!({
+                "one": one, "five": five, "fifteen": fifteen,
+            })),
+            "sample_secs": sample_secs,
+            "per_core_pct": cores,
+            "average_core_pct": average_core_pct,
+        }),
+        error: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PMSET_THROTTLED: &str = "\
+2024-05-01 10:00:00 +0200 Thermal Warning Level Set To 1
+2024-05-01 10:00:00 +0200 CPU Power notify
+\tCPU_Scheduler_Limit \t= 100
+\tCPU_Available_CPUs \t= 8
+\tCPU_Speed_Limit \t= 70
+";
+
+    const PMSET_QUIET: &str = "\
+Note: No thermal warning level has been recorded
+Note: No performance warning level has been recorded
+Note: No CPU power status has been recorded
+";
+
+    const POWERMETRICS: &str = "\
+Machine model: MacBookPro18,3
+OS version: 23E224
+
+*** Sampled system activity (Wed May  1 10:00:00 2024 +0200) (502.10ms elapsed) ***
+
+**** SMC sensors ****
+
+CPU die temperature: 96.50 C
+Fan: 5400.00 rpm
+
+**** Thermal pressure ****
+
+Current pressure level: Heavy
+";
+
+    #[test]
+    fn test_parse_pmset_therm() {
+        let state = parse_pmset_therm(PMSET_THROTTLED);
+        assert_eq!(state.cpu_speed_limit, Some(70));
+        assert_eq!(state.cpu_scheduler_limit, Some(100));
+        assert_eq!(state.cpu_available_cpus, Some(8));
+        assert_eq!(state.thermal_warning_level, Some(1));
+        assert_eq!(state.performance_warning_level, None);
+
+        assert_eq!(parse_pmset_therm(PMSET_QUIET), ThermState::default());
+    }
+
+    #[test]
+    fn test_parse_powermetrics() {
+        let m = parse_powermetrics(POWERMETRICS).unwrap();
+        assert_eq!(m.thermal_pressure.as_deref(), Some("Heavy"));
+        assert_eq!(m.cpu_die_temp_c, Some(96.5));
+        assert_eq!(m.fan_rpm, Some(5400.0));
+        // sudo refused: no output, no metrics.
+        assert!(parse_powermetrics("").is_none());
+    }
+
+    #[test]
+    fn test_parse_loadavg() {
+        assert_eq!(
+            parse_loadavg("{ 2.15 1.98 1.75 }"),
+            Some([2.15, 1.98, 1.75])
+        );
+        assert_eq!(parse_loadavg(""), None);
+    }
+
+    #[test]
+    fn test_core_usage() {
+        let before = [
+            CoreTicks {
+                user: 100,
+                system: 50,
+                idle: 850,
+                nice: 0,
+            },
+            CoreTicks {
+                user: 0,
+                system: 0,
+                idle: 1000,
+                nice: 0,
+            },
+        ];
+        let after = [
+            CoreTicks {
+                user: 170,
+                system: 70,
+                idle: 860,
+                nice: 0,
+            },
+            CoreTicks {
+                user: 0,
+                system: 0,
+                idle: 1000,
+                nice: 0,
+            },
+        ];
+        assert_eq!(core_usage(&before, &after), vec![90.0, 0.0]);
+    }
+
+    #[test]
+    fn test_verdict() {
+        let quiet = parse_pmset_therm(PMSET_QUIET);
+        let (v, reasons) = verdict(&quiet, None, Some([1.0, 1.0, 1.0]), &[20.0, 10.0]);
+        assert_eq!(v, Verdict::Normal);
+        assert!(reasons.is_empty());
+
+        // Busy but not throttled.
+        let (v, reasons) = verdict(&quiet, None, Some([3.5, 2.0, 1.0]), &[95.0, 92.0]);
+        assert_eq!(v, Verdict::Elevated);
+        assert_eq!(reasons.len(), 2);
+
+        // A speed limit is throttling regardless of order of other signals.
+        let throttled = parse_pmset_therm(PMSET_THROTTLED);
+        let metrics = parse_powermetrics(POWERMETRICS).unwrap();
+        let (v, reasons) = verdict(&throttled, Some(&metrics), None, &[]);
+        assert_eq!(v, Verdict::Throttled);
+        assert!(reasons[0].contains("70%"));
+        assert!(reasons.iter().any(|r| r.contains("heavy")));
+    }
+}
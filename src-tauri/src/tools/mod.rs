@@ -1,17 +1,37 @@
 pub mod auto_mechanic;
+pub mod declarative;
 pub mod mac_troubleshoot;
+pub mod plugin;
+pub mod policy;
 
+use policy::{PendingAction, PendingActions, ToolPolicy};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::sync::Arc;
 
+/// How much a tool can change the system.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskLevel {
+    /// Only reads state.
+    #[default]
+    ReadOnly,
+    /// Changes state in an easily reversible way.
+    Modifying,
+    /// Kills, deletes, or otherwise changes state that is hard to undo.
+    /// Always requires user confirmation.
+    Destructive,
+}
+
 /// Describes a single tool that a module exposes.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolDefinition {
     pub name: String,
     pub description: String,
     pub parameters: Value,
+    #[serde(default)]
+    pub risk: RiskLevel,
 }
 
 /// The result of executing a tool.
@@ -35,12 +55,31 @@ pub trait ToolModule: Send + Sync {
 
     /// Execute a named tool with the given JSON arguments.
     fn execute(&self, tool_name: &str, args: Value) -> ToolResult;
+
+    /// Describe what a call would do without doing it. Shown to the user
+    /// alongside the confirmation prompt for destructive tools.
+    fn preview(&self, _tool_name: &str, _args: &Value) -> Option<Value> {
+        None
+    }
+
+    /// Phrases that route straight to a tool in the keyword router. Native
+    /// modules route in the engine instead and declare none.
+    fn keywords(&self, _tool_name: &str) -> Vec<String> {
+        Vec::new()
+    }
 }
 
 /// Registry that holds N tool modules and dispatches by tool name in O(1).
+///
+/// Tools denied by the [`ToolPolicy`] are hidden from every listing and
+/// cannot be executed. Destructive tools are never run directly by
+/// [`execute`](Self::execute); they are parked until [`confirm`](Self::confirm)
+/// is called with the returned token.
 pub struct ModuleRegistry {
     modules: Vec<Arc<dyn ToolModule>>,
     tool_index: HashMap<String, usize>, // tool_name → index into modules
+    policy: ToolPolicy,
+    pending: PendingActions,
 }
 
 impl ModuleRegistry {
@@ -49,13 +88,26 @@ impl ModuleRegistry {
         Self {
             modules: Vec::new(),
             tool_index: HashMap::new(),
+            policy: ToolPolicy::default(),
+            pending: PendingActions::default(),
         }
     }
 
-    /// Register a module. Rejects name collisions with a descriptive error.
+    /// Replace the allow/deny/confirm policy.
+    pub fn set_policy(&mut self, policy: ToolPolicy) {
+        self.policy = policy;
+    }
+
+    fn allowed(&self, module: &dyn ToolModule, tool_name: &str) -> bool {
+        self.policy.is_allowed(module.name(), tool_name)
+    }
+
+    /// Register a module. Rejects name collisions with a descriptive error,
+    /// leaving the registry unchanged.
     pub fn register(&mut self, module: Arc<dyn ToolModule>) -> Result<(), String> {
         let idx = self.modules.len();
-        for tool in module.tools() {
+        let tools = module.tools();
+        for tool in &tools {
             if let Some(&existing_idx) = self.tool_index.get(&tool.name) {
                 return Err(format!(
                     "Tool '{}' from module '{}' collides with module '{}'",
@@ -64,6 +116,8 @@ impl ModuleRegistry {
                     self.modules[existing_idx].name()
                 ));
             }
+        }
+        for tool in tools {
             self.tool_index.insert(tool.name, idx);
         }
         self.modules.push(module);
@@ -72,43 +126,134 @@ impl ModuleRegistry {
 
     /// Return all tool definitions across all registered modules.
     pub fn all_tools(&self) -> Vec<ToolDefinition> {
-        self.modules.iter().flat_map(|m| m.tools()).collect()
+        self.modules
+            .iter()
+            .flat_map(|m| {
+                m.tools()
+                    .into_iter()
+                    .filter(|t| self.allowed(m.as_ref(), &t.name))
+            })
+            .collect()
     }
 
     /// Execute a tool by name, dispatching to the owning module via the index.
+    ///
+    /// Calls that need confirmation (destructive tools, or tools listed under
+    /// `confirm` in the policy) are not run. Instead the result carries
+    /// `requires_confirmation: true` and a `confirmation` object with a token
+    /// for [`confirm`](Self::confirm). Calls with `"dry_run": true` are run
+    /// directly only if the tool declares a `dry_run` parameter; a tool that
+    /// would ignore it is parked like any other call.
     pub fn execute(&self, tool_name: &str, args: Value) -> ToolResult {
-        match self.tool_index.get(tool_name) {
-            Some(&idx) => self.modules[idx].execute(tool_name, args),
-            None => ToolResult {
+        let module = match self.tool_index.get(tool_name) {
+            Some(&idx) => &self.modules[idx],
+            None => return unknown_tool(tool_name),
+        };
+        if !self.allowed(module.as_ref(), tool_name) {
+            return ToolResult {
                 success: false,
                 data: Value::Null,
-                error: Some(format!("Unknown tool: {}", tool_name)),
-            },
+                error: Some(format!("Tool '{}' is disabled by policy", tool_name)),
+            };
         }
+
+        let def = module.tools().into_iter().find(|t| t.name == tool_name);
+        let risk = def.as_ref().map(|d| d.risk).unwrap_or_default();
+        let dry_run = args.get("dry_run").and_then(|v| v.as_bool()) == Some(true)
+            && def
+                .as_ref()
+                .is_some_and(|d| d.parameters.pointer("/properties/dry_run").is_some());
+
+        if !dry_run && self.policy.requires_confirmation(module.name(), tool_name, risk) {
+            let summary = format!(
+                "{} ({})",
+                def.map(|d| d.description).unwrap_or_else(|| tool_name.to_string()),
+                args
+            );
+            let preview = module.preview(tool_name, &args);
+            let action = PendingAction::new(tool_name, args, risk, summary, preview);
+            let data = json!({
+                "requires_confirmation": true,
+                "confirmation": &action,
+            });
+            self.pending.insert(action);
+            return ToolResult {
+                success: true,
+                data,
+                error: None,
+            };
+        }
+
+        module.execute(tool_name, args)
     }
 
-    /// Check if a tool name is registered.
+    /// Run a previously proposed action. Tokens are single-use and expire.
+    pub fn confirm(&self, token: &str) -> ToolResult {
+        let action = match self.pending.take(token) {
+            Some(a) => a,
+            None => {
+                return ToolResult {
+                    success: false,
+                    data: Value::Null,
+                    error: Some("Unknown or expired confirmation token".into()),
+                }
+            }
+        };
+        match self.tool_index.get(&action.tool_name) {
+            Some(&idx) if self.allowed(self.modules[idx].as_ref(), &action.tool_name) => {
+                self.modules[idx].execute(&action.tool_name, action.arguments)
+            }
+            _ => unknown_tool(&action.tool_name),
+        }
+    }
+
+    /// Discard a proposed action. Returns `false` if the token was unknown.
+    pub fn cancel(&self, token: &str) -> bool {
+        self.pending.take(token).is_some()
+    }
+
+    /// Check if a tool name is registered and allowed by the policy.
     pub fn has_tool(&self, tool_name: &str) -> bool {
-        self.tool_index.contains_key(tool_name)
+        match self.tool_index.get(tool_name) {
+            Some(&idx) => self.allowed(self.modules[idx].as_ref(), tool_name),
+            None => false,
+        }
     }
 
     /// Return tool definitions for a specific module only.
     pub fn module_tools(&self, module_name: &str) -> Vec<ToolDefinition> {
-        self.modules
-            .iter()
-            .filter(|m| m.name() == module_name)
-            .flat_map(|m| m.tools())
+        self.all_tools()
+            .into_iter()
+            .filter(|t| self.tool_belongs_to_module(&t.name, module_name))
             .collect()
     }
 
-    /// Check if a tool belongs to a specific module.
+    /// Check if an allowed tool belongs to a specific module.
     pub fn tool_belongs_to_module(&self, tool_name: &str, module_name: &str) -> bool {
         match self.tool_index.get(tool_name) {
-            Some(&idx) => self.modules[idx].name() == module_name,
+            Some(&idx) => {
+                self.modules[idx].name() == module_name
+                    && self.allowed(self.modules[idx].as_ref(), tool_name)
+            }
             None => false,
         }
     }
 
+    /// Routing keywords declared by modules, per allowed tool, in
+    /// registration order.
+    pub fn routing_keywords(&self) -> Vec<(String, Vec<String>)> {
+        let mut routes = Vec::new();
+        for module in &self.modules {
+            for tool in module.tools() {
+                let keywords = module.keywords(&tool.name);
+                if !keywords.is_empty() && self.allowed(module.as_ref(), &tool.name) {
+                    routes.push((tool.name, keywords));
+                }
+            }
+        }
+        routes
+    }
+
     /// Return the names of all registered modules (for status/debug).
     pub fn module_names(&self) -> Vec<&str> {
         self.modules.iter().map(|m| m.name()).collect()
@@ -118,16 +263,32 @@ impl ModuleRegistry {
     pub fn modules_info(&self) -> Vec<ModuleInfo> {
         self.modules
             .iter()
-            .map(|m| ModuleInfo {
-                name: m.name().to_string(),
-                description: m.description().to_string(),
-                tool_count: m.tools().len(),
-                tool_names: m.tools().iter().map(|t| t.name.clone()).collect(),
+            .map(|m| {
+                let tool_names: Vec<String> = m
+                    .tools()
+                    .into_iter()
+                    .map(|t| t.name)
+                    .filter(|name| self.allowed(m.as_ref(), name))
+                    .collect();
+                ModuleInfo {
+                    name: m.name().to_string(),
+                    description: m.description().to_string(),
+                    tool_count: tool_names.len(),
+                    tool_names,
+                }
             })
             .collect()
     }
 }
 
+fn unknown_tool(tool_name: &str) -> ToolResult {
+    ToolResult {
+        success: false,
+        data: Value::Null,
+        error: Some(format!("Unknown tool: {}", tool_name)),
+    }
+}
+
 /// Summary info about a registered module, suitable for sending to the frontend.
 #[derive(Debug, Clone, Serialize)]
 pub struct ModuleInfo {
@@ -192,4 +353,99 @@ mod tests {
         let names = registry.module_names();
         assert_eq!(names, vec!["mac_troubleshoot"]);
     }
+
+    #[test]
+    fn test_destructive_tool_requires_confirmation() {
+        let mut registry = ModuleRegistry::new();
+        registry
+            .register(Arc::new(MacTroubleshootModule::new()))
+            .unwrap();
+
+        let result = registry.execute("kill_process", json!({"process_name": "nonexistent-proc"}));
+        assert!(result.success);
+        assert_eq!(result.data["requires_confirmation"], true);
+        let confirmation = &result.data["confirmation"];
+        assert_eq!(confirmation["tool_name"], "kill_process");
+        assert_eq!(confirmation["risk"], "destructive");
+
+        let token = confirmation["token"].as_str().unwrap();
+        assert!(registry.cancel(token));
+        let result = registry.confirm(token);
+        assert!(!result.success);
+        assert!(result.error.unwrap().contains("expired"));
+    }
+
+    /// A destructive tool that knows nothing about `dry_run`.
+    struct Shredder;
+
+    impl ToolModule for Shredder {
+        fn name(&self) -> &str {
+            "shredder"
+        }
+
+        fn description(&self) -> &str {
+            "Test module"
+        }
+
+        fn tools(&self) -> Vec<ToolDefinition> {
+            vec![ToolDefinition {
+                name: "shred".into(),
+                description: "Shred a file".into(),
+                parameters: json!({"type": "object", "properties": {"path": {"type": "string"}}}),
+                risk: RiskLevel::Destructive,
+            }]
+        }
+
+        fn execute(&self, _tool_name: &str, _args: Value) -> ToolResult {
+            ToolResult {
+                success: true,
+                data: json!({"shredded": true}),
+                error: None,
+            }
+        }
+    }
+
+    #[test]
+    fn test_dry_run_only_skips_confirmation_when_declared() {
+        let mut registry = ModuleRegistry::new();
+        registry.register(Arc::new(Shredder)).unwrap();
+        registry
+            .register(Arc::new(MacTroubleshootModule::new()))
+            .unwrap();
+
+        let result = registry.execute("shred", json!({"path": "/tmp/x", "dry_run": true}));
+        assert_eq!(result.data["requires_confirmation"], true);
+        assert!(result.data.get("shredded").is_none());
+
+        // disable_startup_item declares dry_run and previews by itself.
+        let result = registry.execute(
+            "disable_startup_item",
+            json!({"label": "com.example.none", "dry_run": true}),
+        );
+        assert!(result.data["requires_confirmation"].is_null());
+    }
+
+    #[test]
+    fn test_policy_hides_and_blocks_denied_tools() {
+        let mut registry = ModuleRegistry::new();
+        registry
+            .register(Arc::new(MacTroubleshootModule::new()))
+            .unwrap();
+        registry.set_policy(
+            serde_json::from_value(json!({
+                "modules": {"mac_troubleshoot": {"deny": ["kill_process"]}}
+            }))
+            .unwrap(),
+        );
+
+        assert!(!registry.has_tool("kill_process"));
+        assert!(registry.all_tools().iter().all(|t| t.name != "kill_process"));
+        assert!(!registry.modules_info()[0]
+            .tool_names
+            .contains(&"kill_process".to_string()));
+
+        let result = registry.execute("kill_process", json!({"process_name": "x"}));
+        assert!(!result.success);
+        assert!(result.error.unwrap().contains("disabled by policy"));
+    }
 }
@@ -0,0 +1,297 @@
+//! Plugin modules: tools served by a separate executable, in any language,
+//! over JSON-RPC 2.0 on its stdin and stdout (one message per line).
+//!
+//! Methods a plugin answers:
+//!
+//! - `list_tools` → `{"name", "description", "tools": [ToolDefinition]}`;
+//!   a tool without a `risk` is treated as destructive
+//! - `execute` `{"tool", "arguments"}` → `{"success", "data", "error"}`
+//! - `health` → any result; an error or no answer means unhealthy
+//! - `preview` `{"tool", "arguments"}` → optional, shown when confirming a
+//!   destructive tool
+//!
+//! Stderr is passed through for logging. A plugin that crashes or overruns
+//! its timeout is killed and restarted on the next call, so it can never
+//! take the app down with it.
+
+mod rpc;
+
+use super::{RiskLevel, ToolDefinition, ToolModule, ToolResult};
+use rpc::PluginProcess;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+fn default_timeout() -> u64 {
+    30
+}
+
+/// `<name>.toml` next to a plugin that needs an interpreter or arguments.
+/// Relative paths in `command` resolve against the plugin directory.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    command: Vec<String>,
+    /// Per-call timeout, including `list_tools` at startup.
+    #[serde(default = "default_timeout")]
+    timeout_secs: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ToolList {
+    name: String,
+    #[serde(default)]
+    description: String,
+    tools: Vec<PluginTool>,
+}
+
+/// A tool as a plugin declares it. Unlike built-in tools, `risk` has no
+/// read-only default: an out-of-process tool that does not say what it does
+/// is confirmed like a destructive one.
+#[derive(Debug, Deserialize)]
+struct PluginTool {
+    name: String,
+    description: String,
+    parameters: Value,
+    risk: Option<RiskLevel>,
+}
+
+impl From<PluginTool> for ToolDefinition {
+    fn from(tool: PluginTool) -> Self {
+        ToolDefinition {
+            name: tool.name,
+            description: tool.description,
+            parameters: tool.parameters,
+            risk: tool.risk.unwrap_or(RiskLevel::Destructive),
+        }
+    }
+}
+
+/// A running plugin, registered like any native module.
+pub struct PluginModule {
+    name: String,
+    description: String,
+    tools: Vec<ToolDefinition>,
+    timeout: Duration,
+    process: Mutex<PluginProcess>,
+}
+
+impl PluginModule {
+    /// Start the plugin and ask it for its tools.
+    pub fn start(command: Vec<String>, dir: &Path, timeout: Duration) -> Result<Self, String> {
+        let mut process = PluginProcess::new(command, dir.to_path_buf());
+        let list = process.call("list_tools", json!({}), timeout)?;
+        let list: ToolList = serde_json::from_value(list)
+            .map_err(|e| format!("Invalid list_tools result: {}", e))?;
+        if list.name.trim().is_empty() {
+            return Err("plugin name is empty".into());
+        }
+        if list.tools.is_empty() {
+            return Err(format!("plugin {:?} offers no tools", list.name));
+        }
+        Ok(PluginModule {
+            name: list.name,
+            description: list.description,
+            tools: list.tools.into_iter().map(ToolDefinition::from).collect(),
+            timeout,
+            process: Mutex::new(process),
+        })
+    }
+
+    /// Start a plugin from an executable or a `.toml` manifest.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let dir = path.parent().unwrap_or(Path::new("."));
+        let (command, timeout_secs) = if path.extension().is_some_and(|ext| ext == "toml") {
+            let raw = std::fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read plugin {}: {}", path.display(), e))?;
+            let manifest: Manifest = toml::from_str(&raw)
+                .map_err(|e| format!("Invalid plugin {}: {}", path.display(), e))?;
+            (manifest.command, manifest.timeout_secs)
+        } else {
+            (vec![path.display().to_string()], default_timeout())
+        };
+        Self::start(command, dir, Duration::from_secs(timeout_secs.max(1)))
+            .map_err(|e| format!("Plugin {} failed to start: {}", path.display(), e))
+    }
+
+    /// Ask the plugin whether it is well, restarting it if it had crashed.
+    pub fn health(&self) -> Result<Value, String> {
+        let mut process = self.process.lock().map_err(|e| e.to_string())?;
+        let status = process.call("health", json!({}), self.timeout)?;
+        Ok(json!({"status": status, "pid": process.pid(), "restarts": process.restarts}))
+    }
+
+    fn call(&self, method: &str, tool_name: &str, args: &Value) -> Result<Value, String> {
+        let mut process = self.process.lock().map_err(|e| e.to_string())?;
+        process.call(
+            method,
+            json!({"tool": tool_name, "arguments": args}),
+            self.timeout,
+        )
+    }
+}
+
+/// Plugins in `dir`: every `*.toml` manifest, plus every executable file
+/// without one. A missing directory has no plugins; a plugin that fails to
+/// start is reported without stopping the others.
+pub fn load_dir(dir: &Path) -> Vec<Result<PluginModule, String>> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            !p.file_name()
+                .is_some_and(|n| n.to_string_lossy().starts_with('.'))
+        })
+        .collect();
+    paths.sort();
+    let manifests: Vec<PathBuf> = paths
+        .iter()
+        .filter(|p| p.extension().is_some_and(|ext| ext == "toml"))
+        .cloned()
+        .collect();
+    paths
+        .iter()
+        .filter(|p| {
+            manifests.contains(p)
+                || (is_executable(p) && !manifests.contains(&p.with_extension("toml")))
+        })
+        .map(|p| PluginModule::load(p))
+        .collect()
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "exe")
+}
+
+fn failure(error: String) -> ToolResult {
+    ToolResult {
+        success: false,
+        data: Value::Null,
+        error: Some(error),
+    }
+}
+
+impl ToolModule for PluginModule {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn tools(&self) -> Vec<ToolDefinition> {
+        self.tools.clone()
+    }
+
+    fn execute(&self, tool_name: &str, args: Value) -> ToolResult {
+        if !self.tools.iter().any(|t| t.name == tool_name) {
+            return failure(format!("Unknown tool: {}", tool_name));
+        }
+        match self.call("execute", tool_name, &args) {
+            Ok(result) => serde_json::from_value(result).unwrap_or_else(|e| {
+                failure(format!(
+                    "Plugin {} returned an invalid result: {}",
+                    self.name, e
+                ))
+            }),
+            Err(e) => failure(format!("Plugin {}: {}", self.name, e)),
+        }
+    }
+
+    fn preview(&self, tool_name: &str, args: &Value) -> Option<Value> {
+        // Plugins without a preview answer "method not found".
+        self.call("preview", tool_name, args)
+            .ok()
+            .filter(|p| !p.is_null())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::ModuleRegistry;
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    fn fixtures() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/plugins")
+    }
+
+    fn pid(module: &PluginModule) -> Value {
+        let result = module.execute("plugin_pid", json!({}));
+        assert!(result.success, "{:?}", result.error);
+        result.data["pid"].clone()
+    }
+
+    #[test]
+    fn test_plugin_lifecycle() {
+        let mut plugins = load_dir(&fixtures());
+        assert_eq!(plugins.len(), 1);
+        let module = plugins.remove(0).unwrap();
+        assert_eq!(module.name(), "echo_plugin");
+        assert_eq!(module.tools().len(), 6);
+        assert_eq!(module.health().unwrap()["status"]["ok"], true);
+
+        let echo = module.execute("plugin_echo", json!({"message": "hi"}));
+        assert_eq!(echo.data, json!({"echo": "hi"}));
+        assert!(!module.execute("plugin_missing", json!({})).success);
+
+        // A crash fails the call; the next one runs in a fresh process.
+        let first = pid(&module);
+        let crashed = module.execute("plugin_crash", json!({}));
+        assert!(crashed.error.unwrap().contains("exited"));
+        let second = pid(&module);
+        assert_ne!(first, second);
+        assert_eq!(module.health().unwrap()["restarts"], 1);
+
+        // Overrunning the 2s manifest timeout kills the plugin.
+        let started = Instant::now();
+        let slow = module.execute("plugin_sleep", json!({"seconds": 10}));
+        assert!(slow.error.unwrap().contains("timed out"));
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_ne!(pid(&module), second);
+    }
+
+    #[test]
+    fn test_plugin_destructive_tools_confirm() {
+        let module = PluginModule::load(&fixtures().join("echo_plugin.toml")).unwrap();
+        let mut registry = ModuleRegistry::new();
+        registry.register(Arc::new(module)).unwrap();
+        assert!(registry.has_tool("plugin_echo"));
+
+        let parked = registry.execute("plugin_wipe", json!({"path": "/tmp/x"}));
+        assert_eq!(parked.data["requires_confirmation"], true);
+        let confirmation = &parked.data["confirmation"];
+        assert_eq!(confirmation["preview"], json!({"would_delete": "/tmp/x"}));
+        let token = confirmation["token"].as_str().unwrap();
+        assert_eq!(registry.confirm(token).data, json!({"deleted": "/tmp/x"}));
+
+        // A tool that does not declare its risk is confirmed too.
+        let parked = registry.execute("plugin_touch", json!({"path": "/tmp/x"}));
+        assert_eq!(parked.data["requires_confirmation"], true);
+        assert_eq!(parked.data["confirmation"]["risk"], "destructive");
+        let read_only = registry.execute("plugin_echo", json!({"message": "hi"}));
+        assert_eq!(read_only.data, json!({"echo": "hi"}));
+    }
+
+    #[test]
+    fn test_plugin_that_fails_to_start() {
+        let dir = std::env::temp_dir();
+        for command in [vec!["false"], vec!["sh", "-c", "echo not json"]] {
+            let command = command.into_iter().map(String::from).collect();
+            assert!(PluginModule::start(command, &dir, Duration::from_secs(1)).is_err());
+        }
+    }
+}
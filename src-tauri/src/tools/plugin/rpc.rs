@@ -0,0 +1,224 @@
+//! The plugin process and its JSON-RPC 2.0 channel: one request per line on
+//! the plugin's stdin, one response per line on its stdout.
+//!
+//! A plugin that exits or stops answering is killed and started again on
+//! the next call. After [`MAX_CRASHES`] failures in a row it is left alone
+//! for [`COOLDOWN`] so a broken plugin does not respawn on every request.
+
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+pub(super) const MAX_CRASHES: u32 = 3;
+pub(super) const COOLDOWN: Duration = Duration::from_secs(30);
+
+struct Running {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+pub(super) struct PluginProcess {
+    command: Vec<String>,
+    dir: PathBuf,
+    running: Option<Running>,
+    next_id: u64,
+    /// Failures since the last good response.
+    crashes: u32,
+    cooldown_until: Option<Instant>,
+    /// Times the process was started after the first.
+    pub restarts: u32,
+    started: bool,
+}
+
+impl PluginProcess {
+    /// `command` runs in `dir`, where relative paths in it resolve.
+    pub(super) fn new(command: Vec<String>, dir: PathBuf) -> Self {
+        PluginProcess {
+            command,
+            dir,
+            running: None,
+            next_id: 1,
+            crashes: 0,
+            cooldown_until: None,
+            restarts: 0,
+            started: false,
+        }
+    }
+
+    pub(super) fn pid(&self) -> Option<u32> {
+        self.running.as_ref().map(|r| r.child.id())
+    }
+
+    fn spawn(&mut self) -> Result<(), String> {
+        let (program, args) = self
+            .command
+            .split_first()
+            .ok_or("plugin command is empty")?;
+        let mut child = Command::new(program)
+            .args(args)
+            .current_dir(&self.dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|e| format!("Failed to start {}: {}", program, e))?;
+        let stdin = child.stdin.take().ok_or("plugin stdin unavailable")?;
+        let stdout = child.stdout.take().ok_or("plugin stdout unavailable")?;
+        // Lines arrive on a channel so a call can wait with a timeout; the
+        // channel disconnects when the plugin closes stdout or exits.
+        let (tx, lines) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        if self.started {
+            self.restarts += 1;
+        }
+        self.started = true;
+        self.running = Some(Running {
+            child,
+            stdin,
+            lines,
+        });
+        Ok(())
+    }
+
+    /// Kill the process; the next call starts a new one.
+    pub(super) fn stop(&mut self) {
+        if let Some(mut running) = self.running.take() {
+            let _ = running.child.kill();
+            let _ = running.child.wait();
+        }
+    }
+
+    fn fail(&mut self, error: String) -> String {
+        self.stop();
+        self.crashes += 1;
+        if self.crashes >= MAX_CRASHES {
+            self.cooldown_until = Some(Instant::now() + COOLDOWN);
+        }
+        error
+    }
+
+    /// Send one request and wait up to `timeout` for its response.
+    pub(super) fn call(
+        &mut self,
+        method: &str,
+        params: Value,
+        timeout: Duration,
+    ) -> Result<Value, String> {
+        if let Some(until) = self.cooldown_until {
+            let now = Instant::now();
+            if now < until {
+                return Err(format!(
+                    "plugin failed {} times in a row; retrying in {}s",
+                    self.crashes,
+                    (until - now).as_secs() + 1
+                ));
+            }
+            self.cooldown_until = None;
+        }
+        if self.running.is_none() {
+            self.spawn()?;
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        let request = json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params});
+        let running = self.running.as_mut().expect("spawned above");
+        let sent = writeln!(running.stdin, "{}", request).and_then(|_| running.stdin.flush());
+        if let Err(e) = sent {
+            return Err(self.fail(format!("plugin is not reading requests: {}", e)));
+        }
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            let running = self.running.as_mut().expect("still running");
+            let wait = deadline.saturating_duration_since(Instant::now());
+            match running.lines.recv_timeout(wait) {
+                Ok(line) => {
+                    // Anything but the response to this request (log lines,
+                    // a late answer to a call that timed out) is skipped.
+                    let Ok(response) = serde_json::from_str::<Value>(&line) else {
+                        continue;
+                    };
+                    if response.get("id") != Some(&json!(id)) {
+                        continue;
+                    }
+                    self.crashes = 0;
+                    if let Some(error) = response.get("error") {
+                        return Err(format!(
+                            "{} (code {})",
+                            error["message"].as_str().unwrap_or("plugin error"),
+                            error["code"]
+                        ));
+                    }
+                    return Ok(response.get("result").cloned().unwrap_or(Value::Null));
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err(self.fail(format!(
+                        "{} timed out after {}s; the plugin was restarted",
+                        method,
+                        timeout.as_secs_f64()
+                    )));
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    let status = running.child.wait().ok();
+                    return Err(self.fail(match status {
+                        Some(status) => format!("plugin exited during {} ({})", method, status),
+                        None => format!("plugin exited during {}", method),
+                    }));
+                }
+            }
+        }
+    }
+}
+
+impl Drop for PluginProcess {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crashing_plugin_cools_down() {
+        let mut process = PluginProcess::new(
+            vec!["sh".into(), "-c".into(), "exit 1".into()],
+            std::env::temp_dir(),
+        );
+        for _ in 0..MAX_CRASHES {
+            let err = process
+                .call("health", json!({}), Duration::from_secs(5))
+                .unwrap_err();
+            assert!(err.contains("plugin"), "{}", err);
+        }
+        assert_eq!(process.restarts, MAX_CRASHES - 1);
+        let err = process
+            .call("health", json!({}), Duration::from_secs(5))
+            .unwrap_err();
+        assert!(err.contains("retrying in"), "{}", err);
+        // No process is started while cooling down.
+        assert_eq!(process.restarts, MAX_CRASHES - 1);
+    }
+
+    #[test]
+    fn test_missing_program() {
+        let mut process =
+            PluginProcess::new(vec!["/nonexistent/plugin".into()], std::env::temp_dir());
+        let err = process
+            .call("health", json!({}), Duration::from_secs(1))
+            .unwrap_err();
+        assert!(err.contains("Failed to start"));
+    }
+}
@@ -0,0 +1,229 @@
+//! Tool policy and confirmation tokens.
+//!
+//! A policy file can allow or deny tools per module and require confirmation
+//! for tools beyond the ones that are destructive by definition. Destructive
+//! calls are not executed directly: the registry parks them as a
+//! [`PendingAction`] and hands back a one-time token that the UI must confirm.
+
+use super::RiskLevel;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// How long a proposed action stays confirmable.
+const CONFIRMATION_TTL: Duration = Duration::from_secs(300);
+
+/// Per-module rules. Tool names may use a trailing `*` wildcard.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModulePolicy {
+    /// When present, only these tools are available.
+    #[serde(default)]
+    pub allow: Option<Vec<String>>,
+    /// Tools that are never available. Takes precedence over `allow`.
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// Tools that need confirmation even though they are not destructive.
+    #[serde(default)]
+    pub confirm: Vec<String>,
+}
+
+/// Policy loaded from `policy.json`:
+///
+/// ```json
+/// {
+///   "modules": {
+///     "mac_troubleshoot": { "deny": ["kill_process"] },
+///     "auto_mechanic": { "allow": ["check_*", "run_vehicle_checkup"] }
+///   }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolPolicy {
+    #[serde(default)]
+    pub modules: HashMap<String, ModulePolicy>,
+}
+
+fn rule_matches(rule: &str, tool_name: &str) -> bool {
+    match rule.strip_suffix('*') {
+        Some(prefix) => tool_name.starts_with(prefix),
+        None => rule == tool_name,
+    }
+}
+
+impl ToolPolicy {
+    /// Load a policy file. A missing file yields the permissive default.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let raw = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read policy {}: {}", path.display(), e))?;
+        serde_json::from_str(&raw)
+            .map_err(|e| format!("Invalid policy {}: {}", path.display(), e))
+    }
+
+    /// Whether `tool_name` from `module_name` may be offered and executed.
+    pub fn is_allowed(&self, module_name: &str, tool_name: &str) -> bool {
+        let Some(rules) = self.modules.get(module_name) else {
+            return true;
+        };
+        if rules.deny.iter().any(|r| rule_matches(r, tool_name)) {
+            return false;
+        }
+        match &rules.allow {
+            Some(allow) => allow.iter().any(|r| rule_matches(r, tool_name)),
+            None => true,
+        }
+    }
+
+    /// Whether a call needs explicit user confirmation before it runs.
+    pub fn requires_confirmation(&self, module_name: &str, tool_name: &str, risk: RiskLevel) -> bool {
+        risk == RiskLevel::Destructive
+            || self
+                .modules
+                .get(module_name)
+                .is_some_and(|rules| rules.confirm.iter().any(|r| rule_matches(r, tool_name)))
+    }
+}
+
+/// A tool call waiting for the user to confirm it.
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingAction {
+    pub token: String,
+    pub tool_name: String,
+    pub arguments: Value,
+    pub risk: RiskLevel,
+    /// Human-readable description of what will happen.
+    pub summary: String,
+    /// Module-provided dry-run of the call, if it supports one.
+    pub preview: Option<Value>,
+    #[serde(skip)]
+    created: Option<Instant>,
+}
+
+impl PendingAction {
+    pub fn new(
+        tool_name: &str,
+        arguments: Value,
+        risk: RiskLevel,
+        summary: String,
+        preview: Option<Value>,
+    ) -> Self {
+        Self {
+            token: new_token(),
+            tool_name: tool_name.to_string(),
+            arguments,
+            risk,
+            summary,
+            preview,
+            created: Some(Instant::now()),
+        }
+    }
+
+    fn expired(&self) -> bool {
+        self.created.is_some_and(|t| t.elapsed() > CONFIRMATION_TTL)
+    }
+}
+
+/// Unpredictable one-time token (128 bits from the std randomly seeded hasher).
+fn new_token() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0);
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let half = |salt: u64| {
+        let mut h = RandomState::new().build_hasher();
+        h.write_u64(nanos);
+        h.write_u64(count);
+        h.write_u64(salt);
+        h.finish()
+    };
+    format!("{:016x}{:016x}", half(0), half(1))
+}
+
+/// Store of proposed actions keyed by token.
+#[derive(Default)]
+pub struct PendingActions {
+    actions: Mutex<HashMap<String, PendingAction>>,
+}
+
+impl PendingActions {
+    pub fn insert(&self, action: PendingAction) {
+        let mut actions = self.actions.lock().unwrap();
+        actions.retain(|_, a| !a.expired());
+        actions.insert(action.token.clone(), action);
+    }
+
+    /// Remove and return the action for `token` if it exists and has not expired.
+    pub fn take(&self, token: &str) -> Option<PendingAction> {
+        let action = self.actions.lock().unwrap().remove(token)?;
+        if action.expired() {
+            None
+        } else {
+            Some(action)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn policy() -> ToolPolicy {
+        serde_json::from_value(json!({
+            "modules": {
+                "mac_troubleshoot": {
+                    "deny": ["kill_process"],
+                    "confirm": ["check_startup_items"]
+                },
+                "auto_mechanic": { "allow": ["check_*"] }
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_allow_and_deny_rules() {
+        let p = policy();
+        assert!(!p.is_allowed("mac_troubleshoot", "kill_process"));
+        assert!(p.is_allowed("mac_troubleshoot", "monitor_cpu"));
+        assert!(p.is_allowed("auto_mechanic", "check_engine"));
+        assert!(!p.is_allowed("auto_mechanic", "run_vehicle_checkup"));
+        assert!(p.is_allowed("unknown_module", "anything"));
+    }
+
+    #[test]
+    fn test_confirmation_rules() {
+        let p = policy();
+        assert!(p.requires_confirmation("mac_troubleshoot", "clear_caches", RiskLevel::Destructive));
+        assert!(p.requires_confirmation("mac_troubleshoot", "check_startup_items", RiskLevel::ReadOnly));
+        assert!(!p.requires_confirmation("mac_troubleshoot", "monitor_cpu", RiskLevel::ReadOnly));
+    }
+
+    #[test]
+    fn test_tokens_are_single_use() {
+        let store = PendingActions::default();
+        let action = PendingAction::new("clear_caches", json!({}), RiskLevel::Destructive, "x".into(), None);
+        let token = action.token.clone();
+        assert_eq!(token.len(), 32);
+        store.insert(action);
+        assert!(store.take("not-a-token").is_none());
+        assert_eq!(store.take(&token).unwrap().tool_name, "clear_caches");
+        assert!(store.take(&token).is_none());
+    }
+
+    #[test]
+    fn test_missing_policy_file_is_permissive() {
+        let p = ToolPolicy::load(Path::new("/nonexistent/policy.json")).unwrap();
+        assert!(p.is_allowed("mac_troubleshoot", "kill_process"));
+    }
+}
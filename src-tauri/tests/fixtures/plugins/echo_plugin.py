@@ -0,0 +1,122 @@
+"""Test plugin for the JSON-RPC plugin protocol.
+
+Reads one JSON-RPC 2.0 request per line on stdin and writes one response
+per line on stdout. Exercises the host's lifecycle handling: a tool that
+crashes the process, one that overruns its timeout, a destructive tool
+with a preview, and one that leaves its risk undeclared.
+"""
+
+import json
+import os
+import sys
+import time
+
+TOOLS = [
+    {
+        "name": "plugin_echo",
+        "description": "Echo the arguments back",
+        "parameters": {
+            "type": "object",
+            "properties": {"message": {"type": "string"}},
+            "required": ["message"],
+        },
+        "risk": "read_only",
+    },
+    {
+        "name": "plugin_pid",
+        "description": "Report the plugin's process id",
+        "parameters": {"type": "object", "properties": {}, "required": []},
+        "risk": "read_only",
+    },
+    {
+        "name": "plugin_crash",
+        "description": "Exit without answering",
+        "parameters": {"type": "object", "properties": {}, "required": []},
+        "risk": "read_only",
+    },
+    {
+        "name": "plugin_sleep",
+        "description": "Sleep before answering",
+        "parameters": {
+            "type": "object",
+            "properties": {"seconds": {"type": "number"}},
+            "required": ["seconds"],
+        },
+        "risk": "read_only",
+    },
+    {
+        "name": "plugin_wipe",
+        "description": "Pretend to delete a path",
+        "parameters": {
+            "type": "object",
+            "properties": {"path": {"type": "string"}},
+            "required": ["path"],
+        },
+        "risk": "destructive",
+    },
+    {
+        "name": "plugin_touch",
+        "description": "Pretend to create a file, without declaring a risk",
+        "parameters": {
+            "type": "object",
+            "properties": {"path": {"type": "string"}},
+            "required": ["path"],
+        },
+    },
+]
+
+
+class RpcError(Exception):
+    def __init__(self, code, message):
+        super().__init__(message)
+        self.code = code
+        self.message = message
+
+
+def execute(tool, args):
+    if tool == "plugin_echo":
+        return {"success": True, "data": {"echo": args.get("message")}, "error": None}
+    if tool == "plugin_pid":
+        return {"success": True, "data": {"pid": os.getpid()}, "error": None}
+    if tool == "plugin_crash":
+        sys.exit(3)
+    if tool == "plugin_sleep":
+        time.sleep(float(args.get("seconds", 0)))
+        return {"success": True, "data": {"slept": args.get("seconds")}, "error": None}
+    if tool == "plugin_wipe":
+        return {"success": True, "data": {"deleted": args.get("path")}, "error": None}
+    if tool == "plugin_touch":
+        return {"success": True, "data": {"touched": args.get("path")}, "error": None}
+    return {"success": False, "data": None, "error": "Unknown tool: %s" % tool}
+
+
+def handle(method, params):
+    if method == "list_tools":
+        return {"name": "echo_plugin", "description": "Protocol test plugin", "tools": TOOLS}
+    if method == "health":
+        return {"ok": True, "pid": os.getpid()}
+    if method == "execute":
+        return execute(params.get("tool"), params.get("arguments") or {})
+    if method == "preview" and params.get("tool") == "plugin_wipe":
+        return {"would_delete": (params.get("arguments") or {}).get("path")}
+    raise RpcError(-32601, "Method not found: %s" % method)
+
+
+def main():
+    # Noise on stdout that is not a response must be ignored by the host.
+    print("echo_plugin starting", flush=True)
+    for line in sys.stdin:
+        try:
+            request = json.loads(line)
+        except ValueError:
+            continue
+        response = {"jsonrpc": "2.0", "id": request.get("id")}
+        try:
+            response["result"] = handle(request.get("method"), request.get("params") or {})
+        except RpcError as e:
+            response["error"] = {"code": e.code, "message": e.message}
+        print(json.dumps(response), flush=True)
+
+
+if __name__ == "__main__":
+    main()
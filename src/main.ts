@@ -1,4 +1,5 @@
 import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
 
 // ---------------------------------------------------------------------------
 // Types (matching Rust structs)
@@ -19,6 +20,36 @@ interface RouteResult {
   tool_result: ToolResult | null;
 }
 
+interface PendingAction {
+  token: string;
+  tool_name: string;
+  arguments: Record<string, unknown>;
+  risk: string;
+  summary: string;
+  preview: Record<string, unknown> | null;
+}
+
+interface TelemetryReading {
+  pid: number;
+  key: string;
+  value: number;
+  unit: string;
+}
+
+interface TelemetrySample {
+  t_ms: number;
+  timestamp_ms: number;
+  readings: TelemetryReading[];
+  error?: string;
+}
+
+interface TelemetrySummary {
+  samples: number;
+  errors: number;
+  duration_ms: number;
+  recording: string[];
+}
+
 interface ModuleInfo {
   name: string;
   description: string;
@@ -50,22 +81,41 @@ let mediaRecorder: MediaRecorder | null = null;
 let audioChunks: Blob[] = [];
 let micBtn: HTMLButtonElement;
 
+// Live telemetry state
+let telemetryCard: HTMLElement | null = null;
+let telemetryUnlisten: UnlistenFn | null = null;
+let telemetrySamples = 0;
+
 // Tool-to-quick-command mapping
 const TOOL_QUICK_COMMANDS: Record<string, { label: string; command: string }> = {
   monitor_cpu: { label: "CPU", command: "check cpu usage" },
+  check_thermals: { label: "THERMAL", command: "is my mac overheating" },
   monitor_memory: { label: "MEM", command: "check memory usage" },
   monitor_disk: { label: "DISK", command: "check disk space" },
+  analyze_disk: { label: "SPACE", command: "what is taking up space" },
   monitor_network: { label: "NET", command: "show network connections" },
   diagnose_network: { label: "DIAG NET", command: "diagnose network issues" },
   diagnose_battery: { label: "BATT", command: "check battery status" },
+  check_hardware: { label: "HW", command: "list connected hardware" },
   check_security: { label: "SEC", command: "check security status" },
   check_startup_items: { label: "STARTUP", command: "check startup items" },
+  list_apps: { label: "APPS", command: "which apps are outdated" },
+  search_logs: { label: "LOGS", command: "show errors in the system log" },
+  analyze_crashes: { label: "CRASHES", command: "show recent crash reports" },
   kill_process: { label: "KILL", command: "kill process" },
   clear_caches: { label: "CACHE", command: "clear caches" },
   run_full_checkup: { label: "FULL CHECKUP", command: "run full health checkup" },
   troubleshoot: { label: "TROUBLESHOOT", command: "troubleshoot my mac" },
   // auto_mechanic
   check_engine: { label: "ENGINE", command: "check engine health" },
+  explain_dtc: { label: "CODES", command: "what does P0420 mean" },
+  read_dtcs: { label: "ALL CODES", command: "show stored, pending and permanent codes" },
+  read_freeze_frame: { label: "FREEZE FRAME", command: "show the freeze frame data" },
+  clear_dtcs: { label: "CLEAR CODES", command: "clear the check engine light" },
+  decode_vin: { label: "VIN", command: "decode my vin" },
+  get_vehicle_profile: { label: "PROFILE", command: "show my vehicle profile" },
+  check_maintenance: { label: "MAINTENANCE", command: "when is my next oil change due" },
+  log_maintenance: { label: "LOG SERVICE", command: "log an oil change" },
   check_tires: { label: "TIRES", command: "check tire pressure" },
   check_battery_vehicle: { label: "BATT", command: "check car battery voltage" },
   check_fluids: { label: "FLUIDS", command: "check fluid levels" },
@@ -192,6 +242,21 @@ function updateQuickActions(): void {
     });
     quickActionsSection.appendChild(btn);
   }
+
+  if (mod.name === "auto_mechanic") {
+    const live = document.createElement("button");
+    live.className = `quick-btn${telemetryCard ? " accent" : ""}`;
+    live.textContent = telemetryCard ? "STOP LIVE" : "LIVE";
+    live.addEventListener("click", () => (telemetryCard ? stopTelemetry() : startTelemetry(false)));
+    quickActionsSection.appendChild(live);
+    if (!telemetryCard) {
+      const rec = document.createElement("button");
+      rec.className = "quick-btn";
+      rec.textContent = "RECORD";
+      rec.addEventListener("click", () => startTelemetry(true));
+      quickActionsSection.appendChild(rec);
+    }
+  }
 }
 
 async function processCommand(input: string): Promise<void> {
@@ -313,9 +378,13 @@ function addResultCard(result: RouteResult, query: string): void {
 
   if (result.tool_result) {
     if (!result.tool_result.success && result.tool_result.error) {
-      body.innerHTML = `<div class="error-display">${escapeHtml(result.tool_result.error)}</div>`;
+      body.innerHTML = renderFailure(result.tool_name, result.tool_result);
+      bindCommandButtons(body);
+    } else if (result.tool_result.data.requires_confirmation) {
+      renderConfirmation(body, result.tool_name, result.tool_result.data.confirmation as PendingAction);
     } else {
       body.innerHTML = renderToolData(result.tool_name, result.tool_result.data);
+      bindCommandButtons(body);
     }
   } else {
     // No tool result (cloud fallback)
@@ -338,6 +407,70 @@ function addResultCard(result: RouteResult, query: string): void {
   resultsContainer.insertBefore(card, resultsContainer.firstChild);
 }
 
+/** Error message, plus the tool's data when it carries candidates to pick from. */
+function renderFailure(toolName: string, result: ToolResult): string {
+  let html = `<div class="error-display">${escapeHtml(result.error || "Failed")}</div>`;
+  if (result.data && result.data.candidates) {
+    html += renderToolData(toolName, result.data);
+  }
+  return html;
+}
+
+/** Wire up buttons rendered with a `data-command` to run that command. */
+function bindCommandButtons(el: HTMLElement): void {
+  el.querySelectorAll<HTMLElement>("button[data-command]").forEach((btn) => {
+    btn.addEventListener("click", () => {
+      const command = btn.dataset.command || "";
+      commandInput.value = command;
+      processCommand(command);
+    });
+  });
+}
+
+/**
+ * Show a proposed destructive action with Confirm / Cancel buttons. The card
+ * body is replaced with the tool result once the user decides.
+ */
+function renderConfirmation(body: HTMLElement, toolName: string, action: PendingAction): void {
+  let html = `
+    <div class="confirm-action">
+      <div class="confirm-summary">
+        <span class="text-amber">${escapeHtml(action.risk.toUpperCase())}</span>
+        ${escapeHtml(action.summary)}
+      </div>
+  `;
+  if (action.preview) {
+    html += `<div class="confirm-preview">${renderToolData(toolName, action.preview)}</div>`;
+  }
+  html += `
+      <div class="confirm-buttons">
+        <button class="quick-btn accent confirm-yes">CONFIRM</button>
+        <button class="quick-btn confirm-no">CANCEL</button>
+      </div>
+    </div>
+  `;
+  body.innerHTML = html;
+  bindCommandButtons(body);
+
+  body.querySelector(".confirm-yes")?.addEventListener("click", async () => {
+    body.innerHTML = `<div class="dim">Running ${escapeHtml(formatToolName(toolName))}...</div>`;
+    try {
+      const result = await invoke<ToolResult>("confirm_action", { token: action.token });
+      body.innerHTML =
+        !result.success && result.error ? renderFailure(toolName, result) : renderToolData(toolName, result.data);
+      bindCommandButtons(body);
+    } catch (err) {
+      const msg = err instanceof Error ? err.message : String(err);
+      body.innerHTML = `<div class="error-display">${escapeHtml(msg)}</div>`;
+    }
+  });
+
+  body.querySelector(".confirm-no")?.addEventListener("click", async () => {
+    await invoke<boolean>("cancel_action", { token: action.token });
+    body.innerHTML = `<div class="dim">Cancelled</div>`;
+  });
+}
+
 function addErrorCard(query: string, errorMsg: string): void {
   const card = document.createElement("div");
   card.className = "result-card";
@@ -367,6 +500,87 @@ function addErrorCard(query: string, errorMsg: string): void {
   resultsContainer.insertBefore(card, resultsContainer.firstChild);
 }
 
+// ---------------------------------------------------------------------------
+// Live Telemetry
+// ---------------------------------------------------------------------------
+
+/** Stream vehicle PIDs into a card that updates in place until stopped. */
+async function startTelemetry(record: boolean): Promise<void> {
+  hideEmptyState();
+  const card = document.createElement("div");
+  card.className = "result-card";
+  card.innerHTML = `
+    <div class="card-header">
+      <div class="card-header-left">
+        <span class="card-tool-name">Live Telemetry</span>
+        <span class="card-module-tag">auto_mechanic</span>
+        ${record ? `<span class="badge badge-cloud">REC</span>` : ""}
+      </div>
+      <div class="card-meta"><span class="telemetry-count">starting...</span></div>
+    </div>
+    <div class="card-body telemetry-body"><div class="dim">Waiting for the first sample...</div></div>
+  `;
+  resultsContainer.insertBefore(card, resultsContainer.firstChild);
+
+  telemetrySamples = 0;
+  telemetryUnlisten = await listen<TelemetrySample>("telemetry-sample", (event) => {
+    renderTelemetrySample(card, event.payload);
+  });
+  try {
+    await invoke("start_telemetry", { record });
+    telemetryCard = card;
+    statusText.textContent = record ? "Recording vehicle telemetry" : "Streaming vehicle telemetry";
+  } catch (err) {
+    telemetryUnlisten();
+    telemetryUnlisten = null;
+    const msg = err instanceof Error ? err.message : String(err);
+    card.querySelector(".telemetry-body")!.innerHTML = `<div class="error-display">${escapeHtml(msg)}</div>`;
+  }
+  updateQuickActions();
+}
+
+async function stopTelemetry(): Promise<void> {
+  const card = telemetryCard;
+  telemetryCard = null;
+  try {
+    const summary = await invoke<TelemetrySummary>("stop_telemetry");
+    if (card) {
+      const files = summary.recording.map((path) => `<div class="item dim">${escapeHtml(path)}</div>`).join("");
+      card.insertAdjacentHTML(
+        "beforeend",
+        `<div class="card-footer">
+          <span>${summary.samples} samples in ${(summary.duration_ms / 1000).toFixed(1)}s${summary.errors ? `, ${summary.errors} errors` : ""}</span>
+        </div>${files ? `<div class="card-body"><div class="section-header">Recorded to</div>${files}</div>` : ""}`,
+      );
+    }
+  } catch (err) {
+    statusText.textContent = `Error: ${err instanceof Error ? err.message : String(err)}`;
+  } finally {
+    telemetryUnlisten?.();
+    telemetryUnlisten = null;
+    updateQuickActions();
+  }
+}
+
+function renderTelemetrySample(card: HTMLElement, sample: TelemetrySample): void {
+  telemetrySamples += 1;
+  const count = card.querySelector(".telemetry-count");
+  if (count) count.textContent = `${telemetrySamples} samples | ${(sample.t_ms / 1000).toFixed(1)}s`;
+  let html = `<div class="stat-row">`;
+  for (const r of sample.readings) {
+    html += `
+      <div class="stat-item">
+        <span class="stat-value">${escapeHtml(String(r.value))}${r.unit ? ` <span class="dim">${escapeHtml(r.unit)}</span>` : ""}</span>
+        <span class="stat-label">${escapeHtml(r.key.replace(/_/g, " "))}</span>
+      </div>`;
+  }
+  html += `</div>`;
+  if (sample.error) {
+    html += `<div class="error-display">${escapeHtml(sample.error)}</div>`;
+  }
+  card.querySelector(".telemetry-body")!.innerHTML = html;
+}
+
 // ---------------------------------------------------------------------------
 // Tool Data Renderers
 // ---------------------------------------------------------------------------
@@ -375,20 +589,34 @@ function renderToolData(toolName: string, data: Record<string, unknown>): string
   switch (toolName) {
     case "monitor_cpu":
       return renderCpuData(data);
+    case "check_thermals":
+      return renderThermalData(data);
     case "monitor_memory":
       return renderMemoryData(data);
     case "monitor_disk":
       return renderDiskData(data);
+    case "analyze_disk":
+      return renderAnalyzeDiskData(data);
     case "monitor_network":
       return renderNetworkData(data);
     case "diagnose_network":
       return renderDiagnoseNetworkData(data);
     case "diagnose_battery":
       return renderBatteryData(data);
+    case "check_hardware":
+      return renderHardwareData(data);
     case "check_security":
       return renderSecurityData(data);
+    case "list_apps":
+      return renderAppsData(data);
     case "check_startup_items":
       return renderStartupData(data);
+    case "disable_startup_item":
+      return renderDisableStartupData(data);
+    case "search_logs":
+      return renderSearchLogsData(data);
+    case "analyze_crashes":
+      return renderCrashesData(data);
     case "kill_process":
       return renderKillProcessData(data);
     case "clear_caches":
@@ -400,12 +628,29 @@ function renderToolData(toolName: string, data: Record<string, unknown>): string
     // auto_mechanic tools
     case "check_engine":
       return renderEngineData(data);
+    case "explain_dtc":
+      return renderDtcData(data);
+    case "read_dtcs":
+      return renderCodeListsData(data);
+    case "read_freeze_frame":
+      return renderFreezeFrameData(data);
+    case "clear_dtcs":
+      return renderClearDtcsData(data);
     case "check_tires":
       return renderTiresData(data);
     case "check_battery_vehicle":
       return renderVehicleBatteryData(data);
     case "check_fluids":
       return renderFluidsData(data);
+    case "decode_vin":
+      return renderVinData(data);
+    case "get_vehicle_profile":
+    case "set_vehicle_profile":
+      return renderVehicleProfileData(data);
+    case "check_maintenance":
+      return renderMaintenanceData(data);
+    case "log_maintenance":
+      return renderLogMaintenanceData(data);
     case "run_vehicle_checkup":
       return renderVehicleCheckupData(data);
     default:
@@ -418,7 +663,15 @@ function renderToolData(toolName: string, data: Record<string, unknown>): string
 function renderCpuData(data: Record<string, unknown>): string {
   const brand = String(data.cpu_brand || "Unknown");
   const cores = String(data.core_count || "?");
-  const processes = asArray(data.top_processes);
+  const processes = asArray(data.processes);
+  const hogs = asArray(data.sustained_hogs).map(String);
+  const spikes = asArray(data.spikes).map(String);
+  const overall = data.overall as Record<string, Record<string, number>> | null;
+  const loadLabel: Record<string, [string, string]> = {
+    sustained: ["HOG", "text-red"],
+    spike: ["SPIKE", "text-amber"],
+    normal: ["", ""],
+  };
 
   let html = `
     <div class="stat-row">
@@ -430,20 +683,38 @@ function renderCpuData(data: Record<string, unknown>): string {
         <span class="stat-value">${escapeHtml(cores)}</span>
         <span class="stat-label">Cores</span>
       </div>
+      ${overall ? `<div class="stat-item">
+        <span class="stat-value">${(100 - overall.idle.avg).toFixed(1)}%</span>
+        <span class="stat-label">Busy (${escapeHtml(String(data.samples))} samples / ${escapeHtml(String(data.duration_secs))}s)</span>
+      </div>` : ""}
     </div>
   `;
 
+  if (hogs.length > 0) {
+    html += `<div class="text-red" style="font-size: 0.7rem; margin-bottom: 4px;">Sustained: ${escapeHtml(hogs.join(", "))}</div>`;
+  }
+  if (spikes.length > 0) {
+    html += `<div class="text-amber" style="font-size: 0.7rem; margin-bottom: 4px;">Spikes: ${escapeHtml(spikes.join(", "))}</div>`;
+  }
+
   if (processes.length > 0) {
     html += `<div class="section-header">Top Processes</div>`;
     html += `<table class="data-table">
-      <thead><tr><th>PID</th><th>Command</th><th>CPU %</th></tr></thead>
+      <thead><tr><th>PID</th><th>Command</th><th>Avg CPU %</th><th>Peak</th><th>Energy</th><th>Threads</th><th></th></tr></thead>
       <tbody>`;
     for (const proc of processes.slice(0, 10)) {
       const p = proc as Record<string, unknown>;
+      const cpu = p.cpu as Record<string, number>;
+      const energy = p.energy as Record<string, number>;
+      const [label, cls] = loadLabel[String(p.load)] ?? ["", ""];
       html += `<tr>
-        <td>${escapeHtml(String(p.pid || ""))}</td>
+        <td>${escapeHtml(String(p.pid ?? ""))}</td>
         <td>${escapeHtml(String(p.command || ""))}</td>
-        <td>${escapeHtml(String(p.cpu_pct || ""))}</td>
+        <td class="${cls}">${cpu.avg.toFixed(1)}</td>
+        <td>${cpu.peak.toFixed(1)}</td>
+        <td>${energy.avg.toFixed(1)}</td>
+        <td>${escapeHtml(String(p.threads ?? ""))}</td>
+        <td class="${cls}">${label}</td>
       </tr>`;
     }
     html += `</tbody></table>`;
@@ -456,7 +727,12 @@ function renderCpuData(data: Record<string, unknown>): string {
 
 function renderMemoryData(data: Record<string, unknown>): string {
   const totalGb = Number(data.total_memory_gb || 0);
+  const mem = (data.memory_bytes || {}) as Record<string, unknown>;
+  const swap = (data.swap_bytes || {}) as Record<string, unknown>;
+  const pressure = (data.pressure || {}) as Record<string, unknown>;
   const consumers = asArray(data.top_memory_consumers);
+  const level = String(pressure.level || "normal");
+  const levelClass = level === "critical" ? "text-red" : level === "warning" ? "text-amber" : "text-green";
 
   let html = `
     <div class="stat-row">
@@ -464,21 +740,60 @@ function renderMemoryData(data: Record<string, unknown>): string {
         <span class="stat-value">${totalGb.toFixed(1)} GB</span>
         <span class="stat-label">Total Memory</span>
       </div>
+      <div class="stat-item">
+        <span class="stat-value">${formatBytes(mem.used)}</span>
+        <span class="stat-label">Used</span>
+      </div>
+      <div class="stat-item">
+        <span class="stat-value">${formatBytes(swap.used)}</span>
+        <span class="stat-label">Swap Used</span>
+      </div>
+      <div class="stat-item">
+        <span class="stat-value ${levelClass}">${escapeHtml(level)}</span>
+        <span class="stat-label">Pressure</span>
+      </div>
     </div>
   `;
 
+  if (data.verdict) {
+    html += `<div class="dim" style="font-size: 0.75rem; margin: 4px 0 8px;">${escapeHtml(String(data.verdict))}</div>`;
+  }
+
+  const total = Number(mem.total || 0);
+  if (total > 0) {
+    html += `<div class="section-header">Breakdown</div>`;
+    for (const [key, label] of [
+      ["app", "App"],
+      ["wired", "Wired"],
+      ["compressed", "Compressed"],
+      ["cached", "Cached"],
+      ["free", "Free"],
+    ]) {
+      const pct = (Number(mem[key] || 0) / total) * 100;
+      html += `
+        <div class="bar-container">
+          <span class="bar-label">${label}</span>
+          <div class="bar-track">
+            <div class="bar-fill bar-fill-green" style="width: ${pct.toFixed(1)}%"></div>
+          </div>
+          <span class="bar-value">${formatBytes(mem[key])}</span>
+        </div>
+      `;
+    }
+  }
+
   if (consumers.length > 0) {
     html += `<div class="section-header">Top Memory Consumers</div>`;
     html += `<table class="data-table">
-      <thead><tr><th>PID</th><th>Command</th><th>MEM %</th><th>CPU %</th></tr></thead>
+      <thead><tr><th>PID</th><th>Process</th><th>Resident</th><th>Share</th></tr></thead>
       <tbody>`;
     for (const proc of consumers.slice(0, 10)) {
       const p = proc as Record<string, unknown>;
       html += `<tr>
         <td>${escapeHtml(String(p.pid || ""))}</td>
-        <td>${escapeHtml(String(p.command || ""))}</td>
-        <td>${escapeHtml(String(p.mem_pct || ""))}</td>
-        <td>${escapeHtml(String(p.cpu_pct || ""))}</td>
+        <td>${escapeHtml(String(p.name || ""))}</td>
+        <td>${formatBytes(p.rss_bytes)}</td>
+        <td>${Number(p.pct_of_resident || 0).toFixed(1)}%</td>
       </tr>`;
     }
     html += `</tbody></table>`;
@@ -490,40 +805,27 @@ function renderMemoryData(data: Record<string, unknown>): string {
 // --- Disk ---
 
 function renderDiskData(data: Record<string, unknown>): string {
-  const root = data.root_volume as Record<string, unknown> | undefined;
+  const volumes = asArray(data.volumes);
   const dirSizes = data.directory_sizes as Record<string, unknown> | undefined;
 
   let html = "";
 
-  if (root) {
-    const capacity = String(root.capacity || "0%");
-    const pctNum = parseInt(capacity, 10) || 0;
-    const barColor = pctNum > 90 ? "bar-fill-red" : pctNum > 70 ? "bar-fill-amber" : "bar-fill-green";
-
-    html += `
-      <div class="section-header">Root Volume</div>
-      <div class="stat-row">
-        <div class="stat-item">
-          <span class="stat-value">${escapeHtml(String(root.size || "?"))}</span>
-          <span class="stat-label">Total</span>
-        </div>
-        <div class="stat-item">
-          <span class="stat-value">${escapeHtml(String(root.used || "?"))}</span>
-          <span class="stat-label">Used</span>
-        </div>
-        <div class="stat-item">
-          <span class="stat-value">${escapeHtml(String(root.available || "?"))}</span>
-          <span class="stat-label">Available</span>
-        </div>
-      </div>
-      <div class="bar-container">
-        <span class="bar-label">Usage</span>
-        <div class="bar-track">
-          <div class="bar-fill ${barColor}" style="width: ${pctNum}%"></div>
+  if (volumes.length > 0) {
+    html += `<div class="section-header">Volumes</div>`;
+    for (const item of volumes) {
+      const v = item as Record<string, unknown>;
+      const pctNum = Number(v.capacity_pct || 0);
+      const barColor = pctNum > 90 ? "bar-fill-red" : pctNum > 70 ? "bar-fill-amber" : "bar-fill-green";
+      html += `
+        <div class="bar-container">
+          <span class="bar-label">${escapeHtml(String(v.mount_point || "?"))}</span>
+          <div class="bar-track">
+            <div class="bar-fill ${barColor}" style="width: ${pctNum}%"></div>
+          </div>
+          <span class="bar-value">${formatBytes(v.available_bytes)} free of ${formatBytes(v.size_bytes)}</span>
         </div>
-        <span class="bar-value">${escapeHtml(capacity)}</span>
-      </div>
-    `;
+      `;
+    }
   }
 
   if (dirSizes && Object.keys(dirSizes).length > 0) {
@@ -542,34 +844,156 @@ function renderDiskData(data: Record<string, unknown>): string {
   return html || renderGenericData(data);
 }
 
+// --- Disk analysis ---
+
+function renderAnalyzeDiskData(data: Record<string, unknown>): string {
+  const categories = asArray(data.categories);
+  const suggestions = asArray(data.suggestions);
+
+  let html = `
+    <div class="stat-row">
+      <div class="stat-item">
+        <span class="stat-value">${formatBytes(data.total_bytes)}</span>
+        <span class="stat-label">Scanned</span>
+      </div>
+      <div class="stat-item">
+        <span class="stat-value text-green">${formatBytes(data.reclaimable_bytes)}</span>
+        <span class="stat-label">Reclaimable</span>
+      </div>
+      <div class="stat-item">
+        <span class="stat-value">${escapeHtml(String(data.files_scanned || 0))}</span>
+        <span class="stat-label">Files</span>
+      </div>
+    </div>
+  `;
+  if (data.timed_out) {
+    html += `<div class="dim" style="font-size: 0.7rem;">Time budget reached; results are partial</div>`;
+  }
+
+  if (categories.length > 0) {
+    html += `<div class="section-header">Categories</div>`;
+    html += `<div class="kv-grid">`;
+    for (const item of categories) {
+      const c = item as Record<string, unknown>;
+      html += `
+        <span class="kv-key">${escapeHtml(String(c.label || c.category))}</span>
+        <span class="kv-value ${c.reclaimable ? "text-green" : ""}">${formatBytes(c.bytes)}</span>
+      `;
+    }
+    html += `</div>`;
+  }
+
+  for (const [key, label] of [
+    ["largest_dirs", "Largest Folders"],
+    ["largest_files", "Largest Files"],
+  ]) {
+    const entries = asArray(data[key]);
+    if (entries.length === 0) continue;
+    html += `<div class="section-header">${label}</div>`;
+    html += `<div class="kv-grid">`;
+    for (const item of entries) {
+      const e = item as Record<string, unknown>;
+      const shortPath = String(e.path || "").replace(/^\/Users\/[^/]+\//, "~/");
+      html += `
+        <span class="kv-key">${escapeHtml(shortPath)}</span>
+        <span class="kv-value">${formatBytes(e.bytes)}</span>
+      `;
+    }
+    html += `</div>`;
+  }
+
+  if (suggestions.length > 0) {
+    html += `<div class="section-header">Suggestions</div>`;
+    html += `<div class="items-list">`;
+    for (const s of suggestions) {
+      html += `<div class="item">${escapeHtml(String(s))}</div>`;
+    }
+    html += `</div>`;
+  }
+
+  return html;
+}
+
 // --- Network (monitor) ---
 
 function renderNetworkData(data: Record<string, unknown>): string {
-  const connections = asArray(data.established_connections);
+  const listening = asArray(data.listening);
+  const processes = asArray(data.processes);
+  const sockets = asArray(data.sockets);
 
-  let html = "";
+  let html = `
+    <div class="stat-row">
+      <div class="stat-item">
+        <span class="stat-value">${escapeHtml(String(data.socket_count ?? 0))}</span>
+        <span class="stat-label">Sockets</span>
+      </div>
+      <div class="stat-item">
+        <span class="stat-value">${listening.length}</span>
+        <span class="stat-label">Listening</span>
+      </div>
+      <div class="stat-item">
+        <span class="stat-value">${escapeHtml(String(data.internet_processes ?? 0))}</span>
+        <span class="stat-label">Apps online</span>
+      </div>
+    </div>
+  `;
 
-  if (connections.length > 0) {
-    html += `<div class="section-header">Established Connections</div>`;
+  if (listening.length > 0) {
+    html += `<div class="section-header">Listening Ports</div>`;
     html += `<table class="data-table">
-      <thead><tr><th>Command</th><th>PID</th><th>User</th><th>Connection</th></tr></thead>
+      <thead><tr><th>Port</th><th>Proto</th><th>Address</th><th>Process</th></tr></thead>
       <tbody>`;
-    for (const conn of connections.slice(0, 20)) {
-      const c = conn as Record<string, unknown>;
-      if (c.raw) {
-        html += `<tr><td colspan="4">${escapeHtml(String(c.raw))}</td></tr>`;
-      } else {
-        html += `<tr>
-          <td>${escapeHtml(String(c.command || ""))}</td>
-          <td>${escapeHtml(String(c.pid || ""))}</td>
-          <td>${escapeHtml(String(c.user || ""))}</td>
-          <td>${escapeHtml(String(c.name || ""))}</td>
-        </tr>`;
-      }
+    for (const l of listening) {
+      const p = l as Record<string, unknown>;
+      const owner = p.app ? `${p.app} (${p.process})` : String(p.process || "");
+      const exposed = p.exposed ? ` <span class="text-amber">exposed</span>` : "";
+      html += `<tr>
+        <td>${escapeHtml(String(p.port ?? ""))}</td>
+        <td>${escapeHtml(String(p.protocol || ""))}</td>
+        <td>${escapeHtml(String(p.address || ""))}${exposed}</td>
+        <td>${escapeHtml(owner)} [${escapeHtml(String(p.pid))}]</td>
+      </tr>`;
     }
     html += `</tbody></table>`;
-  } else {
-    html += `<div class="dim" style="padding: 8px 0;">No established connections found</div>`;
+  }
+
+  if (processes.length > 0) {
+    html += `<div class="section-header">Connections by Process</div>`;
+    html += `<table class="data-table">
+      <thead><tr><th>Process</th><th>Established</th><th>Internet</th><th>Remote hosts</th></tr></thead>
+      <tbody>`;
+    for (const row of processes.slice(0, 15)) {
+      const p = row as Record<string, unknown>;
+      html += `<tr>
+        <td>${escapeHtml(String(p.app || p.process || ""))}</td>
+        <td>${escapeHtml(String(p.established ?? 0))}</td>
+        <td>${escapeHtml(String(p.internet ?? 0))}</td>
+        <td>${escapeHtml(String(p.remote_hosts ?? 0))}</td>
+      </tr>`;
+    }
+    html += `</tbody></table>`;
+  }
+
+  const connected = sockets.filter((s) => (s as Record<string, unknown>).remote_address);
+  if (connected.length > 0) {
+    html += `<div class="section-header">Connections</div>`;
+    html += `<table class="data-table">
+      <thead><tr><th>Process</th><th>Remote</th><th>State</th></tr></thead>
+      <tbody>`;
+    for (const sock of connected.slice(0, 20)) {
+      const c = sock as Record<string, unknown>;
+      const remote = `${c.remote_host || c.remote_address}:${c.remote_port ?? ""}`;
+      html += `<tr>
+        <td>${escapeHtml(String(c.process || ""))} [${escapeHtml(String(c.pid))}]</td>
+        <td>${escapeHtml(remote)}</td>
+        <td>${escapeHtml(String(c.state || c.protocol || ""))}</td>
+      </tr>`;
+    }
+    html += `</tbody></table>`;
+  }
+
+  if (sockets.length === 0) {
+    html += `<div class="dim" style="padding: 8px 0;">No matching sockets</div>`;
   }
 
   return html;
@@ -578,44 +1002,34 @@ function renderNetworkData(data: Record<string, unknown>): string {
 // --- Network (diagnose) ---
 
 function renderDiagnoseNetworkData(data: Record<string, unknown>): string {
-  const wifi = data.wifi as Record<string, unknown> | undefined;
-  const ping = data.ping as Record<string, unknown> | undefined;
-  const dns = data.dns as Record<string, unknown> | undefined;
+  const layers = asArray(data.layers);
+  const statusClass: Record<string, string> = {
+    ok: "text-green",
+    warn: "text-amber",
+    fail: "text-red",
+    skipped: "dim",
+  };
 
   let html = "";
-
-  // Wi-Fi info
-  if (wifi && Object.keys(wifi).length > 0) {
-    html += `<div class="section-header">Wi-Fi</div>`;
-    html += `<div class="kv-grid">`;
-    for (const [key, val] of Object.entries(wifi)) {
-      html += `
-        <span class="kv-key">${escapeHtml(key)}</span>
-        <span class="kv-value">${escapeHtml(String(val))}</span>
-      `;
-    }
-    html += `</div>`;
+  if (data.verdict) {
+    const cls = data.healthy ? "text-green" : "text-amber";
+    html += `<div class="${cls}" style="margin-bottom: 8px;">${escapeHtml(String(data.verdict))}</div>`;
   }
 
-  // Ping
-  if (ping) {
-    const reachable = Boolean(ping.reachable);
-    html += `<div class="section-header">Ping (8.8.8.8)</div>`;
-    html += `<div class="checklist">`;
-    html += renderCheckItem("Internet reachable", reachable);
-    html += `</div>`;
-    if (ping.summary) {
-      html += `<div style="margin-top: 4px; font-size: 0.7rem; color: var(--text-dim);">${escapeHtml(String(ping.summary))}</div>`;
+  if (layers.length > 0) {
+    html += `<table class="data-table">
+      <thead><tr><th>Layer</th><th>Status</th><th>Detail</th></tr></thead>
+      <tbody>`;
+    for (const l of layers) {
+      const layer = l as Record<string, unknown>;
+      const status = String(layer.status || "");
+      html += `<tr>
+        <td>${escapeHtml(String(layer.name || ""))}</td>
+        <td class="${statusClass[status] || ""}">${escapeHtml(status.toUpperCase())}</td>
+        <td>${escapeHtml(String(layer.detail || ""))}</td>
+      </tr>`;
     }
-  }
-
-  // DNS
-  if (dns) {
-    const resolves = Boolean(dns.resolves);
-    html += `<div class="section-header">DNS</div>`;
-    html += `<div class="checklist">`;
-    html += renderCheckItem("DNS resolves (google.com)", resolves);
-    html += `</div>`;
+    html += `</tbody></table>`;
   }
 
   return html || renderGenericData(data);
@@ -649,101 +1063,691 @@ function renderBatteryData(data: Record<string, unknown>): string {
   html += `<span class="battery-status">${escapeHtml(status.replace(/_/g, " "))}</span>`;
   html += `</div>`;
 
-  return html;
-}
-
-// --- Security ---
-
-function renderSecurityData(data: Record<string, unknown>): string {
-  const filevault = data.filevault as Record<string, unknown> | undefined;
-  const sip = data.sip as Record<string, unknown> | undefined;
-  const firewall = data.firewall as Record<string, unknown> | undefined;
-
-  let html = `<div class="section-header">Security Status</div>`;
-  html += `<div class="checklist">`;
-
-  if (filevault) {
-    html += renderCheckItem("FileVault (Disk Encryption)", Boolean(filevault.enabled));
-  }
-  if (sip) {
-    html += renderCheckItem("System Integrity Protection", Boolean(sip.enabled));
-  }
-  if (firewall) {
-    html += renderCheckItem("Firewall", Boolean(firewall.enabled));
+  if (data.has_battery === false) {
+    html += `<div class="dim" style="font-size: 0.75rem;">${escapeHtml(String(data.summary || "No battery found"))}</div>`;
+    return html;
   }
 
-  html += `</div>`;
-  return html;
-}
-
-// --- Startup Items ---
-
-function renderStartupData(data: Record<string, unknown>): string {
-  const loginItems = asArray(data.login_items);
-  const launchAgents = asArray(data.launch_agents);
-
-  let html = "";
+  if (data.summary) {
+    const cls = data.verdict === "good" ? "text-green" : data.verdict === "fair" ? "text-amber" : "text-red";
+    html += `<div class="${cls}" style="font-size: 0.75rem; margin: 4px 0 8px;">${escapeHtml(String(data.summary))}</div>`;
+  }
 
-  html += `<div class="section-header">Login Items</div>`;
-  if (loginItems.length > 0) {
-    html += `<div class="items-list">`;
-    for (const item of loginItems) {
-      html += `<div class="item">${escapeHtml(String(item))}</div>`;
-    }
-    html += `</div>`;
-  } else {
-    html += `<div class="dim" style="font-size: 0.7rem;">No login items found</div>`;
+  const health: [string, unknown][] = [
+    ["Health", data.health_percent != null ? `${data.health_percent}%` : null],
+    ["Condition", data.condition],
+    ["Cycles", data.cycle_count],
+    [
+      "Capacity",
+      data.full_charge_capacity_mah != null && data.design_capacity_mah != null
+        ? `${data.full_charge_capacity_mah} / ${data.design_capacity_mah} mAh`
+        : null,
+    ],
+    ["Temperature", data.temperature_c != null ? `${Number(data.temperature_c).toFixed(1)}°C` : null],
+    ["Adapter", data.adapter_watts != null ? `${data.adapter_watts} W` : null],
+    ["Time remaining", data.time_remaining],
+  ];
+  html += `<div class="kv-grid">`;
+  for (const [key, val] of health) {
+    if (val === null || val === undefined) continue;
+    html += `
+      <span class="kv-key">${escapeHtml(key)}</span>
+      <span class="kv-value">${escapeHtml(String(val))}</span>
+    `;
   }
+  html += `</div>`;
 
-  html += `<div class="section-header">Launch Agents</div>`;
-  if (launchAgents.length > 0) {
+  const blockers = asArray(data.sleep_blockers);
+  if (blockers.length > 0) {
+    html += `<div class="section-header">Preventing Sleep</div>`;
     html += `<div class="items-list">`;
-    for (const item of launchAgents) {
-      html += `<div class="item">${escapeHtml(String(item))}</div>`;
+    for (const b of blockers) {
+      const blocker = b as Record<string, unknown>;
+      const reason = blocker.reason ? ` — ${blocker.reason}` : "";
+      html += `<div class="item">${escapeHtml(`${blocker.process} [${blocker.pid}] ${blocker.assertion}${reason}`)}</div>`;
     }
     html += `</div>`;
-  } else {
-    html += `<div class="dim" style="font-size: 0.7rem;">No launch agents found</div>`;
   }
 
   return html;
 }
 
-// --- Kill Process ---
-
-function renderKillProcessData(data: Record<string, unknown>): string {
-  const processName = String(data.process_name || "unknown");
-  const killed = Boolean(data.killed);
+// --- Security ---
 
-  let html = `<div class="checklist">`;
-  html += renderCheckItem(`Process "${processName}" terminated`, killed);
-  html += `</div>`;
+const SECURITY_HISTORY_KEY = "sentinel.securityScores";
 
-  if (data.stderr && String(data.stderr).length > 0) {
-    html += `<div style="margin-top: 8px; font-size: 0.7rem; color: var(--text-dim);">${escapeHtml(String(data.stderr))}</div>`;
+/**
+ * Remember each posture score (keyed by when it was checked) so the card can
+ * show the trend. Returns the previous score, if any.
+ */
+function recordSecurityScore(checkedAt: number, score: number): number | null {
+  let history: Array<{ t: number; score: number }> = [];
+  try {
+    history = JSON.parse(localStorage.getItem(SECURITY_HISTORY_KEY) || "[]");
+  } catch {
+    history = [];
   }
-
-  return html;
+  if (!history.some((h) => h.t === checkedAt)) {
+    history.push({ t: checkedAt, score });
+    localStorage.setItem(SECURITY_HISTORY_KEY, JSON.stringify(history.slice(-30)));
+  }
+  const earlier = history.filter((h) => h.t < checkedAt);
+  return earlier.length > 0 ? earlier[earlier.length - 1].score : null;
 }
 
-// --- Clear Caches ---
-
-function renderClearCachesData(data: Record<string, unknown>): string {
-  const target = String(data.target || "both");
+function renderSecurityData(data: Record<string, unknown>): string {
+  const findings = asArray(data.findings);
+  const score = data.score as number | null;
 
-  let html = `<div class="checklist">`;
+  let html = "";
+  if (score !== null && score !== undefined) {
+    const previous = recordSecurityScore(Number(data.checked_at || 0), score);
+    const cls = score >= 80 ? "text-green" : score >= 60 ? "text-amber" : "text-red";
+    let trend = "";
+    if (previous !== null && previous !== score) {
+      const delta = score - previous;
+      trend = `<span class="${delta > 0 ? "text-green" : "text-red"}">${delta > 0 ? "+" : ""}${delta} since last check</span>`;
+    }
+    html += `
+      <div class="stat-row">
+        <div class="stat-item">
+          <span class="stat-value ${cls}">${score}/100</span>
+          <span class="stat-label">Posture (${escapeHtml(String(data.grade || ""))})</span>
+        </div>
+        <div class="stat-item">
+          <span class="stat-value">${escapeHtml(String(data.failed ?? 0))}</span>
+          <span class="stat-label">Failed checks</span>
+        </div>
+      </div>
+    `;
+    if (trend) html += `<div style="font-size: 0.7rem; margin-bottom: 6px;">${trend}</div>`;
+  }
+
+  html += `<div class="section-header">Security Checks</div>`;
+  html += `<div class="checklist">`;
+  for (const f of findings) {
+    const finding = f as Record<string, unknown>;
+    const label = `${finding.title} — ${finding.detail}`;
+    if (finding.status === "unknown") {
+      html += `<div class="check-item dim"><span class="check-icon">?</span><span class="check-label">${escapeHtml(label)}</span></div>`;
+      continue;
+    }
+    html += renderCheckItem(label, finding.status === "pass");
+    if (finding.status === "fail" && finding.remediation) {
+      html += `<div class="dim" style="font-size: 0.65rem; margin: -2px 0 6px 22px;">
+        <span class="text-amber">${escapeHtml(String(finding.severity).toUpperCase())}</span>
+        ${escapeHtml(String(finding.remediation))}
+      </div>`;
+    }
+  }
+  html += `</div>`;
+  return html;
+}
+
+// --- Logs & Crashes ---
+
+function renderSearchLogsData(data: Record<string, unknown>): string {
+  const entries = asArray(data.entries);
+  const topProcesses = asArray(data.top_processes);
+  const levelClass: Record<string, string> = { fault: "text-red", error: "text-red", default: "", info: "dim", debug: "dim" };
+
+  let html = `
+    <div class="stat-row">
+      <div class="stat-item">
+        <span class="stat-value">${escapeHtml(String(data.match_count ?? 0))}</span>
+        <span class="stat-label">Entries (last ${escapeHtml(String(data.window || ""))})</span>
+      </div>
+    </div>
+  `;
+  if (data.predicate) {
+    html += `<div class="dim" style="font-size: 0.65rem; margin-bottom: 6px;">${escapeHtml(String(data.predicate))}</div>`;
+  }
+
+  if (topProcesses.length > 0) {
+    html += `<div class="section-header">Top Processes</div><div class="kv-grid">`;
+    for (const entry of topProcesses) {
+      const p = entry as Record<string, unknown>;
+      html += `<span class="kv-key">${escapeHtml(String(p.process || "?"))}</span><span class="kv-value">${escapeHtml(String(p.count))}</span>`;
+    }
+    html += `</div>`;
+  }
+
+  if (entries.length === 0) {
+    html += `<div class="dim" style="font-size: 0.7rem;">No matching log entries</div>`;
+    return html;
+  }
+
+  html += `<div class="section-header">Entries${data.truncated ? " (most recent)" : ""}</div>`;
+  html += `<table class="data-table">
+    <thead><tr><th>Time</th><th>Level</th><th>Process</th><th>Message</th></tr></thead>
+    <tbody>`;
+  for (const entry of entries.slice().reverse()) {
+    const e = entry as Record<string, unknown>;
+    const level = String(e.level || "");
+    html += `<tr>
+      <td class="dim">${escapeHtml(String(e.timestamp || "").slice(11, 19))}</td>
+      <td class="${levelClass[level] ?? ""}">${escapeHtml(level)}</td>
+      <td>${escapeHtml(String(e.process || ""))}</td>
+      <td>${escapeHtml(String(e.message || ""))}</td>
+    </tr>`;
+  }
+  html += `</tbody></table>`;
+  return html;
+}
+
+function renderCrashesData(data: Record<string, unknown>): string {
+  const apps = asArray(data.apps);
+  const reports = asArray(data.reports);
+
+  let html = `
+    <div class="stat-row">
+      <div class="stat-item">
+        <span class="stat-value ${Number(data.crash_count) > 0 ? "text-red" : "text-green"}">${escapeHtml(String(data.crash_count ?? 0))}</span>
+        <span class="stat-label">Crashes</span>
+      </div>
+      <div class="stat-item">
+        <span class="stat-value ${Number(data.hang_count) > 0 ? "text-amber" : "text-green"}">${escapeHtml(String(data.hang_count ?? 0))}</span>
+        <span class="stat-label">Hangs</span>
+      </div>
+      <div class="stat-item">
+        <span class="stat-value">${escapeHtml(String(data.days ?? ""))}d</span>
+        <span class="stat-label">Window</span>
+      </div>
+    </div>
+  `;
 
-  if (target === "disk" || target === "both") {
-    html += renderCheckItem("Disk caches cleared", Boolean(data.disk_caches_cleared));
+  if (apps.length === 0) {
+    html += `<div class="dim" style="font-size: 0.7rem;">No crash or hang reports found</div>`;
+    return html;
+  }
+
+  html += `<div class="section-header">By App</div>`;
+  html += `<table class="data-table">
+    <thead><tr><th>App</th><th>Crashes</th><th>Hangs</th><th>Usual exception</th><th>Last seen</th></tr></thead>
+    <tbody>`;
+  for (const entry of apps) {
+    const a = entry as Record<string, unknown>;
+    html += `<tr>
+      <td>${escapeHtml(String(a.app || ""))}</td>
+      <td class="${Number(a.crashes) > 0 ? "text-red" : ""}">${escapeHtml(String(a.crashes ?? 0))}</td>
+      <td class="${Number(a.hangs) > 0 ? "text-amber" : ""}">${escapeHtml(String(a.hangs ?? 0))}</td>
+      <td>${escapeHtml(String(a.top_exception || "—"))}</td>
+      <td class="dim">${escapeHtml(String(a.last_seen || "").slice(0, 16))}</td>
+    </tr>`;
   }
-  if (target === "memory" || target === "both") {
-    html += renderCheckItem("Memory purged", Boolean(data.memory_purged));
+  html += `</tbody></table>`;
+
+  const latest = reports[0] as Record<string, unknown> | undefined;
+  if (latest) {
+    const thread = latest.crashing_thread as Record<string, unknown> | null;
+    const exception = [latest.exception_type, latest.signal].filter(Boolean).join(" / ");
+    html += `<div class="section-header">Latest: ${escapeHtml(String(latest.app))} (${escapeHtml(String(latest.kind))})</div>`;
+    html += `<div class="kv-grid">
+      <span class="kv-key">When</span><span class="kv-value">${escapeHtml(String(latest.timestamp || ""))}</span>
+      <span class="kv-key">Version</span><span class="kv-value">${escapeHtml(String(latest.version || "—"))}</span>
+      ${exception ? `<span class="kv-key">Exception</span><span class="kv-value text-red">${escapeHtml(exception)}</span>` : ""}
+      ${latest.reason ? `<span class="kv-key">Reason</span><span class="kv-value">${escapeHtml(String(latest.reason))}</span>` : ""}
+      ${thread ? `<span class="kv-key">Thread</span><span class="kv-value">${escapeHtml(String(thread.index))} ${escapeHtml(String(thread.name || thread.queue || ""))}</span>` : ""}
+    </div>`;
+    const frames = thread ? asArray(thread.frames) : [];
+    if (frames.length > 0) {
+      html += `<div class="items-list">`;
+      frames.forEach((frame, i) => {
+        html += `<div class="item"><span class="dim">${i}</span> ${escapeHtml(String(frame))}</div>`;
+      });
+      html += `</div>`;
+    }
   }
+  return html;
+}
+
+// --- Thermals ---
+
+function renderThermalData(data: Record<string, unknown>): string {
+  const reasons = asArray(data.reasons);
+  const cores = asArray(data.per_core_pct).map(Number);
+  const load = data.load_average as Record<string, number> | null;
+  const metrics = data.powermetrics as Record<string, unknown> | null;
+  const verdictLabel: Record<string, [string, string]> = {
+    normal: ["NORMAL", "text-green"],
+    elevated: ["ELEVATED", "text-amber"],
+    throttled: ["THROTTLED", "text-red"],
+  };
+  const [label, cls] = verdictLabel[String(data.verdict)] ?? [String(data.verdict || "?").toUpperCase(), ""];
+  const speedLimit = data.cpu_speed_limit as number | null;
 
+  let html = `
+    <div class="stat-row">
+      <div class="stat-item">
+        <span class="stat-value ${cls}">${escapeHtml(label)}</span>
+        <span class="stat-label">Thermal state</span>
+      </div>
+      <div class="stat-item">
+        <span class="stat-value ${speedLimit !== null && speedLimit < 100 ? "text-red" : ""}">${speedLimit ?? "—"}${speedLimit !== null ? "%" : ""}</span>
+        <span class="stat-label">CPU speed limit</span>
+      </div>
+      <div class="stat-item">
+        <span class="stat-value">${load ? load.one.toFixed(2) : "—"}</span>
+        <span class="stat-label">Load (1m)</span>
+      </div>
+    </div>
+  `;
+
+  if (reasons.length > 0) {
+    html += `<div class="items-list">`;
+    for (const reason of reasons) {
+      html += `<div class="item text-amber">${escapeHtml(String(reason))}</div>`;
+    }
+    html += `</div>`;
+  }
+
+  html += `<div class="kv-grid">`;
+  if (load) {
+    html += `<span class="kv-key">Load average</span><span class="kv-value">${load.one.toFixed(2)} / ${load.five.toFixed(2)} / ${load.fifteen.toFixed(2)}</span>`;
+  }
+  if (metrics) {
+    if (metrics.thermal_pressure) html += `<span class="kv-key">Thermal pressure</span><span class="kv-value">${escapeHtml(String(metrics.thermal_pressure))}</span>`;
+    if (metrics.cpu_die_temp_c != null) html += `<span class="kv-key">CPU die</span><span class="kv-value">${Number(metrics.cpu_die_temp_c).toFixed(1)}°C</span>`;
+    if (metrics.fan_rpm != null) html += `<span class="kv-key">Fan</span><span class="kv-value">${Math.round(Number(metrics.fan_rpm))} rpm</span>`;
+  } else {
+    html += `<span class="kv-key">Sensors</span><span class="kv-value dim">powermetrics needs administrator access</span>`;
+  }
   html += `</div>`;
+
+  if (cores.length > 0) {
+    html += `<div class="section-header">Per-core usage (${escapeHtml(String(data.sample_secs))}s, avg ${escapeHtml(String(data.average_core_pct))}%)</div>`;
+    html += `<div class="kv-grid">`;
+    cores.forEach((pct, i) => {
+      const coreCls = pct >= 90 ? "text-red" : pct >= 60 ? "text-amber" : "";
+      html += `<span class="kv-key">Core ${i}</span><span class="kv-value ${coreCls}">${pct.toFixed(1)}%</span>`;
+    });
+    html += `</div>`;
+  }
   return html;
 }
 
+// --- Apps ---
+
+function renderAppsData(data: Record<string, unknown>): string {
+  const apps = asArray(data.apps);
+  const intelOnly = asArray(data.intel_only).map(String);
+  const rosetta = asArray(data.running_under_rosetta).map(String);
+  const brew = data.homebrew as Record<string, unknown> | null;
+  const outdated = brew ? asArray(brew.outdated) : [];
+  const archLabel: Record<string, string> = {
+    universal: "Universal",
+    apple_silicon: "Apple silicon",
+    intel: "Intel",
+    unknown: "?",
+  };
+
+  let html = `
+    <div class="stat-row">
+      <div class="stat-item">
+        <span class="stat-value">${escapeHtml(String(data.app_count ?? 0))}</span>
+        <span class="stat-label">Apps</span>
+      </div>
+      <div class="stat-item">
+        <span class="stat-value ${intelOnly.length > 0 && data.apple_silicon ? "text-amber" : ""}">${intelOnly.length}</span>
+        <span class="stat-label">Intel-only</span>
+      </div>
+      ${brew ? `<div class="stat-item">
+        <span class="stat-value ${outdated.length > 0 ? "text-amber" : "text-green"}">${outdated.length}</span>
+        <span class="stat-label">Outdated (brew)</span>
+      </div>` : ""}
+    </div>
+  `;
+
+  if (rosetta.length > 0) {
+    html += `<div class="text-amber" style="font-size: 0.7rem; margin-bottom: 4px;">Running under Rosetta: ${escapeHtml(rosetta.join(", "))}</div>`;
+  }
+
+  if (apps.length > 0) {
+    html += `<div class="section-header">Applications</div>`;
+    html += `<table class="data-table">
+      <thead><tr><th>App</th><th>Version</th><th>Bundle ID</th><th>Arch</th><th>Signed</th></tr></thead>
+      <tbody>`;
+    for (const entry of apps.slice(0, 50)) {
+      const app = entry as Record<string, unknown>;
+      const signature = app.signature as Record<string, unknown> | null;
+      const signed = signatureText(signature);
+      const arch = String(app.architecture || "unknown");
+      const archCls = arch === "intel" && data.apple_silicon ? "text-amber" : "";
+      html += `<tr>
+        <td>${escapeHtml(String(app.name || ""))}${app.rosetta ? ` <span class="text-amber">(Rosetta)</span>` : ""}</td>
+        <td>${escapeHtml(String(app.version || "—"))}</td>
+        <td class="dim">${escapeHtml(String(app.bundle_id || ""))}</td>
+        <td class="${archCls}">${escapeHtml(archLabel[arch] ?? arch)}</td>
+        <td class="${signature?.status === "unsigned" || signature?.status === "invalid" ? "text-red" : ""}">${escapeHtml(signed)}</td>
+      </tr>`;
+    }
+    html += `</tbody></table>`;
+    if (apps.length > 50) {
+      html += `<div class="dim" style="font-size: 0.65rem;">+${apps.length - 50} more</div>`;
+    }
+  } else {
+    html += `<div class="dim" style="font-size: 0.7rem;">No matching apps found</div>`;
+  }
+
+  if (brew) {
+    const formulae = asArray(brew.formulae);
+    const casks = asArray(brew.casks);
+    html += `<div class="section-header">Homebrew (${formulae.length} formulae, ${casks.length} casks)</div>`;
+    if (outdated.length > 0) {
+      html += `<table class="data-table">
+        <thead><tr><th>Package</th><th>Installed</th><th>Latest</th></tr></thead>
+        <tbody>`;
+      for (const entry of outdated) {
+        const pkg = entry as Record<string, unknown>;
+        html += `<tr>
+          <td>${escapeHtml(String(pkg.name))}${pkg.kind === "cask" ? ` <span class="dim">(cask)</span>` : ""}</td>
+          <td class="text-amber">${escapeHtml(String(pkg.installed))}</td>
+          <td class="text-green">${escapeHtml(String(pkg.latest))}</td>
+        </tr>`;
+      }
+      html += `</tbody></table>`;
+    } else {
+      html += `<div class="dim" style="font-size: 0.7rem;">Everything is up to date</div>`;
+    }
+  }
+  return html;
+}
+
+// --- Hardware ---
+
+function renderHardwareData(data: Record<string, unknown>): string {
+  const issues = asArray(data.issues);
+  const usb = data.usb ? asArray(data.usb) : null;
+  const thunderbolt = data.thunderbolt ? asArray(data.thunderbolt) : null;
+  const displays = data.displays ? asArray(data.displays) : null;
+  const bluetooth = data.bluetooth as Record<string, unknown> | null;
+  const storage = data.storage ? asArray(data.storage) : null;
+
+  let html = "";
+  if (issues.length > 0) {
+    html += `<div class="items-list">`;
+    for (const issue of issues) {
+      html += `<div class="item text-amber">${escapeHtml(String(issue))}</div>`;
+    }
+    html += `</div>`;
+  }
+
+  if (displays) {
+    html += `<div class="section-header">Displays</div>`;
+    if (displays.length === 0) {
+      html += `<div class="dim" style="font-size: 0.7rem;">No displays detected</div>`;
+    }
+    for (const entry of displays) {
+      const d = entry as Record<string, unknown>;
+      const tags = [d.internal ? "internal" : String(d.connection || "external"), d.main ? "main" : "", d.mirrored ? "mirrored" : ""].filter(Boolean);
+      html += `<div class="kv-grid">
+        <span class="kv-key">${escapeHtml(String(d.name))}</span>
+        <span class="kv-value ${d.online ? "" : "text-red"}">${escapeHtml(String(d.resolution || d.native_pixels || ""))} <span class="dim">${escapeHtml(tags.join(", "))}</span></span>
+      </div>`;
+    }
+  }
+
+  if (usb) {
+    html += `<div class="section-header">USB</div>`;
+    if (usb.length === 0) {
+      html += `<div class="dim" style="font-size: 0.7rem;">No USB devices connected</div>`;
+    } else {
+      html += `<table class="data-table">
+        <thead><tr><th>Device</th><th>Maker</th><th>Speed</th><th>Power (mA)</th></tr></thead>
+        <tbody>`;
+      for (const entry of usb) {
+        const d = entry as Record<string, unknown>;
+        const over = d.power_used_ma != null && d.power_available_ma != null && Number(d.power_used_ma) > Number(d.power_available_ma);
+        html += `<tr>
+          <td>${d.hub ? "&nbsp;&nbsp;↳ " : ""}${escapeHtml(String(d.name))}</td>
+          <td>${escapeHtml(String(d.manufacturer || ""))}</td>
+          <td class="dim">${escapeHtml(String(d.speed || ""))}</td>
+          <td class="${over ? "text-red" : ""}">${d.power_used_ma != null ? `${d.power_used_ma}/${d.power_available_ma ?? "?"}` : ""}</td>
+        </tr>`;
+      }
+      html += `</tbody></table>`;
+    }
+  }
+
+  if (thunderbolt && thunderbolt.length > 0) {
+    html += `<div class="section-header">Thunderbolt / USB4</div><div class="kv-grid">`;
+    for (const entry of thunderbolt) {
+      const port = entry as Record<string, unknown>;
+      const devices = asArray(port.devices).map((d) => String((d as Record<string, unknown>).name));
+      html += `<span class="kv-key">Port ${escapeHtml(String(port.port ?? "?"))}</span>
+        <span class="kv-value">${escapeHtml(devices.length ? devices.join(", ") : String(port.status))} <span class="dim">${escapeHtml(String(port.speed || ""))}</span></span>`;
+    }
+    html += `</div>`;
+  }
+
+  if (bluetooth) {
+    const devices = asArray(bluetooth.devices);
+    html += `<div class="section-header">Bluetooth${bluetooth.powered === false ? ` <span class="text-red">(off)</span>` : ""}</div>`;
+    if (devices.length > 0) {
+      html += `<div class="kv-grid">`;
+      for (const entry of devices) {
+        const d = entry as Record<string, unknown>;
+        const levels = Object.entries((d.battery_pct as Record<string, number>) || {})
+          .map(([part, pct]) => `<span class="${pct <= 20 ? "text-red" : ""}">${escapeHtml(part)} ${pct}%</span>`)
+          .join(" ");
+        html += `<span class="kv-key ${d.connected ? "" : "dim"}">${escapeHtml(String(d.name))}</span>
+          <span class="kv-value">${d.connected ? levels || "connected" : `<span class="dim">not connected</span>`}</span>`;
+      }
+      html += `</div>`;
+    }
+  }
+
+  if (storage && storage.length > 0) {
+    html += `<div class="section-header">Storage</div>`;
+    html += `<table class="data-table">
+      <thead><tr><th>Volume</th><th>Drive</th><th>Free</th><th>SMART</th></tr></thead>
+      <tbody>`;
+    for (const entry of storage) {
+      const v = entry as Record<string, unknown>;
+      const smart = v.smart_status ? String(v.smart_status) : "—";
+      html += `<tr>
+        <td>${escapeHtml(String(v.name))}</td>
+        <td class="dim">${escapeHtml(String(v.device || ""))}${v.internal ? "" : " (external)"}</td>
+        <td>${v.free_bytes != null ? formatBytes(Number(v.free_bytes)) : ""}</td>
+        <td class="${smart === "Verified" ? "text-green" : smart === "—" ? "dim" : "text-red"}">${escapeHtml(smart)}</td>
+      </tr>`;
+    }
+    html += `</tbody></table>`;
+  }
+
+  return html || renderGenericData(data);
+}
+
+// --- Startup Items ---
+
+function renderStartupData(data: Record<string, unknown>): string {
+  const loginItems = asArray(data.login_items);
+  const launchItems = asArray(data.launch_items);
+  const scopeLabel: Record<string, string> = {
+    user_agent: "User agent",
+    agent: "Agent",
+    daemon: "Daemon",
+  };
+
+  let html = "";
+
+  html += `<div class="section-header">Login Items</div>`;
+  if (loginItems.length > 0) {
+    html += `<div class="items-list">`;
+    for (const item of loginItems) {
+      html += `<div class="item">${escapeHtml(String(item))}</div>`;
+    }
+    html += `</div>`;
+  } else {
+    html += `<div class="dim" style="font-size: 0.7rem;">No login items found</div>`;
+  }
+
+  html += `<div class="section-header">Launch Agents &amp; Daemons</div>`;
+  if (launchItems.length > 0) {
+    html += `<table class="data-table">
+      <thead><tr><th>Label</th><th>Type</th><th>Program</th><th>Signed</th><th></th></tr></thead>
+      <tbody>`;
+    for (const entry of launchItems) {
+      const item = entry as Record<string, unknown>;
+      const flags = asArray(item.flags).map(String);
+      const signature = item.signature as Record<string, unknown> | undefined;
+      const signed = signatureText(signature);
+      const label = String(item.label || "");
+      const flagHtml = flags.length
+        ? `<div class="text-amber" style="font-size: 0.65rem;">${escapeHtml(flags.join("; "))}</div>`
+        : "";
+      const action = item.disabled
+        ? `<span class="dim">disabled</span>`
+        : `<button class="quick-btn" data-command="disable startup item ${escapeHtml(label)}">DISABLE</button>`;
+      html += `<tr>
+        <td>${escapeHtml(label)}${flagHtml}</td>
+        <td>${escapeHtml(scopeLabel[String(item.scope)] || String(item.scope || ""))}</td>
+        <td title="${escapeHtml(asArray(item.arguments).join(" "))}">${escapeHtml(String(item.program || "—"))}</td>
+        <td class="${signature?.status === "invalid" ? "text-red" : ""}">${escapeHtml(signed)}</td>
+        <td>${action}</td>
+      </tr>`;
+    }
+    html += `</tbody></table>`;
+  } else {
+    html += `<div class="dim" style="font-size: 0.7rem;">No launch agents or daemons found</div>`;
+  }
+
+  return html;
+}
+
+// --- Disable Startup Item ---
+
+function renderDisableStartupData(data: Record<string, unknown>): string {
+  const item = (data.item || {}) as Record<string, unknown>;
+  const commands = asArray(data.commands).map(String);
+
+  let html = `<div class="kv-grid">
+    <span class="kv-key">Label</span><span class="kv-value">${escapeHtml(String(item.label || ""))}</span>
+    <span class="kv-key">Program</span><span class="kv-value">${escapeHtml(String(item.program || "—"))}</span>
+    <span class="kv-key">Plist</span><span class="kv-value">${escapeHtml(String(item.plist_path || ""))}</span>
+  </div>`;
+
+  if (data.disabled !== undefined) {
+    html += `<div class="checklist">${renderCheckItem("Disabled", Boolean(data.disabled))}</div>`;
+  } else if (commands.length > 0) {
+    html += `<div class="section-header">Will run</div>`;
+    html += `<div class="items-list">${commands.map((c) => `<div class="item">${escapeHtml(c)}</div>`).join("")}</div>`;
+  }
+  if (data.undo) {
+    html += `<div class="dim" style="font-size: 0.7rem; margin-top: 6px;">Undo with: ${escapeHtml(String(data.undo))}</div>`;
+  }
+  return html;
+}
+
+// --- Kill Process ---
+
+function renderKillProcessData(data: Record<string, unknown>): string {
+  const target = String(data.target || "process");
+  let html = "";
+
+  if (data.outcomes) {
+    html += `<div class="checklist">`;
+    for (const o of asArray(data.outcomes)) {
+      const outcome = o as Record<string, unknown>;
+      const signal = outcome.signal === "SIGKILL" ? " (force-killed)" : "";
+      html += renderCheckItem(
+        `${String(outcome.name || "")} [${String(outcome.pid)}] terminated${signal}`,
+        Boolean(outcome.exited),
+      );
+    }
+    html += `</div>`;
+  }
+
+  const candidates = asArray(data.candidates);
+  if (data.candidates && candidates.length === 0) {
+    html += `<div class="dim">No process matches ${escapeHtml(target)}</div>`;
+  }
+  if (candidates.length > 0) {
+    if (data.ambiguous) {
+      html += `<div class="section-header">${candidates.length} processes match ${escapeHtml(target)} — pick one</div>`;
+    } else {
+      html += `<div class="section-header">Matches for ${escapeHtml(target)}</div>`;
+    }
+    html += `<table class="data-table">
+      <thead><tr><th>PID</th><th>Name</th><th>User</th><th>CPU %</th><th>Mem</th><th></th></tr></thead>
+      <tbody>`;
+    for (const c of candidates) {
+      const p = c as Record<string, unknown>;
+      const action = p.refused
+        ? `<span class="dim">${escapeHtml(String(p.refused))}</span>`
+        : data.ambiguous
+          ? `<button class="quick-btn" data-command="kill pid ${escapeHtml(String(p.pid))}">KILL</button>`
+          : "";
+      html += `<tr>
+        <td>${escapeHtml(String(p.pid))}</td>
+        <td title="${escapeHtml(String(p.command || ""))}">${escapeHtml(String(p.name || ""))}</td>
+        <td>${escapeHtml(String(p.user || ""))}</td>
+        <td>${Number(p.cpu_percent || 0).toFixed(1)}</td>
+        <td>${formatBytes(Number(p.rss_kb || 0) * 1024)}</td>
+        <td>${action}</td>
+      </tr>`;
+    }
+    html += `</tbody></table>`;
+  }
+
+  return html;
+}
+
+// --- Clear Caches ---
+
+function renderClearCachesData(data: Record<string, unknown>): string {
+  const disk = data.disk as Record<string, unknown> | undefined;
+  const memory = data.memory as Record<string, unknown> | undefined;
+  const dryRun = Boolean(data.dry_run);
+
+  let html = "";
+
+  if (disk) {
+    const entries = asArray(disk.entries);
+    const verb = dryRun ? "Reclaimable" : "Moved to Trash";
+    html += `
+      <div class="stat-row">
+        <div class="stat-item">
+          <span class="stat-value text-green">${formatBytes(disk.bytes_reclaimed)}</span>
+          <span class="stat-label">${verb}</span>
+        </div>
+        <div class="stat-item">
+          <span class="stat-value">${escapeHtml(String(disk.excluded || 0))}</span>
+          <span class="stat-label">Excluded</span>
+        </div>
+        <div class="stat-item">
+          <span class="stat-value ${Number(disk.failed || 0) > 0 ? "text-red" : ""}">${escapeHtml(String(disk.failed || 0))}</span>
+          <span class="stat-label">Failed</span>
+        </div>
+      </div>
+    `;
+    if (entries.length > 0) {
+      html += `<table class="data-table">
+        <thead><tr><th>Cache</th><th>Size</th><th>Status</th></tr></thead>
+        <tbody>`;
+      for (const item of entries.slice(0, 25)) {
+        const e = item as Record<string, unknown>;
+        const status = String(e.status || "");
+        const statusClass = status === "failed" ? "text-red" : status === "excluded" ? "text-amber" : "text-green";
+        html += `<tr>
+          <td title="${escapeHtml(String(e.reason || ""))}">${escapeHtml(String(e.name || ""))}</td>
+          <td>${formatBytes(e.bytes)}</td>
+          <td class="${statusClass}">${escapeHtml(status)}</td>
+        </tr>`;
+      }
+      html += `</tbody></table>`;
+    }
+  }
+
+  if (memory && !dryRun) {
+    html += `<div class="checklist">`;
+    html += renderCheckItem("Memory purged", Boolean(memory.purged));
+    html += `</div>`;
+  }
+
+  return html || renderGenericData(data);
+}
+
 // --- Full Checkup ---
 
 function renderFullCheckupData(data: Record<string, unknown>): string {
@@ -775,11 +1779,13 @@ function renderFullCheckupData(data: Record<string, unknown>): string {
 // --- Engine (auto_mechanic) ---
 
 function renderEngineData(data: Record<string, unknown>): string {
-  const rpm = String(data.rpm || "?");
-  const temp = String(data.temp_f || "?");
-  const oil = String(data.oil_pressure_psi || "?");
+  const rpm = data.rpm == null ? "?" : String(data.rpm);
+  const temp = data.temp_f == null ? "?" : String(data.temp_f);
+  const load = data.engine_load_pct == null ? "?" : String(data.engine_load_pct);
   const status = String(data.status || "unknown");
   const codes = asArray(data.codes);
+  const trims = (data.fuel_trims || {}) as Record<string, unknown>;
+  const source = String(data.source || "demo");
 
   let html = `
     <div class="stat-row">
@@ -789,11 +1795,11 @@ function renderEngineData(data: Record<string, unknown>): string {
       </div>
       <div class="stat-item">
         <span class="stat-value">${escapeHtml(temp)}&deg;F</span>
-        <span class="stat-label">Temp</span>
+        <span class="stat-label">Coolant</span>
       </div>
       <div class="stat-item">
-        <span class="stat-value">${escapeHtml(oil)} psi</span>
-        <span class="stat-label">Oil Pressure</span>
+        <span class="stat-value">${escapeHtml(load)}%</span>
+        <span class="stat-label">Load</span>
       </div>
       <div class="stat-item">
         <span class="stat-value">${escapeHtml(status)}</span>
@@ -802,19 +1808,39 @@ function renderEngineData(data: Record<string, unknown>): string {
     </div>
   `;
 
+  const adapter = [source, data.adapter, data.protocol].filter(Boolean).map(String);
+  const milClass = data.mil_on ? "text-amber" : "text-green";
+  html += `<div class="kv-grid">
+    <span class="kv-key">Source</span>
+    <span class="kv-value">${escapeHtml(adapter.join(" · "))}</span>
+    <span class="kv-key">Check engine light</span>
+    <span class="kv-value ${milClass}">${data.mil_on ? "on" : "off"}</span>
+  `;
+  for (const [key, value] of Object.entries(trims)) {
+    const pct = Number(value);
+    const trimClass = Math.abs(pct) > 10 ? "text-amber" : "";
+    const label = key.replace("stft_bank", "Short-term trim B").replace("ltft_bank", "Long-term trim B");
+    html += `
+      <span class="kv-key">${escapeHtml(label)}</span>
+      <span class="kv-value ${trimClass}">${pct > 0 ? "+" : ""}${pct.toFixed(1)}%</span>
+    `;
+  }
+  html += `</div>`;
+
   if (codes.length > 0) {
     html += `<div class="section-header">OBD-II Codes</div>`;
     html += `<table class="data-table">
-      <thead><tr><th>Code</th><th>Description</th><th>Severity</th></tr></thead>
+      <thead><tr><th>Code</th><th>Description</th><th>Severity</th><th>Drive?</th></tr></thead>
       <tbody>`;
     for (const item of codes) {
       const c = item as Record<string, unknown>;
       const sev = String(c.severity || "");
-      const sevClass = sev === "moderate" ? "text-amber" : sev === "low" ? "text-green" : "text-red";
+      const drive = String(c.safe_to_drive || "");
       html += `<tr>
         <td><strong>${escapeHtml(String(c.code || ""))}</strong></td>
         <td>${escapeHtml(String(c.description || ""))}</td>
-        <td class="${sevClass}">${escapeHtml(sev)}</td>
+        <td class="${dtcSeverityClass(sev)}">${escapeHtml(sev)}</td>
+        <td class="${dtcDriveClass(drive)}">${escapeHtml(drive)}</td>
       </tr>`;
     }
     html += `</tbody></table>`;
@@ -823,6 +1849,149 @@ function renderEngineData(data: Record<string, unknown>): string {
   return html;
 }
 
+// --- Trouble codes (auto_mechanic) ---
+
+function dtcSeverityClass(severity: string): string {
+  switch (severity) {
+    case "low":
+      return "text-green";
+    case "moderate":
+      return "text-amber";
+    case "high":
+    case "critical":
+      return "text-red";
+    default:
+      return "dim";
+  }
+}
+
+function dtcDriveClass(safe: string): string {
+  return safe === "yes" ? "text-green" : safe === "no" ? "text-red" : "text-amber";
+}
+
+function renderDtcData(data: Record<string, unknown>): string {
+  const codes = asArray(data.codes);
+  if (codes.length === 0) return renderGenericData(data);
+
+  let html = "";
+  for (const item of codes) {
+    const c = item as Record<string, unknown>;
+    const sev = String(c.severity || "unknown");
+    const drive = String(c.safe_to_drive || "caution");
+    const scope = [c.system, c.scope, c.area].filter(Boolean).map(String).join(" · ");
+    html += `<div class="section-header">${escapeHtml(String(c.code || ""))} &mdash; ${escapeHtml(String(c.description || ""))}</div>`;
+    html += `<div class="kv-grid">
+      <span class="kv-key">Type</span>
+      <span class="kv-value">${escapeHtml(scope)}</span>
+      <span class="kv-key">Severity</span>
+      <span class="kv-value ${dtcSeverityClass(sev)}">${escapeHtml(sev)}</span>
+      <span class="kv-key">Safe to drive</span>
+      <span class="kv-value ${dtcDriveClass(drive)}">${escapeHtml(drive)}</span>
+    </div>`;
+    html += `<div class="item">${escapeHtml(String(c.guidance || ""))}</div>`;
+
+    const causes = asArray(c.causes);
+    if (causes.length > 0) {
+      html += `<div class="items-list">`;
+      for (const cause of causes) {
+        html += `<div class="item"><span class="dim">cause:</span> ${escapeHtml(String(cause))}</div>`;
+      }
+      html += `</div>`;
+    }
+    for (const cand of asArray(c.candidates)) {
+      const m = cand as Record<string, unknown>;
+      html += `<div class="item"><span class="dim">${escapeHtml(String(m.manufacturer || ""))}:</span> ${escapeHtml(String(m.description || ""))}</div>`;
+    }
+  }
+  return html;
+}
+
+function renderCodeTable(codes: unknown[]): string {
+  let html = `<table class="data-table">
+    <thead><tr><th>Code</th><th>Description</th><th>Severity</th></tr></thead>
+    <tbody>`;
+  for (const item of codes) {
+    const c = item as Record<string, unknown>;
+    const sev = String(c.severity || "");
+    html += `<tr>
+      <td><strong>${escapeHtml(String(c.code || ""))}</strong></td>
+      <td>${escapeHtml(String(c.description || ""))}</td>
+      <td class="${dtcSeverityClass(sev)}">${escapeHtml(sev)}</td>
+    </tr>`;
+  }
+  return html + `</tbody></table>`;
+}
+
+function renderCodeListsData(data: Record<string, unknown>): string {
+  let html = `<div class="checklist">
+    ${renderCheckItem(`Check engine light ${data.mil_on ? "on" : "off"}`, !data.mil_on)}
+  </div>`;
+  const lists: Array<[string, string]> = [
+    ["stored", "Stored (confirmed)"],
+    ["pending", "Pending (not yet confirmed)"],
+    ["permanent", "Permanent (clear after the fault is fixed)"],
+  ];
+  for (const [key, label] of lists) {
+    const codes = asArray(data[key]);
+    html += `<div class="section-header">${escapeHtml(label)}</div>`;
+    html += codes.length > 0 ? renderCodeTable(codes) : `<div class="item dim">none</div>`;
+  }
+  return html;
+}
+
+function renderFreezeFrameData(data: Record<string, unknown>): string {
+  const frames = asArray(data.freeze_frames);
+  let html = "";
+  if (frames.length === 0) {
+    html += `<div class="item dim">No freeze frames stored.</div>`;
+  }
+  for (const item of frames) {
+    const f = item as Record<string, unknown>;
+    html += `<div class="section-header">Frame ${escapeHtml(String(f.frame ?? ""))}: ${escapeHtml(String(f.code || ""))} &mdash; ${escapeHtml(String(f.description || ""))}</div>`;
+    html += `<div class="kv-grid">`;
+    for (const r of asArray(f.readings)) {
+      const reading = r as Record<string, unknown>;
+      html += `
+        <span class="kv-key">${escapeHtml(String(reading.key || "").replace(/_/g, " "))}</span>
+        <span class="kv-value">${escapeHtml(String(reading.value ?? ""))} ${escapeHtml(String(reading.unit || ""))}</span>
+      `;
+    }
+    html += `</div>`;
+  }
+  const without = asArray(data.codes_without_frame).map(String);
+  if (without.length > 0) {
+    html += `<div class="item dim">No freeze frame for ${escapeHtml(without.join(", "))}</div>`;
+  }
+  return html;
+}
+
+function renderClearDtcsData(data: Record<string, unknown>): string {
+  let html = "";
+  if (data.dry_run) {
+    const codes = asArray(data.would_clear);
+    html += `<div class="section-header">Will clear</div>`;
+    html += codes.length > 0 ? renderCodeTable(codes) : `<div class="item dim">No codes to clear.</div>`;
+    const permanent = asArray(data.permanent_stays).map(String);
+    if (permanent.length > 0) {
+      html += `<div class="item dim">Stays until the fault is fixed: ${escapeHtml(permanent.join(", "))}</div>`;
+    }
+  } else {
+    const cleared = asArray(data.cleared).map(String);
+    const remaining = asArray(data.remaining).map(String);
+    const before = (data.before || {}) as Record<string, unknown>;
+    const after = (data.after || {}) as Record<string, unknown>;
+    html += `<div class="checklist">
+      ${renderCheckItem(`Cleared: ${cleared.join(", ") || "none"}`, true)}
+      ${renderCheckItem(`Still present: ${remaining.join(", ") || "none"}`, remaining.length === 0)}
+      ${renderCheckItem(`Check engine light: ${before.mil_on ? "on" : "off"} → ${after.mil_on ? "on" : "off"}`, !after.mil_on)}
+    </div>`;
+  }
+  if (data.note) {
+    html += `<div class="item dim">${escapeHtml(String(data.note))}</div>`;
+  }
+  return html;
+}
+
 // --- Tires (auto_mechanic) ---
 
 function renderTiresData(data: Record<string, unknown>): string {
@@ -848,6 +2017,10 @@ function renderTiresData(data: Record<string, unknown>): string {
   }
 
   html += `</tbody></table>`;
+  const vehicle = vehicleLabel(data);
+  if (vehicle) {
+    html += `<div class="item dim">Targets for the ${escapeHtml(vehicle)}</div>`;
+  }
   return html;
 }
 
@@ -856,12 +2029,13 @@ function renderTiresData(data: Record<string, unknown>): string {
 function renderVehicleBatteryData(data: Record<string, unknown>): string {
   const voltage = Number(data.voltage || 0);
   const cca = String(data.cca || "?");
-  const health = Number(data.health_pct || 0);
+  const health = data.health_pct == null ? null : Number(data.health_pct);
   const age = String(data.age_months || "?");
   const status = String(data.status || "unknown");
 
   const voltClass = voltage >= 12.4 ? "text-green" : voltage >= 12.0 ? "text-amber" : "text-red";
-  const healthClass = health >= 80 ? "text-green" : health >= 50 ? "text-amber" : "text-red";
+  const healthClass =
+    health === null ? "" : health >= 80 ? "text-green" : health >= 50 ? "text-amber" : "text-red";
 
   return `
     <div class="stat-row">
@@ -874,7 +2048,7 @@ function renderVehicleBatteryData(data: Record<string, unknown>): string {
         <span class="stat-label">CCA</span>
       </div>
       <div class="stat-item">
-        <span class="stat-value ${healthClass}">${health}%</span>
+        <span class="stat-value ${healthClass}">${health === null ? "?" : `${health}%`}</span>
         <span class="stat-label">Health</span>
       </div>
       <div class="stat-item">
@@ -883,7 +2057,7 @@ function renderVehicleBatteryData(data: Record<string, unknown>): string {
       </div>
     </div>
     <div class="checklist">
-      ${renderCheckItem("Battery status: " + status, status === "good")}
+      ${renderCheckItem("Battery status: " + status, status === "good" || status === "charging")}
     </div>
   `;
 }
@@ -902,11 +2076,179 @@ function renderFluidsData(data: Record<string, unknown>): string {
   }
 
   html += `</div>`;
+  const specs = (data.specs || {}) as Record<string, unknown>;
+  if (Object.keys(specs).length > 0) {
+    html += `<div class="section-header">Specs</div><div class="kv-grid">`;
+    for (const [key, spec] of Object.entries(specs)) {
+      html += `
+        <span class="kv-key">${escapeHtml(key.replace(/_/g, " "))}</span>
+        <span class="kv-value">${escapeHtml(String(spec))}</span>
+      `;
+    }
+    html += `</div>`;
+  }
+  return html;
+}
+
+// --- Vehicle Profile + Maintenance (auto_mechanic) ---
+
+/** The identity the backend attaches to vehicle results, e.g. "2020 Honda Accord". */
+function vehicleLabel(data: Record<string, unknown>): string | null {
+  const vehicle = data.vehicle as Record<string, unknown> | undefined;
+  return vehicle && vehicle.label ? String(vehicle.label) : null;
+}
+
+function renderVinData(data: Record<string, unknown>): string {
+  const check = (data.check_digit || {}) as Record<string, unknown>;
+  const years = asArray(data.model_year_candidates).map(String);
+  const rows: Array<[string, unknown]> = [
+    ["VIN", data.vin],
+    ["Manufacturer", data.manufacturer ?? `Unknown (WMI ${data.wmi})`],
+    ["Make", data.make],
+    ["Country", data.country ?? data.region],
+    ["Model year", data.model_year != null ? `${data.model_year} (or ${years.filter((y) => y !== String(data.model_year)).join(", ")})` : years.length ? years.join(" or ") : null],
+    ["Plant", data.plant ?? (data.plant_code ? `Code ${data.plant_code}` : null)],
+    ["Descriptor", data.vds],
+    ["Serial", data.serial],
+    ["Read from", data.vin_source],
+  ];
+  let html = `<div class="kv-grid">`;
+  for (const [label, value] of rows) {
+    if (value == null || value === "") continue;
+    html += `
+      <span class="kv-key">${escapeHtml(label)}</span>
+      <span class="kv-value">${escapeHtml(String(value))}</span>
+    `;
+  }
+  html += `</div>`;
+  const digit = check.required
+    ? `Check digit ${check.actual} ${check.valid ? "is valid" : `should be ${check.expected}`}`
+    : `Check digit not required for this region`;
+  html += `<div class="checklist">${renderCheckItem(digit, Boolean(data.valid))}</div>`;
+  if (data.warning) {
+    html += `<div class="item text-amber">${escapeHtml(String(data.warning))}</div>`;
+  }
+  return html;
+}
+
+function renderVehicleProfileData(data: Record<string, unknown>): string {
+  const pressure = (data.tire_pressure || {}) as Record<string, unknown>;
+  const fluids = (data.fluids || {}) as Record<string, unknown>;
+  let html = "";
+  if (!data.configured) {
+    html += `<div class="item dim">No vehicle profile yet. Set the make, model and year, or read the VIN from the car.</div>`;
+  }
+  const rows: Array<[string, unknown]> = [
+    ["Vehicle", ((data.identity || {}) as Record<string, unknown>).label],
+    ["VIN", data.vin],
+    ["VIN from car", data.vin_from_vehicle],
+    ["Front tires", pressure.front_psi != null ? `${pressure.front_psi} psi` : null],
+    ["Rear tires", pressure.rear_psi != null ? `${pressure.rear_psi} psi` : null],
+    ["Oil", fluids.oil],
+    ["Coolant", fluids.coolant],
+    ["Brake fluid", fluids.brake_fluid],
+    ["Transmission", fluids.transmission],
+  ];
+  html += `<div class="kv-grid">`;
+  for (const [label, value] of rows) {
+    if (value == null || value === "") continue;
+    html += `
+      <span class="kv-key">${escapeHtml(label)}</span>
+      <span class="kv-value">${escapeHtml(String(value))}</span>
+    `;
+  }
+  html += `</div>`;
+  if (data.vin_matches === false) {
+    html += `<div class="checklist">${renderCheckItem("The car's VIN does not match the profile", false)}</div>`;
+  }
+  if (data.vin_error) {
+    html += `<div class="item dim">Could not read the VIN: ${escapeHtml(String(data.vin_error))}</div>`;
+  }
+  return html;
+}
+
+function maintenanceStatusClass(status: string): string {
+  if (status === "overdue") return "text-red";
+  if (status === "due_soon") return "text-amber";
+  if (status === "ok") return "text-green";
+  return "dim";
+}
+
+function renderMaintenanceRow(item: Record<string, unknown>): string {
+  const status = String(item.status || "");
+  const due: string[] = [];
+  if (item.due_mileage != null) due.push(`${Number(item.due_mileage).toLocaleString()} mi`);
+  if (item.due_date) due.push(String(item.due_date));
+  const left: string[] = [];
+  if (item.miles_left != null) left.push(`${Number(item.miles_left).toLocaleString()} mi`);
+  if (item.days_left != null) left.push(`${item.days_left} days`);
+  return `<tr>
+    <td>${escapeHtml(formatToolName(String(item.service || "")))}</td>
+    <td class="${maintenanceStatusClass(status)}">${escapeHtml(status.replace(/_/g, " "))}</td>
+    <td>${escapeHtml(due.join(" / ") || "—")}</td>
+    <td>${escapeHtml(left.join(" / ") || "—")}</td>
+  </tr>`;
+}
+
+function renderMaintenanceData(data: Record<string, unknown>): string {
+  const mileage = data.mileage == null ? "unknown" : `${Number(data.mileage).toLocaleString()} mi`;
+  let html = `
+    <div class="stat-row">
+      <div class="stat-item">
+        <span class="stat-value">${escapeHtml(mileage)}</span>
+        <span class="stat-label">Mileage (${escapeHtml(String(data.mileage_source || ""))})</span>
+      </div>
+      ${vehicleLabel(data) ? `<div class="stat-item">
+        <span class="stat-value">${escapeHtml(vehicleLabel(data) || "")}</span>
+        <span class="stat-label">Vehicle</span>
+      </div>` : ""}
+    </div>
+    <table class="data-table">
+      <thead><tr><th>Service</th><th>Status</th><th>Due</th><th>Left</th></tr></thead>
+      <tbody>`;
+  for (const item of asArray(data.items)) {
+    html += renderMaintenanceRow(item as Record<string, unknown>);
+  }
+  html += `</tbody></table>`;
+
+  const history = asArray(data.history);
+  if (history.length > 0) {
+    html += `<div class="section-header">History</div><div class="items-list">`;
+    for (const item of history) {
+      const e = item as Record<string, unknown>;
+      const miles = e.mileage != null ? ` at ${Number(e.mileage).toLocaleString()} mi` : "";
+      const notes = e.notes ? ` <span class="dim">${escapeHtml(String(e.notes))}</span>` : "";
+      html += `<div class="item">${escapeHtml(String(e.date || ""))} ${escapeHtml(formatToolName(String(e.service || "")))}${escapeHtml(miles)}${notes}</div>`;
+    }
+    html += `</div>`;
+  }
+  return html;
+}
+
+function renderLogMaintenanceData(data: Record<string, unknown>): string {
+  const e = (data.logged || {}) as Record<string, unknown>;
+  const miles = e.mileage != null ? ` at ${Number(e.mileage).toLocaleString()} mi` : "";
+  let html = `<div class="checklist">
+    ${renderCheckItem(`Logged ${formatToolName(String(e.service || ""))} on ${String(e.date || "")}${miles}`, true)}
+  </div>`;
+  if (data.next) {
+    html += `<table class="data-table">
+      <thead><tr><th>Service</th><th>Status</th><th>Due</th><th>Left</th></tr></thead>
+      <tbody>${renderMaintenanceRow(data.next as Record<string, unknown>)}</tbody>
+    </table>`;
+  }
   return html;
 }
 
 // --- Vehicle Full Checkup (auto_mechanic) ---
 
+function findingClass(severity: string): string {
+  if (severity === "critical") return "text-red";
+  if (severity === "warning") return "text-amber";
+  if (severity === "advisory") return "dim";
+  return "text-green";
+}
+
 function renderVehicleCheckupData(data: Record<string, unknown>): string {
   const sections: Array<[string, string, (d: Record<string, unknown>) => string]> = [
     ["engine", "Engine", renderEngineData],
@@ -915,7 +2257,24 @@ function renderVehicleCheckupData(data: Record<string, unknown>): string {
     ["fluids", "Fluids", renderFluidsData],
   ];
 
+  const findings = asArray(data.findings) as Array<Record<string, unknown>>;
+  const worst = findings.length ? String(findings[0].severity) : "ok";
   let html = "";
+  if (data.verdict) {
+    html += `<div class="item ${findingClass(worst)}">${escapeHtml(String(data.verdict))}</div>`;
+  }
+  if (findings.length) {
+    html += `<div class="section-header">Findings</div><div class="items-list">`;
+    for (const finding of findings) {
+      html += `
+        <div class="item">
+          <span class="${findingClass(String(finding.severity))}">${escapeHtml(String(finding.severity).toUpperCase())}</span>
+          ${escapeHtml(String(finding.message))}
+          ${finding.action ? `<div class="dim">${escapeHtml(String(finding.action))}</div>` : ""}
+        </div>`;
+    }
+    html += `</div>`;
+  }
   for (const [key, label, renderer] of sections) {
     const sectionData = data[key] as Record<string, unknown> | undefined;
     if (sectionData) {
@@ -928,6 +2287,11 @@ function renderVehicleCheckupData(data: Record<string, unknown>): string {
     }
   }
 
+  const unavailable = (data.unavailable || {}) as Record<string, unknown>;
+  for (const [key, reason] of Object.entries(unavailable)) {
+    html += `<div class="item"><span class="dim">${escapeHtml(key)}:</span> ${escapeHtml(String(reason))}</div>`;
+  }
+
   return html || renderGenericData(data);
 }
 
@@ -974,6 +2338,13 @@ function renderGenericData(data: Record<string, unknown>): string {
 // Helpers
 // ---------------------------------------------------------------------------
 
+/** Signing authority, or the status (with the reason when it fails to verify). */
+function signatureText(signature: Record<string, unknown> | null | undefined): string {
+  if (!signature) return "";
+  if (signature.status === "invalid") return `invalid: ${String(signature.authority || "")}`;
+  return String(signature.authority || signature.status || "");
+}
+
 function renderCheckItem(label: string, passed: boolean): string {
   const cls = passed ? "check-pass" : "check-fail";
   const icon = passed ? "\u2713" : "\u2717";
@@ -1002,6 +2373,14 @@ function escapeHtml(text: string): string {
   return div.innerHTML;
 }
 
+function formatBytes(value: unknown): string {
+  const bytes = Number(value || 0);
+  if (bytes >= 1024 ** 3) return `${(bytes / 1024 ** 3).toFixed(1)} GB`;
+  if (bytes >= 1024 ** 2) return `${(bytes / 1024 ** 2).toFixed(0)} MB`;
+  if (bytes >= 1024) return `${(bytes / 1024).toFixed(0)} KB`;
+  return `${bytes} B`;
+}
+
 function asArray(value: unknown): unknown[] {
   if (Array.isArray(value)) return value;
   return [];